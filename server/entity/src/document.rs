@@ -13,6 +13,11 @@ pub struct Model {
     pub content: String,
     pub source: Option<String>,
     pub created_at: Option<DateTime>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_hash: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub metadata: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -13,6 +13,10 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub project_version_version: i32,
     pub document_id: i32,
+    pub folder_id: Option<i32>,
+    pub position: i32,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub tags: Json,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,7 +2,12 @@
 
 pub mod prelude;
 
+pub mod crawl_credential;
+pub mod crawl_run;
+pub mod crawl_run_page;
+pub mod crawl_schedule;
 pub mod document;
+pub mod document_folder;
 pub mod document_version;
 pub mod embedding;
 pub mod project;
@@ -1,6 +1,11 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.1
 
+pub use super::crawl_credential::Entity as CrawlCredential;
+pub use super::crawl_run::Entity as CrawlRun;
+pub use super::crawl_run_page::Entity as CrawlRunPage;
+pub use super::crawl_schedule::Entity as CrawlSchedule;
 pub use super::document::Entity as Document;
+pub use super::document_folder::Entity as DocumentFolder;
 pub use super::document_version::Entity as DocumentVersion;
 pub use super::embedding::Entity as Embedding;
 pub use super::project::Entity as Project;
@@ -15,6 +15,12 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::crawl_credential::Entity")]
+    CrawlCredential,
+    #[sea_orm(has_many = "super::crawl_run::Entity")]
+    CrawlRun,
+    #[sea_orm(has_many = "super::crawl_schedule::Entity")]
+    CrawlSchedule,
     #[sea_orm(has_many = "super::project_version::Entity")]
     ProjectVersion,
     #[sea_orm(has_many = "super::role_permission::Entity")]
@@ -23,6 +29,24 @@ pub enum Relation {
     UserPermission,
 }
 
+impl Related<super::crawl_credential::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CrawlCredential.def()
+    }
+}
+
+impl Related<super::crawl_run::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CrawlRun.def()
+    }
+}
+
+impl Related<super::crawl_schedule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CrawlSchedule.def()
+    }
+}
+
 impl Related<super::project_version::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ProjectVersion.def()
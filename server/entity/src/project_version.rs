@@ -15,6 +15,8 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::document_folder::Entity")]
+    DocumentFolder,
     #[sea_orm(has_many = "super::document_version::Entity")]
     DocumentVersion,
     #[sea_orm(
@@ -27,6 +29,12 @@ pub enum Relation {
     Project,
 }
 
+impl Related<super::document_folder::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DocumentFolder.def()
+    }
+}
+
 impl Related<super::document_version::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::DocumentVersion.def()
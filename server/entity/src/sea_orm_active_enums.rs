@@ -3,6 +3,46 @@
 use sea_orm::entity::prelude::*;
 use serde::Serialize;
 
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "crawl_page_outcome_enum"
+)]
+pub enum CrawlPageOutcome {
+    #[sea_orm(string_value = "duplicate")]
+    Duplicate,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "fetched")]
+    Fetched,
+    #[sea_orm(string_value = "http_error")]
+    HttpError,
+    #[sea_orm(string_value = "not_html")]
+    NotHtml,
+    #[sea_orm(string_value = "not_modified")]
+    NotModified,
+    #[sea_orm(string_value = "parse_error")]
+    ParseError,
+    #[sea_orm(string_value = "skipped_by_depth")]
+    SkippedByDepth,
+    #[sea_orm(string_value = "skipped_by_robots")]
+    SkippedByRobots,
+}
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "crawl_run_status_enum"
+)]
+pub enum CrawlRunStatus {
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "running")]
+    Running,
+    #[sea_orm(string_value = "succeeded")]
+    Succeeded,
+}
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "permission_enum")]
 pub enum PermissionEnum {
@@ -4,6 +4,13 @@ pub mod m20240422_000001_create_tables;
 pub mod m20240510_000002_create_embedding_table;
 pub mod m20240516_000003_add_finalized_column;
 pub mod m20240516_000004_alter_role_permission_role_id_type;
+pub mod m20240520_000005_add_document_cache_columns;
+pub mod m20240524_000006_create_crawl_schedule_tables;
+pub mod m20240527_000007_create_crawl_run_page_table;
+pub mod m20240529_000008_create_crawl_credential_table;
+pub mod m20240603_000009_add_document_metadata_column;
+pub mod m20240605_000010_create_document_folder_table;
+pub mod m20240607_000011_add_document_version_tags_column;
 
 pub struct Migrator;
 
@@ -15,6 +22,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20240510_000002_create_embedding_table::Migration),
             Box::new(m20240516_000003_add_finalized_column::Migration),
             Box::new(m20240516_000004_alter_role_permission_role_id_type::Migration),
+            Box::new(m20240520_000005_add_document_cache_columns::Migration),
+            Box::new(m20240524_000006_create_crawl_schedule_tables::Migration),
+            Box::new(m20240527_000007_create_crawl_run_page_table::Migration),
+            Box::new(m20240529_000008_create_crawl_credential_table::Migration),
+            Box::new(m20240603_000009_add_document_metadata_column::Migration),
+            Box::new(m20240605_000010_create_document_folder_table::Migration),
+            Box::new(m20240607_000011_add_document_version_tags_column::Migration),
         ]
     }
 }
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240422_000001_create_tables::Document;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Document::Table)
+                .add_column(ColumnDef::new(DocumentCache::Etag).string())
+                .add_column(ColumnDef::new(DocumentCache::LastModified).string())
+                .add_column(ColumnDef::new(DocumentCache::ContentHash).string())
+                .to_owned()
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Document::Table)
+                .drop_column(DocumentCache::Etag)
+                .drop_column(DocumentCache::LastModified)
+                .drop_column(DocumentCache::ContentHash)
+                .to_owned()
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum DocumentCache {
+    Etag,
+    LastModified,
+    ContentHash,
+}
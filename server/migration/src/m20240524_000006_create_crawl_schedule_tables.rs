@@ -0,0 +1,162 @@
+use sea_orm_migration::{prelude::*, sea_orm::{EnumIter, Iterable}, sea_query::extension::postgres::Type};
+
+use crate::m20240422_000001_create_tables::Project;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const CURRENT_TIMESTAMP: sea_query::expr::SimpleExpr = SimpleExpr::Keyword(Keyword::CurrentTimestamp);
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        //=======================//
+        // CRAWL RUN STATUS ENUM //
+        //=======================//
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(CrawlRunStatusEnum)
+                    .values(CrawlRunStatus::iter())
+                    .to_owned()
+            )
+            .await?;
+
+        //======================//
+        // CRAWL SCHEDULE TABLE //
+        //======================//
+        manager
+            .create_table(
+                Table::create()
+                    .table(CrawlSchedule::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CrawlSchedule::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CrawlSchedule::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(CrawlSchedule::Url).string().not_null())
+                    .col(ColumnDef::new(CrawlSchedule::MaxDepth).integer())
+                    .col(ColumnDef::new(CrawlSchedule::Cron).string().not_null())
+                    .col(ColumnDef::new(CrawlSchedule::Finalize).boolean().not_null().default(false))
+                    .col(ColumnDef::new(CrawlSchedule::Enabled).boolean().not_null().default(true))
+                    .col(ColumnDef::new(CrawlSchedule::LastRunAt).timestamp())
+                    .col(ColumnDef::new(CrawlSchedule::CreatedAt).timestamp().not_null().default(CURRENT_TIMESTAMP))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_crawl_schedule_project_id")
+                            .from(CrawlSchedule::Table, CrawlSchedule::ProjectId)
+                            .to(Project::Table, Project::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        //=================//
+        // CRAWL RUN TABLE //
+        //=================//
+        manager
+            .create_table(
+                Table::create()
+                    .table(CrawlRun::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CrawlRun::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CrawlRun::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(CrawlRun::ScheduleId).integer())
+                    .col(ColumnDef::new(CrawlRun::Url).string().not_null())
+                    .col(
+                        ColumnDef::new(CrawlRun::Status)
+                            .enumeration(CrawlRunStatusEnum, CrawlRunStatus::iter())
+                            .not_null()
+                    )
+                    .col(ColumnDef::new(CrawlRun::Summary).text())
+                    .col(ColumnDef::new(CrawlRun::Error).text())
+                    .col(ColumnDef::new(CrawlRun::Version).integer())
+                    .col(ColumnDef::new(CrawlRun::StartedAt).timestamp().not_null().default(CURRENT_TIMESTAMP))
+                    .col(ColumnDef::new(CrawlRun::FinishedAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_crawl_run_project_id")
+                            .from(CrawlRun::Table, CrawlRun::ProjectId)
+                            .to(Project::Table, Project::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_crawl_run_schedule_id")
+                            .from(CrawlRun::Table, CrawlRun::ScheduleId)
+                            .to(CrawlSchedule::Table, CrawlSchedule::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CrawlRun::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(CrawlSchedule::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(CrawlRunStatusEnum).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrawlSchedule {
+    Table,
+    Id,
+    ProjectId,
+    Url,
+    MaxDepth,
+    Cron,
+    Finalize,
+    Enabled,
+    LastRunAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum CrawlRun {
+    Table,
+    Id,
+    ProjectId,
+    ScheduleId,
+    Url,
+    Status,
+    Summary,
+    Error,
+    Version,
+    StartedAt,
+    FinishedAt,
+}
+
+#[derive(DeriveIden)]
+struct CrawlRunStatusEnum;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum CrawlRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
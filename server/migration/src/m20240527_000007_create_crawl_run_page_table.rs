@@ -0,0 +1,107 @@
+use sea_orm_migration::{prelude::*, sea_orm::{EnumIter, Iterable}, sea_query::extension::postgres::Type};
+
+use crate::m20240524_000006_create_crawl_schedule_tables::CrawlRun;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        //=========================//
+        // CRAWL PAGE OUTCOME ENUM //
+        //=========================//
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(CrawlPageOutcomeEnum)
+                    .values(CrawlPageOutcome::iter())
+                    .to_owned()
+            )
+            .await?;
+
+        //======================//
+        // CRAWL RUN PAGE TABLE //
+        //======================//
+        manager
+            .create_table(
+                Table::create()
+                    .table(CrawlRunPage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CrawlRunPage::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CrawlRunPage::CrawlRunId).integer().not_null())
+                    .col(ColumnDef::new(CrawlRunPage::Url).text().not_null())
+                    .col(ColumnDef::new(CrawlRunPage::Depth).integer().not_null())
+                    .col(
+                        ColumnDef::new(CrawlRunPage::Outcome)
+                            .enumeration(CrawlPageOutcomeEnum, CrawlPageOutcome::iter())
+                            .not_null()
+                    )
+                    .col(ColumnDef::new(CrawlRunPage::Detail).text())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_crawl_run_page_crawl_run_id")
+                            .from(CrawlRunPage::Table, CrawlRunPage::CrawlRunId)
+                            .to(CrawlRun::Table, CrawlRun::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_crawl_run_page_crawl_run_id")
+                    .table(CrawlRunPage::Table)
+                    .col(CrawlRunPage::CrawlRunId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CrawlRunPage::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(CrawlPageOutcomeEnum).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CrawlRunPage {
+    Table,
+    Id,
+    CrawlRunId,
+    Url,
+    Depth,
+    Outcome,
+    Detail,
+}
+
+#[derive(DeriveIden)]
+struct CrawlPageOutcomeEnum;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum CrawlPageOutcome {
+    Fetched,
+    NotModified,
+    SkippedByRobots,
+    SkippedByDepth,
+    HttpError,
+    NotHtml,
+    Duplicate,
+    ParseError,
+    Failed,
+}
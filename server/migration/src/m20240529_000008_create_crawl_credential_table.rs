@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240422_000001_create_tables::Project;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const CURRENT_TIMESTAMP: sea_query::expr::SimpleExpr = SimpleExpr::Keyword(Keyword::CurrentTimestamp);
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        //========================//
+        // CRAWL CREDENTIAL TABLE //
+        //========================//
+        manager
+            .create_table(
+                Table::create()
+                    .table(CrawlCredential::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CrawlCredential::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CrawlCredential::ProjectId).integer().not_null())
+                    .col(ColumnDef::new(CrawlCredential::Host).string().not_null())
+                    .col(ColumnDef::new(CrawlCredential::Secret).text().not_null())
+                    .col(ColumnDef::new(CrawlCredential::CreatedAt).timestamp().not_null().default(CURRENT_TIMESTAMP))
+                    .col(ColumnDef::new(CrawlCredential::UpdatedAt).timestamp().not_null().default(CURRENT_TIMESTAMP))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_crawl_credential_project_id")
+                            .from(CrawlCredential::Table, CrawlCredential::ProjectId)
+                            .to(Project::Table, Project::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_crawl_credential_project_id_host")
+                    .table(CrawlCredential::Table)
+                    .col(CrawlCredential::ProjectId)
+                    .col(CrawlCredential::Host)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CrawlCredential::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum CrawlCredential {
+    Table,
+    Id,
+    ProjectId,
+    Host,
+    /// Encrypted JSON of the credentials
+    Secret,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240422_000001_create_tables::Document;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Document::Table)
+                .add_column(ColumnDef::new(DocumentMetadata::Metadata).json_binary())
+                .to_owned()
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Document::Table)
+                .drop_column(DocumentMetadata::Metadata)
+                .to_owned()
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum DocumentMetadata {
+    Metadata,
+}
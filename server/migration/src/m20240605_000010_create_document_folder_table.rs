@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240422_000001_create_tables::{DocumentVersion, ProjectVersion};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+
+        //=======================//
+        // DOCUMENT FOLDER TABLE //
+        //=======================//
+        manager
+            .create_table(
+                Table::create()
+                    .table(DocumentFolder::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(DocumentFolder::Id).integer().not_null().auto_increment())
+                    .col(ColumnDef::new(DocumentFolder::ProjectVersionProjectId).integer().not_null())
+                    .col(ColumnDef::new(DocumentFolder::ProjectVersionVersion).integer().not_null())
+                    .col(ColumnDef::new(DocumentFolder::Name).string().not_null())
+                    .col(ColumnDef::new(DocumentFolder::ParentId).integer())
+                    .col(ColumnDef::new(DocumentFolder::Position).integer().not_null().default(0))
+                    .primary_key(
+                        Index::create()
+                            .col(DocumentFolder::Id)
+                            .col(DocumentFolder::ProjectVersionProjectId)
+                            .col(DocumentFolder::ProjectVersionVersion)
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_document_folder_project_version")
+                            .from(DocumentFolder::Table, DocumentFolder::ProjectVersionProjectId)
+                            .to(ProjectVersion::Table, ProjectVersion::ProjectId)
+                            .from(DocumentFolder::Table, DocumentFolder::ProjectVersionVersion)
+                            .to(ProjectVersion::Table, ProjectVersion::Version)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        //===============================//
+        // DOCUMENT VERSION TREE COLUMNS //
+        //===============================//
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DocumentVersion::Table)
+                    .add_column(ColumnDef::new(DocumentVersionTree::FolderId).integer())
+                    .add_column(
+                        ColumnDef::new(DocumentVersionTree::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        //============================//
+        // DOCUMENT TREE FOREIGN KEYS //
+        //============================//
+        // Only the parent column is cleared when a folder is deleted, the project version
+        // columns are part of the primary keys
+        manager.get_connection().execute_unprepared(
+            r#"ALTER TABLE "document_folder" ADD CONSTRAINT "fk_document_folder_parent"
+            FOREIGN KEY ("parent_id", "project_version_project_id", "project_version_version")
+            REFERENCES "document_folder" ("id", "project_version_project_id", "project_version_version")
+            ON DELETE SET NULL ("parent_id")"#
+        ).await?;
+
+        manager.get_connection().execute_unprepared(
+            r#"ALTER TABLE "document_version" ADD CONSTRAINT "fk_document_version_folder"
+            FOREIGN KEY ("folder_id", "project_version_project_id", "project_version_version")
+            REFERENCES "document_folder" ("id", "project_version_project_id", "project_version_version")
+            ON DELETE SET NULL ("folder_id")"#
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(DocumentVersion::Table)
+                    .drop_column(DocumentVersionTree::FolderId)
+                    .drop_column(DocumentVersionTree::Position)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(DocumentFolder::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum DocumentFolder {
+    Table,
+    Id,
+    ProjectVersionProjectId,
+    ProjectVersionVersion,
+    Name,
+    ParentId,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum DocumentVersionTree {
+    FolderId,
+    Position,
+}
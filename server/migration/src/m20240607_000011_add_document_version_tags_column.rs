@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240422_000001_create_tables::DocumentVersion;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(DocumentVersion::Table)
+                .add_column(
+                    ColumnDef::new(DocumentVersionTags::Tags)
+                        .json_binary()
+                        .not_null()
+                        .default(Expr::cust("'[]'::jsonb"))
+                )
+                .to_owned()
+        ).await?;
+
+        // Documents start with the tags of their front matter
+        manager.get_connection().execute_unprepared(
+            r#"UPDATE "document_version" SET "tags" = "document"."metadata" -> 'tags'
+            FROM "document"
+            WHERE "document"."id" = "document_version"."document_id"
+            AND jsonb_typeof("document"."metadata" -> 'tags') = 'array'"#
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(DocumentVersion::Table)
+                .drop_column(DocumentVersionTags::Tags)
+                .to_owned()
+        ).await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum DocumentVersionTags {
+    Tags,
+}
@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use entity::crawl_credential::{ActiveModel, Column, Entity, Model};
+use migration::{
+    sea_orm::{prelude::*, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set},
+    OnConflict,
+};
+
+pub struct CrawlCredentialRepo<'a>(&'a DatabaseConnection);
+
+impl<'a> CrawlCredentialRepo<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self(db)
+    }
+
+    pub async fn all(&self, project_id: i32) -> Result<Vec<Model>> {
+        Entity::find()
+            .filter(Column::ProjectId.eq(project_id))
+            .order_by_asc(Column::Host)
+            .all(self.0)
+            .await
+            .context("Failed to get crawl credentials")
+    }
+
+    pub async fn find(&self, project_id: i32, host: &str) -> Result<Option<Model>> {
+        Entity::find()
+            .filter(Column::ProjectId.eq(project_id))
+            .filter(Column::Host.eq(host))
+            .one(self.0)
+            .await
+            .context("Failed to get crawl credentials")
+    }
+
+    /// Save the encrypted credentials for a host, replacing the ones saved before
+    pub async fn save(&self, project_id: i32, host: &str, secret: &str) -> Result<()> {
+        let model = ActiveModel {
+            project_id: Set(project_id),
+            host: Set(host.to_owned()),
+            secret: Set(secret.to_owned()),
+            updated_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([Column::ProjectId, Column::Host])
+                    .update_columns([Column::Secret, Column::UpdatedAt])
+                    .to_owned(),
+            )
+            .exec(self.0)
+            .await
+            .context("Failed to save crawl credentials")?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: i32, project_id: i32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::Id.eq(id))
+            .filter(Column::ProjectId.eq(project_id))
+            .exec(self.0)
+            .await
+            .context("Failed to delete crawl credentials")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use entity::{
+    crawl_run::{ActiveModel, Column, Entity, Model},
+    crawl_run_page,
+    sea_orm_active_enums::{CrawlPageOutcome, CrawlRunStatus},
+};
+use migration::sea_orm::{
+    prelude::*, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+
+use crate::web_crawler::outcome::{PageOutcome, PageReport};
+
+/// Rows inserted per statement, to stay below the bind parameter limit
+const PAGE_BATCH_SIZE: usize = 1000;
+
+pub struct CrawlRunRepo<'a>(&'a DatabaseConnection);
+
+impl<'a> CrawlRunRepo<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self(db)
+    }
+
+    /// Get the latest runs of a project, newest first
+    pub async fn latest(&self, project_id: i32, limit: u64) -> Result<Vec<Model>> {
+        Entity::find()
+            .filter(Column::ProjectId.eq(project_id))
+            .order_by_desc(Column::StartedAt)
+            .limit(limit)
+            .all(self.0)
+            .await
+            .context("Failed to get crawl runs")
+    }
+
+    pub async fn start(&self, project_id: i32, schedule_id: Option<i32>, url: &str) -> Result<i32> {
+        let model = ActiveModel {
+            project_id: Set(project_id),
+            schedule_id: Set(schedule_id),
+            url: Set(url.to_owned()),
+            status: Set(CrawlRunStatus::Running),
+            ..Default::default()
+        };
+
+        let res = Entity::insert(model)
+            .exec(self.0)
+            .await
+            .context("Failed to create crawl run")?;
+
+        Ok(res.last_insert_id)
+    }
+
+    pub async fn succeed(&self, id: i32, summary: &str, version: Option<i32>) -> Result<()> {
+        let model = ActiveModel {
+            id: Set(id),
+            status: Set(CrawlRunStatus::Succeeded),
+            summary: Set(Some(summary.to_owned())),
+            version: Set(version),
+            finished_at: Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        };
+
+        model
+            .update(self.0)
+            .await
+            .context("Failed to update crawl run")?;
+
+        Ok(())
+    }
+
+    pub async fn fail(&self, id: i32, error: &str) -> Result<()> {
+        let model = ActiveModel {
+            id: Set(id),
+            status: Set(CrawlRunStatus::Failed),
+            error: Set(Some(error.to_owned())),
+            finished_at: Set(Some(Utc::now().naive_utc())),
+            ..Default::default()
+        };
+
+        model
+            .update(self.0)
+            .await
+            .context("Failed to update crawl run")?;
+
+        Ok(())
+    }
+
+    /// Mark runs that were still running as failed, e.g. after a server restart
+    pub async fn fail_unfinished(&self, error: &str) -> Result<()> {
+        Entity::update_many()
+            .col_expr(Column::Status, Expr::value(CrawlRunStatus::Failed))
+            .col_expr(Column::Error, Expr::value(error))
+            .col_expr(Column::FinishedAt, Expr::value(Utc::now().naive_utc()))
+            .filter(Column::Status.eq(CrawlRunStatus::Running))
+            .exec(self.0)
+            .await
+            .context("Failed to update crawl runs")?;
+
+        Ok(())
+    }
+
+    /// Get a run of the project, or its latest run if no id is given
+    pub async fn find(&self, project_id: i32, id: Option<i32>) -> Result<Option<Model>> {
+        let query = Entity::find().filter(Column::ProjectId.eq(project_id));
+        let query = match id {
+            Some(id) => query.filter(Column::Id.eq(id)),
+            None => query.order_by_desc(Column::StartedAt),
+        };
+
+        query.one(self.0).await.context("Failed to get crawl run")
+    }
+
+    /// Save the outcome of every url of a run
+    pub async fn add_pages(&self, id: i32, reports: &[PageReport]) -> Result<()> {
+        for batch in reports.chunks(PAGE_BATCH_SIZE) {
+            let models = batch.iter().map(|report| crawl_run_page::ActiveModel {
+                crawl_run_id: Set(id),
+                url: Set(report.url.to_string()),
+                depth: Set(report.depth.try_into().unwrap_or(i32::MAX)),
+                outcome: Set(Self::outcome(&report.outcome)),
+                detail: Set(report.outcome.detail()),
+                ..Default::default()
+            });
+
+            crawl_run_page::Entity::insert_many(models)
+                .exec(self.0)
+                .await
+                .context("Failed to save crawl run pages")?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the outcome of every url of a run, ordered by url
+    pub async fn pages(&self, id: i32) -> Result<Vec<crawl_run_page::Model>> {
+        crawl_run_page::Entity::find()
+            .filter(crawl_run_page::Column::CrawlRunId.eq(id))
+            .order_by_asc(crawl_run_page::Column::Url)
+            .all(self.0)
+            .await
+            .context("Failed to get crawl run pages")
+    }
+
+    fn outcome(outcome: &PageOutcome) -> CrawlPageOutcome {
+        match outcome {
+            PageOutcome::Fetched => CrawlPageOutcome::Fetched,
+            PageOutcome::NotModified => CrawlPageOutcome::NotModified,
+            PageOutcome::SkippedByRobots => CrawlPageOutcome::SkippedByRobots,
+            PageOutcome::SkippedByDepth => CrawlPageOutcome::SkippedByDepth,
+            PageOutcome::HttpError(_) => CrawlPageOutcome::HttpError,
+            PageOutcome::Unsupported(_) => CrawlPageOutcome::NotHtml,
+            PageOutcome::Duplicate(_) => CrawlPageOutcome::Duplicate,
+            PageOutcome::ParseError(_) => CrawlPageOutcome::ParseError,
+            PageOutcome::Failed(_) => CrawlPageOutcome::Failed,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use entity::{
+    crawl_run,
+    crawl_schedule::{ActiveModel, Column, Entity, Model},
+    project,
+    sea_orm_active_enums::CrawlRunStatus,
+};
+use migration::sea_orm::{
+    prelude::*, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+
+pub struct CrawlScheduleRepo<'a>(&'a DatabaseConnection);
+
+impl<'a> CrawlScheduleRepo<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self(db)
+    }
+
+    pub async fn all(&self, project_id: i32) -> Result<Vec<Model>> {
+        Entity::find()
+            .filter(Column::ProjectId.eq(project_id))
+            .order_by_asc(Column::Id)
+            .all(self.0)
+            .await
+            .context("Failed to get crawl schedules")
+    }
+
+    pub async fn all_enabled(&self) -> Result<Vec<Model>> {
+        Entity::find()
+            .filter(Column::Enabled.eq(true))
+            .all(self.0)
+            .await
+            .context("Failed to get enabled crawl schedules")
+    }
+
+    pub async fn create(
+        &self,
+        project_id: i32,
+        url: &str,
+        max_depth: Option<i32>,
+        cron: &str,
+        finalize: bool,
+    ) -> Result<i32> {
+        let model = ActiveModel {
+            project_id: Set(project_id),
+            url: Set(url.to_owned()),
+            max_depth: Set(max_depth),
+            cron: Set(cron.to_owned()),
+            finalize: Set(finalize),
+            ..Default::default()
+        };
+
+        let res = Entity::insert(model)
+            .exec(self.0)
+            .await
+            .context("Failed to create crawl schedule")?;
+
+        Ok(res.last_insert_id)
+    }
+
+    pub async fn set_enabled(&self, id: i32, project_id: i32, enabled: bool) -> Result<()> {
+        Entity::update_many()
+            .col_expr(Column::Enabled, Expr::value(enabled))
+            .filter(Column::Id.eq(id))
+            .filter(Column::ProjectId.eq(project_id))
+            .exec(self.0)
+            .await
+            .context("Failed to update crawl schedule")?;
+
+        Ok(())
+    }
+
+    /// Claim a due schedule by starting a run of it, returning the id of the run. Returns
+    /// `None` when the schedule was already claimed since it was read, e.g. by another
+    /// server, or when a crawl of the project is still running. The project is locked
+    /// meanwhile, so only one crawl of a project is started at a time.
+    pub async fn claim(&self, schedule: &Model, now: NaiveDateTime) -> Result<Option<i32>> {
+        let tnx = self.0.begin().await?;
+
+        project::Entity::find_by_id(schedule.project_id)
+            .lock_exclusive()
+            .one(&tnx)
+            .await
+            .context("Failed to lock project")?;
+
+        let running = crawl_run::Entity::find()
+            .filter(crawl_run::Column::ProjectId.eq(schedule.project_id))
+            .filter(crawl_run::Column::Status.eq(CrawlRunStatus::Running))
+            .count(&tnx)
+            .await
+            .context("Failed to get running crawl runs")?;
+        if running > 0 {
+            tnx.rollback().await?;
+            return Ok(None);
+        }
+
+        // Only move the last run forward if it is still the one the schedule was due for
+        let last_run_at = match schedule.last_run_at {
+            Some(last_run_at) => Column::LastRunAt.eq(last_run_at),
+            None => Column::LastRunAt.is_null(),
+        };
+        let res = Entity::update_many()
+            .col_expr(Column::LastRunAt, Expr::value(now))
+            .filter(Column::Id.eq(schedule.id))
+            .filter(last_run_at)
+            .exec(&tnx)
+            .await
+            .context("Failed to update crawl schedule")?;
+        if res.rows_affected == 0 {
+            tnx.rollback().await?;
+            return Ok(None);
+        }
+
+        let run = crawl_run::ActiveModel {
+            project_id: Set(schedule.project_id),
+            schedule_id: Set(Some(schedule.id)),
+            url: Set(schedule.url.to_owned()),
+            status: Set(CrawlRunStatus::Running),
+            ..Default::default()
+        };
+        let run_id = crawl_run::Entity::insert(run)
+            .exec(&tnx)
+            .await
+            .context("Failed to create crawl run")?
+            .last_insert_id;
+
+        tnx.commit().await?;
+        Ok(Some(run_id))
+    }
+
+    pub async fn delete(&self, id: i32, project_id: i32) -> Result<()> {
+        Entity::delete_many()
+            .filter(Column::Id.eq(id))
+            .filter(Column::ProjectId.eq(project_id))
+            .exec(self.0)
+            .await
+            .context("Failed to delete crawl schedule")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use entity::{
+    document_folder::{ActiveModel, Column, Entity, Model},
+    document_version,
+};
+use migration::sea_orm::{
+    ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::server_functions::models::TreeNode;
+
+use super::Repo;
+
+pub struct DocumentFolderRepo<'a>(&'a DatabaseConnection);
+
+/// The folder of a document and its position among the children of the folder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub folder_id: Option<i32>,
+    pub position: i32,
+}
+
+impl<'a> DocumentFolderRepo<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self(db)
+    }
+
+    pub async fn all(
+        &self,
+        project_id: i32,
+        version: i32,
+        tnx: Option<&DatabaseTransaction>,
+    ) -> Result<Vec<Model>> {
+        let query = Entity::find()
+            .filter(Column::ProjectVersionProjectId.eq(project_id))
+            .filter(Column::ProjectVersionVersion.eq(version))
+            .order_by_asc(Column::Position);
+
+        let res = match tnx {
+            Some(tnx) => query.all(tnx).await,
+            None => query.all(self.0).await,
+        }
+        .context("Failed to get document folders")?;
+
+        Ok(res)
+    }
+
+    /// Create a folder at the end of its parent folder, returning the new project version if
+    /// the project version was finalized
+    pub async fn create(
+        &self,
+        project_id: i32,
+        version: i32,
+        name: &str,
+        parent_id: Option<i32>,
+    ) -> Result<Option<i32>> {
+        let (tnx, new_version) = self
+            .0
+            .projects_versions()
+            .begin_change(project_id, version)
+            .await?;
+        let version = new_version.unwrap_or(version);
+
+        let mut tree = Tree::load(&tnx, project_id, version).await?;
+        if let Some(parent_id) = parent_id {
+            if !tree.folders.iter().any(|folder| folder.id == parent_id) {
+                tnx.rollback().await?;
+                bail!("Folder not found");
+            }
+        }
+        tree.create_folder(&tnx, name, parent_id).await?;
+
+        tnx.commit().await?;
+        Ok(new_version)
+    }
+
+    /// Move a document or folder into `parent_id` before the child at `position` among the
+    /// current children of the parent, returning the new project version if the project version was finalized
+    pub async fn move_node(
+        &self,
+        project_id: i32,
+        version: i32,
+        node: TreeNode,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Result<Option<i32>> {
+        let (tnx, new_version) = self
+            .0
+            .projects_versions()
+            .begin_change(project_id, version)
+            .await?;
+        let version = new_version.unwrap_or(version);
+
+        let tree = Tree::load(&tnx, project_id, version).await?;
+        let Some(children) = tree.move_node(node, parent_id, position) else {
+            tnx.rollback().await?;
+            bail!("The node cannot be moved there");
+        };
+
+        for (position, child) in children.into_iter().enumerate() {
+            tree.set_parent(&tnx, child, parent_id, position as i32)
+                .await?;
+        }
+
+        tnx.commit().await?;
+        Ok(new_version)
+    }
+
+    /// Delete a folder, its children are moved to the parent of the folder. Returns the new
+    /// project version if the project version was finalized
+    pub async fn delete(&self, id: i32, project_id: i32, version: i32) -> Result<Option<i32>> {
+        let (tnx, new_version) = self
+            .0
+            .projects_versions()
+            .begin_change(project_id, version)
+            .await?;
+        let version = new_version.unwrap_or(version);
+
+        let tree = Tree::load(&tnx, project_id, version).await?;
+        let Some(folder) = tree.folders.iter().find(|folder| folder.id == id) else {
+            tnx.rollback().await?;
+            bail!("Folder not found");
+        };
+
+        let positions = tree.next_position(folder.parent_id)..;
+        for (position, child) in positions.zip(tree.children(Some(id))) {
+            tree.set_parent(&tnx, child, folder.parent_id, position)
+                .await?;
+        }
+
+        Entity::delete_by_id((id, project_id, version))
+            .exec(&tnx)
+            .await
+            .context("Failed to delete document folder")?;
+
+        tnx.commit().await?;
+        Ok(new_version)
+    }
+
+    /// Find the folder and position of new documents from the folder path of each, creating
+    /// the folders that do not exist yet. The documents are placed after the existing
+    /// children of their folder.
+    pub async fn place(
+        &self,
+        project_id: i32,
+        version: i32,
+        paths: Vec<Vec<String>>,
+        tnx: &DatabaseTransaction,
+    ) -> Result<Vec<Placement>> {
+        let mut tree = Tree::load(tnx, project_id, version).await?;
+        let mut placements = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            let mut parent_id = None;
+            for name in path {
+                let existing = tree
+                    .folders
+                    .iter()
+                    .find(|folder| folder.parent_id == parent_id && folder.name == name)
+                    .map(|folder| folder.id);
+                parent_id = Some(match existing {
+                    Some(id) => id,
+                    None => tree.create_folder(tnx, &name, parent_id).await?,
+                });
+            }
+
+            let position = tree.next_position(parent_id);
+            tree.positions.insert(parent_id, position + 1);
+            placements.push(Placement {
+                folder_id: parent_id,
+                position,
+            });
+        }
+
+        Ok(placements)
+    }
+
+    pub async fn bump_project_version(
+        &self,
+        project_id: i32,
+        version: i32,
+        tnx: &DatabaseTransaction,
+    ) -> Result<()> {
+        let models = self
+            .all(project_id, version, Some(tnx))
+            .await?
+            .into_iter()
+            .map(|model| ActiveModel {
+                id: Set(model.id),
+                project_version_project_id: Set(project_id),
+                project_version_version: Set(version + 1),
+                name: Set(model.name),
+                parent_id: Set(model.parent_id),
+                position: Set(model.position),
+            })
+            .collect::<Vec<_>>();
+
+        if models.is_empty() {
+            return Ok(());
+        }
+
+        Entity::insert_many(models)
+            .exec(tnx)
+            .await
+            .context("Failed to bump project version for document folders")?;
+
+        Ok(())
+    }
+}
+
+/// The folders and documents of a project version
+struct Tree {
+    project_id: i32,
+    version: i32,
+    folders: Vec<Model>,
+    documents: Vec<document_version::Model>,
+    /// The next free position in a folder
+    positions: HashMap<Option<i32>, i32>,
+}
+
+impl Tree {
+    async fn load(tnx: &DatabaseTransaction, project_id: i32, version: i32) -> Result<Self> {
+        let folders = Entity::find()
+            .filter(Column::ProjectVersionProjectId.eq(project_id))
+            .filter(Column::ProjectVersionVersion.eq(version))
+            .all(tnx)
+            .await
+            .context("Failed to get document folders")?;
+
+        let documents = document_version::Entity::find()
+            .filter(document_version::Column::ProjectVersionProjectId.eq(project_id))
+            .filter(document_version::Column::ProjectVersionVersion.eq(version))
+            .all(tnx)
+            .await
+            .context("Failed to get document versions")?;
+
+        let mut positions = HashMap::new();
+        let nodes = folders
+            .iter()
+            .map(|folder| (folder.parent_id, folder.position))
+            .chain(documents.iter().map(|doc| (doc.folder_id, doc.position)));
+        for (parent_id, position) in nodes {
+            let next = positions.entry(parent_id).or_insert(0);
+            *next = (*next).max(position + 1);
+        }
+
+        Ok(Self {
+            project_id,
+            version,
+            folders,
+            documents,
+            positions,
+        })
+    }
+
+    fn next_position(&self, parent_id: Option<i32>) -> i32 {
+        self.positions.get(&parent_id).copied().unwrap_or_default()
+    }
+
+    /// The children of a folder in order, folders first when they have the same position
+    fn children(&self, parent_id: Option<i32>) -> Vec<TreeNode> {
+        let mut children = self
+            .folders
+            .iter()
+            .filter(|folder| folder.parent_id == parent_id)
+            .map(|folder| (folder.position, TreeNode::Folder(folder.id)))
+            .chain(
+                self.documents
+                    .iter()
+                    .filter(|doc| doc.folder_id == parent_id)
+                    .map(|doc| (doc.position, TreeNode::Document(doc.id))),
+            )
+            .collect::<Vec<_>>();
+        children.sort_by_key(|(position, _)| *position);
+
+        children.into_iter().map(|(_, node)| node).collect()
+    }
+
+    /// The new children of `parent_id` in order after moving `node` into it, `None` if the
+    /// node or the parent do not exist or a folder would be moved into itself
+    fn move_node(
+        &self,
+        node: TreeNode,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Option<Vec<TreeNode>> {
+        let exists = match node {
+            TreeNode::Folder(id) => self.folders.iter().any(|folder| folder.id == id),
+            TreeNode::Document(id) => self.documents.iter().any(|doc| doc.id == id),
+        };
+        if !exists {
+            return None;
+        }
+
+        let mut ancestor = parent_id;
+        while let Some(id) = ancestor {
+            if node == TreeNode::Folder(id) {
+                return None;
+            }
+            ancestor = self
+                .folders
+                .iter()
+                .find(|folder| folder.id == id)?
+                .parent_id;
+        }
+
+        let mut children = self.children(parent_id);
+        let mut position = position;
+        if let Some(index) = children.iter().position(|child| *child == node) {
+            children.remove(index);
+            // The position counts the node itself when it stays in the same folder
+            if index < position {
+                position -= 1;
+            }
+        }
+        children.insert(position.min(children.len()), node);
+
+        Some(children)
+    }
+
+    async fn create_folder(
+        &mut self,
+        tnx: &DatabaseTransaction,
+        name: &str,
+        parent_id: Option<i32>,
+    ) -> Result<i32> {
+        let position = self.next_position(parent_id);
+        let model = ActiveModel {
+            project_version_project_id: Set(self.project_id),
+            project_version_version: Set(self.version),
+            name: Set(name.to_owned()),
+            parent_id: Set(parent_id),
+            position: Set(position),
+            ..Default::default()
+        };
+
+        let (id, _, _) = Entity::insert(model)
+            .exec(tnx)
+            .await
+            .context("Failed to create document folder")?
+            .last_insert_id;
+
+        self.positions.insert(parent_id, position + 1);
+        self.folders.push(Model {
+            id,
+            project_version_project_id: self.project_id,
+            project_version_version: self.version,
+            name: name.to_owned(),
+            parent_id,
+            position,
+        });
+
+        Ok(id)
+    }
+
+    async fn set_parent(
+        &self,
+        tnx: &DatabaseTransaction,
+        node: TreeNode,
+        parent_id: Option<i32>,
+        position: i32,
+    ) -> Result<()> {
+        match node {
+            TreeNode::Folder(id) => {
+                Entity::update(ActiveModel {
+                    id: Set(id),
+                    project_version_project_id: Set(self.project_id),
+                    project_version_version: Set(self.version),
+                    parent_id: Set(parent_id),
+                    position: Set(position),
+                    ..Default::default()
+                })
+                .exec(tnx)
+                .await
+                .context("Failed to move document folder")?;
+            }
+            TreeNode::Document(id) => {
+                document_version::Entity::update(document_version::ActiveModel {
+                    id: Set(id),
+                    project_version_project_id: Set(self.project_id),
+                    project_version_version: Set(self.version),
+                    folder_id: Set(parent_id),
+                    position: Set(position),
+                    ..Default::default()
+                })
+                .exec(tnx)
+                .await
+                .context("Failed to move document")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+    use TreeNode::{Document, Folder};
+
+    /// A tree with the folder 1 and the documents 10 and 11 at the top level, and the
+    /// document 12 and the empty folder 2 in folder 1
+    #[fixture]
+    fn tree() -> Tree {
+        let folder = |id, parent_id, position| Model {
+            id,
+            project_version_project_id: 1,
+            project_version_version: 1,
+            name: format!("Folder {id}"),
+            parent_id,
+            position,
+        };
+        let document = |id, folder_id, position| document_version::Model {
+            id,
+            project_version_project_id: 1,
+            project_version_version: 1,
+            document_id: id,
+            folder_id,
+            position,
+            tags: serde_json::json!([]),
+        };
+
+        Tree {
+            project_id: 1,
+            version: 1,
+            folders: vec![folder(1, None, 0), folder(2, Some(1), 1)],
+            documents: vec![
+                document(11, None, 1),
+                document(10, None, 0),
+                document(12, Some(1), 0),
+            ],
+            positions: HashMap::new(),
+        }
+    }
+
+    #[rstest]
+    #[case(None, vec![Folder(1), Document(10), Document(11)])]
+    #[case(Some(1), vec![Document(12), Folder(2)])]
+    #[case(Some(2), vec![])]
+    fn test_children(tree: Tree, #[case] parent_id: Option<i32>, #[case] expected: Vec<TreeNode>) {
+        assert_eq!(tree.children(parent_id), expected);
+    }
+
+    #[rstest]
+    #[case(Document(11), None, 0, vec![Document(11), Folder(1), Document(10)])]
+    #[case(Document(10), None, 3, vec![Folder(1), Document(11), Document(10)])]
+    #[case(Document(10), None, 2, vec![Folder(1), Document(10), Document(11)])]
+    #[case(Document(11), Some(1), 1, vec![Document(12), Document(11), Folder(2)])]
+    #[case(Document(10), Some(2), 0, vec![Document(10)])]
+    #[case(Folder(2), None, 99, vec![Folder(1), Document(10), Document(11), Folder(2)])]
+    fn test_move_node(
+        tree: Tree,
+        #[case] node: TreeNode,
+        #[case] parent_id: Option<i32>,
+        #[case] position: usize,
+        #[case] expected: Vec<TreeNode>,
+    ) {
+        assert_eq!(tree.move_node(node, parent_id, position), Some(expected));
+    }
+
+    #[rstest]
+    #[case(Folder(1), Some(1))]
+    #[case(Folder(1), Some(2))]
+    #[case(Document(99), None)]
+    #[case(Document(10), Some(99))]
+    fn test_move_node_invalid(tree: Tree, #[case] node: TreeNode, #[case] parent_id: Option<i32>) {
+        assert_eq!(tree.move_node(node, parent_id, 0), None);
+    }
+}
@@ -1,24 +1,25 @@
 use std::vec;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use entity::{
     document::{ActiveModel, Column, Entity, Model, Relation},
     document_version, embedding, project_version,
 };
 use migration::{
     sea_orm::{
-        prelude::*, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QuerySelect,
-        Set, Statement, TransactionTrait,
+        prelude::*, DatabaseConnection, DatabaseTransaction, EntityTrait, IntoActiveModel,
+        QueryFilter, QuerySelect, Set, Statement, TransactionTrait,
     },
     JoinType, PostgresQueryBuilder, Query,
 };
 
 use crate::{
-    models::DocumentWithoutContent,
-    parsing::{Done, HtmlParser},
+    models::{CacheValidators, DocumentChanges, DocumentWithoutContent},
+    parsing::{folder_path, front_matter, Done, HtmlParser},
+    utils::hash::content_hash,
 };
 
-use super::Repo;
+use super::{document_version_repo::tags_json, Repo};
 
 pub struct DocumentRepo<'a>(&'a DatabaseConnection);
 
@@ -35,6 +36,10 @@ impl<'a> DocumentRepo<'a> {
     /// SELECT
     ///     "document_version"."id",
     ///     "document"."name",
+    ///     "document"."metadata",
+    ///     "document_version"."folder_id",
+    ///     "document_version"."position",
+    ///     "document_version"."tags",
     ///     COUNT("embedding"."id") <> 0 AS "is_embedded",
     ///     "project_version"."finalized" AS "is_finalized"
     /// FROM
@@ -54,6 +59,10 @@ impl<'a> DocumentRepo<'a> {
     /// GROUP BY
     ///     "document_version"."id",
     ///     "document"."name",
+    ///     "document"."metadata",
+    ///     "document_version"."folder_id",
+    ///     "document_version"."position",
+    ///     "document_version"."tags",
     ///     "project_version"."finalized"
     pub async fn all_only_id_and_name(
         &self,
@@ -64,6 +73,10 @@ impl<'a> DocumentRepo<'a> {
             .select_only()
             .column_as(document_version::Column::Id, "id")
             .column(Column::Name)
+            .column(Column::Metadata)
+            .column(document_version::Column::FolderId)
+            .column(document_version::Column::Position)
+            .column(document_version::Column::Tags)
             .column_as(embedding::Column::Id.count().ne(0), "is_embedded")
             .column_as(project_version::Column::Finalized, "is_finalized")
             .join(JoinType::InnerJoin, Relation::DocumentVersion.def())
@@ -85,6 +98,10 @@ impl<'a> DocumentRepo<'a> {
             .filter(document_version::Column::ProjectVersionVersion.eq(project_version))
             .group_by(document_version::Column::Id)
             .group_by(Column::Name)
+            .group_by(Column::Metadata)
+            .group_by(document_version::Column::FolderId)
+            .group_by(document_version::Column::Position)
+            .group_by(document_version::Column::Tags)
             .group_by(project_version::Column::Finalized)
             .into_model::<DocumentWithoutContent>()
             .all(self.0)
@@ -110,6 +127,29 @@ impl<'a> DocumentRepo<'a> {
         Ok(res)
     }
 
+    /// Get all documents of a project version that were imported from a source,
+    /// together with the id of their document version
+    pub async fn all_with_source(
+        &self,
+        project_id: i32,
+        project_version: i32,
+    ) -> Result<Vec<(i32, Model)>> {
+        let res = document_version::Entity::find()
+            .find_also_related(Entity)
+            .filter(document_version::Column::ProjectVersionProjectId.eq(project_id))
+            .filter(document_version::Column::ProjectVersionVersion.eq(project_version))
+            .filter(Column::Source.is_not_null())
+            .all(self.0)
+            .await?
+            .into_iter()
+            .filter_map(|(document_version, document)| {
+                document.map(|document| (document_version.id, document))
+            })
+            .collect();
+
+        Ok(res)
+    }
+
     pub async fn find_unembedded(
         &self,
         project_id: i32,
@@ -132,8 +172,60 @@ impl<'a> DocumentRepo<'a> {
         Ok(res)
     }
 
+    /// Fill the metadata of documents stored before the `metadata` column was added from
+    /// their front matter. Their versions without tags get the tags of the front matter.
+    pub async fn backfill_metadata(&self) -> Result<usize> {
+        let documents = Entity::find()
+            .filter(Column::Metadata.is_null())
+            .filter(Column::Content.starts_with("---"))
+            .all(self.0)
+            .await
+            .context("Failed to get documents without metadata")?;
+
+        let tnx = self.0.begin().await?;
+        let mut count = 0;
+
+        for document in documents {
+            let Some(metadata) = metadata(&document.content) else {
+                continue;
+            };
+
+            let tags = tags_json(front_matter_tags(&document.content));
+            let result = async {
+                ActiveModel {
+                    id: Set(document.id),
+                    metadata: Set(Some(metadata)),
+                    ..Default::default()
+                }
+                .update(&tnx)
+                .await?;
+
+                document_version::Entity::update_many()
+                    .col_expr(document_version::Column::Tags, Expr::value(tags))
+                    .filter(document_version::Column::DocumentId.eq(document.id))
+                    .filter(Expr::cust(r#""document_version"."tags" = '[]'::jsonb"#))
+                    .exec(&tnx)
+                    .await
+            }
+            .await;
+
+            if let Err(e) = result {
+                tnx.rollback().await?;
+                return Err(e).context("Failed to backfill document metadata");
+            }
+            count += 1;
+        }
+
+        tnx.commit().await?;
+        Ok(count)
+    }
+
     pub async fn create(&self, project_id: i32, name: &str, content: &str) -> Result<(i32, i32)> {
-        let project_version = self.0.projects_versions().find_latest(project_id).await?;
+        let project_version = self
+            .0
+            .projects_versions()
+            .find_latest(project_id, None)
+            .await?;
 
         let tnx = self.0.begin().await?;
 
@@ -166,6 +258,8 @@ impl<'a> DocumentRepo<'a> {
         let model = ActiveModel {
             name: Set(name.to_owned()),
             content: Set(content.to_owned()),
+            content_hash: Set(Some(content_hash(content))),
+            metadata: Set(metadata(content)),
             ..Default::default()
         };
 
@@ -173,6 +267,18 @@ impl<'a> DocumentRepo<'a> {
 
         let document_id = res.last_insert_id;
 
+        // New documents are added at the end of the top level of the tree
+        let placement = self
+            .0
+            .document_folders()
+            .place(
+                project_version_id,
+                project_version_version,
+                vec![vec![]],
+                &tnx,
+            )
+            .await?[0];
+
         let doc_version_id = match self
             .0
             .documents_versions()
@@ -180,6 +286,8 @@ impl<'a> DocumentRepo<'a> {
                 project_version_id,
                 project_version_version,
                 document_id,
+                placement,
+                front_matter_tags(content),
                 Some(&tnx),
             )
             .await
@@ -197,36 +305,102 @@ impl<'a> DocumentRepo<'a> {
         Ok((doc_version_id, project_version_version))
     }
 
+    /// Create documents in the latest project version, returning the version they were added to
+    ///
+    /// If the latest project version is finalized, a new version is created first
     pub async fn create_many_from_documents(
         &self,
         project_id: i32,
         data: Vec<HtmlParser<Done>>,
-    ) -> Result<()> {
-        let project_version = self.0.projects_versions().find_latest(project_id).await?;
-
+    ) -> Result<i32> {
         let tnx = self.0.begin().await?;
 
-        let (project_version_project_id, project_version_version) = match project_version {
-            Some(version) => (version.project_id, version.version),
-            None => {
-                self.0
-                    .projects_versions()
-                    .create(project_id, Some(&tnx))
-                    .await?
+        match self
+            .create_many_in_transaction(&tnx, project_id, data)
+            .await
+        {
+            Ok(version) => {
+                tnx.commit().await?;
+                Ok(version)
             }
-        };
+            Err(e) => {
+                tnx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn create_many_in_transaction(
+        &self,
+        tnx: &DatabaseTransaction,
+        project_id: i32,
+        data: Vec<HtmlParser<Done>>,
+    ) -> Result<i32> {
+        let project_version = self
+            .0
+            .projects_versions()
+            .find_latest(project_id, Some(tnx))
+            .await?;
+
+        let (mut project_version_project_id, mut project_version_version, finalized) =
+            match project_version {
+                Some(version) => (version.project_id, version.version, version.finalized),
+                None => {
+                    let result = self
+                        .0
+                        .projects_versions()
+                        .create(project_id, Some(tnx))
+                        .await?;
+                    (result.0, result.1, false)
+                }
+            };
+
+        if data.is_empty() {
+            return Ok(project_version_version);
+        }
+
+        if finalized {
+            let old_version = project_version_version.to_owned();
+            (project_version_project_id, project_version_version) = self
+                .0
+                .projects_versions()
+                .create(project_id, Some(tnx))
+                .await?;
+            self.0
+                .documents_versions()
+                .bump_project_version(project_id, old_version, vec![], tnx)
+                .await?;
+        }
+
+        let paths = data
+            .iter()
+            .map(|doc| folder_path(doc.name(), doc.source()))
+            .collect();
+        let tags = data
+            .iter()
+            .map(|doc| front_matter_tags(doc.content()))
+            .collect::<Vec<_>>();
 
         let mut builder = Query::insert();
-        let mut builder =
-            builder
-                .into_table(Entity)
-                .columns(vec![Column::Name, Column::Content, Column::Source]);
+        let mut builder = builder.into_table(Entity).columns(vec![
+            Column::Name,
+            Column::Content,
+            Column::Source,
+            Column::Etag,
+            Column::LastModified,
+            Column::ContentHash,
+            Column::Metadata,
+        ]);
 
         for doc in data {
             builder = builder.values_panic(vec![
                 doc.name().into(),
                 doc.content().into(),
                 doc.source().into(),
+                doc.validators().etag().into(),
+                doc.validators().last_modified().into(),
+                content_hash(doc.content()).into(),
+                metadata(doc.content()).into(),
             ]);
         }
 
@@ -247,19 +421,116 @@ impl<'a> DocumentRepo<'a> {
 
         let document_ids = ids.into_iter().collect::<Result<Vec<_>, _>>()?;
 
+        // Fill the tree from the paths of the imported files and the urls of crawled pages
+        let placements = self
+            .0
+            .document_folders()
+            .place(
+                project_version_project_id,
+                project_version_version,
+                paths,
+                tnx,
+            )
+            .await?;
+
         self.0
             .documents_versions()
             .create_many(
                 project_version_project_id,
                 project_version_version,
-                document_ids,
-                Some(&tnx),
+                document_ids
+                    .into_iter()
+                    .zip(placements)
+                    .zip(tags)
+                    .map(|((document_id, placement), tags)| (document_id, placement, tags))
+                    .collect(),
+                Some(tnx),
             )
             .await?;
 
-        tnx.commit().await?;
+        Ok(project_version_version)
+    }
+
+    /// Apply the changes found by a re-import of a source to the latest project version
+    ///
+    /// Changed documents are updated like in [`DocumentRepo::update`] and removed documents
+    /// deleted like in [`DocumentRepo::delete`], so a finalized version is only bumped once
+    /// something changes. All changes are applied in one transaction, so a failure leaves the
+    /// project as it was. Returns the new project version if one was created.
+    pub async fn apply_changes(
+        &self,
+        project_id: i32,
+        changes: DocumentChanges,
+    ) -> Result<Option<i32>> {
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        let tnx = self.0.begin().await?;
+
+        match self
+            .apply_changes_in_transaction(&tnx, project_id, changes)
+            .await
+        {
+            Ok(new_version) => {
+                tnx.commit().await?;
+                Ok(new_version)
+            }
+            Err(e) => {
+                tnx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn apply_changes_in_transaction(
+        &self,
+        tnx: &DatabaseTransaction,
+        project_id: i32,
+        changes: DocumentChanges,
+    ) -> Result<Option<i32>> {
+        let Some(latest) = self
+            .0
+            .projects_versions()
+            .find_latest(project_id, Some(tnx))
+            .await?
+        else {
+            bail!("Project version not found")
+        };
 
-        Ok(())
+        let mut version = latest.version;
+
+        for (document_version, doc) in changes.changed {
+            if let Some(new_version) = self
+                .update_in_transaction(
+                    tnx,
+                    (document_version, project_id, version),
+                    (doc.name(), doc.content()),
+                    Some(doc.validators()),
+                    Some(doc.source()),
+                )
+                .await?
+            {
+                version = new_version;
+            }
+        }
+
+        for document_version in changes.removed {
+            if let Some(new_version) = self
+                .delete_in_transaction(tnx, (document_version, project_id, version))
+                .await?
+            {
+                version = new_version;
+            }
+        }
+
+        if !changes.added.is_empty() {
+            version = self
+                .create_many_in_transaction(tnx, project_id, changes.added)
+                .await?;
+        }
+
+        Ok((version != latest.version).then_some(version))
     }
 
     /// Update a document
@@ -267,17 +538,53 @@ impl<'a> DocumentRepo<'a> {
     /// 1. If the project version is finalized, bump project version, create new document, and add the new document version
     /// 2. If the document is embedded, create new document and update the relevant row in the document_version table
     /// 3. If the document is not embedded, update the document
+    ///
+    /// Cache validators and the source are kept from the previous document unless new
+    /// ones are given.
     pub async fn update(
         &self,
         id: i32,
         project_id: i32,
         version: i32,
         (name, content): (&str, &str),
+        validators: Option<&CacheValidators>,
+        source: Option<&str>,
+    ) -> Result<Option<i32>> {
+        let tnx = self.0.begin().await?;
+
+        match self
+            .update_in_transaction(
+                &tnx,
+                (id, project_id, version),
+                (name, content),
+                validators,
+                source,
+            )
+            .await
+        {
+            Ok(new_version) => {
+                tnx.commit().await?;
+                Ok(new_version)
+            }
+            Err(e) => {
+                tnx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn update_in_transaction(
+        &self,
+        tnx: &DatabaseTransaction,
+        (id, project_id, version): (i32, i32, i32),
+        (name, content): (&str, &str),
+        validators: Option<&CacheValidators>,
+        source: Option<&str>,
     ) -> Result<Option<i32>> {
         let Some(project_version) = self
             .0
             .projects_versions()
-            .find_by_pks(project_id, version)
+            .find_by_pks(project_id, version, Some(tnx))
             .await?
         else {
             bail!("Project version not found")
@@ -286,7 +593,7 @@ impl<'a> DocumentRepo<'a> {
         let Some((document_version, document)) = self
             .0
             .documents_versions()
-            .find_by_pks_with_related_documents(id, project_id, version)
+            .find_by_pks_with_related_documents(id, project_id, version, Some(tnx))
             .await?
         else {
             bail!("Document version not found")
@@ -294,34 +601,48 @@ impl<'a> DocumentRepo<'a> {
 
         let (document_version, document) = (document_version.to_owned(), document.to_owned());
 
+        let (etag, last_modified) = match validators {
+            Some(validators) => (
+                validators.etag().map(str::to_owned),
+                validators.last_modified().map(str::to_owned),
+            ),
+            None => (document.etag.to_owned(), document.last_modified.to_owned()),
+        };
+        let source = match source {
+            Some(source) => Some(source.to_owned()),
+            None => document.source.to_owned(),
+        };
+        let tags = updated_tags(&document_version.tags, &document.content, content);
+
         // 1.
         if project_version.finalized {
-            let tnx = self.0.begin().await?;
-
             let (_, new_version) = self
                 .0
                 .projects_versions()
-                .create(project_id, Some(&tnx))
+                .create(project_id, Some(tnx))
                 .await?;
 
             if new_version - 1 != version {
-                tnx.rollback().await?;
                 bail!("You can only update documents from the latest version of the project");
             }
 
             self.0
                 .documents_versions()
-                .bump_project_version(project_id, version, vec![], &tnx)
+                .bump_project_version(project_id, version, vec![], tnx)
                 .await?;
 
             let model = ActiveModel {
                 name: Set(name.to_owned()),
                 content: Set(content.to_owned()),
-                source: Set(document.source),
+                source: Set(source),
+                etag: Set(etag),
+                last_modified: Set(last_modified),
+                content_hash: Set(Some(content_hash(content))),
+                metadata: Set(metadata(content)),
                 ..Default::default()
             };
 
-            let res = Entity::insert(model).exec(&tnx).await?;
+            let res = Entity::insert(model).exec(tnx).await?;
             let new_document_id = res.last_insert_id;
 
             let model = document_version::ActiveModel {
@@ -329,36 +650,40 @@ impl<'a> DocumentRepo<'a> {
                 project_version_project_id: Set(project_id),
                 project_version_version: Set(new_version),
                 document_id: Set(new_document_id),
+                tags: Set(tags),
+                ..Default::default()
             };
-            model.save(&tnx).await?;
+            model.save(tnx).await?;
 
-            tnx.commit().await?;
             return Ok(Some(new_version));
         }
 
         // 2.
         if document_version.is_embedded {
-            let tnx = self.0.begin().await?;
-
             let model = ActiveModel {
                 name: Set(name.to_owned()),
                 content: Set(content.to_owned()),
-                source: Set(document.source.to_owned()),
+                source: Set(source),
+                etag: Set(etag),
+                last_modified: Set(last_modified),
+                content_hash: Set(Some(content_hash(content))),
+                metadata: Set(metadata(content)),
                 ..Default::default()
             };
 
-            let new_document_id = Entity::insert(model).exec(&tnx).await?.last_insert_id;
+            let new_document_id = Entity::insert(model).exec(tnx).await?.last_insert_id;
 
             document_version::ActiveModel {
                 id: Set(document_version.id),
                 project_version_project_id: Set(document_version.project_version_project_id),
                 project_version_version: Set(document_version.project_version_version),
                 document_id: Set(new_document_id),
+                tags: Set(tags),
+                ..Default::default()
             }
-            .save(&tnx)
+            .save(tnx)
             .await?;
 
-            tnx.commit().await?;
             return Ok(None);
         }
 
@@ -366,7 +691,24 @@ impl<'a> DocumentRepo<'a> {
         let mut model = document.into_active_model();
         model.name = Set(name.to_owned());
         model.content = Set(content.to_owned());
-        model.save(self.0).await?;
+        model.source = Set(source);
+        model.etag = Set(etag);
+        model.last_modified = Set(last_modified);
+        model.content_hash = Set(Some(content_hash(content)));
+        model.metadata = Set(metadata(content));
+        model.save(tnx).await?;
+
+        if tags != document_version.tags {
+            document_version::ActiveModel {
+                id: Set(document_version.id),
+                project_version_project_id: Set(document_version.project_version_project_id),
+                project_version_version: Set(document_version.project_version_version),
+                tags: Set(tags),
+                ..Default::default()
+            }
+            .update(tnx)
+            .await?;
+        }
 
         Ok(None)
     }
@@ -381,11 +723,33 @@ impl<'a> DocumentRepo<'a> {
         document_version: i32,
         project_id: i32,
         version: i32,
+    ) -> Result<Option<i32>> {
+        let tnx = self.0.begin().await?;
+
+        match self
+            .delete_in_transaction(&tnx, (document_version, project_id, version))
+            .await
+        {
+            Ok(new_version) => {
+                tnx.commit().await?;
+                Ok(new_version)
+            }
+            Err(e) => {
+                tnx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn delete_in_transaction(
+        &self,
+        tnx: &DatabaseTransaction,
+        (document_version, project_id, version): (i32, i32, i32),
     ) -> Result<Option<i32>> {
         let Some(project_version) = self
             .0
             .projects_versions()
-            .find_by_pks(project_id, version)
+            .find_by_pks(project_id, version, Some(tnx))
             .await?
         else {
             bail!("Project version not found")
@@ -393,31 +757,27 @@ impl<'a> DocumentRepo<'a> {
 
         // 1.Project version is finalized
         if project_version.finalized {
-            let tnx = self.0.begin().await?;
-
             let (_, new_version) = self
                 .0
                 .projects_versions()
-                .create(project_id, Some(&tnx))
+                .create(project_id, Some(tnx))
                 .await?;
             if new_version - 1 != version {
-                tnx.rollback().await?;
                 bail!("You can only delete documents from the latest version of the project");
             }
 
             self.0
                 .documents_versions()
-                .bump_project_version(project_id, version, vec![document_version], &tnx)
+                .bump_project_version(project_id, version, vec![document_version], tnx)
                 .await?;
 
-            tnx.commit().await?;
             return Ok(Some(new_version));
         }
 
         let Some(doc_v) = self
             .0
             .documents_versions()
-            .find_by_pks(document_version, project_id, version)
+            .find_by_pks(document_version, project_id, version, Some(tnx))
             .await?
         else {
             bail!("Document version not found")
@@ -427,7 +787,7 @@ impl<'a> DocumentRepo<'a> {
         let document_versions = self
             .0
             .documents_versions()
-            .find_by_doc_id(doc_v.document_id)
+            .find_by_doc_id(doc_v.document_id, Some(tnx))
             .await?;
 
         if document_versions.is_empty() {
@@ -436,7 +796,7 @@ impl<'a> DocumentRepo<'a> {
             tracing::info!("Document has mutliple versions, deleting the latest document_version");
             self.0
                 .documents_versions()
-                .delete(document_version, project_id, version)
+                .delete(document_version, project_id, version, Some(tnx))
                 .await?;
             return Ok(None);
         }
@@ -444,8 +804,59 @@ impl<'a> DocumentRepo<'a> {
         // 3. Document has only one version
         let id = document_versions[0].document_id;
         tracing::info!("Document has only one version, deleting the document");
-        let _ = Entity::delete_by_id(id).exec(self.0).await?;
+        let _ = Entity::delete_by_id(id).exec(tnx).await?;
 
         Ok(None)
     }
 }
+
+/// The tags in the front matter of the content
+fn front_matter_tags(content: &str) -> Vec<String> {
+    front_matter(content)
+        .map(|metadata| metadata.tags)
+        .unwrap_or_default()
+}
+
+/// The tags of a document after its content changed. The `tags` column is kept in sync with
+/// the front matter, tags removed from it are removed from the document. Tags that were set
+/// on the document itself are kept.
+fn updated_tags(tags: &Json, old_content: &str, content: &str) -> Json {
+    let old_front_matter_tags = front_matter_tags(old_content);
+    let tags: Vec<String> = serde_json::from_value(tags.to_owned()).unwrap_or_default();
+
+    tags_json(
+        tags.into_iter()
+            .filter(|tag| !old_front_matter_tags.iter().any(|old| old.trim() == tag))
+            .chain(front_matter_tags(content)),
+    )
+}
+
+/// The front matter of the content as JSON, for the `metadata` column
+fn metadata(content: &str) -> Option<Json> {
+    front_matter(content).and_then(|metadata| serde_json::to_value(metadata).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_updated_tags() {
+        let tags = Json::from(vec!["setup", "manual"]);
+        let old_content = "---\ntags: [setup]\n---\n# Guide";
+
+        // Tags removed from the front matter are removed, tags set on the document are kept
+        let content = "---\ntags: [install]\n---\n# Guide";
+        assert_eq!(
+            updated_tags(&tags, old_content, content),
+            Json::from(vec!["manual", "install"])
+        );
+
+        let content = "# Guide";
+        assert_eq!(
+            updated_tags(&tags, old_content, content),
+            Json::from(vec!["manual"])
+        );
+        assert_eq!(updated_tags(&tags, content, content), tags);
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use entity::{
     document,
     document_version::{ActiveModel, Column, Entity, Model},
@@ -6,12 +6,14 @@ use entity::{
 };
 use migration::{
     sea_orm::{
-        self, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, FromQueryResult,
-        QueryFilter, QuerySelect, RelationTrait, Set,
+        self, prelude::Json, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+        EntityTrait, FromQueryResult, QueryFilter, QuerySelect, RelationTrait, Set, Statement,
     },
     JoinType,
 };
 
+use super::{document_folder_repo::Placement, Repo};
+
 pub struct DocumentVersionRepo<'a>(&'a DatabaseConnection);
 
 #[derive(Debug, Clone, FromQueryResult)]
@@ -20,6 +22,7 @@ pub struct DocumentVersionWithIsEmbedded {
     pub project_version_project_id: i32,
     pub project_version_version: i32,
     pub document_id: i32,
+    pub tags: Json,
     pub is_embedded: bool,
 }
 
@@ -33,6 +36,7 @@ impl<'a> DocumentVersionRepo<'a> {
         id: i32,
         project_id: i32,
         version: i32,
+        tnx: Option<&DatabaseTransaction>,
     ) -> Result<Option<(DocumentVersionWithIsEmbedded, document::Model)>> {
         let query = Entity::find_by_id((id, project_id, version))
             .column_as(embedding::Column::Id.count().ne(0), "is_embedded")
@@ -44,11 +48,12 @@ impl<'a> DocumentVersionRepo<'a> {
             .group_by(Column::ProjectVersionVersion)
             .group_by(document::Column::Id);
 
-        let result = query
-            .into_model::<DocumentVersionWithIsEmbedded, document::Model>()
-            .one(self.0)
-            .await
-            .context("Failed to get document version")?;
+        let query = query.into_model::<DocumentVersionWithIsEmbedded, document::Model>();
+        let result = match tnx {
+            Some(tnx) => query.one(tnx).await,
+            None => query.one(self.0).await,
+        }
+        .context("Failed to get document version")?;
 
         let Some((result, Some(document))) = result else {
             return Ok(None);
@@ -62,21 +67,29 @@ impl<'a> DocumentVersionRepo<'a> {
         id: i32,
         project_id: i32,
         version: i32,
+        tnx: Option<&DatabaseTransaction>,
     ) -> Result<Option<Model>> {
-        let result = Entity::find_by_id((id, project_id, version))
-            .one(self.0)
-            .await
-            .context("Failed to get document version")?;
+        let query = Entity::find_by_id((id, project_id, version));
+        let result = match tnx {
+            Some(tnx) => query.one(tnx).await,
+            None => query.one(self.0).await,
+        }
+        .context("Failed to get document version")?;
 
         Ok(result)
     }
 
-    pub async fn find_by_doc_id(&self, doc_id: i32) -> Result<Vec<Model>> {
-        let result = Entity::find()
-            .filter(Column::DocumentId.eq(doc_id))
-            .all(self.0)
-            .await
-            .context("Failed to get document versions")?;
+    pub async fn find_by_doc_id(
+        &self,
+        doc_id: i32,
+        tnx: Option<&DatabaseTransaction>,
+    ) -> Result<Vec<Model>> {
+        let query = Entity::find().filter(Column::DocumentId.eq(doc_id));
+        let result = match tnx {
+            Some(tnx) => query.all(tnx).await,
+            None => query.all(self.0).await,
+        }
+        .context("Failed to get document versions")?;
 
         Ok(result)
     }
@@ -86,12 +99,17 @@ impl<'a> DocumentVersionRepo<'a> {
         project_version_project_id: i32,
         project_version_version: i32,
         document_id: i32,
+        placement: Placement,
+        tags: Vec<String>,
         tnx: Option<&DatabaseTransaction>,
     ) -> Result<(i32, i32, i32)> {
         let model = ActiveModel {
             project_version_project_id: Set(project_version_project_id),
             project_version_version: Set(project_version_version),
             document_id: Set(document_id),
+            folder_id: Set(placement.folder_id),
+            position: Set(placement.position),
+            tags: Set(tags_json(tags)),
             ..Default::default()
         };
 
@@ -113,15 +131,18 @@ impl<'a> DocumentVersionRepo<'a> {
         &self,
         project_version_project_id: i32,
         project_version_version: i32,
-        document_ids: Vec<i32>,
+        documents: Vec<(i32, Placement, Vec<String>)>,
         tnx: Option<&DatabaseTransaction>,
     ) -> Result<()> {
-        let models = document_ids
-            .iter()
-            .map(|&document_id| ActiveModel {
+        let models = documents
+            .into_iter()
+            .map(|(document_id, placement, tags)| ActiveModel {
                 project_version_project_id: Set(project_version_project_id),
                 project_version_version: Set(project_version_version),
                 document_id: Set(document_id),
+                folder_id: Set(placement.folder_id),
+                position: Set(placement.position),
+                tags: Set(tags_json(tags)),
                 ..Default::default()
             })
             .collect::<Vec<_>>();
@@ -147,6 +168,13 @@ impl<'a> DocumentVersionRepo<'a> {
         exclude_documents: Vec<i32>,
         tnx: &DatabaseTransaction,
     ) -> Result<()> {
+        // The folders of the documents are kept in the new version. They are copied first
+        // as the document versions refer to them.
+        self.0
+            .document_folders()
+            .bump_project_version(project_id, version, tnx)
+            .await?;
+
         // Find all document versions for the project and version
         let mut result = Entity::find()
             .filter(Column::ProjectVersionProjectId.eq(project_id))
@@ -166,6 +194,9 @@ impl<'a> DocumentVersionRepo<'a> {
                     project_version_project_id: Set(project_id),
                     project_version_version: Set(project_version_version),
                     document_id: Set(model.document_id),
+                    folder_id: Set(model.folder_id),
+                    position: Set(model.position),
+                    tags: Set(model.tags.to_owned()),
                 }
             })
             .collect();
@@ -179,11 +210,89 @@ impl<'a> DocumentVersionRepo<'a> {
         Ok(())
     }
 
-    pub async fn delete(&self, id: i32, project_id: i32, version: i32) -> Result<()> {
-        let _ = Entity::delete_by_id((id, project_id, version))
-            .exec(self.0)
+    /// Replace the tags of a document, returning the new project version if the project version
+    /// was finalized
+    pub async fn set_tags(
+        &self,
+        id: i32,
+        project_id: i32,
+        version: i32,
+        tags: Vec<String>,
+    ) -> Result<Option<i32>> {
+        let (tnx, new_version) = self
+            .0
+            .projects_versions()
+            .begin_change(project_id, version)
             .await?;
 
+        let model = ActiveModel {
+            id: Set(id),
+            project_version_project_id: Set(project_id),
+            project_version_version: Set(new_version.unwrap_or(version)),
+            tags: Set(tags_json(tags)),
+            ..Default::default()
+        };
+
+        if let Err(e) = Entity::update(model).exec(&tnx).await {
+            tnx.rollback().await?;
+            bail!("Failed to set document tags: {e}");
+        }
+
+        tnx.commit().await?;
+        Ok(new_version)
+    }
+
+    /// The tags of the documents of a project version and how many documents have each,
+    /// the most used tags first
+    pub async fn tag_facets(&self, project_id: i32, version: i32) -> Result<Vec<(String, i64)>> {
+        let stmt = Statement::from_sql_and_values(
+            self.0.get_database_backend(),
+            r#"SELECT "tag", COUNT(*) AS "count"
+            FROM "document_version", jsonb_array_elements_text("document_version"."tags") AS "tag"
+            WHERE "project_version_project_id" = $1 AND "project_version_version" = $2
+            GROUP BY "tag"
+            ORDER BY "count" DESC, "tag""#,
+            [project_id.into(), version.into()],
+        );
+
+        let facets = self
+            .0
+            .query_all(stmt)
+            .await
+            .context("Failed to get document tags")?
+            .iter()
+            .map(|row| Ok((row.try_get("", "tag")?, row.try_get("", "count")?)))
+            .collect::<Result<Vec<_>, sea_orm::DbErr>>()?;
+
+        Ok(facets)
+    }
+
+    pub async fn delete(
+        &self,
+        id: i32,
+        project_id: i32,
+        version: i32,
+        tnx: Option<&DatabaseTransaction>,
+    ) -> Result<()> {
+        let query = Entity::delete_by_id((id, project_id, version));
+        let _ = match tnx {
+            Some(tnx) => query.exec(tnx).await?,
+            None => query.exec(self.0).await?,
+        };
+
         Ok(())
     }
 }
+
+/// Tags as stored in the `tags` column, trimmed and without duplicates
+pub fn tags_json(tags: impl IntoIterator<Item = String>) -> Json {
+    let mut unique = Vec::<String>::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !unique.iter().any(|t| t == tag) {
+            unique.push(tag.to_owned());
+        }
+    }
+
+    Json::from(unique)
+}
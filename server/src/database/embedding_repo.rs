@@ -3,11 +3,11 @@ use entity::embedding::{ActiveModel, Column, Entity};
 use migration::{
     m20240422_000001_create_tables::{Document, DocumentVersion, ProjectVersion},
     m20240510_000002_create_embedding_table::Embedding as EmbeddingTbl,
-    sea_orm::{self, DatabaseConnection, EntityTrait, Set, Statement},
+    sea_orm::{self, prelude::Json, DatabaseConnection, EntityTrait, Set, Statement},
     Alias, Cond, ConnectionTrait, Expr, JoinType, PostgresQueryBuilder, SelectStatement,
 };
 
-use crate::models::{Embedding, SearchResult};
+use crate::models::{Embedding, SearchFilter, SearchResult};
 
 pub struct EmbeddingRepo<'a>(&'a DatabaseConnection);
 
@@ -33,13 +33,26 @@ impl<'a> EmbeddingRepo<'a> {
     /// WHERE
     ///         "project_version"."project_id" = $2
     ///     AND
-    ///         "project_version"."version" = $3 ORDER BY "score" DESC
+    ///         "project_version"."version" = $3
+    ///     AND
+    ///         "document_version"."project_version_version" = $4
+    ///     AND
+    ///         "document_version"."tags" @> $5
+    ///     AND
+    ///         NOT jsonb_exists_any("document_version"."tags", ARRAY(SELECT jsonb_array_elements_text($6)))
+    ///     AND
+    ///         "document"."metadata" @> $7
+    /// ORDER BY "score" DESC
     /// ```
+    ///
+    /// The tag conditions are only added when the filter has tags to include or exclude, and the
+    /// metadata condition when it has front matter to match
     pub async fn similarity_search(
         &self,
         project_id: i32,
         version: i32,
         query: Vec<f32>,
+        filter: &SearchFilter,
     ) -> Result<Vec<SearchResult>> {
         let (sql, values) = SelectStatement::new()
             .from(EmbeddingTbl::Table)
@@ -71,7 +84,26 @@ impl<'a> EmbeddingRepo<'a> {
                         Expr::col((ProjectVersion::Table, ProjectVersion::ProjectId))
                             .eq(project_id),
                     )
-                    .add(Expr::col((ProjectVersion::Table, ProjectVersion::Version)).eq(version)),
+                    .add(Expr::col((ProjectVersion::Table, ProjectVersion::Version)).eq(version))
+                    .add(
+                        Expr::col((DocumentVersion::Table, DocumentVersion::ProjectVersionVersion))
+                            .eq(version),
+                    )
+                    .add_option((!filter.include.is_empty()).then(|| {
+                        Expr::cust_with_values(
+                            "\"document_version\".\"tags\" @> $1",
+                            [Json::from(filter.include.to_owned())],
+                        )
+                    }))
+                    .add_option((!filter.exclude.is_empty()).then(|| {
+                        Expr::cust_with_values(
+                            "NOT jsonb_exists_any(\"document_version\".\"tags\", ARRAY(SELECT jsonb_array_elements_text($1)))",
+                            [Json::from(filter.exclude.to_owned())],
+                        )
+                    }))
+                    .add_option(filter.metadata.to_owned().map(|metadata| {
+                        Expr::cust_with_values("\"document\".\"metadata\" @> $1", [metadata])
+                    })),
             )
             .expr(Expr::cust_with_expr(
                 "1 - (\"embedding\" <=> $1::vector) AS score",
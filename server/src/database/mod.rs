@@ -1,3 +1,7 @@
+mod crawl_credential_repo;
+mod crawl_run_repo;
+mod crawl_schedule_repo;
+mod document_folder_repo;
 mod document_repo;
 mod document_version_repo;
 mod embedding_repo;
@@ -5,6 +9,10 @@ mod permission_repo;
 mod project_repo;
 mod project_version_repo;
 
+use crawl_credential_repo::CrawlCredentialRepo;
+use crawl_run_repo::CrawlRunRepo;
+use crawl_schedule_repo::CrawlScheduleRepo;
+use document_folder_repo::DocumentFolderRepo;
 use document_repo::DocumentRepo;
 use document_version_repo::DocumentVersionRepo;
 use embedding_repo::EmbeddingRepo;
@@ -19,9 +27,13 @@ pub trait Repo {
     fn projects_versions(&self) -> ProjectVersionRepo;
     fn documents_versions(&self) -> DocumentVersionRepo;
     fn documents(&self) -> DocumentRepo;
+    fn document_folders(&self) -> DocumentFolderRepo;
     fn embeddings(&self) -> EmbeddingRepo;
     fn user_permissions(&self) -> UserPermissionRepo;
     fn role_permissions(&self) -> RolePermissionRepo;
+    fn crawl_schedules(&self) -> CrawlScheduleRepo;
+    fn crawl_runs(&self) -> CrawlRunRepo;
+    fn crawl_credentials(&self) -> CrawlCredentialRepo;
 }
 
 impl Repo for DatabaseConnection {
@@ -37,6 +49,9 @@ impl Repo for DatabaseConnection {
     fn documents(&self) -> DocumentRepo {
         DocumentRepo::new(self)
     }
+    fn document_folders(&self) -> DocumentFolderRepo {
+        DocumentFolderRepo::new(self)
+    }
     fn embeddings(&self) -> EmbeddingRepo {
         EmbeddingRepo::new(self)
     }
@@ -46,4 +61,13 @@ impl Repo for DatabaseConnection {
     fn role_permissions(&self) -> RolePermissionRepo {
         RolePermissionRepo::new(self)
     }
+    fn crawl_schedules(&self) -> CrawlScheduleRepo {
+        CrawlScheduleRepo::new(self)
+    }
+    fn crawl_runs(&self) -> CrawlRunRepo {
+        CrawlRunRepo::new(self)
+    }
+    fn crawl_credentials(&self) -> CrawlCredentialRepo {
+        CrawlCredentialRepo::new(self)
+    }
 }
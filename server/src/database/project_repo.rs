@@ -64,6 +64,37 @@ impl<'a> ProjectRepo<'a> {
         Ok(result)
     }
 
+    /// Whether the user has the permission on the project, directly or through one of their roles
+    pub async fn has_permission(
+        &self,
+        project_id: i32,
+        user_id: &str,
+        roles: Vec<String>,
+        permission: PermissionEnum,
+    ) -> Result<bool> {
+        let user_permissions = user_permission::Entity::find()
+            .filter(user_permission::Column::ProjectId.eq(project_id))
+            .filter(user_permission::Column::UserId.eq(user_id))
+            .filter(user_permission::Column::Type.eq(permission.to_owned()))
+            .count(self.0)
+            .await
+            .context("Failed to get user permissions")?;
+
+        if user_permissions > 0 {
+            return Ok(true);
+        }
+
+        let role_permissions = role_permission::Entity::find()
+            .filter(role_permission::Column::ProjectId.eq(project_id))
+            .filter(role_permission::Column::RoleId.is_in(roles))
+            .filter(role_permission::Column::Type.eq(permission))
+            .count(self.0)
+            .await
+            .context("Failed to get role permissions")?;
+
+        Ok(role_permissions > 0)
+    }
+
     pub async fn all_with_user_permissions(
         &self,
         user_id: &str,
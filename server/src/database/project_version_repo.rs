@@ -1,10 +1,12 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use entity::project_version::{ActiveModel, Column, Entity, Model};
 use migration::sea_orm::{
     self, prelude::*, DatabaseTransaction, EntityTrait, FromQueryResult, QueryFilter, QueryOrder,
-    QuerySelect, Set,
+    QuerySelect, Set, TransactionTrait,
 };
 
+use super::Repo;
+
 pub struct ProjectVersionRepo<'a>(&'a DatabaseConnection);
 
 impl<'a> ProjectVersionRepo<'a> {
@@ -12,39 +14,61 @@ impl<'a> ProjectVersionRepo<'a> {
         Self(db)
     }
 
-    pub async fn find_latest(&self, id: i32) -> Result<Option<Model>> {
-        let res = Entity::find()
+    pub async fn find_latest(
+        &self,
+        id: i32,
+        tnx: Option<&DatabaseTransaction>,
+    ) -> Result<Option<Model>> {
+        let query = Entity::find()
             .filter(Column::ProjectId.eq(id))
-            .order_by_desc(Column::Version)
-            .one(self.0)
-            .await?;
+            .order_by_desc(Column::Version);
+
+        let res = match tnx {
+            Some(tnx) => query.one(tnx).await?,
+            None => query.one(self.0).await?,
+        };
 
         Ok(res)
     }
 
-    pub async fn find_by_pks(&self, project_id: i32, version: i32) -> Result<Option<Model>> {
-        let res = Entity::find()
+    pub async fn find_by_pks(
+        &self,
+        project_id: i32,
+        version: i32,
+        tnx: Option<&DatabaseTransaction>,
+    ) -> Result<Option<Model>> {
+        let query = Entity::find()
             .filter(Column::ProjectId.eq(project_id))
-            .filter(Column::Version.eq(version))
-            .one(self.0)
-            .await?;
+            .filter(Column::Version.eq(version));
+
+        let res = match tnx {
+            Some(tnx) => query.one(tnx).await?,
+            None => query.one(self.0).await?,
+        };
 
         Ok(res)
     }
 
-    pub async fn find_latest_version_number(&self, project_id: i32) -> Result<Option<i32>> {
+    pub async fn find_latest_version_number(
+        &self,
+        project_id: i32,
+        tnx: Option<&DatabaseTransaction>,
+    ) -> Result<Option<i32>> {
         #[derive(FromQueryResult)]
         struct Version {
             version: i32,
         }
 
-        let res = Entity::find()
+        let query = Entity::find()
             .column(Column::Version)
             .filter(Column::ProjectId.eq(project_id))
             .order_by_desc(Column::Version)
-            .into_model::<Version>()
-            .one(self.0)
-            .await?;
+            .into_model::<Version>();
+
+        let res = match tnx {
+            Some(tnx) => query.one(tnx).await?,
+            None => query.one(self.0).await?,
+        };
 
         Ok(res.map(|model| model.version))
     }
@@ -60,7 +84,7 @@ impl<'a> ProjectVersionRepo<'a> {
     }
 
     pub async fn find_latest_version_number_or_create(&self, project_id: i32) -> Result<i32> {
-        match self.find_latest(project_id).await? {
+        match self.find_latest(project_id, None).await? {
             Some(version) => Ok(version.version),
             None => {
                 let (_, version) = self.create(project_id, None).await?;
@@ -74,7 +98,7 @@ impl<'a> ProjectVersionRepo<'a> {
         project_id: i32,
         tnx: Option<&DatabaseTransaction>,
     ) -> Result<(i32, i32)> {
-        let version = match self.find_latest_version_number(project_id).await? {
+        let version = match self.find_latest_version_number(project_id, tnx).await? {
             Some(version) => version + 1,
             None => 1,
         };
@@ -108,4 +132,37 @@ impl<'a> ProjectVersionRepo<'a> {
 
         Ok(model.update(self.0).await?)
     }
+
+    /// Begin a transaction for changing the documents of a project version. If the project
+    /// version is finalized, a new project version is created in the transaction first and
+    /// returned.
+    pub async fn begin_change(
+        &self,
+        project_id: i32,
+        version: i32,
+    ) -> Result<(DatabaseTransaction, Option<i32>)> {
+        let Some(project_version) = self.find_by_pks(project_id, version, None).await? else {
+            bail!("Project version not found")
+        };
+
+        let tnx = self.0.begin().await?;
+
+        if !project_version.finalized {
+            return Ok((tnx, None));
+        }
+
+        let (_, new_version) = self.create(project_id, Some(&tnx)).await?;
+
+        if new_version - 1 != version {
+            tnx.rollback().await?;
+            bail!("You can only change documents from the latest version of the project");
+        }
+
+        self.0
+            .documents_versions()
+            .bump_project_version(project_id, version, vec![], &tnx)
+            .await?;
+
+        Ok((tnx, Some(new_version)))
+    }
 }
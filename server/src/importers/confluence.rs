@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use scraper::{Html, Selector};
+
+use super::links::{rewrite_links, LinkRewrite};
+use crate::{
+    models::CacheValidators,
+    parsing::{html_to_markdown, Content, Done, HtmlParser},
+};
+
+static PAGE_ID: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|_)(\d+)$").expect("valid page id regex"));
+static PAGE_ID_PARAM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[?&]pageId=(\d+)").expect("valid page id param regex"));
+
+/// Elements of the page content that only make sense inside Confluence
+const CHROME: &str = "script, style, .toc-macro, .plugin_pagetree, .expand-control, \
+    .confluence-information-macro-icon, .aui-icon";
+
+/// A page of the export, before it is converted to Markdown
+struct Page {
+    id: String,
+    title: String,
+    /// File names of the parent pages, from the top of the space
+    ancestors: Vec<String>,
+    content: String,
+}
+
+/// An HTML export of a Confluence space, a zip archive with a folder named
+/// after the space key holding a file per page. Pages are named by their
+/// position in the page tree, links between them point to the source of the
+/// linked page and attachments are dropped.
+pub struct ConfluenceExport {
+    space: String,
+    pub documents: Vec<HtmlParser<Done>>,
+    /// Pages that could not be imported, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+impl ConfluenceExport {
+    /// Read the pages from the extracted files of the export
+    pub fn read(files: Vec<(String, Vec<u8>)>) -> Result<Self> {
+        let pages = files
+            .into_iter()
+            .filter(|(path, _)| is_page(path))
+            .collect::<Vec<_>>();
+
+        let Some(space) = pages
+            .iter()
+            .find(|(path, _)| path.ends_with("/index.html") || path == "index.html")
+            .map(|(path, _)| path.rsplit_once('/').map_or("", |(folder, _)| folder))
+            .map(|folder| folder.rsplit('/').next().unwrap_or(folder).to_owned())
+        else {
+            bail!("Not a Confluence export, the space index.html is missing");
+        };
+
+        let mut skipped = vec![];
+        let mut by_file = HashMap::new();
+        for (path, bytes) in pages {
+            let file_name = path.rsplit('/').next().unwrap_or(&path).to_owned();
+            if file_name == "index.html" {
+                continue;
+            }
+
+            match read_page(&file_name, &String::from_utf8_lossy(&bytes)) {
+                Some(page) => {
+                    by_file.insert(file_name, page);
+                }
+                None => skipped.push((path, "No page content found".to_owned())),
+            }
+        }
+
+        let ids = by_file
+            .iter()
+            .map(|(file_name, page)| (file_name.as_str(), page.id.as_str()))
+            .collect::<HashMap<_, _>>();
+        let source = |id: &str| format!("confluence:{space}:{id}");
+
+        let mut documents = vec![];
+        for (file_name, page) in &by_file {
+            let names = page
+                .ancestors
+                .iter()
+                .filter_map(|ancestor| by_file.get(ancestor))
+                .map(|ancestor| ancestor.title.as_str())
+                .chain([page.title.as_str()])
+                .map(|title| title.replace('/', "-"))
+                .collect::<Vec<_>>();
+
+            let markdown = rewrite_links(&page.content, |link| {
+                let target = percent_decode_str(link.target).decode_utf8_lossy();
+                if is_external(&target) {
+                    // Links to the pages in Confluence itself
+                    let id = PAGE_ID_PARAM
+                        .captures(&target)
+                        .and_then(|captures| captures.get(1))
+                        .map(|id| id.as_str());
+                    return match id.filter(|id| ids.values().any(|page| page == id)) {
+                        Some(id) => LinkRewrite::Target(source(id)),
+                        None => LinkRewrite::Keep,
+                    };
+                }
+
+                let file = target.split('#').next().unwrap_or_default();
+                let file = file.rsplit('/').next().unwrap_or(file);
+                match ids.get(file) {
+                    Some(id) if !link.is_image => LinkRewrite::Target(source(id)),
+                    // Attachments, images and pages that were not exported
+                    _ if link.is_image => LinkRewrite::Text(String::new()),
+                    _ => LinkRewrite::Text(link.text.to_owned()),
+                }
+            });
+            let markdown = format!("# {}\n\n{}", page.title, markdown.trim());
+
+            match Content::Markdown(markdown).parse(
+                &names.join("/"),
+                source(&page.id),
+                CacheValidators::default(),
+            ) {
+                Ok(doc) => documents.push(doc),
+                Err(e) => skipped.push((file_name.to_owned(), e.to_string())),
+            }
+        }
+        documents.sort_by(|a, b| a.name().cmp(b.name()));
+
+        Ok(Self {
+            space,
+            documents,
+            skipped,
+        })
+    }
+
+    /// The key of the space the pages were exported from
+    pub fn space(&self) -> &str {
+        &self.space
+    }
+
+    /// Identifies the pages of an earlier export of the same space by their id
+    pub fn document_key(&self, source: &str) -> Option<String> {
+        let id = source
+            .strip_prefix("confluence:")?
+            .strip_prefix(self.space.as_str())?
+            .strip_prefix(':')?;
+        Some(id.to_owned())
+    }
+}
+
+/// Pages are the HTML files next to `index.html`, not the ones in the
+/// `attachments`, `images` or `styles` folders
+fn is_page(path: &str) -> bool {
+    let folders = path.matches('/').count();
+    path.to_lowercase().ends_with(".html") && folders <= 1 && !path.starts_with("attachments/")
+}
+
+fn is_external(target: &str) -> bool {
+    target.starts_with('/') || target.starts_with('#') || target.contains(':')
+}
+
+fn read_page(file_name: &str, html: &str) -> Option<Page> {
+    let mut html = Html::parse_document(html);
+
+    let (Ok(main), Ok(title), Ok(breadcrumbs), Ok(chrome)) = (
+        Selector::parse("#main-content"),
+        Selector::parse("#title-text, title"),
+        Selector::parse("#breadcrumbs a[href]"),
+        Selector::parse(CHROME),
+    ) else {
+        return None;
+    };
+
+    let breadcrumbs = html
+        .select(&breadcrumbs)
+        .filter_map(|a| {
+            let href = a.value().attr("href")?;
+            Some((href.to_owned(), a.text().collect::<String>()))
+        })
+        .collect::<Vec<_>>();
+    // The space home is the first breadcrumb, page titles start with its name
+    let space_name = breadcrumbs
+        .first()
+        .map(|(_, name)| name.trim().to_owned())
+        .unwrap_or_default();
+
+    let title = html
+        .select(&title)
+        .next()
+        .map(|title| title.text().collect::<String>())
+        .unwrap_or_default();
+    let title = title
+        .trim()
+        .strip_prefix(&format!("{space_name} : "))
+        .unwrap_or(title.trim())
+        .to_owned();
+
+    let chrome = html
+        .select(&chrome)
+        .map(|element| element.id())
+        .collect::<Vec<_>>();
+    for id in chrome {
+        if let Some(mut node) = html.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    let content = html.select(&main).next()?.inner_html();
+    let stem = file_name.trim_end_matches(".html");
+    let id = PAGE_ID
+        .captures(stem)
+        .and_then(|captures| captures.get(1))
+        .map_or(stem, |id| id.as_str());
+
+    Some(Page {
+        id: id.to_owned(),
+        title: match title.is_empty() {
+            true => stem.to_owned(),
+            false => title,
+        },
+        ancestors: breadcrumbs
+            .into_iter()
+            .map(|(href, _)| href)
+            .filter(|href| href != "index.html")
+            .collect(),
+        content: html_to_markdown(&content, None),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::{upload::extract_zip, zip_archive};
+
+    fn page(title: &str, breadcrumbs: &[(&str, &str)], content: &str) -> Vec<u8> {
+        let breadcrumbs = breadcrumbs
+            .iter()
+            .map(|(href, name)| format!(r#"<li><span><a href="{href}">{name}</a></span></li>"#))
+            .collect::<String>();
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head><title>Docs : {title}</title></head>
+<body class="theme-default aui-theme-default">
+<div id="page"><div id="main" class="aui-page-panel">
+<div id="main-header">
+<div id="breadcrumb-section"><ol id="breadcrumbs">{breadcrumbs}</ol></div>
+<h1 id="title-heading" class="pagetitle"><span id="title-text"> Docs : {title} </span></h1>
+</div>
+<div id="content" class="view">
+<div class="page-metadata">Created by Jane Doe, last modified on Jan 01, 2024</div>
+<div id="main-content" class="wiki-content group">{content}</div>
+<div class="pageSection group"><h2 id="attachments" class="pageSectionTitle">Attachments:</h2>
+<div class="greybox"><a href="attachments/65540/65541.png">diagram.png</a> (image/png)</div></div>
+</div></div>
+<div id="footer" role="contentinfo"><p>Document generated by Confluence</p></div>
+</div>
+</body>
+</html>"#
+        )
+        .into_bytes()
+    }
+
+    fn export() -> Vec<(String, Vec<u8>)> {
+        let home = [("index.html", "Docs")];
+        let guide = [
+            ("index.html", "Docs"),
+            ("Docs-Home_65538.html", "Docs Home"),
+        ];
+        let install = [
+            ("index.html", "Docs"),
+            ("Docs-Home_65538.html", "Docs Home"),
+            ("Guide_65539.html", "Guide"),
+        ];
+
+        let archive = zip_archive(&[
+            (
+                "DOCS/index.html",
+                b"<html><body><ul><li>Docs Home</li></ul></body></html>",
+            ),
+            (
+                "DOCS/Docs-Home_65538.html",
+                &page(
+                    "Docs Home",
+                    &home,
+                    "<p>Read the <a href=\"Guide_65539.html\">guide</a>.</p>",
+                ),
+            ),
+            (
+                "DOCS/Guide_65539.html",
+                &page(
+                    "Guide",
+                    &guide,
+                    r##"<div class="toc-macro"><ul><li><a href="#Guide-Setup">Setup</a></li></ul></div>
+<h2 id="Guide-Setup">Setup</h2>
+<p><span class="confluence-embedded-file-wrapper"><img class="confluence-embedded-image" src="attachments/65539/65540.png"></span></p>
+<p>See <a href="Install-on-Linux_65540.html#Install-Steps">installing</a>, <a href="attachments/65539/65541.pdf">the PDF</a>, <a href="/pages/viewpage.action?pageId=65538">home</a> and <a href="https://example.com">example</a>.</p>"##,
+                ),
+            ),
+            (
+                "DOCS/Install-on-Linux_65540.html",
+                &page("Install on Linux/macOS", &install, "<p>Run it.</p>"),
+            ),
+            ("DOCS/attachments/65539/65540.png", b"png"),
+            ("DOCS/styles/site.css", b"body {}"),
+        ]);
+
+        extract_zip(archive, &Default::default()).unwrap().files
+    }
+
+    #[test]
+    fn test_read() {
+        let export = ConfluenceExport::read(export()).unwrap();
+
+        assert_eq!(export.space(), "DOCS");
+        assert!(export.skipped.is_empty());
+        let documents = export
+            .documents
+            .iter()
+            .map(|doc| (doc.name(), doc.source()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            documents,
+            vec![
+                ("Docs Home", "confluence:DOCS:65538"),
+                ("Docs Home/Guide", "confluence:DOCS:65539"),
+                (
+                    "Docs Home/Guide/Install on Linux-macOS",
+                    "confluence:DOCS:65540"
+                ),
+            ]
+        );
+
+        assert_eq!(
+            export.documents[0].content(),
+            "# Docs Home\n\nRead the [guide](confluence:DOCS:65539)."
+        );
+        let guide = export.documents[1].content();
+        assert!(guide.starts_with("# Guide\n\n"));
+        assert!(!guide.contains("toc-macro") && !guide.contains("[Setup](#Guide-Setup)"));
+        assert!(!guide.contains("65540.png"));
+        assert!(guide.contains(
+            "See [installing](confluence:DOCS:65540), the PDF, [home](confluence:DOCS:65538) and [example](https://example.com)."
+        ));
+        assert!(!guide.contains("Created by") && !guide.contains("Attachments"));
+    }
+
+    #[test]
+    fn test_document_key() {
+        let export = ConfluenceExport::read(export()).unwrap();
+
+        assert_eq!(
+            export.document_key("confluence:DOCS:65538"),
+            Some("65538".to_owned())
+        );
+        assert_eq!(export.document_key("confluence:OTHER:65538"), None);
+        assert_eq!(export.document_key("notion:65538"), None);
+    }
+
+    #[test]
+    fn test_not_an_export() {
+        let files = vec![("README.md".to_owned(), b"# Readme".to_vec())];
+        assert!(ConfluenceExport::read(files).is_err());
+    }
+}
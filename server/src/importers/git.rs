@@ -0,0 +1,477 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+use super::document_name;
+use crate::{
+    models::CacheValidators,
+    parsing::{Content, Done, HtmlParser},
+    web_crawler::limits::format_bytes,
+};
+
+/// Extensions of the files imported from a repository
+pub const EXTENSIONS: [&str; 3] = ["md", "mdx", "rst"];
+
+/// Larger files are skipped, they are unlikely to be written documentation
+const MAX_FILE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Characters encoded in the repository of a source, as `:` separates its parts
+const REPOSITORY_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'%').add(b':');
+
+/// Where a document imported from git came from, stored as its source
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitSource {
+    /// The name of the repository, see [`LocalRepository::name`]
+    pub repository: String,
+    pub commit: String,
+    pub path: String,
+}
+
+impl GitSource {
+    const PREFIX: &'static str = "git:";
+
+    pub fn parse(source: &str) -> Option<Self> {
+        let (repository, rest) = source.strip_prefix(Self::PREFIX)?.split_once(':')?;
+        let (commit, path) = rest.split_once(':')?;
+        if repository.is_empty()
+            || commit.is_empty()
+            || !commit.chars().all(|c| c.is_ascii_hexdigit())
+            || path.is_empty()
+        {
+            return None;
+        }
+
+        Some(Self {
+            repository: percent_decode_str(repository)
+                .decode_utf8()
+                .ok()?
+                .into_owned(),
+            commit: commit.to_owned(),
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl Display for GitSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}:{}:{}",
+            Self::PREFIX,
+            utf8_percent_encode(&self.repository, REPOSITORY_ENCODE_SET),
+            self.commit,
+            self.path
+        )
+    }
+}
+
+/// A repository below the git import root
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalRepository {
+    pub path: PathBuf,
+    /// The path relative to the import root, which tells the documents of
+    /// repositories imported into the same project apart
+    pub name: String,
+}
+
+/// The documents read from a commit
+pub struct GitImport {
+    pub commit: String,
+    pub documents: Vec<HtmlParser<Done>>,
+    /// Files that could not be imported, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Reads the documentation files of a commit from a local repository, which
+/// may be a bare clone. Files are read from the commit rather than the working
+/// tree, so any branch, tag or commit can be imported.
+pub struct GitImporter {
+    repository: LocalRepository,
+    reference: String,
+    globs: Option<GlobSet>,
+}
+
+impl GitImporter {
+    /// Import the commit `reference` points to, `HEAD` if it is empty
+    pub fn new(repository: LocalRepository, reference: &str) -> Self {
+        let reference = match reference.trim() {
+            "" => "HEAD",
+            reference => reference,
+        };
+
+        Self {
+            repository,
+            reference: reference.to_owned(),
+            globs: None,
+        }
+    }
+
+    /// Only import files matching one of the globs, e.g. `docs/**`. A `*` does
+    /// not match across folders.
+    pub fn with_globs(mut self, globs: &[String]) -> Result<Self> {
+        let globs = globs
+            .iter()
+            .map(|glob| glob.trim())
+            .filter(|glob| !glob.is_empty())
+            .collect::<Vec<_>>();
+        if globs.is_empty() {
+            return Ok(self);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            let glob = GlobBuilder::new(glob.trim_start_matches('/'))
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("Invalid glob {glob}"))?;
+            builder.add(glob);
+        }
+        self.globs = Some(builder.build()?);
+        Ok(self)
+    }
+
+    /// Whether the file at the path is imported, by its extension and the globs
+    pub fn includes(&self, path: &str) -> bool {
+        let is_documentation = path
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+
+        is_documentation && self.globs.as_ref().is_none_or(|globs| globs.is_match(path))
+    }
+
+    /// Read the documents of the commit. Files are named by their path without
+    /// the extension, so folders show up in the names.
+    pub fn read(&self) -> Result<GitImport> {
+        let repository = Repository::open(&self.repository.path)
+            .with_context(|| format!("No git repository at {}", self.repository.path.display()))?;
+        let commit = repository
+            .revparse_single(&self.reference)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| format!("Reference {} not found", self.reference))?;
+        let commit_id = commit.id().to_string();
+
+        let mut files: Vec<(String, Oid)> = vec![];
+        commit
+            .tree()?
+            .walk(TreeWalkMode::PreOrder, |folder, entry| {
+                if entry.kind() == Some(ObjectType::Blob) {
+                    let path = format!("{folder}{}", entry.name().unwrap_or_default());
+                    if self.includes(&path) {
+                        files.push((path, entry.id()));
+                    }
+                }
+                TreeWalkResult::Ok
+            })?;
+
+        let mut documents = vec![];
+        let mut skipped = vec![];
+
+        for (path, id) in files {
+            let blob = repository.find_blob(id)?;
+            if blob.size() > MAX_FILE_BYTES {
+                let reason = format!("Larger than {}", format_bytes(MAX_FILE_BYTES));
+                skipped.push((path, reason));
+                continue;
+            }
+            let Some(content) = Content::from_file(&path, blob.content().to_vec()) else {
+                continue;
+            };
+
+            let source = GitSource {
+                repository: self.repository.name.to_owned(),
+                commit: commit_id.to_owned(),
+                path: path.to_owned(),
+            };
+            match content.parse(&document_name(&path), source, CacheValidators::default()) {
+                Ok(doc) => documents.push(doc),
+                Err(e) => skipped.push((path, e.to_string())),
+            }
+        }
+
+        Ok(GitImport {
+            commit: commit_id,
+            documents,
+            skipped,
+        })
+    }
+
+    /// Identifies the documents of a previous import from the same repository
+    /// that this import would include, by their path
+    pub fn document_key(&self, source: &str) -> Option<String> {
+        let source = GitSource::parse(source)?;
+        (source.repository == self.repository.name && self.includes(&source.path))
+            .then_some(source.path)
+    }
+}
+
+/// The repository at the path, which is relative to the import root or an
+/// absolute path inside of it. Paths leading out of the root are rejected.
+pub fn resolve_repository(root: &Path, path: &str) -> Result<LocalRepository> {
+    let root = root
+        .canonicalize()
+        .context("The git import root does not exist")?;
+    let repository = root
+        .join(path.trim())
+        .canonicalize()
+        .with_context(|| format!("Repository {} not found", path.trim()))?;
+
+    let Ok(relative) = repository.strip_prefix(&root) else {
+        bail!("The repository must be inside the git import root");
+    };
+    let name = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    Ok(LocalRepository {
+        name: match name.is_empty() {
+            true => ".".to_owned(),
+            false => name,
+        },
+        path: repository,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{IndexAddOption, Signature};
+    use std::{
+        fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// A new empty folder in the temp dir
+    fn temp_dir() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "magicdocs-git-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn local(path: &Path) -> LocalRepository {
+        LocalRepository {
+            path: path.to_owned(),
+            name: "docs".to_owned(),
+        }
+    }
+
+    /// Write the files and commit them, returning the commit id
+    fn commit(repository: &Repository, files: &[(&str, &str)], message: &str) -> String {
+        let workdir = repository.workdir().unwrap();
+        for (path, content) in files {
+            let path = workdir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let mut index = repository.index().unwrap();
+        index
+            .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parents = match repository.head() {
+            Ok(head) => vec![head.peel_to_commit().unwrap()],
+            Err(_) => vec![],
+        };
+        let parents = parents.iter().collect::<Vec<_>>();
+
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_source() {
+        let source = GitSource {
+            repository: "team/docs:v2".to_owned(),
+            commit: "a1b2c3".to_owned(),
+            path: "docs/getting started.md".to_owned(),
+        };
+
+        assert_eq!(
+            source.to_string(),
+            "git:team/docs%3Av2:a1b2c3:docs/getting started.md"
+        );
+        assert_eq!(GitSource::parse(&source.to_string()), Some(source));
+        assert_eq!(GitSource::parse("https://example.com/docs"), None);
+        assert_eq!(GitSource::parse("git:docs:not-a-sha:docs/a.md"), None);
+        assert_eq!(GitSource::parse("git:a1b2c3:docs/a.md"), None);
+    }
+
+    #[test]
+    fn test_document_key() {
+        let importer = GitImporter::new(local(Path::new("repo")), "main")
+            .with_globs(&["docs/**".to_owned()])
+            .unwrap();
+
+        assert_eq!(
+            importer.document_key("git:docs:a1b2c3:docs/a.md"),
+            Some("docs/a.md".to_owned())
+        );
+        assert_eq!(importer.document_key("git:docs:a1b2c3:README.md"), None);
+        assert_eq!(importer.document_key("git:other:a1b2c3:docs/a.md"), None);
+        assert_eq!(importer.document_key("upload:docs/a.md"), None);
+    }
+
+    #[test]
+    fn test_includes() {
+        let importer = GitImporter::new(local(Path::new("repo")), "main");
+        assert!(importer.includes("README.md"));
+        assert!(importer.includes("docs/guide.MDX"));
+        assert!(importer.includes("docs/api/index.rst"));
+        assert!(!importer.includes("src/main.rs"));
+        assert!(!importer.includes("Makefile"));
+
+        let importer = importer
+            .with_globs(&["docs/**".to_owned(), "/*.md".to_owned()])
+            .unwrap();
+        assert!(importer.includes("README.md"));
+        assert!(importer.includes("docs/api/index.rst"));
+        assert!(!importer.includes("examples/README.md"));
+        assert!(!importer.includes("docs/logo.png"));
+
+        assert!(GitImporter::new(local(Path::new("repo")), "main")
+            .with_globs(&["docs/[".to_owned()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_commits() {
+        let dir = temp_dir();
+        let repository = Repository::init(&dir).unwrap();
+        let first = commit(
+            &repository,
+            &[
+                ("README.md", "# Project\n\nIntro"),
+                (
+                    "docs/guide/install.rst",
+                    "Install\n=======\n\nRun ``make``.",
+                ),
+                ("docs/usage.mdx", "import X from 'x';\n\n# Usage"),
+                ("src/main.rs", "fn main() {}"),
+            ],
+            "First",
+        );
+        repository
+            .tag_lightweight("v1", &repository.revparse_single(&first).unwrap(), false)
+            .unwrap();
+        let second = commit(
+            &repository,
+            &[("README.md", "# Project\n\nChanged")],
+            "Second",
+        );
+
+        let import = GitImporter::new(local(&dir), "v1").read().unwrap();
+
+        assert_eq!(import.commit, first);
+        let mut documents = import
+            .documents
+            .iter()
+            .map(|doc| (doc.name(), doc.content(), doc.source()))
+            .collect::<Vec<_>>();
+        documents.sort();
+        assert_eq!(
+            documents,
+            vec![
+                (
+                    "README",
+                    "# Project\n\nIntro",
+                    format!("git:docs:{first}:README.md").as_str()
+                ),
+                (
+                    "docs/guide/install",
+                    "# Install\n\nRun `make`.",
+                    format!("git:docs:{first}:docs/guide/install.rst").as_str()
+                ),
+                (
+                    "docs/usage",
+                    "# Usage",
+                    format!("git:docs:{first}:docs/usage.mdx").as_str()
+                ),
+            ]
+        );
+
+        let import = GitImporter::new(local(&dir), "").read().unwrap();
+        assert_eq!(import.commit, second);
+
+        assert!(GitImporter::new(local(&dir), "missing").read().is_err());
+        assert!(GitImporter::new(local(&dir.join("missing")), "")
+            .read()
+            .is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_bare_clone() {
+        let dir = temp_dir();
+        let repository = Repository::init(dir.join("work")).unwrap();
+        let head = commit(&repository, &[("docs/a.md", "# A")], "First");
+        let bare = dir.join("bare-clone.git");
+        git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(dir.join("work").to_str().unwrap(), &bare)
+            .unwrap();
+
+        let import = GitImporter::new(local(&bare), "HEAD").read().unwrap();
+
+        assert_eq!(import.commit, head);
+        assert_eq!(import.documents.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_repository() {
+        let root = temp_dir();
+        fs::create_dir_all(root.join("docs-repo")).unwrap();
+
+        fs::create_dir_all(root.join("team/guides")).unwrap();
+
+        let resolved = resolve_repository(&root, "docs-repo").unwrap();
+        assert_eq!(
+            resolved.path,
+            root.canonicalize().unwrap().join("docs-repo")
+        );
+        assert_eq!(resolved.name, "docs-repo");
+        assert_eq!(
+            resolve_repository(&root, resolved.path.to_str().unwrap()).unwrap(),
+            resolved
+        );
+        assert_eq!(
+            resolve_repository(&root, "./team/../team/guides/")
+                .unwrap()
+                .name,
+            "team/guides"
+        );
+        assert_eq!(resolve_repository(&root, "").unwrap().name, ".");
+        assert!(resolve_repository(&root, "../").is_err());
+        assert!(resolve_repository(&root, "/etc").is_err());
+        assert!(resolve_repository(&root, "missing").is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+/// A Markdown link or image, e.g. `[text](target "title")`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link<'a> {
+    pub is_image: bool,
+    pub text: &'a str,
+    pub target: &'a str,
+}
+
+/// What to do with a link found by [`rewrite_links`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkRewrite {
+    Keep,
+    /// Point the link somewhere else, keeping its text and title
+    Target(String),
+    /// Replace the whole link, e.g. with its text to remove the link
+    Text(String),
+}
+
+/// Rewrite the inline links and images of a Markdown document. Code blocks and
+/// code spans are left alone, lines that only had removed links are dropped.
+pub fn rewrite_links(markdown: &str, mut rewrite: impl FnMut(&Link) -> LinkRewrite) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    let mut fence: Option<&str> = None;
+    let mut removed = false;
+
+    for line in markdown.split_inclusive('\n') {
+        // The blank line after a dropped paragraph
+        if removed && line.trim().is_empty() && (output.is_empty() || output.ends_with("\n\n")) {
+            removed = false;
+            continue;
+        }
+        removed = false;
+
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+
+        match (fence, marker) {
+            (Some(open), Some(marker)) if open == marker => {
+                fence = None;
+                output.push_str(line);
+            }
+            (Some(_), _) => output.push_str(line),
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                output.push_str(line);
+            }
+            (None, None) => {
+                let start = output.len();
+                rewrite_line(line, &mut output, &mut rewrite);
+                if output[start..].trim().is_empty() && !line.trim().is_empty() {
+                    output.truncate(start);
+                    removed = true;
+                }
+            }
+        }
+    }
+
+    output
+}
+
+fn rewrite_line(line: &str, output: &mut String, rewrite: &mut impl FnMut(&Link) -> LinkRewrite) {
+    let mut rest = line;
+
+    while let Some(start) = rest.find(['[', '`']) {
+        // Code spans end at the same number of backticks
+        if rest[start..].starts_with('`') {
+            let ticks = rest[start..].len() - rest[start..].trim_start_matches('`').len();
+            let after = start + ticks;
+            let end = match rest[after..].find(&rest[start..after]) {
+                Some(end) => after + end + ticks,
+                None => after,
+            };
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let is_image = start > 0 && rest[..start].ends_with('!');
+        let Some((link, title, length)) = parse_link(&rest[start..], is_image) else {
+            output.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        };
+        let link_start = if is_image { start - 1 } else { start };
+        output.push_str(&rest[..link_start]);
+
+        match rewrite(&link) {
+            LinkRewrite::Keep => output.push_str(&rest[link_start..start + length]),
+            LinkRewrite::Target(target) => {
+                let bang = if is_image { "!" } else { "" };
+                output.push_str(&format!("{bang}[{}]({target}{title})", link.text));
+            }
+            LinkRewrite::Text(text) => output.push_str(&text),
+        }
+        rest = &rest[start + length..];
+    }
+
+    output.push_str(rest);
+}
+
+/// The link at the start of the text, its title including the leading space,
+/// and its length
+fn parse_link(text: &str, is_image: bool) -> Option<(Link<'_>, &str, usize)> {
+    let mut depth = 0;
+    let mut text_end = None;
+    for (index, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    text_end = Some(index);
+                    break;
+                }
+            }
+            _ => (),
+        }
+    }
+    let text_end = text_end?;
+    let destination = text[text_end + 1..].strip_prefix('(')?;
+
+    let mut depth = 0;
+    let mut end = None;
+    for (index, c) in destination.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                end = Some(index);
+                break;
+            }
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    let end = end?;
+
+    let destination = &destination[..end];
+    let (target, title) = match destination.trim().split_once(char::is_whitespace) {
+        Some((target, _)) => (
+            target,
+            &destination[destination.find(target)? + target.len()..],
+        ),
+        None => (destination.trim(), ""),
+    };
+    let target = target
+        .strip_prefix('<')
+        .and_then(|target| target.strip_suffix('>'))
+        .unwrap_or(target);
+
+    let link = Link {
+        is_image,
+        text: &text[1..text_end],
+        target,
+    };
+    Some((link, title, text_end + 2 + end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(markdown: &str) -> String {
+        rewrite_links(markdown, |link| match link.target {
+            "a.md" => LinkRewrite::Target("/a".to_owned()),
+            "image.png" => LinkRewrite::Text(String::new()),
+            "file.pdf" => LinkRewrite::Text(link.text.to_owned()),
+            _ => LinkRewrite::Keep,
+        })
+    }
+
+    #[test]
+    fn test_rewrite_links() {
+        assert_eq!(
+            rewrite("See [A](a.md) and [B](b.md)."),
+            "See [A](/a) and [B](b.md)."
+        );
+        assert_eq!(
+            rewrite("[A [nested]](a.md \"Title\")"),
+            "[A [nested]](/a \"Title\")"
+        );
+        assert_eq!(rewrite("[A](<a.md>)"), "[A](/a)");
+        assert_eq!(rewrite("Logo ![logo](image.png) here"), "Logo  here");
+        assert_eq!(rewrite("A\n\n![logo](image.png)\n\nB"), "A\n\nB");
+        assert_eq!(rewrite("[The file](file.pdf)"), "The file");
+        assert_eq!(
+            rewrite("[![badge](image.png)](a.md)"),
+            "[![badge](image.png)](/a)"
+        );
+        assert_eq!(rewrite("[not a link] (a.md)"), "[not a link] (a.md)");
+        assert_eq!(rewrite("[unclosed](a.md"), "[unclosed](a.md");
+    }
+
+    #[test]
+    fn test_rewrite_links_skips_code() {
+        let markdown = "`[A](a.md)` [A](a.md)\n\n```md\n[A](a.md)\n```\n[A](a.md)\n";
+        assert_eq!(
+            rewrite(markdown),
+            "`[A](a.md)` [A](/a)\n\n```md\n[A](a.md)\n```\n[A](/a)\n"
+        );
+        assert_eq!(rewrite("`` `[A](a.md)` ``"), "`` `[A](a.md)` ``");
+    }
+}
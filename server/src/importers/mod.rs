@@ -0,0 +1,393 @@
+pub mod confluence;
+pub mod git;
+pub mod links;
+pub mod notion;
+pub mod openapi;
+pub mod rustdoc;
+pub mod upload;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use links::{rewrite_links, LinkRewrite};
+
+use anyhow::Result;
+use entity::document::Model;
+use migration::sea_orm::DatabaseConnection;
+
+use crate::{
+    database::Repo,
+    models::{CacheValidators, DocumentChanges},
+    parsing::{Content, Done, HtmlParser},
+    utils::hash::content_hash,
+};
+
+/// What an import changed in the latest project version
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    /// Files that could not be imported, with the reason
+    pub skipped: Vec<(String, String)>,
+    /// The project version created for the changes, if any
+    pub version: Option<i32>,
+}
+
+impl ImportSummary {
+    pub fn has_changes(&self) -> bool {
+        !(self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty())
+    }
+}
+
+impl Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.has_changes() {
+            write!(f, "No changes found")?;
+        } else {
+            write!(
+                f,
+                "Added {}, changed {} and removed {} documents",
+                self.added.len(),
+                self.changed.len(),
+                self.removed.len()
+            )?;
+
+            if let Some(version) = self.version {
+                write!(f, " in new project version {version}")?;
+            }
+        }
+
+        match self.skipped.len() {
+            0 => Ok(()),
+            skipped => write!(f, ". {skipped} files were skipped"),
+        }
+    }
+}
+
+/// The name of a document imported from a file, its path without the
+/// extension, so folders show up in the name
+pub fn document_name(path: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((name, _)) if !name.is_empty() && !name.ends_with('/') => name.to_owned(),
+        _ => path.to_owned(),
+    }
+}
+
+/// A document generated as Markdown by an importer, `None` if it is empty
+pub fn markdown_document(name: &str, content: String, source: String) -> Option<HtmlParser<Done>> {
+    Content::Markdown(content)
+        .parse(name, source, CacheValidators::default())
+        .ok()
+}
+
+/// The text with all whitespace, including line breaks, collapsed to single spaces
+pub fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Compare imported documents with the ones a previous import created from the
+/// same source. `key` identifies a document within the source by its `source`
+/// column, e.g. the path of a file, and returns `None` for documents from
+/// elsewhere, which are left alone.
+pub fn find_changes(
+    existing: Vec<(i32, Model)>,
+    documents: Vec<HtmlParser<Done>>,
+    key: impl Fn(&str) -> Option<String>,
+) -> (DocumentChanges, ImportSummary) {
+    let mut existing: HashMap<String, (i32, Model)> = existing
+        .into_iter()
+        .filter_map(|(document_version, document)| {
+            let key = key(document.source.as_deref()?)?;
+            Some((key, (document_version, document)))
+        })
+        .collect();
+
+    let mut changes = DocumentChanges::default();
+    let mut summary = ImportSummary::default();
+
+    for doc in documents {
+        let Some(key) = key(doc.source()) else {
+            continue;
+        };
+
+        match existing.remove(&key) {
+            Some((document_version, document)) => {
+                let old_hash = document
+                    .content_hash
+                    .unwrap_or_else(|| content_hash(&document.content));
+
+                if old_hash != content_hash(doc.content()) || document.name != doc.name() {
+                    summary.changed.push(doc.name().to_owned());
+                    changes.changed.push((document_version, doc));
+                } else {
+                    summary.unchanged += 1;
+                }
+            }
+            None => {
+                summary.added.push(doc.name().to_owned());
+                changes.added.push(doc);
+            }
+        }
+    }
+
+    for (document_version, document) in existing.into_values() {
+        summary.removed.push(document.name);
+        changes.removed.push(document_version);
+    }
+
+    (changes, summary)
+}
+
+/// Apply the changes from an import to the latest project version, see [`find_changes`]
+pub async fn apply_import(
+    db: &DatabaseConnection,
+    project_id: i32,
+    documents: Vec<HtmlParser<Done>>,
+    key: impl Fn(&str) -> Option<String>,
+) -> Result<ImportSummary> {
+    let version = db
+        .projects_versions()
+        .find_latest_version_number_or_create(project_id)
+        .await?;
+    let existing = db.documents().all_with_source(project_id, version).await?;
+
+    let (changes, mut summary) = find_changes(existing, documents, key);
+    summary.version = db.documents().apply_changes(project_id, changes).await?;
+
+    Ok(summary)
+}
+
+/// Apply an import of pages that link to each other by their source, e.g.
+/// `[Setup](notion:0f9e…)`, see [`apply_import`]. Links are rewritten to the
+/// documents of the linked pages, which takes a second pass when pages are new
+/// and have no id yet. Links to pages that are not imported keep their text.
+pub async fn apply_linked_import(
+    db: &DatabaseConnection,
+    project_id: i32,
+    documents: Vec<HtmlParser<Done>>,
+    key: impl Fn(&str) -> Option<String>,
+) -> Result<ImportSummary> {
+    let imported = documents
+        .iter()
+        .filter_map(|doc| key(doc.source()))
+        .collect::<HashSet<_>>();
+
+    let ids = document_ids(db, project_id, &key).await?;
+    let (linked, complete) = link_documents(project_id, &documents, &imported, &ids, &key);
+    let mut summary = apply_import(db, project_id, linked, &key).await?;
+    if complete {
+        return Ok(summary);
+    }
+
+    // Only the new documents with links to other new documents change
+    let ids = document_ids(db, project_id, &key).await?;
+    let (linked, _) = link_documents(project_id, &documents, &imported, &ids, &key);
+    let linking = apply_import(db, project_id, linked, &key).await?;
+    summary.version = summary.version.or(linking.version);
+
+    Ok(summary)
+}
+
+/// The ids of the document versions in the latest project version by their key
+async fn document_ids(
+    db: &DatabaseConnection,
+    project_id: i32,
+    key: impl Fn(&str) -> Option<String>,
+) -> Result<HashMap<String, i32>> {
+    let version = db
+        .projects_versions()
+        .find_latest_version_number_or_create(project_id)
+        .await?;
+    let ids = db
+        .documents()
+        .all_with_source(project_id, version)
+        .await?
+        .into_iter()
+        .filter_map(|(id, document)| Some((key(document.source.as_deref()?)?, id)))
+        .collect();
+
+    Ok(ids)
+}
+
+/// Point the links between imported pages to their documents. Returns the
+/// documents and whether every link to another imported page was resolved.
+fn link_documents(
+    project_id: i32,
+    documents: &[HtmlParser<Done>],
+    imported: &HashSet<String>,
+    ids: &HashMap<String, i32>,
+    key: impl Fn(&str) -> Option<String>,
+) -> (Vec<HtmlParser<Done>>, bool) {
+    let mut complete = true;
+
+    let documents = documents
+        .iter()
+        .map(|doc| {
+            let content = rewrite_links(doc.content(), |link| {
+                let Some(target) = key(link.target) else {
+                    return LinkRewrite::Keep;
+                };
+                match ids.get(&target) {
+                    Some(id) => {
+                        LinkRewrite::Target(format!("/projects/{project_id}/documents/{id}"))
+                    }
+                    None if imported.contains(&target) => {
+                        complete = false;
+                        LinkRewrite::Keep
+                    }
+                    None => LinkRewrite::Text(link.text.to_owned()),
+                }
+            });
+            doc.with_content(content)
+        })
+        .collect();
+
+    (documents, complete)
+}
+
+/// A zip archive of the files, for tests
+#[cfg(test)]
+fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    use std::io::{Cursor, Write};
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (path, content) in files {
+        writer.start_file(*path, options).unwrap();
+        writer.write_all(content).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::Content;
+
+    fn existing(document_version: i32, name: &str, content: &str, source: &str) -> (i32, Model) {
+        let model = Model {
+            id: document_version,
+            name: name.to_owned(),
+            content: content.to_owned(),
+            source: Some(source.to_owned()),
+            created_at: None,
+            etag: None,
+            last_modified: None,
+            content_hash: Some(content_hash(content)),
+            metadata: None,
+        };
+        (document_version, model)
+    }
+
+    fn imported(name: &str, content: &str, source: &str) -> HtmlParser<Done> {
+        Content::Markdown(content.to_owned())
+            .parse(name, source, Default::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_find_changes() {
+        let key = |source: &str| source.strip_prefix("file:").map(str::to_owned);
+        let existing = vec![
+            existing(1, "same", "Same", "file:same.md"),
+            existing(2, "changed", "Old", "file:changed.md"),
+            existing(3, "renamed", "Text", "file:renamed.md"),
+            existing(4, "removed", "Text", "file:removed.md"),
+            existing(5, "crawled", "Text", "https://example.com"),
+        ];
+        let documents = vec![
+            imported("same", "Same", "file:same.md"),
+            imported("changed", "New", "file:changed.md"),
+            imported("new name", "Text", "file:renamed.md"),
+            imported("added", "Text", "file:added.md"),
+        ];
+
+        let (changes, summary) = find_changes(existing, documents, key);
+
+        assert_eq!(summary.added, vec!["added"]);
+        assert_eq!(summary.changed, vec!["changed", "new name"]);
+        assert_eq!(summary.removed, vec!["removed"]);
+        assert_eq!(summary.unchanged, 1);
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(
+            changes
+                .changed
+                .iter()
+                .map(|(version, _)| *version)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(changes.removed, vec![4]);
+    }
+
+    #[test]
+    fn test_document_name() {
+        assert_eq!(
+            document_name("docs/guide/install.rst"),
+            "docs/guide/install"
+        );
+        assert_eq!(document_name("README.md"), "README");
+        assert_eq!(document_name("docs/.md"), "docs/.md");
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = ImportSummary {
+            added: vec!["a".to_owned()],
+            changed: vec!["b".to_owned(), "c".to_owned()],
+            skipped: vec![("d".to_owned(), "Too large".to_owned())],
+            version: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            summary.to_string(),
+            "Added 1, changed 2 and removed 0 documents in new project version 3. 1 files were skipped"
+        );
+        assert_eq!(ImportSummary::default().to_string(), "No changes found");
+    }
+
+    #[test]
+    fn test_link_documents() {
+        let key = |source: &str| source.strip_prefix("page:").map(str::to_owned);
+        let documents = vec![
+            imported("a", "[B](page:b), [C](page:c) and [D](page:d)", "page:a"),
+            imported("b", "[A](page:a) and [web](https://example.com)", "page:b"),
+            imported("c", "Text", "page:c"),
+        ];
+        let keys = documents
+            .iter()
+            .filter_map(|doc| key(doc.source()))
+            .collect::<HashSet<_>>();
+
+        let ids = HashMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+        let (linked, complete) = link_documents(7, &documents, &keys, &ids, key);
+        assert!(!complete);
+        assert_eq!(
+            linked[0].content(),
+            "[B](/projects/7/documents/2), [C](page:c) and D"
+        );
+        assert_eq!(
+            linked[1].content(),
+            "[A](/projects/7/documents/1) and [web](https://example.com)"
+        );
+
+        let ids = HashMap::from([
+            ("a".to_owned(), 1),
+            ("b".to_owned(), 2),
+            ("c".to_owned(), 3),
+        ]);
+        let (linked, complete) = link_documents(7, &documents, &keys, &ids, key);
+        assert!(complete);
+        assert_eq!(
+            linked[0].content(),
+            "[B](/projects/7/documents/2), [C](/projects/7/documents/3) and D"
+        );
+        assert_eq!(linked[0].source(), "page:a");
+    }
+}
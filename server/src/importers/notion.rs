@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+
+use super::{
+    links::{rewrite_links, LinkRewrite},
+    upload::{extract_zip, UploadLimits},
+};
+use crate::{
+    models::CacheValidators,
+    parsing::{Content, Done, HtmlParser},
+};
+
+/// The id Notion appends to the names of exported pages and their folders
+static PAGE_ID: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\s+([0-9a-f]{32})$").expect("valid page id regex"));
+/// The id at the end of a notion.so url
+static URL_PAGE_ID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^https://(?:www\.)?notion\.so/.*?([0-9a-f]{32})(?:[?#].*)?$")
+        .expect("valid page url regex")
+});
+
+/// A Markdown and CSV export of Notion pages, a zip archive with a file per
+/// page and a folder per page with sub pages. Databases are exported as CSV
+/// files, which are inlined as tables where pages link to them. Pages are
+/// named by their folders, links between them point to the source of the
+/// linked page and other attachments are dropped.
+pub struct NotionExport {
+    ids: HashSet<String>,
+    pub documents: Vec<HtmlParser<Done>>,
+    /// Pages that could not be imported, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+impl NotionExport {
+    /// Read the pages from the extracted files of the export. Large exports are
+    /// split into zip archives inside the export, which are extracted as well.
+    pub fn read(files: Vec<(String, Vec<u8>)>, limits: &UploadLimits) -> Result<Self> {
+        let mut extracted = vec![];
+        let mut skipped = vec![];
+        for (path, bytes) in files {
+            if path.to_lowercase().ends_with(".zip") {
+                let archive = extract_zip(bytes, limits)?;
+                extracted.extend(archive.files);
+                skipped.extend(archive.skipped);
+            } else {
+                extracted.push((path, bytes));
+            }
+        }
+
+        let mut pages = HashMap::new();
+        let mut tables = HashMap::new();
+        for (path, bytes) in extracted {
+            let text = || String::from_utf8_lossy(&bytes).into_owned();
+            if path.ends_with(".md") {
+                pages.insert(path, text());
+            } else if path.ends_with(".csv") {
+                tables.insert(path, text());
+            }
+        }
+        if pages.is_empty() {
+            bail!("Not a Notion export, no Markdown pages found");
+        }
+
+        let ids = pages
+            .keys()
+            .map(|path| (path.to_owned(), page_id(path)))
+            .collect::<HashMap<_, _>>();
+        let source = |id: &str| format!("notion:{id}");
+
+        let mut documents = vec![];
+        for (path, text) in &pages {
+            let folder = path.rsplit_once('/').map_or("", |(folder, _)| folder);
+
+            let markdown = rewrite_links(text, |link| {
+                if let Some(id) = URL_PAGE_ID
+                    .captures(link.target)
+                    .and_then(|captures| captures.get(1))
+                    .filter(|id| ids.values().any(|page| page == id.as_str()))
+                {
+                    return LinkRewrite::Target(source(id.as_str()));
+                }
+                if link.target.contains(':') || link.target.starts_with('#') {
+                    return LinkRewrite::Keep;
+                }
+
+                let target = percent_decode_str(link.target).decode_utf8_lossy();
+                let Some(target) = resolve_path(folder, &target) else {
+                    return LinkRewrite::Text(link.text.to_owned());
+                };
+
+                if link.is_image {
+                    LinkRewrite::Text(String::new())
+                } else if let Some(id) = ids.get(&target) {
+                    LinkRewrite::Target(source(id))
+                } else if let Some(table) = tables.get(&target).and_then(|csv| csv_to_table(csv)) {
+                    LinkRewrite::Text(format!("**{}**\n\n{table}", strip_id(link.text)))
+                } else {
+                    LinkRewrite::Text(link.text.to_owned())
+                }
+            });
+
+            match Content::Markdown(markdown).parse(
+                &page_name(path),
+                source(&ids[path]),
+                CacheValidators::default(),
+            ) {
+                Ok(doc) => documents.push(doc),
+                Err(e) => skipped.push((path.to_owned(), e.to_string())),
+            }
+        }
+        documents.sort_by(|a, b| a.name().cmp(b.name()));
+
+        Ok(Self {
+            ids: ids.into_values().collect(),
+            documents,
+            skipped,
+        })
+    }
+
+    /// Identifies the pages of an earlier export by their id. Notion exports
+    /// can be of any part of a workspace, so pages missing from this export
+    /// are left alone.
+    pub fn document_key(&self, source: &str) -> Option<String> {
+        let id = source.strip_prefix("notion:")?;
+        self.ids.contains(id).then(|| id.to_owned())
+    }
+}
+
+/// The id of the page, or its path for exports without ids
+fn page_id(path: &str) -> String {
+    let stem = path.trim_end_matches(".md");
+    match PAGE_ID.captures(stem).and_then(|captures| captures.get(1)) {
+        Some(id) => id.as_str().to_owned(),
+        None => stem.to_owned(),
+    }
+}
+
+/// The name of the page by its folders, without the ids
+fn page_name(path: &str) -> String {
+    path.trim_end_matches(".md")
+        .split('/')
+        .map(strip_id)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn strip_id(name: &str) -> &str {
+    match PAGE_ID.find(name) {
+        Some(id) => &name[..id.start()],
+        None => name,
+    }
+}
+
+/// The path of a link relative to the folder of the page, `None` if it leads
+/// out of the export
+fn resolve_path(folder: &str, target: &str) -> Option<String> {
+    let target = target.split(['#', '?']).next().unwrap_or_default();
+    let mut segments = folder
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    Some(segments.join("/"))
+}
+
+/// A Markdown table of a database exported as CSV
+fn csv_to_table(csv: &str) -> Option<String> {
+    let csv = csv.trim_start_matches('\u{feff}');
+    let mut rows = parse_csv(csv).into_iter();
+    let header = rows.next()?;
+    let cell = |value: &str| value.trim().replace('|', "\\|").replace('\n', "<br>");
+
+    let mut table = format!(
+        "| {} |\n|{}|",
+        header
+            .iter()
+            .map(|value| cell(value))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        vec![" --- "; header.len()].join("|")
+    );
+    for row in rows {
+        let mut values = row.iter().map(|value| cell(value)).collect::<Vec<_>>();
+        values.resize(header.len(), String::new());
+        table.push_str(&format!("\n| {} |", values.join(" | ")));
+    }
+
+    Some(table)
+}
+
+/// The rows of a CSV file, with quoted values that may contain commas,
+/// newlines and doubled quotes
+fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if value.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut value)),
+            ('\r', false) => (),
+            ('\n', false) => {
+                row.push(std::mem::take(&mut value));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => value.push(c),
+        }
+    }
+    if !value.is_empty() || !row.is_empty() {
+        row.push(value);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::zip_archive;
+
+    const HOME: &str = "1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d";
+    const SETUP: &str = "0f9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c";
+    const TASKS: &str = "9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d";
+
+    fn export() -> Vec<(String, Vec<u8>)> {
+        let home = format!(
+            "# Home\n\nStart with [Setup](Home%20{HOME}/Setup%20{SETUP}.md).\n\n\
+             [Tasks](Home%20{HOME}/Tasks%20{TASKS}.csv)\n\n\
+             ![diagram.png](Home%20{HOME}/diagram.png)\n\n\
+             [Notes](https://www.notion.so/Setup-{SETUP}) and [Example](https://example.com)"
+        );
+        let setup = format!(
+            "# Setup\n\nStatus: Done\n\nBack [home](../Home%20{HOME}.md) or to the [spec](spec.pdf)."
+        );
+        let tasks = "\u{feff}Name,Status,Notes\nWrite docs,Done,\"Short, sweet\"\nReview,Open,\"Two\nlines | \"\"quoted\"\"\"\n";
+
+        vec![
+            (format!("Home {HOME}.md"), home.into_bytes()),
+            (format!("Home {HOME}/Setup {SETUP}.md"), setup.into_bytes()),
+            (
+                format!("Home {HOME}/Tasks {TASKS}.csv"),
+                tasks.as_bytes().to_vec(),
+            ),
+            (format!("Home {HOME}/diagram.png"), b"png".to_vec()),
+        ]
+    }
+
+    #[test]
+    fn test_read() {
+        let export = NotionExport::read(export(), &Default::default()).unwrap();
+
+        assert!(export.skipped.is_empty());
+        let documents = export
+            .documents
+            .iter()
+            .map(|doc| (doc.name(), doc.source()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            documents,
+            vec![
+                ("Home", format!("notion:{HOME}").as_str()),
+                ("Home/Setup", format!("notion:{SETUP}").as_str()),
+            ]
+        );
+
+        assert_eq!(
+            export.documents[0].content(),
+            format!(
+                "# Home\n\nStart with [Setup](notion:{SETUP}).\n\n\
+                 **Tasks**\n\n\
+                 | Name | Status | Notes |\n| --- | --- | --- |\n\
+                 | Write docs | Done | Short, sweet |\n\
+                 | Review | Open | Two<br>lines \\| \"quoted\" |\n\n\
+                 [Notes](notion:{SETUP}) and [Example](https://example.com)"
+            )
+        );
+        assert_eq!(
+            export.documents[1].content(),
+            format!("# Setup\n\nStatus: Done\n\nBack [home](notion:{HOME}) or to the spec.")
+        );
+    }
+
+    #[test]
+    fn test_read_nested_archive() {
+        let files = export();
+        let files = files
+            .iter()
+            .map(|(path, bytes)| (path.as_str(), bytes.as_slice()))
+            .collect::<Vec<_>>();
+        let archive = zip_archive(&files);
+
+        let export = NotionExport::read(
+            vec![("Export-Part-1.zip".to_owned(), archive)],
+            &Default::default(),
+        )
+        .unwrap();
+
+        assert_eq!(export.documents.len(), 2);
+        assert_eq!(
+            export.document_key(&format!("notion:{SETUP}")),
+            Some(SETUP.to_owned())
+        );
+        assert_eq!(export.document_key("notion:other"), None);
+        assert_eq!(export.document_key("confluence:DOCS:1"), None);
+    }
+
+    #[test]
+    fn test_not_an_export() {
+        let files = vec![("data.csv".to_owned(), b"a,b".to_vec())];
+        assert!(NotionExport::read(files, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(resolve_path("a/b", "../c.md#x"), Some("a/c.md".to_owned()));
+        assert_eq!(resolve_path("", "a/./b.md"), Some("a/b.md".to_owned()));
+        assert_eq!(resolve_path("a", "../../b.md"), None);
+    }
+}
@@ -0,0 +1,741 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Context, Result};
+use percent_encoding::percent_decode_str;
+use serde_json::{Map, Value};
+
+use super::{markdown_document, one_line};
+use crate::{
+    parsing::{Done, HtmlParser},
+    web_crawler::limits::format_bytes,
+};
+
+/// Larger specs are rejected
+pub const MAX_SPEC_BYTES: usize = 10 * 1024 * 1024;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Nested schemas deeper than this are named but not expanded
+const MAX_SCHEMA_DEPTH: usize = 4;
+
+/// How many `$ref`s are followed before giving up on a chain
+const MAX_REF_HOPS: usize = 16;
+
+/// An OpenAPI 3.x or Swagger 2 spec, which is turned into one document per
+/// operation and one per schema. `$ref`s within the spec are resolved.
+pub struct OpenApiSpec {
+    spec: Value,
+}
+
+impl OpenApiSpec {
+    /// Parse a JSON or YAML spec
+    pub fn parse(text: &str) -> Result<Self> {
+        if text.len() > MAX_SPEC_BYTES {
+            bail!("The spec is larger than {}", format_bytes(MAX_SPEC_BYTES));
+        }
+
+        let text = text.trim_start_matches('\u{feff}');
+        let spec = match serde_json::from_str::<Value>(text) {
+            Ok(spec) => spec,
+            Err(_) => {
+                let yaml: serde_yaml::Value =
+                    serde_yaml::from_str(text).context("The spec is not valid JSON or YAML")?;
+                yaml_to_json(yaml)
+            }
+        };
+
+        let version = spec
+            .get("openapi")
+            .or_else(|| spec.get("swagger"))
+            .and_then(scalar_string)
+            .unwrap_or_default();
+        if !(version.starts_with("3.") || version.starts_with("2.")) {
+            bail!("Not an OpenAPI 3 or Swagger 2 spec");
+        }
+
+        Ok(Self { spec })
+    }
+
+    pub fn title(&self) -> &str {
+        self.spec
+            .pointer("/info/title")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .unwrap_or("API")
+    }
+
+    fn is_swagger(&self) -> bool {
+        self.spec.get("swagger").is_some()
+    }
+
+    fn source(&self, key: &str) -> String {
+        format!("openapi:{}:{key}", self.title())
+    }
+
+    /// Identifies the documents of a previous import of the same API, by their
+    /// operation or schema
+    pub fn document_key(&self, source: &str) -> Option<String> {
+        let prefix = self.source("");
+        source.strip_prefix(&prefix).map(str::to_owned)
+    }
+
+    /// One document per operation, named by its first tag, and one per schema
+    pub fn documents(&self) -> Vec<HtmlParser<Done>> {
+        let mut documents = vec![];
+
+        let paths = self.spec.get("paths").and_then(Value::as_object);
+        for (path, item) in paths.into_iter().flatten() {
+            let item = self.resolve(item);
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let (name, content) = self.operation(path, method, item, operation);
+                let source = self.source(&format!("{} {path}", method.to_uppercase()));
+                documents.extend(markdown_document(&name, content, source));
+            }
+        }
+
+        for (name, schema) in self.schemas().into_iter().flatten() {
+            let content = self.schema(name, schema);
+            let source = self.source(&format!("schema:{name}"));
+            documents.extend(markdown_document(
+                &format!("Schemas/{name}"),
+                content,
+                source,
+            ));
+        }
+
+        documents
+    }
+
+    fn schemas(&self) -> Option<&Map<String, Value>> {
+        self.spec
+            .pointer("/components/schemas")
+            .or_else(|| self.spec.get("definitions"))
+            .and_then(Value::as_object)
+    }
+
+    /// Follow the `$ref`s of a value to what they point to. References to other
+    /// files can not be resolved and are returned as they are.
+    fn resolve<'a>(&'a self, mut value: &'a Value) -> &'a Value {
+        for _ in 0..MAX_REF_HOPS {
+            let target = value
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| {
+                    self.spec
+                        .pointer(&percent_decode_str(pointer).decode_utf8_lossy())
+                });
+            match target {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+
+    fn base_url(&self) -> Option<String> {
+        if let Some(url) = self.spec.pointer("/servers/0/url").and_then(Value::as_str) {
+            return Some(url.to_owned());
+        }
+
+        let host = self.spec.get("host").and_then(Value::as_str)?;
+        let scheme = self
+            .spec
+            .pointer("/schemes/0")
+            .and_then(Value::as_str)
+            .unwrap_or("https");
+        let base_path = self
+            .spec
+            .get("basePath")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        Some(format!("{scheme}://{host}{base_path}"))
+    }
+
+    fn operation(
+        &self,
+        path: &str,
+        method: &str,
+        item: &Value,
+        operation: &Value,
+    ) -> (String, String) {
+        let method = method.to_uppercase();
+        let summary = text(operation, "summary");
+        let operation_id = text(operation, "operationId");
+        let tags = operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| tags.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let title = summary
+            .or(operation_id)
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("{method} {path}"));
+        let name = format!("{}/{title}", tags.first().unwrap_or(&"default"));
+
+        let mut md = format!("# {title}\n\n`{method} {path}`\n\n");
+        if let Some(description) = text(operation, "description") {
+            let _ = write!(md, "{description}\n\n");
+        }
+
+        let mut details = vec![];
+        if let Some(url) = self.base_url() {
+            details.push(format!("Base URL: {url}"));
+        }
+        if let Some(operation_id) = operation_id {
+            details.push(format!("Operation ID: `{operation_id}`"));
+        }
+        if !tags.is_empty() {
+            details.push(format!("Tags: {}", tags.join(", ")));
+        }
+        if operation.get("deprecated").and_then(Value::as_bool) == Some(true) {
+            details.push("Deprecated".to_owned());
+        }
+        if !details.is_empty() {
+            for detail in details {
+                let _ = writeln!(md, "- {detail}");
+            }
+            md.push('\n');
+        }
+
+        // Path level parameters apply unless the operation overrides them
+        let mut parameters: Vec<&Value> = vec![];
+        let all = [item, operation]
+            .into_iter()
+            .filter_map(|value| value.get("parameters").and_then(Value::as_array))
+            .flatten()
+            .map(|parameter| self.resolve(parameter));
+        for parameter in all {
+            let key = |p: &Value| {
+                (
+                    text(p, "name").map(str::to_owned),
+                    text(p, "in").map(str::to_owned),
+                )
+            };
+            parameters.retain(|other| key(other) != key(parameter));
+            parameters.push(parameter);
+        }
+
+        let (body, parameters): (Vec<_>, Vec<_>) = parameters
+            .into_iter()
+            .partition(|parameter| text(parameter, "in") == Some("body"));
+
+        if !parameters.is_empty() {
+            md.push_str("## Parameters\n\n");
+            for parameter in parameters {
+                self.write_parameter(&mut md, parameter);
+            }
+            md.push('\n');
+        }
+
+        if let Some(request_body) = operation.get("requestBody") {
+            let request_body = self.resolve(request_body);
+            md.push_str("## Request body\n\n");
+            if let Some(description) = text(request_body, "description") {
+                let _ = write!(md, "{description}\n\n");
+            }
+            if request_body.get("required").and_then(Value::as_bool) == Some(true) {
+                md.push_str("The request body is required.\n\n");
+            }
+            self.write_content(&mut md, request_body.get("content"));
+        } else if let Some(body) = body.first() {
+            md.push_str("## Request body\n\n");
+            if let Some(description) = text(body, "description") {
+                let _ = write!(md, "{description}\n\n");
+            }
+            let content_type = self.media_types(operation, "consumes");
+            self.write_media(&mut md, &content_type, body.get("schema"), vec![]);
+        }
+
+        let responses = operation.get("responses").and_then(Value::as_object);
+        if let Some(responses) = responses.filter(|responses| !responses.is_empty()) {
+            md.push_str("## Responses\n\n");
+            for (status, response) in responses {
+                let response = self.resolve(response);
+                let reason = status
+                    .parse::<u16>()
+                    .ok()
+                    .and_then(|status| http::StatusCode::from_u16(status).ok())
+                    .and_then(|status| status.canonical_reason());
+                match reason {
+                    Some(reason) => {
+                        let _ = write!(md, "### {status} {reason}\n\n");
+                    }
+                    None => {
+                        let _ = write!(md, "### {status}\n\n");
+                    }
+                }
+                if let Some(description) = text(response, "description") {
+                    let _ = write!(md, "{description}\n\n");
+                }
+
+                if self.is_swagger() {
+                    let examples = response
+                        .get("examples")
+                        .and_then(Value::as_object)
+                        .map(|examples| examples.values().collect())
+                        .unwrap_or_default();
+                    if response.get("schema").is_some() {
+                        let content_type = self.media_types(operation, "produces");
+                        self.write_media(&mut md, &content_type, response.get("schema"), examples);
+                    }
+                } else {
+                    self.write_content(&mut md, response.get("content"));
+                }
+            }
+        }
+
+        (name, md)
+    }
+
+    /// The `consumes` or `produces` media types of a Swagger 2 operation
+    fn media_types(&self, operation: &Value, field: &str) -> String {
+        operation
+            .get(field)
+            .or_else(|| self.spec.get(field))
+            .and_then(Value::as_array)
+            .map(|types| {
+                types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .filter(|types| !types.is_empty())
+            .unwrap_or_else(|| "application/json".to_owned())
+    }
+
+    fn write_parameter(&self, md: &mut String, parameter: &Value) {
+        let name = text(parameter, "name").unwrap_or("unnamed");
+        let location = text(parameter, "in").unwrap_or("query");
+        // Swagger 2 describes the type on the parameter itself
+        let schema = parameter.get("schema").unwrap_or(parameter);
+
+        let mut attributes = vec![format!("in {location}"), self.type_name(schema)];
+        if parameter.get("required").and_then(Value::as_bool) == Some(true) {
+            attributes.push("required".to_owned());
+        }
+        if parameter.get("deprecated").and_then(Value::as_bool) == Some(true) {
+            attributes.push("deprecated".to_owned());
+        }
+
+        let _ = write!(md, "- `{name}` ({})", attributes.join(", "));
+        if let Some(description) = text(parameter, "description") {
+            let _ = write!(md, ": {}", one_line(description));
+        }
+        md.push('\n');
+
+        let example = parameter.get("example").or_else(|| schema.get("example"));
+        if let Some(example) = example {
+            let _ = writeln!(md, "  Example: `{}`", inline_value(example));
+        }
+    }
+
+    /// The media types of an OpenAPI 3 request or response body
+    fn write_content(&self, md: &mut String, content: Option<&Value>) {
+        for (content_type, media) in content.and_then(Value::as_object).into_iter().flatten() {
+            let mut examples = vec![];
+            if let Some(example) = media.get("example") {
+                examples.push(example);
+            }
+            let named = media.get("examples").and_then(Value::as_object);
+            for example in named.into_iter().flatten().map(|(_, e)| self.resolve(e)) {
+                if let Some(value) = example.get("value") {
+                    examples.push(value);
+                }
+            }
+
+            self.write_media(md, content_type, media.get("schema"), examples);
+        }
+    }
+
+    fn write_media<'a>(
+        &'a self,
+        md: &mut String,
+        content_type: &str,
+        schema: Option<&'a Value>,
+        mut examples: Vec<&'a Value>,
+    ) {
+        match schema {
+            Some(schema) => {
+                let _ = write!(
+                    md,
+                    "Content type `{content_type}`, schema `{}`:\n\n",
+                    self.type_name(schema)
+                );
+                let mut fields = String::new();
+                self.write_fields(&mut fields, schema, 0, &mut vec![]);
+                if !fields.is_empty() {
+                    let _ = writeln!(md, "{fields}");
+                }
+
+                if examples.is_empty() {
+                    examples.extend(self.resolve(schema).get("example"));
+                }
+            }
+            None => {
+                let _ = write!(md, "Content type `{content_type}`\n\n");
+            }
+        }
+
+        for example in examples {
+            let _ = write!(md, "Example:\n\n{}\n\n", code_block(example));
+        }
+    }
+
+    fn schema(&self, name: &str, schema: &Value) -> String {
+        let resolved = self.resolve(schema);
+        let mut md = format!("# {name}\n\n");
+        if let Some(description) = text(resolved, "description") {
+            let _ = write!(md, "{description}\n\n");
+        }
+        let _ = write!(md, "Type: `{}`\n\n", self.type_name(resolved));
+
+        let mut fields = String::new();
+        self.write_fields(&mut fields, schema, 0, &mut vec![name.to_owned()]);
+        if !fields.is_empty() {
+            let _ = write!(md, "## Properties\n\n{fields}\n");
+        }
+
+        if let Some(example) = resolved.get("example") {
+            let _ = write!(md, "## Example\n\n{}\n\n", code_block(example));
+        }
+
+        md
+    }
+
+    /// A short description of the type of a schema, using the names of
+    /// referenced schemas
+    fn type_name(&self, schema: &Value) -> String {
+        if let Some(name) = ref_name(schema) {
+            return name.to_owned();
+        }
+
+        for (keyword, label) in [
+            ("oneOf", "one of"),
+            ("anyOf", "any of"),
+            ("allOf", "all of"),
+        ] {
+            if let Some(variants) = schema.get(keyword).and_then(Value::as_array) {
+                let names = variants
+                    .iter()
+                    .map(|variant| self.type_name(variant))
+                    .collect::<Vec<_>>();
+                return format!("{label} {}", names.join(", "));
+            }
+        }
+
+        let mut name = match schema.get("type") {
+            Some(Value::String(kind)) if kind == "array" => match schema.get("items") {
+                Some(items) => format!("array of {}", self.type_name(items)),
+                None => "array".to_owned(),
+            },
+            Some(Value::String(kind)) => kind.to_owned(),
+            // OpenAPI 3.1 allows a list of types
+            Some(Value::Array(kinds)) => kinds
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" or "),
+            _ if schema.get("properties").is_some() => "object".to_owned(),
+            _ => "any".to_owned(),
+        };
+
+        if let Some(format) = text(schema, "format") {
+            let _ = write!(name, " ({format})");
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            let values = values.iter().map(inline_value).collect::<Vec<_>>();
+            let _ = write!(name, ", one of {}", values.join(", "));
+        }
+        if schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+            name.push_str(", nullable");
+        }
+
+        name
+    }
+
+    /// The properties of an object schema or the items of an array as a nested
+    /// list. `seen` holds the named schemas being expanded, so recursive schemas
+    /// end instead of repeating.
+    fn write_fields(&self, md: &mut String, schema: &Value, depth: usize, seen: &mut Vec<String>) {
+        let name = ref_name(schema).map(str::to_owned);
+        if let Some(name) = &name {
+            if seen.contains(name) {
+                return;
+            }
+            seen.push(name.to_owned());
+        }
+
+        let resolved = self.resolve(schema);
+        if let Some(items) = resolved.get("items") {
+            self.write_fields(md, items, depth, seen);
+        }
+
+        let mut properties: Vec<(&String, &Value)> = vec![];
+        let mut required: Vec<&str> = vec![];
+        let parts = resolved
+            .get("allOf")
+            .and_then(Value::as_array)
+            .map(|parts| parts.iter().map(|part| self.resolve(part)).collect())
+            .unwrap_or_else(|| vec![resolved]);
+        for part in parts {
+            properties.extend(
+                part.get("properties")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten(),
+            );
+            required.extend(
+                part.get("required")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str),
+            );
+        }
+
+        let indent = "  ".repeat(depth);
+        for (property, property_schema) in properties {
+            let resolved_property = self.resolve(property_schema);
+            let mut attributes = vec![self.type_name(property_schema)];
+            if required.contains(&property.as_str()) {
+                attributes.push("required".to_owned());
+            }
+            if resolved_property.get("readOnly").and_then(Value::as_bool) == Some(true) {
+                attributes.push("read only".to_owned());
+            }
+
+            let _ = write!(md, "{indent}- `{property}` ({})", attributes.join(", "));
+            if let Some(description) = text(resolved_property, "description") {
+                let _ = write!(md, ": {}", one_line(description));
+            }
+            md.push('\n');
+
+            if depth + 1 < MAX_SCHEMA_DEPTH {
+                self.write_fields(md, property_schema, depth + 1, seen);
+            }
+        }
+
+        if name.is_some() {
+            seen.pop();
+        }
+    }
+}
+
+/// The name of the schema a `$ref` points to, e.g. `Pet` for `#/components/schemas/Pet`
+fn ref_name(value: &Value) -> Option<&str> {
+    let reference = value.get("$ref")?.as_str()?;
+    reference.rsplit('/').next().filter(|name| !name.is_empty())
+}
+
+fn text<'a>(value: &'a Value, field: &str) -> Option<&'a str> {
+    value
+        .get(field)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.to_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn inline_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.to_owned(),
+        value => value.to_string(),
+    }
+}
+
+fn code_block(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("```\n{}\n```", text.trim_end()),
+        value => {
+            let json = serde_json::to_string_pretty(value).unwrap_or_default();
+            format!("```json\n{json}\n```")
+        }
+    }
+}
+
+/// YAML allows keys that are not strings, like the status codes of responses,
+/// which JSON does not
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(value) => Value::Bool(value),
+        serde_yaml::Value::Number(number) => {
+            if let Some(number) = number.as_i64() {
+                Value::from(number)
+            } else if let Some(number) = number.as_u64() {
+                Value::from(number)
+            } else {
+                number
+                    .as_f64()
+                    .and_then(serde_json::Number::from_f64)
+                    .map_or(Value::Null, Value::Number)
+            }
+        }
+        serde_yaml::Value::String(text) => Value::String(text),
+        serde_yaml::Value::Sequence(values) => {
+            Value::Array(values.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match yaml_to_json(key) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, yaml_to_json(value))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PETSTORE: &str = include_str!("test_files/petstore.yaml");
+    const PETSTORE_SWAGGER: &str = include_str!("test_files/petstore-swagger.json");
+
+    fn document<'a>(documents: &'a [HtmlParser<Done>], name: &str) -> &'a HtmlParser<Done> {
+        documents
+            .iter()
+            .find(|doc| doc.name() == name)
+            .unwrap_or_else(|| panic!("No document named {name}"))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(OpenApiSpec::parse(PETSTORE).is_ok());
+        assert!(OpenApiSpec::parse(PETSTORE_SWAGGER).is_ok());
+        assert!(OpenApiSpec::parse("{\"name\": \"not a spec\"}").is_err());
+        assert!(OpenApiSpec::parse("openapi: [").is_err());
+    }
+
+    #[test]
+    fn test_openapi_documents() {
+        let spec = OpenApiSpec::parse(PETSTORE).unwrap();
+        let documents = spec.documents();
+
+        let mut names = documents.iter().map(|doc| doc.name()).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "Schemas/Category",
+                "Schemas/Error",
+                "Schemas/NewPet",
+                "Schemas/Pet",
+                "default/GET /health",
+                "pets/Create a pet",
+                "pets/Delete a pet",
+                "pets/Info for a specific pet",
+                "pets/List all pets",
+            ]
+        );
+
+        let show = document(&documents, "pets/Info for a specific pet");
+        assert_eq!(show.source(), "openapi:Petstore:GET /pets/{petId}");
+        let content = show.content();
+        assert!(content.starts_with("# Info for a specific pet\n\n`GET /pets/{petId}`"));
+        assert!(content.contains("- Base URL: https://petstore.example.com/v1"));
+        assert!(content.contains("- Tags: pets, store"));
+        // Parameters are resolved from the components
+        assert!(content.contains("- `petId` (in path, string, required): The id of the pet"));
+        assert!(content.contains("- `X-Request-Id` (in header, string (uuid))"));
+        assert!(content.contains("### 200 OK"));
+        assert!(content.contains("Content type `application/json`, schema `Pet`:"));
+        assert!(content.contains("- `id` (integer (int64), required, read only)"));
+        assert!(content
+            .contains("- `status` (string, one of available, pending, sold): Status in the store"));
+        // Nested schemas are expanded, recursive ones are not repeated
+        assert!(content.contains("- `category` (Category)\n  - `name` (string)"));
+        assert!(content.contains("  - `parent` (Category)\n- `name`"));
+        assert!(content.contains("### default\n\nUnexpected error"));
+        assert!(content.contains("\"name\": \"Rex\""));
+
+        let create = document(&documents, "pets/Create a pet").content();
+        assert!(
+            create.contains("## Request body\n\nThe pet to add\n\nThe request body is required.")
+        );
+        assert!(create.contains("schema `NewPet`"));
+        // Properties of all parts of an `allOf` are listed
+        assert!(create.contains("- `name` (string, required)"));
+        assert!(create.contains("- `tag` (string)"));
+        assert!(create.contains("### 201 Created"));
+
+        let delete = document(&documents, "pets/Delete a pet").content();
+        assert!(delete.contains("- Deprecated"));
+
+        let health = document(&documents, "default/GET /health");
+        assert!(health.content().contains("### 204 No Content"));
+
+        let pet = document(&documents, "Schemas/Pet");
+        assert_eq!(pet.source(), "openapi:Petstore:schema:Pet");
+        assert!(pet.content().contains("A pet in the store"));
+        assert!(pet.content().contains("Type: `all of NewPet, object`"));
+        assert!(pet
+            .content()
+            .contains("## Properties\n\n- `category` (Category)"));
+        assert!(pet.content().contains("- `name` (string, required)"));
+        assert!(pet.content().contains("## Example"));
+
+        let category = document(&documents, "Schemas/Category").content();
+        assert!(category.contains("- `parent` (Category)\n"));
+        assert!(!category.contains("  - `name`"));
+    }
+
+    #[test]
+    fn test_swagger_documents() {
+        let spec = OpenApiSpec::parse(PETSTORE_SWAGGER).unwrap();
+        let documents = spec.documents();
+
+        assert_eq!(documents.len(), 4);
+
+        let list = document(&documents, "pets/listPets");
+        assert_eq!(list.source(), "openapi:Swagger Petstore:GET /pets");
+        let content = list.content();
+        assert!(content.contains("- Base URL: https://petstore.example.com/api"));
+        assert!(content.contains("- `limit` (in query, integer (int32)): How many items to return"));
+        assert!(content.contains("Content type `application/json`, schema `array of Pet`:"));
+        assert!(content.contains("- `id` (integer (int64), required)"));
+        assert!(content.contains("\"name\": \"Rex\""));
+
+        let create = document(&documents, "pets/createPets").content();
+        assert!(create.contains("## Request body\n\nThe new pet"));
+        assert!(create.contains("Content type `application/json`, schema `Pet`:"));
+        assert!(!create.contains("## Parameters"));
+
+        let pet = document(&documents, "Schemas/Pet");
+        assert!(pet.content().contains("- `name` (string, required)"));
+    }
+
+    #[test]
+    fn test_document_key() {
+        let spec = OpenApiSpec::parse(PETSTORE).unwrap();
+
+        assert_eq!(
+            spec.document_key("openapi:Petstore:GET /pets"),
+            Some("GET /pets".to_owned())
+        );
+        assert_eq!(spec.document_key("openapi:Other:GET /pets"), None);
+        assert_eq!(spec.document_key("https://example.com"), None);
+    }
+}
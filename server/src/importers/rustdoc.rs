@@ -0,0 +1,1303 @@
+use std::{collections::HashSet, fmt::Write, ops::RangeInclusive};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use super::{markdown_document, one_line};
+use crate::{
+    parsing::{Done, HtmlParser},
+    web_crawler::limits::format_bytes,
+};
+
+/// Larger files are rejected
+pub const MAX_RUSTDOC_BYTES: usize = 50 * 1024 * 1024;
+
+/// The supported `format_version`s of the JSON output. Item kinds are object
+/// keys of `inner` starting with the oldest, the newest is the one tested with.
+pub const FORMAT_VERSIONS: RangeInclusive<u64> = 28..=57;
+
+/// The item kinds listed in module documents, in order, with their section
+/// title and the word used in the title of their own document
+const KINDS: [(&str, &str, &str); 13] = [
+    ("module", "Modules", "Module"),
+    ("struct", "Structs", "Struct"),
+    ("enum", "Enums", "Enum"),
+    ("union", "Unions", "Union"),
+    ("trait", "Traits", "Trait"),
+    ("trait_alias", "Trait aliases", "Trait alias"),
+    ("function", "Functions", "Function"),
+    ("type_alias", "Type aliases", "Type alias"),
+    ("typedef", "Type aliases", "Type alias"),
+    ("constant", "Constants", "Constant"),
+    ("static", "Statics", "Static"),
+    ("macro", "Macros", "Macro"),
+    ("proc_macro", "Macros", "Macro"),
+];
+
+/// The JSON output of `cargo rustdoc -- --output-format json`, which is turned
+/// into one document per public module and item, named by their path like
+/// `my_crate::module::Type`. Methods and trait implementations are part of the
+/// document of their type.
+///
+/// Field names changed between format versions, the older names are accepted
+/// where they are known.
+pub struct RustdocCrate {
+    doc: Value,
+}
+
+/// An item listed in a module document
+struct Entry {
+    kind: &'static str,
+    name: String,
+    summary: Option<String>,
+}
+
+impl RustdocCrate {
+    pub fn parse(text: &str) -> Result<Self> {
+        if text.len() > MAX_RUSTDOC_BYTES {
+            bail!(
+                "The file is larger than {}",
+                format_bytes(MAX_RUSTDOC_BYTES)
+            );
+        }
+
+        let doc: Value = serde_json::from_str(text).context("The file is not valid JSON")?;
+        let crate_doc = Self { doc };
+        if crate_doc
+            .doc
+            .get("index")
+            .and_then(Value::as_object)
+            .is_none()
+            || crate_doc.root().is_none()
+        {
+            bail!("Not the JSON output of rustdoc");
+        }
+
+        let format_version = crate_doc
+            .doc
+            .get("format_version")
+            .and_then(Value::as_u64)
+            .context("The rustdoc output has no format version")?;
+        if !FORMAT_VERSIONS.contains(&format_version) {
+            bail!(
+                "Unsupported rustdoc format version {format_version}, versions {} to {} are supported",
+                FORMAT_VERSIONS.start(),
+                FORMAT_VERSIONS.end()
+            );
+        }
+
+        Ok(crate_doc)
+    }
+
+    fn root(&self) -> Option<&Value> {
+        self.item(self.doc.get("root")?)
+    }
+
+    /// The name of the crate
+    pub fn name(&self) -> &str {
+        self.root()
+            .and_then(|root| root.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or("crate")
+    }
+
+    /// The version of the crate, if cargo passed it to rustdoc
+    pub fn version(&self) -> Option<&str> {
+        self.doc
+            .get("crate_version")
+            .and_then(Value::as_str)
+            .filter(|version| !version.is_empty())
+    }
+
+    fn source(&self, path: &str) -> String {
+        let version = self.version().unwrap_or("unversioned");
+        format!("rustdoc:{}@{version}:{path}", self.name())
+    }
+
+    /// Identifies the documents of any previous release of the crate by their
+    /// item path
+    pub fn document_key(&self, source: &str) -> Option<String> {
+        let prefix = format!("rustdoc:{}@", self.name());
+        let (_, path) = source.strip_prefix(&prefix)?.split_once(':')?;
+        Some(path.to_owned())
+    }
+
+    /// Documents for the public modules and items, starting at the crate root
+    pub fn documents(&self) -> Vec<HtmlParser<Done>> {
+        let mut documents = vec![];
+        let mut visited = HashSet::new();
+
+        if let Some(root) = self.root() {
+            let name = self.name().to_owned();
+            self.module(root, &name, &mut visited, &mut documents);
+        }
+
+        documents
+    }
+
+    fn item(&self, id: &Value) -> Option<&Value> {
+        self.doc.get("index")?.get(id_key(id)?)
+    }
+
+    /// Add the documents of a module and the items in it
+    fn module(
+        &self,
+        module: &Value,
+        path: &str,
+        visited: &mut HashSet<String>,
+        documents: &mut Vec<HtmlParser<Done>>,
+    ) {
+        let mut entries = vec![];
+        let items = inner(module)
+            .and_then(|(_, module)| module.get("items"))
+            .and_then(Value::as_array);
+        self.module_items(
+            items.into_iter().flatten(),
+            path,
+            visited,
+            documents,
+            &mut entries,
+        );
+
+        let title = match path.contains("::") {
+            true => format!("Module `{path}`"),
+            false => format!("Crate `{path}`"),
+        };
+        let mut md = format!("# {title}\n\n");
+        write_docs(&mut md, module);
+
+        for (kind, section, _) in KINDS {
+            let mut names = entries.iter().filter(|entry| entry.kind == kind).peekable();
+            if names.peek().is_none() {
+                continue;
+            }
+            // Type aliases and macros have two kinds each, the section is only written once
+            if !md.contains(&format!("\n## {section}\n")) {
+                let _ = write!(md, "## {section}\n\n");
+            }
+            for entry in names {
+                let _ = write!(md, "- `{}`", entry.name);
+                if let Some(summary) = &entry.summary {
+                    let _ = write!(md, ": {summary}");
+                }
+                md.push('\n');
+            }
+            md.push('\n');
+        }
+
+        documents.extend(markdown_document(path, md, self.source(path)));
+    }
+
+    fn module_items<'a>(
+        &'a self,
+        items: impl Iterator<Item = &'a Value>,
+        path: &str,
+        visited: &mut HashSet<String>,
+        documents: &mut Vec<HtmlParser<Done>>,
+        entries: &mut Vec<Entry>,
+    ) {
+        for id in items {
+            let Some(item) = self.item(id) else {
+                continue;
+            };
+            let Some((kind, value)) = inner(item) else {
+                continue;
+            };
+
+            // Re-exports of items from the crate are documented where they are
+            // exported, glob imports add all items of the module
+            if kind == "use" || kind == "import" {
+                let Some(target) = value.get("id").and_then(|id| self.item(id)) else {
+                    continue;
+                };
+                let is_glob = value
+                    .get("is_glob")
+                    .or_else(|| value.get("glob"))
+                    .and_then(Value::as_bool)
+                    == Some(true);
+                if is_glob {
+                    if !visited.insert(id_key(value.get("id").unwrap_or(id)).unwrap_or_default()) {
+                        continue;
+                    }
+                    let items = inner(target)
+                        .and_then(|(_, module)| module.get("items"))
+                        .and_then(Value::as_array);
+                    self.module_items(
+                        items.into_iter().flatten(),
+                        path,
+                        visited,
+                        documents,
+                        entries,
+                    );
+                } else if let Some(name) = value.get("name").and_then(Value::as_str) {
+                    let id = value.get("id").unwrap_or(id);
+                    self.module_item(id, target, name, path, visited, documents, entries);
+                }
+                continue;
+            }
+
+            if let Some(name) = item.get("name").and_then(Value::as_str) {
+                self.module_item(id, item, name, path, visited, documents, entries);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn module_item(
+        &self,
+        id: &Value,
+        item: &Value,
+        name: &str,
+        path: &str,
+        visited: &mut HashSet<String>,
+        documents: &mut Vec<HtmlParser<Done>>,
+        entries: &mut Vec<Entry>,
+    ) {
+        let Some((kind, _)) = inner(item) else {
+            return;
+        };
+        let Some(kind) = KINDS.iter().map(|(kind, _, _)| *kind).find(|k| *k == kind) else {
+            return;
+        };
+        if !visited.insert(id_key(id).unwrap_or_default()) {
+            return;
+        }
+
+        let item_path = format!("{path}::{name}");
+        entries.push(Entry {
+            kind,
+            name: name.to_owned(),
+            summary: summary(item),
+        });
+
+        if kind == "module" {
+            self.module(item, &item_path, visited, documents);
+        } else if let Some(md) = self.item_document(item, name, &item_path) {
+            documents.extend(markdown_document(&item_path, md, self.source(&item_path)));
+        }
+    }
+
+    fn item_document(&self, item: &Value, name: &str, path: &str) -> Option<String> {
+        let (kind, value) = inner(item)?;
+        let (_, _, title) = KINDS.iter().find(|(k, _, _)| *k == kind)?;
+        let title = match (kind, value.get("kind").and_then(Value::as_str)) {
+            ("proc_macro", Some("derive")) => "Derive macro",
+            ("proc_macro", Some("attr")) => "Attribute macro",
+            _ => title,
+        };
+
+        let mut md = format!("# {title} `{path}`\n\n");
+        if let Some(signature) = self.signature(item, name) {
+            let _ = write!(md, "```rust\n{signature}\n```\n\n");
+        }
+        write_deprecation(&mut md, item);
+        write_docs(&mut md, item);
+
+        match kind {
+            "struct" | "union" => {
+                self.write_fields(&mut md, value);
+                self.write_impls(&mut md, value);
+            }
+            "enum" => {
+                self.write_variants(&mut md, value);
+                self.write_impls(&mut md, value);
+            }
+            "trait" => self.write_trait_items(&mut md, value),
+            _ => (),
+        }
+
+        Some(md)
+    }
+
+    /// The declaration of an item, without bodies
+    fn signature(&self, item: &Value, name: &str) -> Option<String> {
+        let (kind, value) = inner(item)?;
+        let visibility = visibility(item);
+
+        let signature = match kind {
+            "function" => self.function(item, name)?,
+            "struct" => {
+                let generics = self.generics(value.get("generics"));
+                let mut signature = format!("{visibility}struct {name}{}", generics.params);
+                match value.get("kind") {
+                    Some(Value::Object(kind)) if kind.contains_key("tuple") => {
+                        let fields = self.tuple_fields(&kind["tuple"]);
+                        let _ = write!(signature, "({fields}){};", generics.where_clause);
+                    }
+                    Some(Value::Object(kind)) if kind.contains_key("plain") => {
+                        let _ = writeln!(signature, "{} {{", generics.where_clause);
+                        signature.push_str(&self.named_fields(&kind["plain"]));
+                        signature.push('}');
+                    }
+                    _ => {
+                        let _ = write!(signature, "{};", generics.where_clause);
+                    }
+                }
+                signature
+            }
+            "union" => {
+                let generics = self.generics(value.get("generics"));
+                let mut signature = format!(
+                    "{visibility}union {name}{}{} {{\n",
+                    generics.params, generics.where_clause
+                );
+                signature.push_str(&self.named_fields(value));
+                signature.push('}');
+                signature
+            }
+            "enum" => {
+                let generics = self.generics(value.get("generics"));
+                let mut signature = format!(
+                    "{visibility}enum {name}{}{} {{\n",
+                    generics.params, generics.where_clause
+                );
+                for variant in self.items(value.get("variants")) {
+                    let _ = writeln!(signature, "    {},", self.variant(variant));
+                }
+                if value.get("has_stripped_variants").and_then(Value::as_bool) == Some(true) {
+                    signature.push_str("    // some variants omitted\n");
+                }
+                signature.push('}');
+                signature
+            }
+            "trait" => {
+                let generics = self.generics(value.get("generics"));
+                let unsafety = match value.get("is_unsafe").and_then(Value::as_bool) {
+                    Some(true) => "unsafe ",
+                    _ => "",
+                };
+                let mut signature =
+                    format!("{visibility}{unsafety}trait {name}{}", generics.params);
+                let bounds = self.bounds(value.get("bounds"));
+                if !bounds.is_empty() {
+                    let _ = write!(signature, ": {bounds}");
+                }
+                let _ = write!(signature, "{} {{ ... }}", generics.where_clause);
+                signature
+            }
+            "trait_alias" => {
+                let generics = self.generics(value.get("generics"));
+                format!(
+                    "{visibility}trait {name}{} = {}{};",
+                    generics.params,
+                    self.bounds(value.get("params").or_else(|| value.get("bounds"))),
+                    generics.where_clause
+                )
+            }
+            "type_alias" | "typedef" => {
+                let generics = self.generics(value.get("generics"));
+                format!(
+                    "{visibility}type {name}{}{} = {};",
+                    generics.params,
+                    generics.where_clause,
+                    self.type_name(value.get("type"))
+                )
+            }
+            "constant" => {
+                let expr = value
+                    .pointer("/const/expr")
+                    .or_else(|| value.get("expr"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("_");
+                format!(
+                    "{visibility}const {name}: {} = {expr};",
+                    self.type_name(value.get("type"))
+                )
+            }
+            "static" => {
+                let mutability = match flag(value, &["is_mutable", "mutable"]) {
+                    true => "mut ",
+                    false => "",
+                };
+                format!(
+                    "{visibility}static {mutability}{name}: {};",
+                    self.type_name(value.get("type"))
+                )
+            }
+            "macro" => value.as_str()?.to_owned(),
+            _ => return None,
+        };
+
+        Some(signature)
+    }
+
+    fn function(&self, item: &Value, name: &str) -> Option<String> {
+        let (_, function) = inner(item)?;
+        let sig = function.get("sig").or_else(|| function.get("decl"))?;
+        let header = function.get("header");
+        let generics = self.generics(function.get("generics"));
+
+        let mut signature = visibility(item).to_owned();
+        if let Some(header) = header {
+            for (keys, word) in [
+                (["is_const", "const"], "const "),
+                (["is_async", "async"], "async "),
+                (["is_unsafe", "unsafe"], "unsafe "),
+            ] {
+                if flag(header, &keys) {
+                    signature.push_str(word);
+                }
+            }
+        }
+
+        let inputs = sig
+            .get("inputs")
+            .and_then(Value::as_array)
+            .map(|inputs| {
+                inputs
+                    .iter()
+                    .filter_map(|input| {
+                        let name = input.get(0)?.as_str()?;
+                        Some(self.argument(name, input.get(1)))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+
+        let _ = write!(signature, "fn {name}{}({inputs})", generics.params);
+        if let Some(output) = sig.get("output").filter(|output| !output.is_null()) {
+            let _ = write!(signature, " -> {}", self.type_name(Some(output)));
+        }
+        signature.push_str(&generics.where_clause);
+
+        Some(signature)
+    }
+
+    fn argument(&self, name: &str, kind: Option<&Value>) -> String {
+        if name == "self" {
+            let self_type =
+                |kind: &Value| kind.get("generic").and_then(Value::as_str) == Some("Self");
+            if let Some(kind) = kind {
+                if self_type(kind) {
+                    return "self".to_owned();
+                }
+                if let Some(reference) = kind.get("borrowed_ref") {
+                    if reference.get("type").is_some_and(self_type) {
+                        let lifetime = reference
+                            .get("lifetime")
+                            .and_then(Value::as_str)
+                            .map(|lifetime| format!("{lifetime} "))
+                            .unwrap_or_default();
+                        let mutability = match flag(reference, &["is_mutable", "mutable"]) {
+                            true => "mut ",
+                            false => "",
+                        };
+                        return format!("&{lifetime}{mutability}self");
+                    }
+                }
+            }
+        }
+
+        format!("{name}: {}", self.type_name(kind))
+    }
+
+    fn items<'a>(&'a self, ids: Option<&'a Value>) -> impl Iterator<Item = &'a Value> + 'a {
+        ids.and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.item(id))
+    }
+
+    fn tuple_fields(&self, fields: &Value) -> String {
+        fields
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|id| match self.item(id) {
+                Some(field) => {
+                    let kind = inner(field).map(|(_, kind)| kind);
+                    format!("{}{}", visibility(field), self.type_name(kind))
+                }
+                None => "_".to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The fields of a struct, union or struct variant, one per line
+    fn named_fields(&self, value: &Value) -> String {
+        let mut fields = String::new();
+        for field in self.items(value.get("fields")) {
+            let name = field.get("name").and_then(Value::as_str).unwrap_or("_");
+            let kind = inner(field).map(|(_, kind)| kind);
+            let _ = writeln!(
+                fields,
+                "    {}{name}: {},",
+                visibility(field),
+                self.type_name(kind)
+            );
+        }
+        if flag(value, &["has_stripped_fields", "fields_stripped"]) {
+            fields.push_str("    // some fields omitted\n");
+        }
+        fields
+    }
+
+    fn variant(&self, variant: &Value) -> String {
+        let name = variant.get("name").and_then(Value::as_str).unwrap_or("_");
+        let kind = inner(variant).and_then(|(_, variant)| variant.get("kind"));
+
+        let mut signature = match kind {
+            Some(Value::Object(kind)) if kind.contains_key("tuple") => {
+                format!("{name}({})", self.tuple_fields(&kind["tuple"]))
+            }
+            Some(Value::Object(kind)) if kind.contains_key("struct") => {
+                let fields = self
+                    .items(kind["struct"].get("fields"))
+                    .map(|field| {
+                        let field_name = field.get("name").and_then(Value::as_str).unwrap_or("_");
+                        let kind = inner(field).map(|(_, kind)| kind);
+                        format!("{field_name}: {}", self.type_name(kind))
+                    })
+                    .collect::<Vec<_>>();
+                format!("{name} {{ {} }}", fields.join(", "))
+            }
+            _ => name.to_owned(),
+        };
+
+        let discriminant = inner(variant)
+            .and_then(|(_, variant)| variant.pointer("/discriminant/expr"))
+            .and_then(Value::as_str);
+        if let Some(discriminant) = discriminant {
+            let _ = write!(signature, " = {discriminant}");
+        }
+        signature
+    }
+
+    /// The documented fields of a struct or union
+    fn write_fields(&self, md: &mut String, value: &Value) {
+        let fields = value
+            .pointer("/kind/plain/fields")
+            .or_else(|| value.get("fields"));
+
+        let mut section = String::new();
+        for field in self.items(fields) {
+            let Some(docs) = docs(field) else {
+                continue;
+            };
+            let name = field.get("name").and_then(Value::as_str).unwrap_or("_");
+            let kind = inner(field).map(|(_, kind)| kind);
+            let _ = writeln!(
+                section,
+                "- `{name}: {}`: {}",
+                self.type_name(kind),
+                one_line(docs)
+            );
+        }
+
+        if !section.is_empty() {
+            let _ = write!(md, "## Fields\n\n{section}\n");
+        }
+    }
+
+    fn write_variants(&self, md: &mut String, value: &Value) {
+        let mut section = String::new();
+        for variant in self.items(value.get("variants")) {
+            let _ = write!(section, "- `{}`", self.variant(variant));
+            if let Some(docs) = docs(variant) {
+                let _ = write!(section, ": {}", one_line(docs));
+            }
+            section.push('\n');
+        }
+
+        if !section.is_empty() {
+            let _ = write!(md, "## Variants\n\n{section}\n");
+        }
+    }
+
+    /// Methods of inherent impls and a list of the trait impls, without the auto
+    /// and blanket impls every type has
+    fn write_impls(&self, md: &mut String, value: &Value) {
+        let mut methods = String::new();
+        let mut traits = vec![];
+
+        for imp in self.items(value.get("impls")) {
+            let Some((_, imp)) = inner(imp) else {
+                continue;
+            };
+            if flag(imp, &["is_synthetic", "synthetic"])
+                || imp
+                    .get("blanket_impl")
+                    .is_some_and(|blanket| !blanket.is_null())
+            {
+                continue;
+            }
+
+            match imp.get("trait").filter(|t| !t.is_null()) {
+                Some(trait_path) => {
+                    let generics = self.generics(imp.get("generics"));
+                    let negative = match flag(imp, &["is_negative", "negative"]) {
+                        true => "!",
+                        false => "",
+                    };
+                    traits.push(format!(
+                        "impl{} {negative}{} for {}{}",
+                        generics.params,
+                        self.path(trait_path),
+                        self.type_name(imp.get("for")),
+                        generics.where_clause
+                    ));
+                }
+                None => {
+                    for item in self.items(imp.get("items")) {
+                        self.write_assoc_item(&mut methods, item);
+                    }
+                }
+            }
+        }
+
+        if !methods.is_empty() {
+            let _ = write!(md, "## Methods\n\n{methods}");
+        }
+        if !traits.is_empty() {
+            md.push_str("## Trait implementations\n\n");
+            for imp in traits {
+                let _ = writeln!(md, "- `{imp}`");
+            }
+            md.push('\n');
+        }
+    }
+
+    fn write_trait_items(&self, md: &mut String, value: &Value) {
+        let mut types = String::new();
+        let mut required = String::new();
+        let mut provided = String::new();
+
+        for item in self.items(value.get("items")) {
+            match inner(item) {
+                Some(("function", function)) => {
+                    let methods = match function.get("has_body").and_then(Value::as_bool) {
+                        Some(true) => &mut provided,
+                        _ => &mut required,
+                    };
+                    self.write_assoc_item(methods, item);
+                }
+                Some(_) => self.write_assoc_item(&mut types, item),
+                None => (),
+            }
+        }
+
+        for (section, content) in [
+            ("Associated items", types),
+            ("Required methods", required),
+            ("Provided methods", provided),
+        ] {
+            if !content.is_empty() {
+                let _ = write!(md, "## {section}\n\n{content}");
+            }
+        }
+
+        let implementors = self
+            .items(
+                value
+                    .get("implementations")
+                    .or_else(|| value.get("implementors")),
+            )
+            .filter_map(|imp| inner(imp))
+            .filter(|(_, imp)| imp.get("blanket_impl").is_none_or(Value::is_null))
+            .map(|(_, imp)| self.type_name(imp.get("for")))
+            .collect::<Vec<_>>();
+        if !implementors.is_empty() {
+            md.push_str("## Implementors\n\n");
+            for implementor in implementors {
+                let _ = writeln!(md, "- `{implementor}`");
+            }
+            md.push('\n');
+        }
+    }
+
+    /// A method, associated type or constant of an impl or trait
+    fn write_assoc_item(&self, md: &mut String, item: &Value) {
+        let Some(name) = item.get("name").and_then(Value::as_str) else {
+            return;
+        };
+
+        let signature = match inner(item) {
+            Some(("function", _)) => self.function(item, name),
+            Some(("assoc_type", value)) => {
+                let mut signature = format!("type {name}");
+                let bounds = self.bounds(value.get("bounds"));
+                if !bounds.is_empty() {
+                    let _ = write!(signature, ": {bounds}");
+                }
+                if let Some(kind) = value
+                    .get("type")
+                    .or_else(|| value.get("default"))
+                    .filter(|kind| !kind.is_null())
+                {
+                    let _ = write!(signature, " = {}", self.type_name(Some(kind)));
+                }
+                Some(format!("{signature};"))
+            }
+            Some(("assoc_const", value)) => {
+                let mut signature = format!("const {name}: {}", self.type_name(value.get("type")));
+                if let Some(default) = value
+                    .get("value")
+                    .or_else(|| value.get("default"))
+                    .and_then(Value::as_str)
+                {
+                    let _ = write!(signature, " = {default}");
+                }
+                Some(format!("{signature};"))
+            }
+            _ => None,
+        };
+        let Some(signature) = signature else {
+            return;
+        };
+
+        let _ = write!(md, "### `{name}`\n\n```rust\n{signature}\n```\n\n");
+        write_deprecation(md, item);
+        write_docs(md, item);
+    }
+
+    fn generics(&self, generics: Option<&Value>) -> Generics {
+        let Some(generics) = generics else {
+            return Generics::default();
+        };
+
+        let params = generics
+            .get("params")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|param| {
+                let name = param.get("name")?.as_str()?;
+                let kind = param.get("kind")?;
+                if let Some(kind) = kind.get("type") {
+                    // Parameters for `impl Trait` arguments are shown in the arguments
+                    if flag(kind, &["is_synthetic", "synthetic"]) {
+                        return None;
+                    }
+                    let mut param = name.to_owned();
+                    let bounds = self.bounds(kind.get("bounds"));
+                    if !bounds.is_empty() {
+                        let _ = write!(param, ": {bounds}");
+                    }
+                    if let Some(default) = kind.get("default").filter(|d| !d.is_null()) {
+                        let _ = write!(param, " = {}", self.type_name(Some(default)));
+                    }
+                    Some(param)
+                } else if let Some(kind) = kind.get("const") {
+                    Some(format!(
+                        "const {name}: {}",
+                        self.type_name(kind.get("type"))
+                    ))
+                } else {
+                    let outlives = kind
+                        .pointer("/lifetime/outlives")
+                        .and_then(Value::as_array)
+                        .map(|outlives| {
+                            outlives
+                                .iter()
+                                .filter_map(Value::as_str)
+                                .collect::<Vec<_>>()
+                                .join(" + ")
+                        })
+                        .filter(|outlives| !outlives.is_empty());
+                    match outlives {
+                        Some(outlives) => Some(format!("{name}: {outlives}")),
+                        None => Some(name.to_owned()),
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let predicates = generics
+            .get("where_predicates")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|predicate| {
+                if let Some(predicate) = predicate.get("bound_predicate") {
+                    Some(format!(
+                        "{}: {}",
+                        self.type_name(predicate.get("type")),
+                        self.bounds(predicate.get("bounds"))
+                    ))
+                } else if let Some(predicate) = predicate
+                    .get("lifetime_predicate")
+                    .or_else(|| predicate.get("region_predicate"))
+                {
+                    let outlives = predicate
+                        .get("outlives")
+                        .or_else(|| predicate.get("bounds"))
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .map(|bound| match bound {
+                            Value::String(lifetime) => lifetime.to_owned(),
+                            bound => self.bound(bound),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" + ");
+                    let lifetime = predicate.get("lifetime").and_then(Value::as_str)?;
+                    Some(format!("{lifetime}: {outlives}"))
+                } else {
+                    let predicate = predicate.get("eq_predicate")?;
+                    Some(format!(
+                        "{} = {}",
+                        self.type_name(predicate.get("lhs")),
+                        self.term(predicate.get("rhs"))
+                    ))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Generics {
+            params: match params.is_empty() {
+                true => String::new(),
+                false => format!("<{}>", params.join(", ")),
+            },
+            where_clause: match predicates.is_empty() {
+                true => String::new(),
+                false => format!("\nwhere\n    {},", predicates.join(",\n    ")),
+            },
+        }
+    }
+
+    fn bounds(&self, bounds: Option<&Value>) -> String {
+        bounds
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|bound| self.bound(bound))
+            .filter(|bound| !bound.is_empty())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+
+    fn bound(&self, bound: &Value) -> String {
+        if let Some(bound) = bound.get("trait_bound") {
+            let modifier = match bound.get("modifier").and_then(Value::as_str) {
+                Some("maybe") => "?",
+                Some("maybe_const") => "~const ",
+                _ => "",
+            };
+            let path = bound.get("trait").map(|path| self.path(path));
+            return format!("{modifier}{}", path.unwrap_or_default());
+        }
+
+        bound
+            .get("outlives")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    /// A path to an item with its generic arguments, like `Vec<T>`
+    fn path(&self, path: &Value) -> String {
+        let name = path
+            .get("path")
+            .or_else(|| path.get("name"))
+            .and_then(Value::as_str)
+            .unwrap_or("_");
+        format!("{name}{}", self.generic_args(path.get("args")))
+    }
+
+    fn generic_args(&self, args: Option<&Value>) -> String {
+        let Some(args) = args.filter(|args| !args.is_null()) else {
+            return String::new();
+        };
+
+        if let Some(args) = args.get("angle_bracketed") {
+            let mut list = args
+                .get("args")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|arg| match arg {
+                    Value::Object(arg) if arg.contains_key("type") => {
+                        self.type_name(arg.get("type"))
+                    }
+                    Value::Object(arg) if arg.contains_key("lifetime") => {
+                        arg["lifetime"].as_str().unwrap_or("'_").to_owned()
+                    }
+                    Value::Object(arg) if arg.contains_key("const") => arg["const"]
+                        .get("expr")
+                        .and_then(Value::as_str)
+                        .unwrap_or("_")
+                        .to_owned(),
+                    _ => "_".to_owned(),
+                })
+                .collect::<Vec<_>>();
+
+            let constraints = args.get("constraints").or_else(|| args.get("bindings"));
+            for constraint in constraints.and_then(Value::as_array).into_iter().flatten() {
+                let name = constraint
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("_");
+                let binding = constraint.get("binding");
+                if let Some(term) = binding.and_then(|binding| binding.get("equality")) {
+                    list.push(format!("{name} = {}", self.term(Some(term))));
+                } else if let Some(bounds) = binding.and_then(|binding| binding.get("constraint")) {
+                    list.push(format!("{name}: {}", self.bounds(Some(bounds))));
+                }
+            }
+
+            return match list.is_empty() {
+                true => String::new(),
+                false => format!("<{}>", list.join(", ")),
+            };
+        }
+
+        if let Some(args) = args.get("parenthesized") {
+            let inputs = args
+                .get("inputs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|input| self.type_name(Some(input)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut signature = format!("({inputs})");
+            if let Some(output) = args.get("output").filter(|output| !output.is_null()) {
+                let _ = write!(signature, " -> {}", self.type_name(Some(output)));
+            }
+            return signature;
+        }
+
+        String::new()
+    }
+
+    fn term(&self, term: Option<&Value>) -> String {
+        match term {
+            Some(term) if term.get("type").is_some() => self.type_name(term.get("type")),
+            Some(term) => term
+                .pointer("/constant/expr")
+                .and_then(Value::as_str)
+                .unwrap_or("_")
+                .to_owned(),
+            None => "_".to_owned(),
+        }
+    }
+
+    /// A type as it would be written in Rust
+    fn type_name(&self, kind: Option<&Value>) -> String {
+        let Some(kind) = kind else {
+            return "_".to_owned();
+        };
+
+        let Some((variant, value)) = kind.as_object().and_then(|kind| kind.iter().next()) else {
+            // `infer` and unknown kinds
+            return "_".to_owned();
+        };
+
+        match variant.as_str() {
+            "resolved_path" => self.path(value),
+            "generic" | "primitive" => value.as_str().unwrap_or("_").to_owned(),
+            "tuple" => {
+                let types = value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|kind| self.type_name(Some(kind)))
+                    .collect::<Vec<_>>();
+                match types.len() {
+                    1 => format!("({},)", types[0]),
+                    _ => format!("({})", types.join(", ")),
+                }
+            }
+            "slice" => format!("[{}]", self.type_name(Some(value))),
+            "array" => format!(
+                "[{}; {}]",
+                self.type_name(value.get("type")),
+                value.get("len").and_then(Value::as_str).unwrap_or("_")
+            ),
+            "borrowed_ref" => {
+                let lifetime = value
+                    .get("lifetime")
+                    .and_then(Value::as_str)
+                    .map(|lifetime| format!("{lifetime} "))
+                    .unwrap_or_default();
+                let mutability = match flag(value, &["is_mutable", "mutable"]) {
+                    true => "mut ",
+                    false => "",
+                };
+                format!(
+                    "&{lifetime}{mutability}{}",
+                    self.type_name(value.get("type"))
+                )
+            }
+            "raw_pointer" => {
+                let mutability = match flag(value, &["is_mutable", "mutable"]) {
+                    true => "mut",
+                    false => "const",
+                };
+                format!("*{mutability} {}", self.type_name(value.get("type")))
+            }
+            "dyn_trait" => {
+                let mut bounds = value
+                    .get("traits")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|bound| bound.get("trait"))
+                    .map(|path| self.path(path))
+                    .collect::<Vec<_>>();
+                if let Some(lifetime) = value.get("lifetime").and_then(Value::as_str) {
+                    bounds.push(lifetime.to_owned());
+                }
+                format!("dyn {}", bounds.join(" + "))
+            }
+            "impl_trait" => format!("impl {}", self.bounds(Some(value))),
+            "function_pointer" => {
+                let sig = value.get("sig").or_else(|| value.get("decl"));
+                let inputs = sig
+                    .and_then(|sig| sig.get("inputs"))
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .map(|input| self.type_name(input.get(1)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let mut pointer = format!("fn({inputs})");
+                if let Some(output) = sig
+                    .and_then(|sig| sig.get("output"))
+                    .filter(|output| !output.is_null())
+                {
+                    let _ = write!(pointer, " -> {}", self.type_name(Some(output)));
+                }
+                pointer
+            }
+            "qualified_path" => {
+                let name = value.get("name").and_then(Value::as_str).unwrap_or("_");
+                let self_type = self.type_name(value.get("self_type"));
+                match value.get("trait").filter(|t| !t.is_null()) {
+                    Some(_) if value.pointer("/self_type/generic").is_some() => {
+                        format!("{self_type}::{name}")
+                    }
+                    Some(trait_path) => {
+                        format!("<{self_type} as {}>::{name}", self.path(trait_path))
+                    }
+                    None => format!("{self_type}::{name}"),
+                }
+            }
+            "pat" => self.type_name(value.get("type")),
+            _ => "_".to_owned(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Generics {
+    /// Like `<T: Clone>`, empty without parameters
+    params: String,
+    /// Starting with a line break, empty without predicates
+    where_clause: String,
+}
+
+/// Ids were strings in older format versions and are numbers now
+fn id_key(id: &Value) -> Option<String> {
+    match id {
+        Value::String(id) => Some(id.to_owned()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// The kind of an item and its details
+fn inner(item: &Value) -> Option<(&str, &Value)> {
+    match item.get("inner")? {
+        Value::Object(inner) => inner
+            .iter()
+            .next()
+            .map(|(kind, value)| (kind.as_str(), value)),
+        Value::String(kind) => Some((kind.as_str(), &Value::Null)),
+        _ => None,
+    }
+}
+
+fn flag(value: &Value, keys: &[&str]) -> bool {
+    keys.iter()
+        .find_map(|key| value.get(key))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+fn visibility(item: &Value) -> &'static str {
+    match item.get("visibility").and_then(Value::as_str) {
+        Some("public") => "pub ",
+        Some("crate") => "pub(crate) ",
+        _ => "",
+    }
+}
+
+fn docs(item: &Value) -> Option<&str> {
+    item.get("docs")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|docs| !docs.is_empty())
+}
+
+/// The first paragraph of the docs on one line
+fn summary(item: &Value) -> Option<String> {
+    let docs = docs(item)?;
+    let paragraph = docs.split("\n\n").next().unwrap_or(docs);
+    Some(one_line(paragraph))
+}
+
+/// Doc comments are Markdown, their headings are moved below the title of the
+/// document
+fn write_docs(md: &mut String, item: &Value) {
+    let Some(docs) = docs(item) else {
+        return;
+    };
+
+    let mut in_code = false;
+    for line in docs.lines() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if !in_code && line.starts_with('#') {
+            let _ = writeln!(md, "##{line}");
+        } else {
+            let _ = writeln!(md, "{line}");
+        }
+    }
+    md.push('\n');
+}
+
+fn write_deprecation(md: &mut String, item: &Value) {
+    let Some(deprecation) = item.get("deprecation").filter(|d| !d.is_null()) else {
+        return;
+    };
+
+    md.push_str("> **Deprecated**");
+    if let Some(since) = deprecation.get("since").and_then(Value::as_str) {
+        let _ = write!(md, " since {since}");
+    }
+    if let Some(note) = deprecation.get("note").and_then(Value::as_str) {
+        let _ = write!(md, ": {note}");
+    }
+    md.push_str("\n\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `cargo rustdoc` output for a small crate, without the items of other crates
+    const SHAPES: &str = include_str!("test_files/shapes.json");
+
+    fn content<'a>(documents: &'a [HtmlParser<Done>], name: &str) -> &'a str {
+        documents
+            .iter()
+            .find(|doc| doc.name() == name)
+            .unwrap_or_else(|| panic!("No document named {name}"))
+            .content()
+    }
+
+    #[test]
+    fn test_parse() {
+        let shapes = RustdocCrate::parse(SHAPES).unwrap();
+        assert_eq!(shapes.name(), "shapes");
+        assert_eq!(shapes.version(), Some("0.2.0"));
+
+        assert!(RustdocCrate::parse("{\"index\": {}}").is_err());
+        assert!(RustdocCrate::parse("not json").is_err());
+
+        let unsupported = SHAPES.replacen("\"format_version\": 57", "\"format_version\": 12", 1);
+        let error = RustdocCrate::parse(&unsupported).err().unwrap();
+        assert!(error.to_string().contains("format version 12"));
+    }
+
+    #[test]
+    fn test_documents() {
+        let documents = RustdocCrate::parse(SHAPES).unwrap().documents();
+
+        let mut names = documents.iter().map(|doc| doc.name()).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "shapes",
+                "shapes::Area",
+                "shapes::Circle",
+                "shapes::MAX_RADIUS",
+                "shapes::Polygon",
+                "shapes::Shape",
+                "shapes::Style",
+                "shapes::circle",
+                "shapes::parse",
+                "shapes::total_area",
+                "shapes::units",
+                "shapes::units::Feet",
+                "shapes::units::Meters",
+            ]
+        );
+        assert!(documents
+            .iter()
+            .all(|doc| doc.source() == format!("rustdoc:shapes@0.2.0:{}", doc.name())));
+
+        let root = content(&documents, "shapes");
+        assert!(root.starts_with("# Crate `shapes`\n\nGeometric shapes and how to measure them."));
+        assert!(root.contains("## Modules\n\n- `units`: Units of length.\n"));
+        assert!(root.contains("## Structs\n\n- `Polygon`: A closed shape from straight lines.\n- `Circle`: A circle around the origin.\n"));
+        assert!(root.contains("## Functions\n\n- `total_area`: The total area of all shapes.\n- `parse`: Parse a shape from its description.\n"));
+
+        let circle = content(&documents, "shapes::Circle");
+        assert!(circle.starts_with("# Struct `shapes::Circle`\n\n```rust\npub struct Circle {\n    pub radius: f64,\n    // some fields omitted\n}\n```\n\nA circle around the origin."));
+        assert!(circle
+            .contains("## Fields\n\n- `radius: f64`: Distance from the center to the edge.\n"));
+        assert!(circle.contains("## Methods\n\n### `new`\n\n```rust\npub fn new(radius: f64) -> Self\n```\n\nA circle with the given radius."));
+        assert!(circle.contains("pub fn with_label(self, label: impl Into<String>) -> Self"));
+        assert!(circle.contains("pub fn scale_all<'a, I>(circles: I, factor: f64)\nwhere\n    I: IntoIterator<Item = &'a mut Circle>,"));
+        assert!(circle.contains("- `impl Shape for Circle`"));
+        assert!(circle.contains("- `impl Clone for Circle`"));
+        // Auto and blanket impls are left out
+        assert!(!circle.contains("Send"));
+        assert!(!circle.contains("impl<T>"));
+
+        let shape = content(&documents, "shapes::Shape");
+        assert!(shape.starts_with(
+            "# Trait `shapes::Shape`\n\n```rust\npub trait Shape: std::fmt::Debug { ... }\n```"
+        ));
+        assert!(shape.contains("## Associated items\n\n### `Unit`\n\n```rust\ntype Unit;\n```\n\nThe unit measurements are given in."));
+        assert!(shape
+            .contains("## Required methods\n\n### `area`\n\n```rust\nfn area(&self) -> f64\n```"));
+        assert!(shape.contains(
+            "## Provided methods\n\n### `name`\n\n```rust\nfn name(&self) -> String\n```"
+        ));
+        assert!(shape.contains("## Implementors\n\n- `Polygon`\n- `Circle`\n"));
+
+        let style = content(&documents, "shapes::Style");
+        assert!(style.contains("pub enum Style {\n    Outline,\n    Filled(u32),\n    Dashed { length: u8, gap: u8 },\n}"));
+        assert!(style.contains("- `Filled(u32)`: Filled with a color."));
+
+        let parse = content(&documents, "shapes::parse");
+        assert!(parse.contains("pub async fn parse(description: &str) -> Result<Box<dyn Shape<Unit = units::Meters>>, String>"));
+        // Headings of the doc comment are moved below the title
+        assert!(parse.contains("\n### Errors\n"));
+
+        let total_area = content(&documents, "shapes::total_area");
+        assert!(total_area.contains("pub fn total_area<S: Shape>(shapes: &[S]) -> f64"));
+
+        assert!(
+            content(&documents, "shapes::MAX_RADIUS").contains("pub const MAX_RADIUS: f64 = 1e9;")
+        );
+        assert!(content(&documents, "shapes::Area").contains("pub type Area = f64;"));
+        assert!(content(&documents, "shapes::circle").contains("macro_rules! circle {"));
+
+        let feet = content(&documents, "shapes::units::Feet");
+        assert!(feet.contains("pub struct Feet(pub f64);"));
+        assert!(feet.contains("> **Deprecated** since 0.2.0: Use `Meters` instead"));
+    }
+
+    #[test]
+    fn test_document_key() {
+        let shapes = RustdocCrate::parse(SHAPES).unwrap();
+
+        assert_eq!(
+            shapes.document_key("rustdoc:shapes@0.1.0:shapes::Circle"),
+            Some("shapes::Circle".to_owned())
+        );
+        assert_eq!(
+            shapes.document_key("rustdoc:other@0.1.0:other::Circle"),
+            None
+        );
+        assert_eq!(shapes.document_key("git:docs:abc:README.md"), None);
+    }
+}
@@ -0,0 +1,563 @@
+use std::{
+    collections::HashSet,
+    io::{Cursor, Read},
+};
+
+use anyhow::{bail, Result};
+use flate2::read::GzDecoder;
+
+use super::document_name;
+use crate::{
+    models::CacheValidators,
+    parsing::{Content, Done, HtmlParser},
+    web_crawler::limits::format_bytes,
+};
+
+/// Extensions of the files that can be uploaded, alone or in an archive
+pub const EXTENSIONS: [&str; 5] = ["md", "markdown", "html", "htm", "txt"];
+
+/// Archive entries that are this many times larger than their compressed size
+/// are taken as a zip bomb
+const MAX_COMPRESSION_RATIO: u64 = 100;
+
+/// Entries smaller than this are not checked for their compression ratio,
+/// small repetitive files compress well without being a threat
+const MIN_RATIO_CHECK_BYTES: u64 = 1024 * 1024;
+
+const SOURCE_PREFIX: &str = "upload:";
+
+/// Limits for an upload, applied to the extracted content of archives
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadLimits {
+    /// Larger files are skipped
+    pub max_file_bytes: usize,
+    /// The whole upload, including the extracted content of archives
+    pub max_total_bytes: usize,
+    /// Files in the upload, including the entries of archives
+    pub max_files: usize,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 10 * 1024 * 1024,
+            max_total_bytes: 100 * 1024 * 1024,
+            max_files: 1_000,
+        }
+    }
+}
+
+/// The documents read from an upload
+pub struct Upload {
+    pub documents: Vec<HtmlParser<Done>>,
+    /// Files that could not be imported, with the reason
+    pub skipped: Vec<(String, String)>,
+    paths: HashSet<String>,
+}
+
+impl Upload {
+    /// Identifies the documents of earlier uploads of the same files by their
+    /// path. Documents of other files are left alone.
+    pub fn document_key(&self, source: &str) -> Option<String> {
+        let path = source.strip_prefix(SOURCE_PREFIX)?;
+        self.paths.contains(path).then(|| path.to_owned())
+    }
+}
+
+/// Reads uploaded Markdown, HTML and text files and extracts `.zip` and
+/// `.tar.gz` archives of them. Documents are named by their relative path.
+pub struct UploadReader {
+    limits: UploadLimits,
+    total_bytes: usize,
+    files: usize,
+    upload: Upload,
+}
+
+impl UploadReader {
+    pub fn new(limits: UploadLimits) -> Self {
+        Self {
+            limits,
+            total_bytes: 0,
+            files: 0,
+            upload: Upload {
+                documents: vec![],
+                skipped: vec![],
+                paths: HashSet::new(),
+            },
+        }
+    }
+
+    /// Add an uploaded file. Fails when the upload as a whole goes over the
+    /// limits, problems with single files are reported as skipped.
+    pub fn add(&mut self, file_name: &str, bytes: Vec<u8>) -> Result<()> {
+        let Some(path) = normalize_path(file_name) else {
+            self.skip(file_name, "Invalid file name");
+            return Ok(());
+        };
+
+        let lowercase = path.to_lowercase();
+        if lowercase.ends_with(".zip") {
+            self.add_zip(&path, bytes)
+        } else if lowercase.ends_with(".tar.gz") || lowercase.ends_with(".tgz") {
+            self.add_tar_gz(&path, bytes)
+        } else {
+            self.count(bytes.len())?;
+            self.add_file(path, bytes);
+            Ok(())
+        }
+    }
+
+    pub fn finish(self) -> Upload {
+        self.upload
+    }
+
+    fn skip(&mut self, path: &str, reason: impl Into<String>) {
+        self.upload.skipped.push((path.to_owned(), reason.into()));
+    }
+
+    /// Count a file against the limits of the upload
+    fn count(&mut self, bytes: usize) -> Result<()> {
+        self.files += 1;
+        self.total_bytes += bytes;
+
+        if self.files > self.limits.max_files {
+            bail!("The upload has more than {} files", self.limits.max_files);
+        }
+        if self.total_bytes > self.limits.max_total_bytes {
+            bail!(
+                "The upload is larger than {}",
+                format_bytes(self.limits.max_total_bytes)
+            );
+        }
+        Ok(())
+    }
+
+    fn add_file(&mut self, path: String, bytes: Vec<u8>) {
+        if bytes.len() > self.limits.max_file_bytes {
+            let reason = format!("Larger than {}", format_bytes(self.limits.max_file_bytes));
+            self.skip(&path, reason);
+            return;
+        }
+
+        let supported = path
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+        let Some(content) = Content::from_file(&path, bytes).filter(|_| supported) else {
+            self.skip(&path, "Unsupported file type");
+            return;
+        };
+
+        let source = format!("{SOURCE_PREFIX}{path}");
+        match content.parse(&document_name(&path), source, CacheValidators::default()) {
+            Ok(doc) => {
+                self.upload.paths.insert(path);
+                self.upload.documents.push(doc);
+            }
+            Err(e) => self.skip(&path, e.to_string()),
+        }
+    }
+
+    fn add_zip(&mut self, archive_path: &str, bytes: Vec<u8>) -> Result<()> {
+        let extracted = extract_zip(bytes, &self.limits);
+        self.add_extracted(archive_path, extracted)
+    }
+
+    fn add_tar_gz(&mut self, archive_path: &str, bytes: Vec<u8>) -> Result<()> {
+        let extracted = extract_tar_gz(bytes, &self.limits);
+        self.add_extracted(archive_path, extracted)
+    }
+
+    fn add_extracted(&mut self, archive_path: &str, extracted: Result<Extracted>) -> Result<()> {
+        let extracted = match extracted {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                self.skip(archive_path, e.to_string());
+                return Ok(());
+            }
+        };
+
+        self.upload.skipped.extend(extracted.skipped);
+        for (path, bytes) in extracted.files {
+            self.count(bytes.len())?;
+            self.add_file(path, bytes);
+        }
+        Ok(())
+    }
+}
+
+/// The files extracted from an archive
+#[derive(Default)]
+pub struct Extracted {
+    /// The files with their normalized paths
+    pub files: Vec<(String, Vec<u8>)>,
+    /// Entries that could not be extracted, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+impl Extracted {
+    fn skip(&mut self, path: &str, reason: impl Into<String>) {
+        self.skipped.push((path.to_owned(), reason.into()));
+    }
+}
+
+/// The files of a zip archive with their normalized paths, leaving out folders
+/// and hidden files. Paths leading out of the archive and entries that cannot
+/// be read are skipped. Files larger than the limit are cut off after it, so
+/// they can be reported by their size. The archive is rejected when it is a
+/// zip bomb or extracts to more than the limits allow.
+pub fn extract_zip(bytes: Vec<u8>, limits: &UploadLimits) -> Result<Extracted> {
+    let mut archive = match zip::ZipArchive::new(Cursor::new(bytes)) {
+        Ok(archive) => archive,
+        Err(e) => bail!("Invalid zip archive: {e}"),
+    };
+
+    // Check the sizes the archive declares before extracting anything
+    let mut declared_bytes = 0;
+    let mut names = vec![];
+    for index in 0..archive.len() {
+        let Ok(entry) = archive.by_index_raw(index) else {
+            names.push(format!("Entry {}", index + 1));
+            continue;
+        };
+        names.push(entry.name().to_owned());
+        if is_zip_bomb(entry.size(), entry.compressed_size()) {
+            bail!("Rejected as a zip bomb");
+        }
+        declared_bytes += entry.size();
+    }
+    if archive.len() > limits.max_files || declared_bytes > limits.max_total_bytes as u64 {
+        bail!("Rejected, the archive is too large");
+    }
+
+    let mut extracted = Extracted::default();
+    let mut total_bytes = 0;
+    for (index, name) in names.iter().enumerate() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(e) => {
+                extracted.skip(name, format!("Failed to extract: {e}"));
+                continue;
+            }
+        };
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(path) = normalize_path(entry.name()) else {
+            extracted.skip(entry.name(), "Invalid file name");
+            continue;
+        };
+        if is_hidden(&path) {
+            continue;
+        }
+
+        // The declared size is not trusted, reading stops after the limit
+        let mut bytes = vec![];
+        let limit = limits.max_file_bytes as u64 + 1;
+        if let Err(e) = entry.by_ref().take(limit).read_to_end(&mut bytes) {
+            extracted.skip(&path, format!("Failed to extract: {e}"));
+            continue;
+        }
+
+        total_bytes += bytes.len();
+        if total_bytes > limits.max_total_bytes {
+            bail!("Rejected, the archive is too large");
+        }
+        extracted.files.push((path, bytes));
+    }
+
+    Ok(extracted)
+}
+
+/// The files of a `.tar.gz` archive, see [`extract_zip`]
+pub fn extract_tar_gz(bytes: Vec<u8>, limits: &UploadLimits) -> Result<Extracted> {
+    // Tar has no compressed sizes per entry, so the whole archive may not
+    // extract to more than the ratio allows
+    let max_bytes = (bytes.len() as u64 * MAX_COMPRESSION_RATIO)
+        .max(MIN_RATIO_CHECK_BYTES)
+        .min(limits.max_total_bytes as u64);
+    let mut archive = tar::Archive::new(GzDecoder::new(Cursor::new(bytes)).take(max_bytes));
+
+    let mut extracted = Extracted::default();
+    let mut error = None;
+    match archive.entries() {
+        Ok(entries) => {
+            for entry in entries {
+                let mut entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                };
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+                let Some(path) = normalize_path(&name) else {
+                    extracted.skip(&name, "Invalid file name");
+                    continue;
+                };
+                if is_hidden(&path) {
+                    continue;
+                }
+
+                // Entries are read in sequence from a single stream, so the
+                // ones after an unreadable entry cannot be read either
+                let mut bytes = vec![];
+                let limit = limits.max_file_bytes as u64 + 1;
+                if let Err(e) = entry.by_ref().take(limit).read_to_end(&mut bytes) {
+                    extracted.skip(&path, format!("Failed to extract: {e}"));
+                    break;
+                }
+                extracted.files.push((path, bytes));
+
+                if extracted.files.len() > limits.max_files {
+                    break;
+                }
+            }
+        }
+        Err(e) => error = Some(e),
+    }
+
+    // Running out of bytes shows up as a broken archive, so it is checked first
+    let exhausted = archive.into_inner().limit() == 0;
+    if exhausted || extracted.files.len() > limits.max_files {
+        bail!("Rejected, the archive is too large");
+    }
+    if let Some(e) = error {
+        bail!("Invalid tar archive: {e}");
+    }
+
+    Ok(extracted)
+}
+
+fn is_zip_bomb(size: u64, compressed_size: u64) -> bool {
+    size > MIN_RATIO_CHECK_BYTES && size > compressed_size.saturating_mul(MAX_COMPRESSION_RATIO)
+}
+
+/// A relative path with forward slashes, `None` for paths leaving the upload
+fn normalize_path(path: &str) -> Option<String> {
+    let mut segments = vec![];
+    for segment in path.split(['/', '\\']) {
+        match segment {
+            "" | "." => (),
+            ".." => return None,
+            segment => segments.push(segment),
+        }
+    }
+
+    match segments.is_empty() {
+        true => None,
+        false => Some(segments.join("/")),
+    }
+}
+
+/// Files added by the OS to archives, like `.DS_Store` and `__MACOSX/`
+fn is_hidden(path: &str) -> bool {
+    path.split('/')
+        .any(|segment| segment.starts_with('.') || segment == "__MACOSX")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importers::zip_archive as zip;
+    use flate2::{write::GzEncoder, Compression};
+
+    fn tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn documents(upload: &Upload) -> Vec<(&str, &str, &str)> {
+        let mut documents = upload
+            .documents
+            .iter()
+            .map(|doc| (doc.name(), doc.content(), doc.source()))
+            .collect::<Vec<_>>();
+        documents.sort();
+        documents
+    }
+
+    #[test]
+    fn test_files() {
+        let limits = UploadLimits {
+            max_file_bytes: 100,
+            ..Default::default()
+        };
+        let mut reader = UploadReader::new(limits);
+
+        reader.add("guide.md", b"# Guide".to_vec()).unwrap();
+        reader
+            .add(
+                "docs/page.html",
+                b"<html><body><h1>Page</h1><p>Text</p></body></html>".to_vec(),
+            )
+            .unwrap();
+        reader.add("notes.txt", b"Plain notes".to_vec()).unwrap();
+        reader.add("logo.png", vec![0; 10]).unwrap();
+        reader.add("intro.rst", b"Intro\n=====".to_vec()).unwrap();
+        reader.add("large.md", vec![b'a'; 101]).unwrap();
+        reader.add("../secret.md", b"# Secret".to_vec()).unwrap();
+        let upload = reader.finish();
+
+        assert_eq!(
+            documents(&upload),
+            vec![
+                ("docs/page", "# Page\n\nText", "upload:docs/page.html"),
+                ("guide", "# Guide", "upload:guide.md"),
+                ("notes", "Plain notes", "upload:notes.txt"),
+            ]
+        );
+        assert_eq!(
+            upload.skipped,
+            vec![
+                ("logo.png".to_owned(), "Unsupported file type".to_owned()),
+                ("intro.rst".to_owned(), "Unsupported file type".to_owned()),
+                ("large.md".to_owned(), "Larger than 100 bytes".to_owned()),
+                ("../secret.md".to_owned(), "Invalid file name".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zip() {
+        let archive = zip(&[
+            ("docs/index.md", b"# Index"),
+            ("docs/guide/install.txt", b"Install it"),
+            ("docs/image.png", b"png"),
+            ("__MACOSX/docs/._index.md", b"junk"),
+            ("docs/.DS_Store", b"junk"),
+            ("../secret.md", b"# Secret"),
+        ]);
+
+        let mut reader = UploadReader::new(UploadLimits::default());
+        reader.add("docs.zip", archive).unwrap();
+        reader.add("broken.zip", b"not a zip".to_vec()).unwrap();
+        let upload = reader.finish();
+
+        assert_eq!(
+            documents(&upload),
+            vec![
+                (
+                    "docs/guide/install",
+                    "Install it",
+                    "upload:docs/guide/install.txt"
+                ),
+                ("docs/index", "# Index", "upload:docs/index.md"),
+            ]
+        );
+        assert_eq!(upload.skipped.len(), 3);
+        assert_eq!(
+            upload.skipped[0],
+            ("../secret.md".to_owned(), "Invalid file name".to_owned())
+        );
+        assert_eq!(upload.skipped[1].0, "docs/image.png");
+        assert_eq!(upload.skipped[2].0, "broken.zip");
+        assert!(upload.skipped[2].1.starts_with("Invalid zip archive"));
+    }
+
+    #[test]
+    fn test_zip_bomb() {
+        let zeros = vec![0; 5 * 1024 * 1024];
+        let archive = zip(&[("docs/index.md", b"# Index"), ("bomb.txt", &zeros)]);
+
+        let mut reader = UploadReader::new(UploadLimits::default());
+        reader.add("bomb.zip", archive).unwrap();
+        let upload = reader.finish();
+
+        assert!(upload.documents.is_empty());
+        assert_eq!(
+            upload.skipped,
+            vec![("bomb.zip".to_owned(), "Rejected as a zip bomb".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_tar_gz() {
+        let archive = tar_gz(&[("./docs/index.md", b"# Index"), ("docs/a.txt", b"A")]);
+
+        let mut reader = UploadReader::new(UploadLimits::default());
+        reader.add("docs.tar.gz", archive).unwrap();
+        let upload = reader.finish();
+
+        assert_eq!(
+            documents(&upload),
+            vec![
+                ("docs/a", "A", "upload:docs/a.txt"),
+                ("docs/index", "# Index", "upload:docs/index.md"),
+            ]
+        );
+        assert!(upload.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_tar_gz_bomb() {
+        let zeros = vec![0; 5 * 1024 * 1024];
+        let archive = tar_gz(&[("bomb.txt", &zeros), ("docs/index.md", b"# Index")]);
+
+        let mut reader = UploadReader::new(UploadLimits::default());
+        reader.add("bomb.tgz", archive).unwrap();
+        let upload = reader.finish();
+
+        assert!(upload.documents.is_empty());
+        assert_eq!(
+            upload.skipped,
+            vec![(
+                "bomb.tgz".to_owned(),
+                "Rejected, the archive is too large".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_upload_limits() {
+        let limits = UploadLimits {
+            max_files: 2,
+            ..Default::default()
+        };
+        let mut reader = UploadReader::new(limits);
+        reader.add("a.md", b"A".to_vec()).unwrap();
+        reader.add("b.md", b"B".to_vec()).unwrap();
+        assert!(reader.add("c.md", b"C".to_vec()).is_err());
+
+        let limits = UploadLimits {
+            max_total_bytes: 10,
+            ..Default::default()
+        };
+        let mut reader = UploadReader::new(limits);
+        reader.add("a.md", vec![b'a'; 6]).unwrap();
+        assert!(reader.add("b.md", vec![b'b'; 6]).is_err());
+    }
+
+    #[test]
+    fn test_document_key() {
+        let mut reader = UploadReader::new(UploadLimits::default());
+        reader.add("docs/a.md", b"# A".to_vec()).unwrap();
+        let upload = reader.finish();
+
+        assert_eq!(
+            upload.document_key("upload:docs/a.md"),
+            Some("docs/a.md".to_owned())
+        );
+        // Earlier uploads of other files are kept
+        assert_eq!(upload.document_key("upload:docs/b.md"), None);
+        assert_eq!(upload.document_key("https://example.com/docs/a.md"), None);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("./docs//a.md"), Some("docs/a.md".to_owned()));
+        assert_eq!(normalize_path("docs\\a.md"), Some("docs/a.md".to_owned()));
+        assert_eq!(normalize_path("/a.md"), Some("a.md".to_owned()));
+        assert_eq!(normalize_path("docs/../../a.md"), None);
+        assert_eq!(normalize_path("./"), None);
+    }
+}
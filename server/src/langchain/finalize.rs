@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use entity::document;
+use futures_util::Stream;
+use migration::sea_orm::DatabaseConnection;
+
+use crate::database::Repo;
+
+use super::{LLMProvider, Langchain};
+
+/// Progress of finalizing a project version
+pub enum FinalizeProgress {
+    /// The document with the name is being embedded
+    Embedding(String),
+    Finalized,
+}
+
+/// Embed the documents and finalize the project version. The stream ends with an error
+/// when a document could not be embedded, the project version is then not finalized.
+pub fn embed_and_finalize(
+    db: &DatabaseConnection,
+    project_id: i32,
+    version: i32,
+    documents: Vec<document::Model>,
+) -> impl Stream<Item = Result<FinalizeProgress>> + '_ {
+    async_stream::try_stream! {
+        let lc = Langchain::new(LLMProvider::OpenAI);
+
+        for document in documents {
+            yield FinalizeProgress::Embedding(document.name.to_owned());
+
+            let embeddings = lc
+                .embed(&document.content)
+                .await
+                .with_context(|| format!("Failed to embed document {}", document.name))?;
+
+            db.embeddings()
+                .create_many(document.id, embeddings)
+                .await
+                .with_context(|| {
+                    format!("Failed to save embeddings of document {}", document.name)
+                })?;
+        }
+
+        db.projects_versions().finalize(project_id, version).await?;
+        yield FinalizeProgress::Finalized;
+    }
+}
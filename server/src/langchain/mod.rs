@@ -4,10 +4,12 @@ use futures_util::Stream;
 use migration::sea_orm::DatabaseConnection;
 
 pub use self::enums::{LLMOutput, LLMProvider};
+pub use self::finalize::{embed_and_finalize, FinalizeProgress};
 use self::openai::OpenAI;
 
 mod constants;
 mod enums;
+mod finalize;
 mod models;
 mod openai;
 
@@ -97,8 +97,10 @@ impl OpenaiCompletionRequest {
 }
 
 impl OpenaiCompletionRequest {
-    pub fn new(project: project::Model, version: i32) -> Self {
-        let tools = vec![OpenaiTool::get(OpenaiToolName::SimilaritySearch)];
+    /// A request with the tools for the project version, `tags` are the tags of its
+    /// documents with how many documents have each
+    pub fn new(project: project::Model, version: i32, tags: &[(String, i64)]) -> Self {
+        let tools = vec![OpenaiTool::get(OpenaiToolName::SimilaritySearch, tags)];
 
         let system_prompt = SYSTEM_PROMPT
             .replace("{{ name }}", &project.name)
@@ -153,26 +155,56 @@ pub struct OpenaiTool {
 }
 
 impl OpenaiTool {
-    fn get(name: OpenaiToolName) -> Self {
+    fn get(name: OpenaiToolName, tags: &[(String, i64)]) -> Self {
         match name {
-            OpenaiToolName::SimilaritySearch => OpenaiTool {
-                tool_type: OpenaiToolType::Function,
-                function: OpenaiToolFunction {
-                    name: OpenaiToolName::SimilaritySearch,
-                    description: "Search embedded documents for relevant information to the query"
-                        .to_owned(),
-                    parameters: Some(OpenaiToolFunctionParameters {
-                        parameter_type: "object".to_owned(),
-                        properties: serde_json::json!({
-                            "query": {
-                                "type": "string",
-                                "description": "The query to search for in the embedded documents. The query is generated from the user's input into a query that is more likely to return relevant information from the database when cosine distance is invoked."
-                            }
+            OpenaiToolName::SimilaritySearch => {
+                let mut properties = serde_json::json!({
+                    "query": {
+                        "type": "string",
+                        "description": "The query to search for in the embedded documents. The query is generated from the user's input into a query that is more likely to return relevant information from the database when cosine distance is invoked."
+                    },
+                    "metadata": {
+                        "type": "object",
+                        "description": "Only search documents whose front matter has these keys with these values, e.g. {\"audience\": \"admin\"}. Leave it out unless the user asks about documents with specific metadata."
+                    }
+                });
+
+                // The tag filters are only offered when the documents have tags
+                if !tags.is_empty() {
+                    let names = tags.iter().map(|(tag, _)| tag).collect::<Vec<_>>();
+                    let counts = tags
+                        .iter()
+                        .map(|(tag, count)| format!("{tag} ({count})"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    properties["tags"] = serde_json::json!({
+                        "type": "array",
+                        "items": { "type": "string", "enum": names },
+                        "description": format!("Only search documents that have all of these tags. Leave it out unless the user asks about a specific part of the documentation. The tags with their number of documents are: {counts}")
+                    });
+                    properties["exclude_tags"] = serde_json::json!({
+                        "type": "array",
+                        "items": { "type": "string", "enum": names },
+                        "description": "Leave out documents that have any of these tags, e.g. deprecated documents when the user asks about the current behavior."
+                    });
+                }
+
+                OpenaiTool {
+                    tool_type: OpenaiToolType::Function,
+                    function: OpenaiToolFunction {
+                        name: OpenaiToolName::SimilaritySearch,
+                        description:
+                            "Search embedded documents for relevant information to the query"
+                                .to_owned(),
+                        parameters: Some(OpenaiToolFunctionParameters {
+                            parameter_type: "object".to_owned(),
+                            properties,
+                            required: vec!["query".to_owned()],
                         }),
-                        required: vec!["query".to_owned()],
-                    }),
-                },
-            },
+                    },
+                }
+            }
         }
     }
 }
@@ -327,3 +359,33 @@ pub struct OpenaiError {
 pub struct OpenaiErrorMessage {
     pub message: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity_search_tag_facets() {
+        let tool = OpenaiTool::get(OpenaiToolName::SimilaritySearch, &[]);
+        let properties = tool.function.parameters.unwrap().properties;
+        assert!(properties.get("tags").is_none());
+        assert_eq!(properties["metadata"]["type"], "object");
+
+        let tags = [("admin-guide".to_owned(), 3), ("deprecated".to_owned(), 1)];
+        let tool = OpenaiTool::get(OpenaiToolName::SimilaritySearch, &tags);
+        let properties = tool.function.parameters.unwrap().properties;
+
+        assert_eq!(
+            properties["tags"]["items"]["enum"],
+            serde_json::json!(["admin-guide", "deprecated"])
+        );
+        assert_eq!(
+            properties["exclude_tags"]["items"]["enum"],
+            serde_json::json!(["admin-guide", "deprecated"])
+        );
+        assert!(properties["tags"]["description"]
+            .as_str()
+            .unwrap()
+            .ends_with("admin-guide (3), deprecated (1)"));
+    }
+}
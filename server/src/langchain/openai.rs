@@ -14,7 +14,8 @@ use crate::{
         enums::{OpenaiFinishReason::*, OpenaiToolName::*},
         models::OpenaiStreamOutput,
     },
-    models::Embedding,
+    models::{Embedding, SearchFilter},
+    parsing::strip_front_matter,
     utils::config::Config,
 };
 
@@ -38,7 +39,7 @@ impl OpenAI {
         let max_characters = 500..2000;
         let chunk_config = ChunkConfig::new(max_characters).with_sizer(tokenizer);
         let splitter = MarkdownSplitter::new(chunk_config);
-        let chunks = splitter.chunks(content);
+        let chunks = splitter.chunks(strip_front_matter(content));
         let texts = chunks
             .into_iter()
             .map(|chunk| chunk.to_owned())
@@ -121,8 +122,14 @@ impl OpenAI {
             bail!("Project with id '{project_id}' not found");
         };
 
+        let tags = db
+            .documents_versions()
+            .tag_facets(project_id, version)
+            .await
+            .unwrap_or_default();
+
         let stream = async_stream::stream! {
-            let mut request = OpenaiCompletionRequest::new(project, version);
+            let mut request = OpenaiCompletionRequest::new(project, version, &tags);
             request.add_user_msg(prompt);
 
             'request_loop: loop {
@@ -221,13 +228,25 @@ impl OpenAI {
                 #[derive(Debug, Deserialize)]
                 struct Query {
                     query: String,
+                    #[serde(default)]
+                    tags: Vec<String>,
+                    #[serde(default)]
+                    exclude_tags: Vec<String>,
+                    #[serde(default)]
+                    metadata: serde_json::Map<String, serde_json::Value>,
                 }
                 if let Some(id) = tool.id() {
                     let query = serde_json::from_str::<Query>(tool.function().arguments())?;
                     let embedded_query = Self::embed_query(&query.query).await?;
+                    let filter = SearchFilter {
+                        include: query.tags,
+                        exclude: query.exclude_tags,
+                        metadata: (!query.metadata.is_empty())
+                            .then_some(serde_json::Value::Object(query.metadata)),
+                    };
                     let result = db
                         .embeddings()
-                        .similarity_search(project_id, version, embedded_query)
+                        .similarity_search(project_id, version, embedded_query, &filter)
                         .await?;
                     let content = result
                         .iter()
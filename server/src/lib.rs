@@ -8,6 +8,8 @@ pub mod database;
 #[cfg(feature = "ssr")]
 pub mod fallback;
 #[cfg(feature = "ssr")]
+pub mod importers;
+#[cfg(feature = "ssr")]
 pub mod keycloak;
 #[cfg(feature = "ssr")]
 pub mod langchain;
@@ -16,12 +18,16 @@ pub mod middleware;
 #[cfg(feature = "ssr")]
 pub mod models;
 #[cfg(feature = "ssr")]
+pub mod outbound;
+#[cfg(feature = "ssr")]
 pub mod parsing;
 #[cfg(feature = "ssr")]
 pub mod responses;
 #[cfg(feature = "ssr")]
 pub mod routes;
 #[cfg(feature = "ssr")]
+pub mod scheduler;
+#[cfg(feature = "ssr")]
 pub mod server;
 #[cfg(feature = "ssr")]
 pub mod utils;
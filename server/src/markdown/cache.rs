@@ -0,0 +1,60 @@
+use std::{num::NonZeroUsize, sync::Mutex};
+
+use lru::LruCache;
+use once_cell::sync::Lazy;
+
+use crate::{server_functions::models::TocEntry, utils::hash::content_hash};
+
+const CAPACITY: usize = 256;
+
+type Rendered = (String, Vec<TocEntry>);
+
+/// Rendered documents by their id and content hash, so an edited document is
+/// rendered again
+static CACHE: Lazy<Mutex<LruCache<(i32, String), Rendered>>> =
+    Lazy::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(CAPACITY).unwrap())));
+
+pub fn get_or_render(
+    document_id: i32,
+    markdown: &str,
+    render: impl FnOnce(&str) -> Rendered,
+) -> Rendered {
+    let key = (document_id, content_hash(markdown));
+
+    if let Ok(mut cache) = CACHE.lock() {
+        if let Some(rendered) = cache.get(&key) {
+            return rendered.clone();
+        }
+    }
+
+    // Rendered without holding the lock, another request may render it too
+    let rendered = render(markdown);
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.put(key, rendered.clone());
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_renders_changed_content_only() {
+        let renders = Cell::new(0);
+        let render = |markdown: &str| {
+            renders.set(renders.get() + 1);
+            (markdown.to_owned(), vec![])
+        };
+
+        assert_eq!(get_or_render(-1, "A", render).0, "A");
+        assert_eq!(get_or_render(-1, "A", render).0, "A");
+        assert_eq!(renders.get(), 1);
+
+        assert_eq!(get_or_render(-1, "B", render).0, "B");
+        assert_eq!(get_or_render(-2, "B", render).0, "B");
+        assert_eq!(renders.get(), 3);
+    }
+}
@@ -0,0 +1,99 @@
+use super::Markdown;
+
+/// Renders Markdown that is streamed in chunks. The blocks that are complete
+/// are rendered once, only the last block is rendered again on every chunk.
+///
+/// The blocks are rendered on their own, so a list with blank lines between
+/// its items is split into several lists while streaming.
+#[derive(Debug, Default)]
+pub struct IncrementalMarkdown {
+    html: String,
+    /// The Markdown of the blocks rendered to `html`
+    rendered: String,
+}
+
+impl IncrementalMarkdown {
+    /// The HTML of the whole Markdown streamed so far
+    pub fn render(&mut self, markdown: &str) -> String {
+        // The Markdown was replaced instead of appended to
+        if !markdown.starts_with(&self.rendered) {
+            *self = Self::default();
+        }
+
+        let offset = self.rendered.len();
+        let end = offset + complete_blocks(&markdown[offset..]);
+        if end > offset {
+            self.html
+                .push_str(&Markdown::to_html(&markdown[offset..end]));
+            self.rendered.push_str(&markdown[offset..end]);
+        }
+
+        format!("{}{}", self.html, Markdown::to_html(&markdown[end..]))
+    }
+}
+
+/// The length of the blocks that are followed by a blank line, which are not
+/// changed by what comes after them. Blank lines in code blocks and display
+/// math do not end a block.
+fn complete_blocks(markdown: &str) -> usize {
+    let mut length = 0;
+    let mut fence: Option<&str> = None;
+    let mut position = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        position += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+
+        let trimmed = line.trim();
+        match fence {
+            Some(open) if trimmed.starts_with(open) => fence = None,
+            Some(_) => (),
+            None if trimmed.starts_with("```") => fence = Some("```"),
+            None if trimmed.starts_with("~~~") => fence = Some("~~~"),
+            None if trimmed.starts_with("$$") && !trimmed[2..].contains("$$") => fence = Some("$$"),
+            None if trimmed.is_empty() => length = position,
+            None => (),
+        }
+    }
+
+    length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_blocks() {
+        assert_eq!(complete_blocks("# Title\n\nText"), 9);
+        assert_eq!(complete_blocks("Text\n"), 0);
+        assert_eq!(complete_blocks("```\ncode\n\nmore"), 0);
+        assert_eq!(complete_blocks("```\ncode\n\n```\n\nText"), 15);
+        assert_eq!(complete_blocks("$$\nx\n\ny\n$$\n\n"), 12);
+    }
+
+    #[test]
+    fn test_same_html_as_whole() {
+        let markdown = "# Title\n\nSome `code`\n\n```rust\nfn main() {\n\n}\n```\n\nThe end";
+        let mut incremental = IncrementalMarkdown::default();
+
+        let mut html = String::new();
+        for end in 1..=markdown.len() {
+            html = incremental.render(&markdown[..end]);
+        }
+
+        assert_eq!(html, Markdown::to_html(markdown));
+    }
+
+    #[test]
+    fn test_replaced_markdown() {
+        let mut incremental = IncrementalMarkdown::default();
+        incremental.render("# First\n\nText");
+
+        // Rendered again from the start although the old blocks end on a char boundary
+        let html = incremental.render("# Other\n\nText");
+        assert_eq!(html, Markdown::to_html("# Other\n\nText"));
+    }
+}
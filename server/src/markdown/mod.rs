@@ -1,18 +1,215 @@
+#[cfg(feature = "ssr")]
+mod cache;
+mod incremental;
+mod plugins;
+mod sanitize;
+
 use comrak::{
-    markdown_to_html_with_plugins, plugins::syntect::SyntectAdapter, ComrakOptions, ComrakPlugins,
+    format_html_with_plugins,
+    nodes::{AstNode, NodeCode, NodeMath, NodeValue},
+    parse_document, Anchorizer, Arena, ComrakOptions, ComrakPlugins,
 };
+use once_cell::sync::Lazy;
+use plugins::{render_math, RenderAdapter};
+
+use crate::server_functions::models::TocEntry;
+
+pub use incremental::IncrementalMarkdown;
+pub use sanitize::sanitize_html;
+use sanitize::ID_PREFIX;
 
 const CODE_BLOCK_THEME: &str = "base16-eighties.dark";
 
+/// Loading the syntaxes and themes is slow, so they are loaded once
+static ADAPTER: Lazy<RenderAdapter> = Lazy::new(|| RenderAdapter::new(CODE_BLOCK_THEME));
+
 pub struct Markdown;
 
 impl Markdown {
+    /// Render the Markdown as HTML that is safe to inject into the page, it is
+    /// sanitized as documents and chat answers come from crawled pages and LLMs
     pub fn to_html(markdown: &str) -> String {
-        let adapter = SyntectAdapter::new(Some(CODE_BLOCK_THEME));
-        let options = ComrakOptions::default();
+        Self::render(markdown, false).0
+    }
+
+    /// Render a document with ids on its headings, which are listed in the
+    /// table of contents so they can be linked to as `#section` anchors
+    pub fn to_html_with_toc(markdown: &str) -> (String, Vec<TocEntry>) {
+        Self::render(markdown, true)
+    }
+
+    /// Like `to_html_with_toc` without the front matter of the document, but
+    /// the document is only rendered again when its content has changed
+    #[cfg(feature = "ssr")]
+    pub fn document_to_html(document_id: i32, markdown: &str) -> (String, Vec<TocEntry>) {
+        let markdown = crate::parsing::strip_front_matter(markdown);
+        cache::get_or_render(document_id, markdown, Self::to_html_with_toc)
+    }
+
+    fn render(markdown: &str, header_ids: bool) -> (String, Vec<TocEntry>) {
+        let options = options(header_ids);
         let mut plugins = ComrakPlugins::default();
-        plugins.render.codefence_syntax_highlighter = Some(&adapter);
+        plugins.render.codefence_syntax_highlighter = Some(&*ADAPTER);
+
+        let arena = Arena::new();
+        let root = parse_document(&arena, markdown, &options);
+        let toc = match header_ids {
+            true => table_of_contents(root),
+            false => vec![],
+        };
+        render_math(root);
+
+        let mut html = vec![];
+        if format_html_with_plugins(root, &options, &mut html, &plugins).is_err() {
+            return (String::new(), toc);
+        }
+
+        (sanitize_html(&String::from_utf8_lossy(&html)), toc)
+    }
+}
+
+/// GitHub flavored Markdown, crawled documentation is mostly written in it
+fn options(header_ids: bool) -> ComrakOptions {
+    let mut options = ComrakOptions::default();
+    options.extension.strikethrough = true;
+    options.extension.table = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+    options.extension.footnotes = true;
+    options.extension.math_dollars = true;
+    // The adapter needs the language on the pre tag to render diagrams
+    options.render.github_pre_lang = true;
+    // Raw HTML is removed from the document before rendering the math
+    options.render.unsafe_ = true;
+    if header_ids {
+        options.extension.header_ids = Some(String::new());
+    }
+    options
+}
+
+/// The headings of the document with the ids comrak renders for them, which
+/// are unique as repeated headings get a number appended. The ids get the
+/// prefix the sanitizer adds to them.
+fn table_of_contents<'a>(root: &'a AstNode<'a>) -> Vec<TocEntry> {
+    let mut anchorizer = Anchorizer::new();
+
+    root.descendants()
+        .filter_map(|node| {
+            let NodeValue::Heading(heading) = node.data.borrow().value else {
+                return None;
+            };
+
+            let mut title = String::new();
+            collect_text(node, &mut title);
+            Some(TocEntry {
+                level: heading.level,
+                id: format!("{ID_PREFIX}{}", anchorizer.anchorize(title.to_owned())),
+                title: title.trim().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// The text of a heading the way comrak collects it for the heading id
+fn collect_text<'a>(node: &'a AstNode<'a>, output: &mut String) {
+    match node.data.borrow().value {
+        NodeValue::Text(ref literal)
+        | NodeValue::Code(NodeCode { ref literal, .. })
+        | NodeValue::Math(NodeMath { ref literal, .. }) => output.push_str(literal),
+        NodeValue::LineBreak | NodeValue::SoftBreak => output.push(' '),
+        _ => {
+            for child in node.children() {
+                collect_text(child, output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gfm() {
+        let html = Markdown::to_html(
+            "| A | B |\n|---|:-:|\n| 1 | 2 |\n\n~~old~~ www.example.com\n\n- [x] Done\n- [ ] Todo\n\nNote[^1]\n\n[^1]: A footnote",
+        );
+
+        assert!(html.contains("<table>"));
+        assert!(html.contains("<th align=\"center\">B</th>"));
+        assert!(html.contains("<del>old</del>"));
+        assert!(html.contains("<a href=\"http://www.example.com\">www.example.com</a>"));
+        assert!(html.contains("<input type=\"checkbox\" checked=\"\" disabled=\"\">"));
+        assert!(html.contains("<section class=\"footnotes\">"));
+        assert!(!html.contains("<h1"));
+    }
+
+    #[test]
+    fn test_table_of_contents() {
+        let (html, toc) = Markdown::to_html_with_toc(
+            "# Getting started\n\nText\n\n## Install `cargo`\n\n## Setup\n\n### Setup\n",
+        );
+
+        assert_eq!(
+            toc,
+            vec![
+                TocEntry {
+                    level: 1,
+                    id: "user-content-getting-started".to_owned(),
+                    title: "Getting started".to_owned()
+                },
+                TocEntry {
+                    level: 2,
+                    id: "user-content-install-cargo".to_owned(),
+                    title: "Install cargo".to_owned()
+                },
+                TocEntry {
+                    level: 2,
+                    id: "user-content-setup".to_owned(),
+                    title: "Setup".to_owned()
+                },
+                TocEntry {
+                    level: 3,
+                    id: "user-content-setup-1".to_owned(),
+                    title: "Setup".to_owned()
+                },
+            ]
+        );
+        for entry in toc {
+            assert!(html.contains(&format!("id=\"{}\"", entry.id)));
+        }
+    }
+
+    #[test]
+    fn test_math() {
+        let html =
+            Markdown::to_html("Inline $x^2$ and\n\n$$\\frac{a}{b}$$\n\n```math\n\\sqrt{2}\n```");
+
+        assert!(html.contains("display=\"inline\""));
+        assert!(html.contains("<msup>"));
+        assert!(html.contains("<mfrac>"));
+        assert!(html.contains("<pre class=\"rendered\"><code><math"));
+        assert!(html.contains("<msqrt>"));
+        assert!(!html.contains('$'));
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let html = Markdown::to_html("```mermaid\nflowchart LR\n    A --> B\n```");
+
+        assert!(html.starts_with("<pre class=\"rendered\"><code><svg"));
+        assert!(!html.contains("flowchart"));
+        assert!(html.contains("viewBox=\""));
+        assert!(html.contains("fill=\"#"));
+        assert!(html.contains("stroke=\"#"));
+        assert!(html.contains("<marker id=\"user-content-arrow-0\""));
+        assert!(html.contains("marker-end=\"url(#user-content-arrow-0)\""));
+    }
+
+    #[test]
+    fn test_raw_html_is_removed() {
+        let html = Markdown::to_html("<div class=\"x\">Raw</div>\n\nText <b>bold</b>");
 
-        markdown_to_html_with_plugins(markdown, &options, &plugins)
+        assert_eq!(html, "<p>Text bold</p>\n");
     }
 }
@@ -0,0 +1,128 @@
+use std::{collections::HashMap, io::Write};
+
+use comrak::{
+    adapters::SyntaxHighlighterAdapter,
+    nodes::{AstNode, NodeCodeBlock, NodeHtmlBlock, NodeMath, NodeValue},
+    plugins::syntect::SyntectAdapter,
+};
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+/// Code block adapter which renders `mermaid` blocks as SVG, the other
+/// languages are highlighted by syntect
+pub struct RenderAdapter {
+    syntect: SyntectAdapter,
+}
+
+impl RenderAdapter {
+    pub fn new(theme: &str) -> Self {
+        Self {
+            syntect: SyntectAdapter::new(Some(theme)),
+        }
+    }
+}
+
+impl SyntaxHighlighterAdapter for RenderAdapter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        lang: Option<&str>,
+        code: &str,
+    ) -> std::io::Result<()> {
+        let rendered = match lang {
+            Some("mermaid") => render_mermaid(code),
+            _ => None,
+        };
+
+        match rendered {
+            Some(html) => output.write_all(html.as_bytes()),
+            // Broken diagrams are shown as their source
+            None => self.syntect.write_highlighted(output, lang, code),
+        }
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<String, String>,
+    ) -> std::io::Result<()> {
+        match attributes.get("lang").map(String::as_str) {
+            Some("mermaid") if cfg!(feature = "ssr") => {
+                output.write_all(b"<pre class=\"rendered\">")
+            }
+            _ => self.syntect.write_pre_tag(output, attributes),
+        }
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn Write,
+        attributes: HashMap<String, String>,
+    ) -> std::io::Result<()> {
+        self.syntect.write_code_tag(output, attributes)
+    }
+}
+
+/// Diagrams are only rendered on the server, the renderer would add to the size
+/// of the WASM bundle. Chat answers, which are rendered in the browser, show
+/// their source.
+#[cfg(feature = "ssr")]
+fn render_mermaid(code: &str) -> Option<String> {
+    // The renderer panics on some malformed diagrams
+    std::panic::catch_unwind(|| mermaid_rs_renderer::render(code))
+        .ok()?
+        .ok()
+}
+
+#[cfg(not(feature = "ssr"))]
+fn render_mermaid(_code: &str) -> Option<String> {
+    None
+}
+
+/// Replace the `$…$`, `$$…$$` and ```` ```math ```` math with MathML. Raw HTML of the document
+/// is removed as the MathML is written as raw HTML, which comrak then has to
+/// keep. Math in headings is kept as is to not change the heading ids.
+pub fn render_math<'a>(root: &'a AstNode<'a>) {
+    let nodes = root.descendants().collect::<Vec<_>>();
+
+    for node in nodes {
+        let mut data = node.data.borrow_mut();
+        match data.value {
+            NodeValue::HtmlInline(_) | NodeValue::HtmlBlock(_) => node.detach(),
+            NodeValue::Math(NodeMath {
+                display_math,
+                ref literal,
+                ..
+            }) => {
+                let in_heading = node
+                    .ancestors()
+                    .skip(1)
+                    .any(|ancestor| matches!(ancestor.data.borrow().value, NodeValue::Heading(_)));
+                if in_heading {
+                    continue;
+                }
+
+                let style = match display_math {
+                    true => DisplayStyle::Block,
+                    false => DisplayStyle::Inline,
+                };
+                if let Ok(mathml) = latex_to_mathml(literal, style) {
+                    data.value = NodeValue::HtmlInline(mathml);
+                }
+            }
+            NodeValue::CodeBlock(NodeCodeBlock {
+                ref info,
+                ref literal,
+                ..
+            }) if info.split_whitespace().next() == Some("math") => {
+                // Broken formulas are highlighted as code
+                if let Ok(mathml) = latex_to_mathml(literal, DisplayStyle::Block) {
+                    data.value = NodeValue::HtmlBlock(NodeHtmlBlock {
+                        block_type: 0,
+                        literal: format!("<pre class=\"rendered\"><code>{mathml}</code></pre>\n"),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,457 @@
+//! An allowlist HTML sanitizer for rendered Markdown, built on ammonia. Only
+//! the tags, attributes and classes comrak, syntect and the diagram and math
+//! renderers emit are kept.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use ammonia::{Builder, UrlRelative};
+use once_cell::sync::Lazy;
+
+/// Prefix of the ids in the HTML, so they cannot clobber the ids and globals of
+/// the app. Links to them are prefixed too.
+pub const ID_PREFIX: &str = "user-content-";
+
+/// Tags that are kept, with the attributes they may have
+const ALLOWED_TAGS: &[(&str, &[&str])] = &[
+    ("a", &["href", "title", "id", "class", "aria-hidden"]),
+    ("abbr", &["title"]),
+    ("b", &[]),
+    ("blockquote", &[]),
+    ("br", &[]),
+    ("code", &["class"]),
+    ("dd", &[]),
+    ("del", &[]),
+    ("details", &["open"]),
+    ("div", &[]),
+    ("dl", &[]),
+    ("dt", &[]),
+    ("em", &[]),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("hr", &[]),
+    ("i", &[]),
+    ("img", &["src", "alt", "title", "width", "height"]),
+    ("input", &["type", "checked", "disabled"]),
+    ("ins", &[]),
+    ("kbd", &[]),
+    ("li", &["id"]),
+    ("mark", &[]),
+    ("ol", &["start"]),
+    ("p", &[]),
+    ("pre", &["class", "style"]),
+    ("s", &[]),
+    ("section", &["class"]),
+    ("span", &["style"]),
+    ("strong", &[]),
+    ("sub", &[]),
+    ("summary", &[]),
+    ("sup", &["class", "id"]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["align", "colspan", "rowspan"]),
+    ("tfoot", &[]),
+    ("th", &["align", "colspan", "rowspan"]),
+    ("thead", &[]),
+    ("tr", &[]),
+    ("ul", &[]),
+];
+
+/// MathML tags rendered from formulas, which may have any of the MathML
+/// attributes
+const MATHML_TAGS: &[&str] = &[
+    "math",
+    "menclose",
+    "mfrac",
+    "mi",
+    "mn",
+    "mo",
+    "mover",
+    "mpadded",
+    "mphantom",
+    "mroot",
+    "mrow",
+    "mspace",
+    "msqrt",
+    "mstyle",
+    "msub",
+    "msubsup",
+    "msup",
+    "mtable",
+    "mtd",
+    "mtext",
+    "mtr",
+    "munder",
+    "munderover",
+    "semantics",
+    "annotation",
+];
+
+const MATHML_ATTRIBUTES: &[&str] = &[
+    "xmlns",
+    "display",
+    "mathvariant",
+    "stretchy",
+    "fence",
+    "separator",
+    "form",
+    "lspace",
+    "rspace",
+    "accent",
+    "accentunder",
+    "movablelimits",
+    "linethickness",
+    "displaystyle",
+    "scriptlevel",
+    "width",
+    "height",
+    "depth",
+    "columnalign",
+    "notation",
+    "encoding",
+];
+
+/// SVG tags rendered from diagrams, which may have any of the SVG attributes.
+/// Names are in the case the HTML parser gives them.
+const SVG_TAGS: &[&str] = &[
+    "svg",
+    "g",
+    "defs",
+    "marker",
+    "symbol",
+    "linearGradient",
+    "stop",
+    "path",
+    "rect",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "text",
+    "tspan",
+];
+
+/// The geometry and presentation attributes diagrams are drawn and styled with
+const SVG_ATTRIBUTES: &[&str] = &[
+    "xmlns",
+    "id",
+    "style",
+    "viewBox",
+    "width",
+    "height",
+    "transform",
+    "d",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "dx",
+    "dy",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "points",
+    "offset",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "clip-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-dasharray",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stop-color",
+    "opacity",
+    "marker-start",
+    "marker-end",
+    "refX",
+    "refY",
+    "markerWidth",
+    "markerHeight",
+    "markerUnits",
+    "gradientUnits",
+    "orient",
+    "text-anchor",
+    "dominant-baseline",
+    "alignment-baseline",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "font-style",
+    "textLength",
+    "lengthAdjust",
+];
+
+/// Tags that are removed together with their content
+const REMOVED_TAGS: &[&str] = &[
+    "script",
+    "style",
+    "iframe",
+    "object",
+    "embed",
+    "noscript",
+    "template",
+    "textarea",
+    "title",
+    "xmp",
+    "foreignObject",
+];
+
+/// CSS properties syntect highlights code with and diagrams are styled with
+const ALLOWED_STYLES: &[&str] = &[
+    "color",
+    "background-color",
+    "font-weight",
+    "font-style",
+    "font-size",
+    "font-family",
+    "text-decoration",
+    "text-anchor",
+    "fill",
+    "stroke",
+    "stroke-width",
+    "stroke-dasharray",
+    "max-width",
+];
+
+/// Classes of the rendered Markdown, besides the `language-` of code blocks.
+/// Other classes could use the ones of the app to cover or imitate its UI.
+const ALLOWED_CLASSES: &[&str] = &[
+    "anchor",
+    "footnotes",
+    "footnote-ref",
+    "footnote-backref",
+    "rendered",
+];
+
+static SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut tag_attributes = ALLOWED_TAGS
+        .iter()
+        .map(|(tag, attributes)| (*tag, attributes.iter().copied().collect()))
+        .collect::<HashMap<_, _>>();
+    tag_attributes.extend(
+        MATHML_TAGS
+            .iter()
+            .map(|tag| (*tag, MATHML_ATTRIBUTES.iter().copied().collect())),
+    );
+    tag_attributes.extend(
+        SVG_TAGS
+            .iter()
+            .map(|tag| (*tag, SVG_ATTRIBUTES.iter().copied().collect())),
+    );
+
+    let mut builder = Builder::empty();
+    builder
+        .tags(tag_attributes.keys().copied().collect())
+        .tag_attributes(tag_attributes)
+        .clean_content_tags(REMOVED_TAGS.iter().copied().collect())
+        .url_schemes(["http", "https", "mailto", "data"].into())
+        .url_relative(UrlRelative::PassThrough)
+        .link_rel(None)
+        .strip_comments(true)
+        .id_prefix(Some(ID_PREFIX))
+        .filter_style_properties(ALLOWED_STYLES.iter().copied().collect())
+        .attribute_filter(filter_attribute);
+    builder
+});
+
+/// Remove every tag, attribute and url that is not known to be safe from
+/// the HTML. Unknown tags are dropped but their text is kept.
+pub fn sanitize_html(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
+
+fn filter_attribute<'a>(tag: &str, attribute: &str, value: &'a str) -> Option<Cow<'a, str>> {
+    match (tag, attribute) {
+        (_, "class") => {
+            let classes = value
+                .split_ascii_whitespace()
+                .filter(|class| ALLOWED_CLASSES.contains(class) || is_language_class(class))
+                .collect::<Vec<_>>();
+            (!classes.is_empty()).then(|| classes.join(" ").into())
+        }
+        (_, "href") if value.starts_with('#') => Some(prefix_fragment(value, "#").into()),
+        ("a", "href") => (!has_scheme(value, "data")).then_some(value.into()),
+        ("img", "src") => is_safe_image(value).then_some(value.into()),
+        ("input", "type") => value
+            .eq_ignore_ascii_case("checkbox")
+            .then_some(value.into()),
+        (_, "style") => plain_declarations(value).map(Cow::Owned),
+        // Only references to elements of the diagram, not to other documents
+        (_, "fill" | "stroke" | "marker-start" | "marker-end") => {
+            let value = value.trim();
+            match value.to_ascii_lowercase().contains("url(") {
+                true => value
+                    .starts_with("url(#")
+                    .then(|| prefix_fragment(value, "url(#").into()),
+                false => Some(value.into()),
+            }
+        }
+        _ => Some(value.into()),
+    }
+}
+
+/// The declarations with plain values, without functions like `url()` which
+/// could load from other origins. The properties are filtered by ammonia.
+fn plain_declarations(style: &str) -> Option<String> {
+    let declarations = style
+        .split(';')
+        .filter(|declaration| {
+            declaration.split_once(':').is_some_and(|(_, value)| {
+                let value = value.trim();
+                !value.is_empty()
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || " #,.%-".contains(c))
+            })
+        })
+        .map(|declaration| format!("{};", declaration.trim()))
+        .collect::<String>();
+
+    (!declarations.is_empty()).then_some(declarations)
+}
+
+/// `language-rust` on the code of a highlighted block
+fn is_language_class(class: &str) -> bool {
+    class.strip_prefix("language-").is_some_and(|language| {
+        !language.is_empty()
+            && language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+#-_.".contains(c))
+    })
+}
+
+/// The reference to an id in the HTML with the prefix of the ids
+fn prefix_fragment(value: &str, start: &str) -> String {
+    let id = &value[start.len()..];
+    match id.starts_with(ID_PREFIX) {
+        true => value.to_owned(),
+        false => format!("{start}{ID_PREFIX}{id}"),
+    }
+}
+
+fn has_scheme(url: &str, scheme: &str) -> bool {
+    url.trim_start()
+        .get(..scheme.len() + 1)
+        .is_some_and(|start| start.eq_ignore_ascii_case(&format!("{scheme}:")))
+}
+
+/// Images are linked by http(s) or relative urls, or inlined as data urls
+fn is_safe_image(url: &str) -> bool {
+    if has_scheme(url, "mailto") {
+        return false;
+    }
+    if !has_scheme(url, "data") {
+        return true;
+    }
+
+    let data = url.trim_start()[5..].to_ascii_lowercase();
+    ["image/png", "image/jpeg", "image/gif", "image/webp"]
+        .iter()
+        .any(|mime| data.starts_with(&format!("{mime};base64,")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("<p>Text</p>", "<p>Text</p>")]
+    #[case("<script>alert(1)</script><p>Text</p>", "<p>Text</p>")]
+    #[case("<SCRIPT src=x></SCRIPT >Text", "Text")]
+    #[case("<p onclick=\"alert(1)\" class=x>Text</p>", "<p>Text</p>")]
+    #[case("<img src=x onerror=alert(1)>", "<img src=\"x\">")]
+    #[case("<a href=\"javascript:alert(1)\">A</a>", "<a>A</a>")]
+    #[case("<a href=\"JaVa&#x09;Script&colon;alert(1)\">A</a>", "<a>A</a>")]
+    #[case("<a href=\"&#106;avascript:alert(1)\">A</a>", "<a>A</a>")]
+    #[case("<a href=\" java\tscript:alert(1)\">A</a>", "<a>A</a>")]
+    #[case(
+        "<a href=\"https://example.com/?a=1&amp;b=2\" target=_blank>A</a>",
+        "<a href=\"https://example.com/?a=1&amp;b=2\">A</a>"
+    )]
+    #[case("<a href=\"/docs#setup\">A</a>", "<a href=\"/docs#setup\">A</a>")]
+    #[case(
+        "<a href=\"mailto:a@example.com\">A</a>",
+        "<a href=\"mailto:a@example.com\">A</a>"
+    )]
+    #[case(
+        "<img src=\"data:image/png;base64,AAAA\">",
+        "<img src=\"data:image/png;base64,AAAA\">"
+    )]
+    #[case("<img src=\"data:text/html;base64,AAAA\">", "<img>")]
+    #[case("<iframe src=x>Text</iframe>After", "After")]
+    #[case("<svg><script>alert(1)</script></svg>After", "<svg></svg>After")]
+    #[case(
+        "<svg viewBox=\"0 0 10 10\" onload=alert(1)><path d=\"M0 0\" marker-end=\"url(#arrow)\"/></svg>",
+        "<svg viewBox=\"0 0 10 10\"><path d=\"M0 0\" marker-end=\"url(#user-content-arrow)\"></path></svg>"
+    )]
+    #[case(
+        "<svg><rect fill=\"url(https://example.com/x)\"/><foreignObject><p>A</p></foreignObject></svg>",
+        "<svg><rect></rect></svg>"
+    )]
+    #[case(
+        "<svg><rect/><text x=\"1\">A</text></svg>",
+        "<svg><rect></rect><text x=\"1\">A</text></svg>"
+    )]
+    #[case(
+        "<math display=\"block\"><mi href=\"javascript:alert(1)\">x</mi></math>",
+        "<math display=\"block\"><mi>x</mi></math>"
+    )]
+    #[case("<!-- raw HTML omitted --><p>A</p>", "<p>A</p>")]
+    #[case("<custom-tag>Text</custom-tag>", "Text")]
+    #[case("<p>Unclosed <strong>tags", "<p>Unclosed <strong>tags</strong></p>")]
+    #[case("</div><p>Stray</p></p>", "<p>Stray</p><p></p>")]
+    #[case("<ul><li>A<li>B</ul>", "<ul><li>A</li><li>B</li></ul>")]
+    #[case(
+        "<div class=\"fixed inset-0 z-50\"><pre class=\"rendered fixed\">A</pre></div>",
+        "<div><pre class=\"rendered\">A</pre></div>"
+    )]
+    #[case(
+        "<li id=\"fn-1\"><a href=\"#fnref-1\" class=\"footnote-backref\">B</a></li>",
+        "<li id=\"user-content-fn-1\"><a href=\"#user-content-fnref-1\" class=\"footnote-backref\">B</a></li>"
+    )]
+    #[case("<h1 id=\"app\">A</h1>", "<h1>A</h1>")]
+    #[case("1 < 2 <3 >", "1 &lt; 2 &lt;3 &gt;")]
+    #[case(
+        "<img src=\"a\" alt='\"><script>'>",
+        "<img src=\"a\" alt=\"&quot;&gt;&lt;script&gt;\">"
+    )]
+    #[case(
+        "<input type=\"checkbox\" checked disabled>",
+        "<input type=\"checkbox\" checked=\"\" disabled=\"\">"
+    )]
+    #[case("<input type=\"text\" value=\"x\">", "<input>")]
+    fn test_sanitize_html(#[case] html: &str, #[case] expected: &str) {
+        assert_eq!(sanitize_html(html), expected);
+    }
+
+    #[test]
+    fn test_keeps_highlighting() {
+        let html = "<pre style=\"background-color:#2d2d2d;\"><code class=\"language-rust\">\
+            <span style=\"color:#cc99cc;\">fn </span><span style=\"font-weight:bold;color:#6699cc;\">main</span>\
+            </code></pre>\n";
+        // Declarations are written without the last semicolon
+        assert_eq!(sanitize_html(html), html.replace(";\"", "\""));
+    }
+
+    #[rstest]
+    #[case("color:#cc99cc;", "<span style=\"color:#cc99cc\">A</span>")]
+    #[case("color: red; position: fixed", "<span style=\"color:red\">A</span>")]
+    #[case("background-color:url(https://example.com/x)", "<span>A</span>")]
+    #[case("color:expression(alert(1))", "<span>A</span>")]
+    fn test_sanitize_style(#[case] style: &str, #[case] expected: &str) {
+        let html = format!("<span style=\"{style}\">A</span>");
+        assert_eq!(sanitize_html(&html), expected);
+    }
+}
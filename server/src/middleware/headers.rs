@@ -1,18 +1,45 @@
 use axum::{extract::Request, middleware::Next, response::Response};
 use http::{
-    header::{CACHE_CONTROL, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS},
+    header::{CACHE_CONTROL, CONTENT_SECURITY_POLICY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS},
     HeaderValue,
 };
 
 use crate::utils::config::Config;
 
+/// The Content Security Policy of a response. Leptos hydrates pages with
+/// inline scripts, which are only allowed with the nonce of the response they
+/// were rendered for. Syntect highlights code with inline styles, so those are
+/// allowed. The policy keeps everything else from loading from or sending data
+/// to other origins.
+pub fn content_security_policy(nonce: Option<&str>) -> HeaderValue {
+    let nonce = nonce.map_or_else(String::new, |nonce| format!(" 'nonce-{nonce}'"));
+    let policy = format!(
+        "default-src 'self'; \
+        script-src 'self'{nonce} 'wasm-unsafe-eval'; \
+        style-src 'self' 'unsafe-inline'; \
+        img-src 'self' https: data:; \
+        connect-src 'self'; \
+        object-src 'none'; \
+        base-uri 'self'; \
+        form-action 'self'; \
+        frame-ancestors 'none'"
+    );
+
+    HeaderValue::from_str(&policy).expect("The policy is a valid header value")
+}
+
 pub async fn default(req: Request, next: Next) -> Response {
     let mut res = next.run(req).await;
 
-    res.headers_mut().extend([
+    let headers = res.headers_mut();
+    headers.extend([
         (X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
         (X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
     ]);
+    // Pages rendered by Leptos come with a policy for their nonce
+    headers
+        .entry(CONTENT_SECURITY_POLICY)
+        .or_insert_with(|| content_security_policy(None));
 
     res
 }
@@ -33,3 +60,20 @@ pub async fn cache_control(req: Request, next: Next) -> Response {
         .append(CACHE_CONTROL, HeaderValue::from_static(cache_control));
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_security_policy() {
+        let policy = content_security_policy(Some("abc"));
+        let policy = policy.to_str().unwrap();
+        assert!(policy.contains("script-src 'self' 'nonce-abc' 'wasm-unsafe-eval';"));
+
+        let policy = content_security_policy(None);
+        let policy = policy.to_str().unwrap();
+        assert!(policy.starts_with("default-src 'self'; script-src 'self' 'wasm-unsafe-eval';"));
+        assert!(!policy.contains("nonce"));
+    }
+}
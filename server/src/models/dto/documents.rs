@@ -1,10 +1,33 @@
-use migration::sea_orm::{self, FromQueryResult};
+use migration::sea_orm::{self, prelude::Json, FromQueryResult};
 use serde::Serialize;
 
+use crate::parsing::{Done, HtmlParser};
+
 #[derive(Debug, Clone, Serialize, FromQueryResult)]
 pub struct DocumentWithoutContent {
     pub id: i32,
     pub name: String,
+    /// The front matter of the document, see `DocumentMetadata`
+    pub metadata: Option<Json>,
+    pub folder_id: Option<i32>,
+    pub position: i32,
+    pub tags: Json,
     pub is_embedded: bool,
     pub is_finalized: bool,
 }
+
+/// Changes to the documents of a project found by re-importing their source
+#[derive(Default)]
+pub struct DocumentChanges {
+    pub added: Vec<HtmlParser<Done>>,
+    /// Changed documents with the id of the document version they replace
+    pub changed: Vec<(i32, HtmlParser<Done>)>,
+    /// Ids of the document versions that no longer exist in the source
+    pub removed: Vec<i32>,
+}
+
+impl DocumentChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
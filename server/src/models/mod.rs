@@ -1,12 +1,14 @@
+mod cache_validators;
 mod dto;
 mod embedding;
 mod form_data;
 mod similarity_search_result;
 // mod slugs;
 
+pub use cache_validators::CacheValidators;
 pub use dto::*;
 pub use embedding::Embedding;
 // pub use form_data::{chat::*, documents::*, project::*, role::*};
 pub use form_data::role::*;
-pub use similarity_search_result::SearchResult;
+pub use similarity_search_result::{SearchFilter, SearchResult};
 // pub use slugs::Slugs;
@@ -3,3 +3,12 @@ pub struct SearchResult {
     pub text: String,
     pub score: f64,
 }
+
+/// Tags the documents of a similarity search must have all of, tags of
+/// documents to leave out, and front matter the documents must contain
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub metadata: Option<serde_json::Value>,
+}
@@ -0,0 +1,421 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::{HeaderMap, AUTHORIZATION, COOKIE, LOCATION, PROXY_AUTHORIZATION},
+    redirect, Response, Url,
+};
+use thiserror::Error;
+use url::Host;
+
+use super::FetchPolicy;
+
+/// Redirects followed before giving up, the same as the reqwest default
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Error)]
+pub enum OutboundError {
+    #[error("Fetching {0} is not allowed: it is not a public address")]
+    Blocked(String),
+    #[error("Only http and https urls can be fetched, not {0}")]
+    Scheme(String),
+    #[error("Too many redirects")]
+    TooManyRedirects,
+}
+
+/// HTTP client for every request to a user provided url. Each request and every
+/// redirect hop is checked against the fetch policy, including the addresses
+/// host names resolve to.
+#[derive(Clone)]
+pub struct OutboundClient {
+    client: reqwest::Client,
+    policy: Arc<FetchPolicy>,
+    credentials: Option<Arc<ScopedHeaders>>,
+}
+
+/// Headers that are only sent to one host
+struct ScopedHeaders {
+    scope: Url,
+    headers: HeaderMap,
+}
+
+impl ScopedHeaders {
+    /// Whether the url is on the same host and port as the scope. Headers for an
+    /// https site are never sent over plain http.
+    fn applies_to(&self, url: &Url) -> bool {
+        url.host_str() == self.scope.host_str()
+            && url.port_or_known_default() == self.scope.port_or_known_default()
+            && (url.scheme() == "https" || self.scope.scheme() == "http")
+    }
+}
+
+impl OutboundClient {
+    pub fn new(policy: FetchPolicy, connect_timeout: Duration) -> Result<Self> {
+        let policy = Arc::new(policy);
+        let resolver = PolicyResolver {
+            policy: policy.clone(),
+        };
+
+        let client = reqwest::Client::builder()
+            .dns_resolver(Arc::new(resolver))
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .connect_timeout(connect_timeout)
+            .build()?;
+
+        Ok(Self {
+            client,
+            policy,
+            credentials: None,
+        })
+    }
+
+    /// Send the headers, e.g. authentication, with every request to the host of
+    /// the url, including redirects back to it. They are never sent to other hosts.
+    pub fn with_credentials(mut self, url: &Url, headers: HeaderMap) -> Self {
+        self.credentials = match headers.is_empty() {
+            true => None,
+            false => Some(Arc::new(ScopedHeaders {
+                scope: url.clone(),
+                headers,
+            })),
+        };
+        self
+    }
+
+    /// Send a GET request, following redirects. Credentials are only sent to the
+    /// host of the original url.
+    pub async fn get(&self, url: Url, headers: HeaderMap) -> Result<Response> {
+        let mut url = url;
+        let mut headers = headers;
+
+        for _ in 0..=MAX_REDIRECTS {
+            self.check_url(&url)?;
+
+            let mut request_headers = headers.clone();
+            if let Some(credentials) = self.credentials.as_ref().filter(|c| c.applies_to(&url)) {
+                request_headers.extend(credentials.headers.clone());
+            }
+
+            let response = self
+                .client
+                .get(url.clone())
+                .headers(request_headers)
+                .send()
+                .await
+                .map_err(Self::unwrap_error)?;
+
+            if !response.status().is_redirection() {
+                return Ok(response);
+            }
+
+            let Some(location) = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| url.join(location).ok())
+            else {
+                return Ok(response);
+            };
+
+            if location.host_str() != url.host_str() {
+                headers.remove(AUTHORIZATION);
+                headers.remove(COOKIE);
+                headers.remove(PROXY_AUTHORIZATION);
+            }
+
+            url = location;
+        }
+
+        Err(OutboundError::TooManyRedirects.into())
+    }
+
+    /// Addresses in urls are not resolved, so they are checked before connecting
+    fn check_url(&self, url: &Url) -> Result<(), OutboundError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(OutboundError::Scheme(url.scheme().to_owned()));
+        }
+
+        let host = url.host_str().unwrap_or_default();
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => ip.into(),
+            Some(Host::Ipv6(ip)) => ip.into(),
+            Some(Host::Domain(_)) => return Ok(()),
+            None => return Err(OutboundError::Blocked(url.to_string())),
+        };
+
+        match self.policy.is_allowed(host, ip) {
+            true => Ok(()),
+            false => Err(OutboundError::Blocked(host.to_owned())),
+        }
+    }
+
+    /// Surface policy errors from the resolver instead of a generic connect error
+    fn unwrap_error(error: reqwest::Error) -> anyhow::Error {
+        let mut source = std::error::Error::source(&error);
+        while let Some(e) = source {
+            if let Some(OutboundError::Blocked(host)) = e.downcast_ref::<OutboundError>() {
+                return OutboundError::Blocked(host.to_owned()).into();
+            }
+            source = e.source();
+        }
+        error.into()
+    }
+}
+
+/// Resolves host names and drops the addresses the policy does not allow
+struct PolicyResolver {
+    policy: Arc<FetchPolicy>,
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        let host = name.as_str().to_owned();
+
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|address| policy.is_allowed(&host, address.ip()))
+                .collect();
+
+            if addresses.is_empty() {
+                return Err(OutboundError::Blocked(host).into());
+            }
+
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Server;
+    use tokio::test;
+
+    fn client(allowlist: &[&str]) -> OutboundClient {
+        let policy = FetchPolicy::new(allowlist).unwrap();
+        OutboundClient::new(policy, Duration::from_secs(5)).unwrap()
+    }
+
+    fn assert_blocked(result: Result<Response>) {
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<OutboundError>(),
+            Some(OutboundError::Blocked(_))
+        ));
+    }
+
+    #[test]
+    async fn test_blocks_private_addresses() {
+        let client = client(&[]);
+
+        for url in [
+            "http://127.0.0.1:5432",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/",
+            "http://10.0.0.1/admin",
+            "http://0x7f000001/",
+        ] {
+            let url = Url::parse(url).unwrap();
+            assert_blocked(client.get(url, HeaderMap::new()).await);
+        }
+    }
+
+    #[test]
+    async fn test_blocks_host_names_resolving_to_private_addresses() {
+        let client = client(&[]);
+        let url = Url::parse("http://localhost:5432").unwrap();
+
+        assert_blocked(client.get(url, HeaderMap::new()).await);
+    }
+
+    #[test]
+    async fn test_blocks_other_schemes() {
+        let client = client(&[]);
+        let url = Url::parse("file:///etc/passwd").unwrap();
+
+        let error = client.get(url, HeaderMap::new()).await.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<OutboundError>(),
+            Some(OutboundError::Scheme(_))
+        ));
+    }
+
+    #[test]
+    async fn test_allowlisted_address() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(&server.url()).unwrap();
+
+        let mock = server.mock("GET", "/").with_body("ok").create_async().await;
+
+        let response = client(&["127.0.0.1"])
+            .get(url, HeaderMap::new())
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+
+    #[test]
+    async fn test_allowlisted_host_name() {
+        let mut server = Server::new_async().await;
+        let port = Url::parse(&server.url()).unwrap().port().unwrap();
+        let url = Url::parse(&format!("http://localhost:{port}")).unwrap();
+
+        let mock = server.mock("GET", "/").with_body("ok").create_async().await;
+
+        let response = client(&["localhost"])
+            .get(url, HeaderMap::new())
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+
+    #[test]
+    async fn test_checks_every_redirect() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(&server.url()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(302)
+            .with_header("location", "http://169.254.169.254/latest/meta-data/")
+            .create_async()
+            .await;
+
+        let result = client(&["127.0.0.1"]).get(url, HeaderMap::new()).await;
+
+        mock.assert();
+        assert_blocked(result);
+    }
+
+    #[test]
+    async fn test_follows_allowed_redirects() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(&server.url()).unwrap();
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_status(301)
+            .with_header("location", "/docs/")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/docs/")
+            .with_body("docs")
+            .create_async()
+            .await;
+
+        let response = client(&["127.0.0.1"])
+            .get(url.clone(), HeaderMap::new())
+            .await
+            .unwrap();
+
+        m1.assert();
+        m2.assert();
+        assert_eq!(response.url(), &url.join("/docs/").unwrap());
+        assert_eq!(response.text().await.unwrap(), "docs");
+    }
+
+    #[test]
+    async fn test_too_many_redirects() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(&server.url()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(302)
+            .with_header("location", "/")
+            .expect(MAX_REDIRECTS + 1)
+            .create_async()
+            .await;
+
+        let result = client(&["127.0.0.1"]).get(url, HeaderMap::new()).await;
+
+        mock.assert();
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<OutboundError>(),
+            Some(OutboundError::TooManyRedirects)
+        ));
+    }
+
+    #[test]
+    async fn test_sends_credentials_only_to_their_host() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(&server.url()).unwrap();
+        let port = url.port().unwrap();
+
+        let mut credentials = HeaderMap::new();
+        credentials.insert(AUTHORIZATION, "Bearer secret".parse().unwrap());
+
+        let m1 = server
+            .mock("GET", "/")
+            .match_header("authorization", "Bearer secret")
+            .with_status(302)
+            .with_header("location", &format!("http://localhost:{port}/other"))
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/other")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_status(302)
+            .with_header("location", &format!("http://127.0.0.1:{port}/docs"))
+            .create_async()
+            .await;
+
+        let m3 = server
+            .mock("GET", "/docs")
+            .match_header("authorization", "Bearer secret")
+            .with_body("docs")
+            .create_async()
+            .await;
+
+        let response = client(&["127.0.0.1", "localhost"])
+            .with_credentials(&url, credentials)
+            .get(url, HeaderMap::new())
+            .await
+            .unwrap();
+
+        m1.assert();
+        m2.assert();
+        m3.assert();
+        assert_eq!(response.text().await.unwrap(), "docs");
+    }
+
+    #[test]
+    async fn test_credentials_scope() {
+        let credentials = ScopedHeaders {
+            scope: Url::parse("https://docs.example.com").unwrap(),
+            headers: HeaderMap::new(),
+        };
+
+        for (url, expected) in [
+            ("https://docs.example.com/page", true),
+            ("https://docs.example.com:443/page", true),
+            ("http://docs.example.com/page", false),
+            ("https://docs.example.com:8443/", false),
+            ("https://example.com/", false),
+            ("https://docs.example.com.evil.com/", false),
+        ] {
+            let url = Url::parse(url).unwrap();
+            assert_eq!(credentials.applies_to(&url), expected, "{url}");
+        }
+
+        let credentials = ScopedHeaders {
+            scope: Url::parse("http://docs.example.com:8080").unwrap(),
+            headers: HeaderMap::new(),
+        };
+
+        assert!(credentials.applies_to(&Url::parse("http://docs.example.com:8080/").unwrap()));
+        assert!(!credentials.applies_to(&Url::parse("http://docs.example.com/").unwrap()));
+    }
+}
@@ -0,0 +1,5 @@
+mod client;
+mod policy;
+
+pub use client::{OutboundClient, OutboundError};
+pub use policy::FetchPolicy;
@@ -0,0 +1,237 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use anyhow::{bail, Context, Result};
+
+/// Decides which hosts outbound requests may reach. Private, loopback,
+/// link-local and other non-public addresses are blocked unless allowlisted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FetchPolicy {
+    allowlist: Vec<AllowRule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum AllowRule {
+    /// A host name, allowed to resolve to any address
+    Host(String),
+    /// An address range in CIDR notation, a single address if no prefix is given
+    Network(IpAddr, u8),
+}
+
+impl FetchPolicy {
+    /// Policy with allowlist entries that are host names, addresses or CIDR ranges
+    pub fn new<S: AsRef<str>>(allowlist: impl IntoIterator<Item = S>) -> Result<Self> {
+        let allowlist = allowlist
+            .into_iter()
+            .map(|entry| AllowRule::parse(entry.as_ref()))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { allowlist })
+    }
+
+    /// Whether a host name was explicitly allowlisted
+    pub fn is_host_allowlisted(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+
+        self.allowlist
+            .iter()
+            .any(|rule| matches!(rule, AllowRule::Host(allowed) if *allowed == host))
+    }
+
+    /// Whether the host may be reached on the address it resolved to
+    pub fn is_allowed(&self, host: &str, ip: IpAddr) -> bool {
+        if self.is_host_allowlisted(host) {
+            return true;
+        }
+
+        is_public(ip)
+            || self.allowlist.iter().any(|rule| match rule {
+                AllowRule::Network(network, prefix) => in_network(ip, *network, *prefix),
+                AllowRule::Host(_) => false,
+            })
+    }
+}
+
+impl AllowRule {
+    fn parse(entry: &str) -> Result<Self> {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            bail!("Empty allowlist entry");
+        }
+
+        let (address, prefix) = match entry.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (entry, None),
+        };
+
+        let Ok(ip) = address.parse::<IpAddr>() else {
+            if prefix.is_some() {
+                bail!("Invalid network in allowlist: {entry}");
+            }
+            return Ok(Self::Host(entry.trim_end_matches('.').to_lowercase()));
+        };
+
+        let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .with_context(|| format!("Invalid prefix length in allowlist: {entry}"))?,
+            None => max_prefix,
+        };
+
+        Ok(Self::Network(ip, prefix))
+    }
+}
+
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether the address is globally reachable
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8 "this network"
+        || a == 0
+        // 100.64.0.0/10 shared address space
+        || (a == 100 && (b & 0b1100_0000) == 64)
+        // 192.0.0.0/24 protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 benchmarking
+        || (a == 198 && (b & 0b1111_1110) == 18)
+        // 240.0.0.0/4 reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    let bits = u128::from(ip);
+
+    // IPv4 addresses embedded in IPv6 ones reach the IPv4 host
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        return is_public_v4(ip);
+    }
+    // ::a.b.c.d IPv4-compatible, which includes :: and ::1
+    if bits >> 32 == 0 {
+        return is_public_v4(Ipv4Addr::from(bits as u32));
+    }
+    // 2002::/16 6to4, with the IPv4 address after the prefix
+    if segments[0] == 0x2002 {
+        return is_public_v4(Ipv4Addr::from((bits >> 80) as u32));
+    }
+    // 2001::/32 Teredo, with the server address and the inverted client address
+    if segments[0] == 0x2001 && segments[1] == 0 {
+        let server = Ipv4Addr::from((bits >> 64) as u32);
+        let client = Ipv4Addr::from(!(bits as u32));
+        return is_public_v4(server) && is_public_v4(client);
+    }
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // fc00::/7 unique local
+        || (segments[0] & 0xfe00) == 0xfc00
+        // fe80::/10 link-local
+        || (segments[0] & 0xffc0) == 0xfe80
+        // 64:ff9b::/96 NAT64 and 64:ff9b:1::/48 local-use NAT64
+        || (segments[0] == 0x64 && segments[1] == 0xff9b)
+        // 2001:db8::/32 documentation
+        || (segments[0] == 0x2001 && segments[1] == 0xdb8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("93.184.216.34", true)]
+    #[case("2606:2800:220:1:248:1893:25c8:1946", true)]
+    #[case("127.0.0.1", false)]
+    #[case("127.1.2.3", false)]
+    #[case("10.0.0.1", false)]
+    #[case("172.16.0.1", false)]
+    #[case("172.31.255.255", false)]
+    #[case("172.32.0.1", true)]
+    #[case("192.168.1.1", false)]
+    #[case("169.254.169.254", false)]
+    #[case("100.64.0.1", false)]
+    #[case("100.128.0.1", true)]
+    #[case("0.0.0.0", false)]
+    #[case("0.1.2.3", false)]
+    #[case("255.255.255.255", false)]
+    #[case("224.0.0.1", false)]
+    #[case("::", false)]
+    #[case("::1", false)]
+    #[case("::ffff:127.0.0.1", false)]
+    #[case("::ffff:169.254.169.254", false)]
+    #[case("::ffff:93.184.216.34", true)]
+    #[case("fd00::1", false)]
+    #[case("fe80::1", false)]
+    #[case("64:ff9b::7f00:1", false)]
+    #[case("::127.0.0.1", false)]
+    #[case("::10.0.0.1", false)]
+    #[case("::93.184.216.34", true)]
+    #[case("2002:7f00:1::", false)]
+    #[case("2002:a9fe:a9fe::1", false)]
+    #[case("2002:5db8:d822::1", true)]
+    #[case("2001:0:5db8:d822::a247:27dd", true)]
+    #[case("2001:0:5db8:d822::f5ff:fffe", false)]
+    #[case("2001:0:a00:1::a247:27dd", false)]
+    #[case("2001:0:4136:e378:8000:63bf:3fff:fdd2", false)]
+    fn test_is_public(#[case] ip: &str, #[case] expected: bool) {
+        assert_eq!(is_public(ip.parse().unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case("docs.internal", "10.0.0.5", true)]
+    #[case("DOCS.internal.", "10.0.0.5", true)]
+    #[case("other.internal", "10.0.0.5", false)]
+    #[case("other.internal", "10.1.2.3", true)]
+    #[case("other.internal", "10.2.0.1", false)]
+    #[case("localhost", "127.0.0.1", true)]
+    #[case("localhost", "::1", false)]
+    #[case("wiki.internal", "fd12::1", true)]
+    #[case("wiki.internal", "fd13::1", false)]
+    fn test_allowlist(#[case] host: &str, #[case] ip: &str, #[case] expected: bool) {
+        let policy =
+            FetchPolicy::new(["docs.internal", "10.1.0.0/16", "127.0.0.1", "fd12::/16"]).unwrap();
+
+        assert_eq!(policy.is_allowed(host, ip.parse().unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case("10.0.0.0/33")]
+    #[case("::/129")]
+    #[case("10.0.0.0/abc")]
+    #[case("docs.internal/16")]
+    #[case(" ")]
+    fn test_invalid_allowlist(#[case] entry: &str) {
+        assert!(FetchPolicy::new([entry]).is_err());
+    }
+}
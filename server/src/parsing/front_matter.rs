@@ -0,0 +1,149 @@
+use serde_yaml::{Mapping, Value};
+
+use crate::server_functions::models::DocumentMetadata;
+
+/// Split a Markdown document into its YAML front matter and its body. The
+/// front matter is between two `---` lines at the start of the document.
+pub fn split_front_matter(markdown: &str) -> Option<(&str, &str)> {
+    let text = markdown.trim_start_matches('\u{feff}');
+    let mut lines = text.split_inclusive('\n');
+
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+
+    let start = text.find('\n')? + 1;
+    let mut end = start;
+    for line in lines {
+        if line.trim_end() == "---" {
+            return Some((&text[start..end], &text[end + line.len()..]));
+        }
+        end += line.len();
+    }
+
+    None
+}
+
+/// The body of a Markdown document without its front matter
+pub fn strip_front_matter(markdown: &str) -> &str {
+    match split_front_matter(markdown) {
+        Some((_, body)) if parse_mapping(markdown).is_some() => body,
+        _ => markdown,
+    }
+}
+
+/// The metadata in the front matter of a Markdown document, if it has front
+/// matter that is a YAML mapping
+pub fn front_matter(markdown: &str) -> Option<DocumentMetadata> {
+    let mapping = parse_mapping(markdown)?;
+    let mut metadata = DocumentMetadata::default();
+
+    for (key, value) in mapping {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+
+        match key {
+            "title" => metadata.title = scalar(&value),
+            "tags" => metadata.tags = strings(&value),
+            "description" => metadata.description = scalar(&value),
+            "order" => {
+                metadata.order = value
+                    .as_i64()
+                    .or_else(|| value.as_str()?.trim().parse().ok())
+            }
+            "aliases" => metadata.aliases = strings(&value),
+            _ => {
+                if let Ok(value) = serde_json::to_value(&value) {
+                    metadata.custom.insert(key.to_owned(), value);
+                }
+            }
+        }
+    }
+
+    Some(metadata)
+}
+
+fn parse_mapping(markdown: &str) -> Option<Mapping> {
+    let (yaml, _) = split_front_matter(markdown)?;
+    match serde_yaml::from_str(yaml) {
+        Ok(Value::Mapping(mapping)) => Some(mapping),
+        // Empty front matter
+        Ok(Value::Null) => Some(Mapping::new()),
+        _ => None,
+    }
+}
+
+/// Strings, numbers and booleans as text
+fn scalar(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(text) => text.trim().to_owned(),
+        Value::Number(number) => number.to_string(),
+        Value::Bool(bool) => bool.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// A list of strings, which may also be written as a comma separated string
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::Sequence(values) => values.iter().filter_map(scalar).collect(),
+        Value::String(text) => text
+            .split(',')
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_owned)
+            .collect(),
+        value => scalar(value).into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn test_front_matter() {
+        let markdown = "---\ntitle: Getting started\ntags: [setup, install]\ndescription: How to install\norder: 2\naliases:\n  - /docs/start\nauthor:\n  name: Jane\n---\n# Getting started\n";
+
+        let metadata = front_matter(markdown).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Getting started"));
+        assert_eq!(metadata.tags, vec!["setup", "install"]);
+        assert_eq!(metadata.description.as_deref(), Some("How to install"));
+        assert_eq!(metadata.order, Some(2));
+        assert_eq!(metadata.aliases, vec!["/docs/start"]);
+        assert_eq!(
+            metadata.custom.get("author"),
+            Some(&serde_json::json!({ "name": "Jane" }))
+        );
+        assert_eq!(strip_front_matter(markdown), "# Getting started\n");
+    }
+
+    #[rstest]
+    #[case("---\ntags: rust, api\norder: \"3\"\n---\n", vec!["rust", "api"], Some(3))]
+    #[case("\u{feff}---\r\ntags: 1\r\n---\r\nText", vec!["1"], None)]
+    #[case("---\n---\nText", vec![], None)]
+    fn test_front_matter_values(
+        #[case] markdown: &str,
+        #[case] tags: Vec<&str>,
+        #[case] order: Option<i64>,
+    ) {
+        let metadata = front_matter(markdown).unwrap();
+
+        assert_eq!(metadata.tags, tags);
+        assert_eq!(metadata.order, order);
+    }
+
+    #[rstest]
+    #[case("# Title\n\n---\ntitle: A\n---\n")]
+    #[case("---\nNot: [closed\n---\nText")]
+    #[case("---\n- a list\n---\nText")]
+    #[case("---\ntitle: Unclosed\n")]
+    fn test_no_front_matter(#[case] markdown: &str) {
+        assert_eq!(front_matter(markdown), None);
+        assert_eq!(strip_front_matter(markdown), markdown);
+    }
+}
@@ -1,5 +1,50 @@
+use std::fmt::Display;
+
 use anyhow::Result;
+use ego_tree::NodeRef;
 use reqwest::Url;
+use scraper::{ElementRef, Html, Node};
+
+use crate::models::CacheValidators;
+
+/// Tags that are left out together with their content
+const SKIPPED_TAGS: [&str; 8] = [
+    "head", "script", "style", "noscript", "template", "iframe", "svg", "button",
+];
+
+/// Tags that start a new block
+const BLOCK_TAGS: [&str; 30] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
 
 pub struct Waiting;
 pub struct Done;
@@ -8,18 +53,26 @@ pub struct HtmlParser<State = Waiting> {
     name: String,
     content: String,
     source: String,
+    validators: CacheValidators,
     state: std::marker::PhantomData<State>,
 }
 
 impl HtmlParser {
-    pub fn new(name: &str, html: &str, url: Url) -> Self {
+    pub fn new(name: &str, html: &str, source: impl Display) -> Self {
         Self {
             name: name.to_string(),
             content: html.to_string(),
-            source: format!("{}", url),
+            source: source.to_string(),
+            validators: CacheValidators::default(),
             state: Default::default(),
         }
     }
+
+    /// Keep the cache validators of the response the html was read from
+    pub fn with_validators(mut self, validators: CacheValidators) -> Self {
+        self.validators = validators;
+        self
+    }
 }
 
 impl HtmlParser<Waiting> {
@@ -27,18 +80,39 @@ impl HtmlParser<Waiting> {
         let content = self.content;
         let source = self.source;
 
-        let result = html2md::parse_html(&content);
+        // Relative links would point nowhere once the page is stored
+        let base = Url::parse(&source)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"));
+        let result = html_to_markdown(&content, base.as_ref());
 
         Ok(HtmlParser {
             name: self.name,
             content: result,
             source,
+            validators: self.validators,
             state: Default::default(),
         })
     }
 }
 
 impl HtmlParser<Done> {
+    /// A parsed document from content that was not HTML, e.g. a PDF
+    pub(super) fn from_parts(
+        name: String,
+        content: String,
+        source: String,
+        validators: CacheValidators,
+    ) -> Self {
+        Self {
+            name,
+            content,
+            source,
+            validators,
+            state: Default::default(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -48,4 +122,579 @@ impl HtmlParser<Done> {
     pub fn source(&self) -> &str {
         &self.source
     }
+    pub fn validators(&self) -> &CacheValidators {
+        &self.validators
+    }
+
+    /// The same document with other content, e.g. with its links rewritten
+    pub fn with_content(&self, content: String) -> Self {
+        Self::from_parts(
+            self.name.to_owned(),
+            content,
+            self.source.to_owned(),
+            self.validators.clone(),
+        )
+    }
+
+    /// The start of the content on a single line, at most `length` characters long
+    pub fn excerpt(&self, length: usize) -> String {
+        let text = self
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        match text.char_indices().nth(length) {
+            Some((end, _)) => format!("{}…", text[..end].trim_end()),
+            None => text,
+        }
+    }
+}
+
+/// Convert HTML to GitHub flavored Markdown. Code blocks keep their language
+/// from the common class conventions, tables become GFM tables and relative
+/// links and images are resolved against `base` when it is given.
+pub fn html_to_markdown(html: &str, base: Option<&Url>) -> String {
+    let html = Html::parse_document(html);
+    let converter = Converter { base };
+
+    converter.blocks(html.tree.root()).join("\n\n")
+}
+
+struct Converter<'a> {
+    base: Option<&'a Url>,
+}
+
+impl Converter<'_> {
+    /// The Markdown blocks of the children of a node, inline content between
+    /// block tags becomes a paragraph
+    fn blocks(&self, node: NodeRef<Node>) -> Vec<String> {
+        let mut blocks = vec![];
+        let mut inline = String::new();
+
+        for child in node.children() {
+            match child.value() {
+                Node::Element(element) if is_block(child) => {
+                    push_paragraph(&mut blocks, &inline);
+                    inline.clear();
+                    blocks.extend(self.block(child, element.name()));
+                }
+                _ => inline.push_str(&self.inline(child)),
+            }
+        }
+        push_paragraph(&mut blocks, &inline);
+
+        blocks
+    }
+
+    fn block(&self, node: NodeRef<Node>, name: &str) -> Vec<String> {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let text = normalize(&self.inline_children(node)).replace("\\\n", " ");
+                match text.is_empty() {
+                    true => vec![],
+                    false => vec![format!("{} {}", "#".repeat(level), text)],
+                }
+            }
+            "pre" => vec![self.code_block(node)],
+            "ul" | "ol" => {
+                let list = self.list(node, name == "ol");
+                match list.is_empty() {
+                    true => vec![],
+                    false => vec![list],
+                }
+            }
+            "blockquote" => {
+                let quote = self.blocks(node).join("\n\n");
+                match quote.is_empty() {
+                    true => vec![],
+                    false => vec![prefix_lines(&quote, "> ", ">")],
+                }
+            }
+            "table" => self.table(node).into_iter().collect(),
+            "hr" => vec!["---".to_owned()],
+            _ => self.blocks(node),
+        }
+    }
+
+    fn inline(&self, node: NodeRef<Node>) -> String {
+        let element = match node.value() {
+            Node::Text(text) => return escape(&collapse_whitespace(text)),
+            Node::Element(element) => element,
+            _ => return String::new(),
+        };
+
+        match element.name() {
+            name if SKIPPED_TAGS.contains(&name) => String::new(),
+            // Text has no line breaks left, so they are the breaks
+            "br" => "\n".to_owned(),
+            // Anchors next to headings, like `¶`
+            "a" if element.attr("aria-hidden") == Some("true")
+                || element.classes().any(|class| class == "headerlink") =>
+            {
+                String::new()
+            }
+            "a" => {
+                let text = self.inline_children(node);
+                match element.attr("href") {
+                    _ if text.trim().is_empty() => String::new(),
+                    Some(href) => format!("[{}]({})", text.trim(), self.url(href)),
+                    None => text,
+                }
+            }
+            "img" => match element.attr("src") {
+                Some(src) => {
+                    let alt = escape(&collapse_whitespace(
+                        element.attr("alt").unwrap_or_default(),
+                    ));
+                    format!("![{}]({})", alt.trim(), self.url(src))
+                }
+                None => String::new(),
+            },
+            "strong" | "b" => emphasis(&self.inline_children(node), "**"),
+            "em" | "i" => emphasis(&self.inline_children(node), "*"),
+            "del" | "s" | "strike" => emphasis(&self.inline_children(node), "~~"),
+            "code" | "kbd" | "samp" | "tt" => {
+                let text = collapse_whitespace(&text(node));
+                code_span(text.trim())
+            }
+            // Block tags inside of inline tags
+            name if BLOCK_TAGS.contains(&name) => format!(" {} ", self.inline_children(node)),
+            _ => self.inline_children(node),
+        }
+    }
+
+    fn inline_children(&self, node: NodeRef<Node>) -> String {
+        node.children().map(|child| self.inline(child)).collect()
+    }
+
+    /// A fenced code block, longer than the longest run of backticks in it
+    fn code_block(&self, node: NodeRef<Node>) -> String {
+        let code = text(node);
+        let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+        let longest = code
+            .split(|c| c != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        let fence = "`".repeat(longest.max(2) + 1);
+
+        format!(
+            "{fence}{}\n{code}\n{fence}",
+            code_language(node).unwrap_or_default()
+        )
+    }
+
+    fn list(&self, node: NodeRef<Node>, ordered: bool) -> String {
+        let start = ElementRef::wrap(node)
+            .and_then(|element| element.value().attr("start"))
+            .and_then(|start| start.trim().parse::<usize>().ok())
+            .unwrap_or(1);
+
+        node.children()
+            .filter(
+                |child| matches!(child.value(), Node::Element(element) if element.name() == "li"),
+            )
+            .enumerate()
+            .map(|(i, item)| {
+                let marker = match ordered {
+                    true => format!("{}. ", start + i),
+                    false => "- ".to_owned(),
+                };
+
+                let blocks = self.blocks(item);
+                let mut content = String::new();
+                for (i, block) in blocks.iter().enumerate() {
+                    if i > 0 {
+                        // Nested lists keep the list tight
+                        content.push_str(match is_list(block) {
+                            true => "\n",
+                            false => "\n\n",
+                        });
+                    }
+                    content.push_str(block);
+                }
+
+                let indent = " ".repeat(marker.len());
+                let content = prefix_lines(&content, &indent, "");
+                let content = content.strip_prefix(&indent).unwrap_or(&content);
+                format!("{marker}{content}").trim_end().to_owned()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A GFM table, the first row is the header
+    fn table(&self, node: NodeRef<Node>) -> Option<String> {
+        let rows = node
+            .descendants()
+            .filter(|row| matches!(row.value(), Node::Element(element) if element.name() == "tr"))
+            // Rows of nested tables are part of their cell
+            .filter(|row| {
+                row.ancestors()
+                    .find(|ancestor| matches!(ancestor.value(), Node::Element(element) if element.name() == "table"))
+                    .is_some_and(|table| table.id() == node.id())
+            })
+            .map(|row| {
+                let mut cells = vec![];
+                for cell in row.children() {
+                    let Node::Element(element) = cell.value() else {
+                        continue;
+                    };
+                    if !matches!(element.name(), "td" | "th") {
+                        continue;
+                    }
+
+                    let text = normalize(&self.inline_children(cell))
+                        .replace("\\\n", " ")
+                        .replace('|', "\\|");
+                    let span = element
+                        .attr("colspan")
+                        .and_then(|span| span.trim().parse::<usize>().ok())
+                        .unwrap_or(1)
+                        .clamp(1, 100);
+                    cells.push((text, alignment(element)));
+                    cells.extend((1..span).map(|_| (String::new(), None)));
+                }
+                cells
+            })
+            .filter(|cells| !cells.is_empty())
+            .collect::<Vec<_>>();
+
+        let columns = rows.iter().map(Vec::len).max()?;
+        let line = |cells: Vec<&str>| {
+            let mut cells = cells;
+            cells.resize(columns, "");
+            format!("| {} |", cells.join(" | "))
+        };
+
+        let header = &rows[0];
+        let mut lines = vec![line(header.iter().map(|(text, _)| text.as_str()).collect())];
+        lines.push(line(
+            (0..columns)
+                .map(
+                    |column| match header.get(column).and_then(|(_, align)| *align) {
+                        Some("center") => ":---:",
+                        Some("right") => "---:",
+                        Some("left") => ":---",
+                        _ => "---",
+                    },
+                )
+                .collect(),
+        ));
+        for row in &rows[1..] {
+            lines.push(line(row.iter().map(|(text, _)| text.as_str()).collect()));
+        }
+
+        Some(lines.join("\n"))
+    }
+
+    /// The url of a link or image, relative ones are resolved against the base
+    fn url(&self, url: &str) -> String {
+        let url = url.trim();
+        let url = match self.base {
+            Some(base) if !url.starts_with('#') => base
+                .join(url)
+                .map_or_else(|_| url.to_owned(), |url| url.to_string()),
+            _ => url.to_owned(),
+        };
+
+        match url.contains([' ', '(', ')']) {
+            true => format!("<{}>", url.replace('<', "%3C").replace('>', "%3E")),
+            false => url,
+        }
+    }
+}
+
+/// The language of a code block from its classes, e.g. `language-rust`,
+/// `lang-rust`, `highlight-rust` or `sourceCode rust`, or its `data-lang`.
+/// The code element and the elements around the block are searched too.
+fn code_language(pre: NodeRef<Node>) -> Option<String> {
+    let code = pre
+        .children()
+        .find(|child| matches!(child.value(), Node::Element(element) if element.name() == "code"));
+    let elements = code
+        .into_iter()
+        .chain([pre])
+        .chain(pre.ancestors().take(2))
+        .filter_map(|node| node.value().as_element());
+
+    for element in elements {
+        let data = element.attr("data-lang").or(element.attr("data-language"));
+        // Not `classes()`, which are sorted, as the language can follow its marker
+        let classes = element
+            .attr("class")
+            .map(|class| class.split_whitespace().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let from_classes = classes.iter().enumerate().filter_map(|(i, class)| {
+            ["language-", "lang-", "highlight-", "brush:"]
+                .iter()
+                .find_map(|prefix| class.strip_prefix(prefix).filter(|lang| !lang.is_empty()))
+                // `class="sourceCode rust"` and `class="brush: rust"`
+                .or_else(|| match *class {
+                    "sourceCode" | "brush:" => classes.get(i + 1).copied(),
+                    _ => None,
+                })
+        });
+
+        let lang = data
+            .into_iter()
+            .chain(from_classes)
+            .map(|lang| lang.trim().trim_end_matches(';').to_lowercase())
+            .find(|lang| is_language(lang));
+        if lang.is_some() {
+            return lang;
+        }
+    }
+
+    None
+}
+
+fn is_language(lang: &str) -> bool {
+    !lang.is_empty()
+        && !matches!(
+            lang,
+            "none" | "text" | "plaintext" | "default" | "sourcecode"
+        )
+        && lang
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_' | '.'))
+}
+
+/// The `align` attribute or `text-align` style of a table cell
+fn alignment(element: &scraper::node::Element) -> Option<&'static str> {
+    let align = element.attr("align").map(str::to_owned).or_else(|| {
+        element.attr("style")?.split(';').find_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            (property.trim() == "text-align").then(|| value.trim().to_owned())
+        })
+    })?;
+
+    ["left", "center", "right"]
+        .into_iter()
+        .find(|known| align.eq_ignore_ascii_case(known))
+}
+
+/// All text of a node as it is, for code
+fn text(node: NodeRef<Node>) -> String {
+    node.descendants()
+        .filter_map(|node| match node.value() {
+            Node::Text(text) => Some(&**text),
+            _ => None,
+        })
+        .collect()
+}
+
+fn push_paragraph(blocks: &mut Vec<String>, inline: &str) {
+    let paragraph = normalize(inline);
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+}
+
+/// Trim the lines of inline content and collapse the spaces between words.
+/// The lines are joined by hard line breaks, empty ones are dropped.
+fn normalize(inline: &str) -> String {
+    inline
+        .split('\n')
+        .map(|line| {
+            line.split(' ')
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\\\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        match c.is_whitespace() {
+            true => space = true,
+            false => {
+                if space {
+                    output.push(' ');
+                    space = false;
+                }
+                output.push(c);
+            }
+        }
+    }
+    if space {
+        output.push(' ');
+    }
+    output
+}
+
+/// Escape the characters that would be read as Markdown or HTML
+fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<') {
+            output.push('\\');
+        }
+        output.push(c);
+    }
+    output
+}
+
+/// Wrap the text in the markers, keeping the spaces around it outside
+fn emphasis(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_owned();
+    }
+    let start = &text[..text.len() - text.trim_start().len()];
+    let end = &text[text.trim_end().len()..];
+    format!("{start}{marker}{trimmed}{marker}{end}")
+}
+
+fn code_span(code: &str) -> String {
+    if code.is_empty() {
+        return String::new();
+    }
+    let longest = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let ticks = "`".repeat(longest + 1);
+    match code.starts_with('`') || code.ends_with('`') {
+        true => format!("{ticks} {code} {ticks}"),
+        false => format!("{ticks}{code}{ticks}"),
+    }
+}
+
+fn prefix_lines(text: &str, prefix: &str, empty: &str) -> String {
+    text.split('\n')
+        .map(|line| match line.is_empty() {
+            true => empty.to_owned(),
+            false => format!("{prefix}{line}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Block tags and the elements around them, e.g. `<body>`. Links stay inline
+/// even when they wrap blocks.
+fn is_block(node: NodeRef<Node>) -> bool {
+    let is_block_tag = |node: NodeRef<Node>| matches!(node.value(), Node::Element(element) if BLOCK_TAGS.contains(&element.name()));
+
+    match node.value() {
+        Node::Element(element) if element.name() == "a" => false,
+        Node::Element(element) if SKIPPED_TAGS.contains(&element.name()) => false,
+        Node::Element(_) => is_block_tag(node) || node.descendants().skip(1).any(is_block_tag),
+        _ => false,
+    }
+}
+
+/// Whether the Markdown block is a list
+fn is_list(block: &str) -> bool {
+    let digits = block.len() - block.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    block.starts_with("- ") || (digits > 0 && block[digits..].starts_with(". "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(
+        "<h1>Title</h1><p>Some <b>bold</b> and <em>em</em> text</p>",
+        "# Title\n\nSome **bold** and *em* text"
+    )]
+    #[case("<div>Loose text<p>Paragraph</p></div>", "Loose text\n\nParagraph")]
+    #[case("<p>A<br>B</p>", "A\\\nB")]
+    #[case(
+        "<p>Use <code>a_b</code> not a_b or &lt;b&gt;</p>",
+        "Use `a_b` not a\\_b or \\<b>"
+    )]
+    #[case("<ul><li>A<ul><li>B</li></ul></li><li>C</li></ul>", "- A\n  - B\n- C")]
+    #[case("<ol start=\"3\"><li><p>A</p><p>B</p></li></ol>", "3. A\n\n   B")]
+    #[case("<blockquote><p>A</p><p>B</p></blockquote>", "> A\n>\n> B")]
+    #[case(
+        "<h2>Setup<a class=\"headerlink\" href=\"#setup\">¶</a></h2>",
+        "## Setup"
+    )]
+    #[case("<script>alert(1)</script><p>Text</p>", "Text")]
+    fn test_html_to_markdown(#[case] html: &str, #[case] expected: &str) {
+        assert_eq!(html_to_markdown(html, None), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "<pre><code class=\"language-rust\">fn main() {}\n</code></pre>",
+        "rust"
+    )]
+    #[case("<pre class=\"lang-js\">let a;</pre>", "js")]
+    #[case("<div class=\"highlight-python notranslate\"><div class=\"highlight\"><pre><span>x</span> = 1</pre></div></div>", "python")]
+    #[case("<pre class=\"sourceCode haskell\"><code>x</code></pre>", "haskell")]
+    #[case("<pre class=\"brush: ts;\">x</pre>", "ts")]
+    #[case("<pre><code data-lang=\"go\">x</code></pre>", "go")]
+    #[case("<pre><code class=\"hljs\">x</code></pre>", "")]
+    fn test_code_block_language(#[case] html: &str, #[case] expected: &str) {
+        let markdown = html_to_markdown(html, None);
+
+        assert!(
+            markdown.starts_with(&format!("```{expected}\n")),
+            "{markdown}"
+        );
+        assert!(markdown.ends_with("\n```"));
+    }
+
+    #[test]
+    fn test_code_block_with_fence() {
+        let markdown = html_to_markdown("<pre>```\ncode\n```</pre>", None);
+
+        assert_eq!(markdown, "````\n```\ncode\n```\n````");
+    }
+
+    #[test]
+    fn test_table() {
+        let html = r#"<table>
+            <thead><tr><th>Name</th><th align="center">Type</th><th style="text-align: right">Size</th></tr></thead>
+            <tbody>
+                <tr><td><code>id</code></td><td>a | b</td><td>4</td></tr>
+                <tr><td colspan="2">Total</td><td>4<table><tr><td>Nested</td></tr></table></td></tr>
+                <tr><td>Short</td></tr>
+            </tbody>
+        </table>"#;
+
+        assert_eq!(
+            html_to_markdown(html, None),
+            "| Name | Type | Size |\n| --- | :---: | ---: |\n| `id` | a \\| b | 4 |\n| Total |  | 4 Nested |\n| Short |  |  |"
+        );
+    }
+
+    #[test]
+    fn test_resolves_relative_links() {
+        let html = r##"<p><a href="../api/">API</a>, <a href="#setup">setup</a>, <a href="https://example.org/x">other</a>
+            and <img src="/img/logo.png" alt="Logo"></p>"##;
+        let url = Url::parse("https://example.com/docs/guide/").unwrap();
+
+        let doc = HtmlParser::new("Guide", html, &url).parse().unwrap();
+
+        assert_eq!(
+            doc.content(),
+            "[API](https://example.com/docs/api/), [setup](#setup), [other](https://example.org/x) and ![Logo](https://example.com/img/logo.png)"
+        );
+    }
+
+    #[test]
+    fn test_keeps_links_without_url_source() {
+        let doc = HtmlParser::new(
+            "Guide",
+            "<a href=\"intro.html\">Intro</a>",
+            "git:docs:a1b2c3:docs/guide.html",
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(doc.content(), "[Intro](intro.html)");
+    }
 }
@@ -1,3 +1,173 @@
+mod front_matter;
 mod html;
+mod pdf;
+mod rst;
+mod text;
 
-pub use html::{Done, HtmlParser};
+use std::fmt::Display;
+
+use anyhow::Result;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+
+use crate::models::CacheValidators;
+
+pub use front_matter::{front_matter, split_front_matter, strip_front_matter};
+pub use html::{html_to_markdown, Done, HtmlParser};
+pub use pdf::PdfParser;
+pub use rst::rst_to_markdown;
+pub use text::{markdown_title, mdx_to_markdown, TextParser};
+
+/// The content of a fetched resource or file, by the parser it needs
+#[derive(Clone)]
+pub enum Content {
+    Html(String),
+    Markdown(String),
+    PlainText(String),
+    /// reStructuredText, which is converted to Markdown
+    Rst(String),
+    Pdf(Vec<u8>),
+}
+
+impl Content {
+    /// The content of a file by its extension, if it is a kind that can be
+    /// imported. Text is read as UTF-8 and MDX is reduced to Markdown.
+    pub fn from_file(path: &str, bytes: Vec<u8>) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        let text = || {
+            let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(&bytes);
+            String::from_utf8_lossy(bytes).into_owned()
+        };
+
+        match extension.to_lowercase().as_str() {
+            "md" | "markdown" => Some(Content::Markdown(text())),
+            "mdx" => Some(Content::Markdown(mdx_to_markdown(&text()))),
+            "rst" => Some(Content::Rst(text())),
+            "txt" => Some(Content::PlainText(text())),
+            "html" | "htm" => Some(Content::Html(text())),
+            "pdf" => Some(Content::Pdf(bytes)),
+            _ => None,
+        }
+    }
+
+    /// Parse the content into a document that keeps the source it came from,
+    /// e.g. its url
+    pub fn parse(
+        &self,
+        name: &str,
+        source: impl Display,
+        validators: CacheValidators,
+    ) -> Result<HtmlParser<Done>> {
+        match self {
+            Content::Html(html) => HtmlParser::new(name, html, source)
+                .with_validators(validators)
+                .parse(),
+            Content::Markdown(text) | Content::PlainText(text) => {
+                TextParser::new(name, text, source)
+                    .with_validators(validators)
+                    .parse()
+            }
+            Content::Rst(rst) => TextParser::new(name, &rst_to_markdown(rst), source)
+                .with_validators(validators)
+                .parse(),
+            Content::Pdf(pdf) => PdfParser::new(name, pdf, source)
+                .with_validators(validators)
+                .parse(),
+        }
+    }
+}
+
+/// A name for a file from the last segment of its url without the extension,
+/// e.g. `getting started` for `https://example.com/docs/getting%20started.md`
+pub fn name_from_url(url: &Url) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+        .unwrap_or_default();
+    let segment = percent_decode_str(segment).decode_utf8_lossy();
+
+    let name = match segment.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => &segment,
+    };
+
+    match name.trim() {
+        "" => url.host_str().unwrap_or("unnamed").to_owned(),
+        name => name.to_owned(),
+    }
+}
+
+/// The folders a document belongs in. Crawled pages use the directories of
+/// their url, e.g. `docs` and `guide` for `https://example.com/docs/guide/install`,
+/// and imported documents the folders of the path they are named after.
+pub fn folder_path(name: &str, source: &str) -> Vec<String> {
+    let segments: Vec<String> = match Url::parse(source) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url
+            .path_segments()
+            .map(|segments| {
+                segments
+                    .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => name.split('/').map(str::to_owned).collect(),
+    };
+
+    let mut folders = segments
+        .into_iter()
+        .map(|segment| segment.trim().to_owned())
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    // The last segment is the document itself
+    folders.pop();
+
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("https://example.com/docs/getting%20started.md", "getting started")]
+    #[case("https://example.com/docs/guide.pdf?download=1", "guide")]
+    #[case("https://example.com/docs/README", "README")]
+    #[case("https://example.com/docs/.env", ".env")]
+    #[case("https://example.com/docs/", "docs")]
+    #[case("https://example.com/", "example.com")]
+    fn test_name_from_url(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(name_from_url(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case("docs/guide/install", "file:docs/guide/install.md", vec!["docs", "guide"])]
+    #[case("Home/Setup", "notion:1", vec!["Home"])]
+    #[case("Install", "https://example.com/docs/getting%20started/install/", vec!["docs", "getting started"])]
+    #[case("Home", "https://example.com/", vec![])]
+    #[case("A/B testing", "https://example.com/ab-testing", vec![])]
+    #[case("README", "file:README.md", vec![])]
+    fn test_folder_path(#[case] name: &str, #[case] source: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(folder_path(name, source), expected);
+    }
+
+    #[test]
+    fn test_content_from_file() {
+        let content = |path: &str, text: &str| Content::from_file(path, text.as_bytes().to_vec());
+
+        assert!(
+            matches!(content("a/README.MD", "\u{feff}# A"), Some(Content::Markdown(text)) if text == "# A")
+        );
+        assert!(
+            matches!(content("a.mdx", "import X from 'x';\n\n# A"), Some(Content::Markdown(text)) if text == "# A")
+        );
+        assert!(matches!(content("a.rst", "A\n="), Some(Content::Rst(_))));
+        assert!(matches!(content("a.txt", "A"), Some(Content::PlainText(_))));
+        assert!(matches!(
+            content("a.htm", "<p>A</p>"),
+            Some(Content::Html(_))
+        ));
+        assert!(content("a.png", "").is_none());
+        assert!(content("Makefile", "").is_none());
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use anyhow::{anyhow, bail, Result};
+use pdf_extract::{Document, MediaBox, Object, OutputDev, OutputError, Transform};
+
+use crate::models::CacheValidators;
+
+use super::{html::Waiting, Done, HtmlParser};
+
+/// Lines this much larger than the body text are taken for headings
+const HEADING_SCALE: f64 = 1.15;
+
+/// Heading levels used for the largest font sizes, smaller headings get the last one
+const HEADING_LEVELS: usize = 3;
+
+/// Extracts the text of a PDF as Markdown, with headings recognised by their
+/// font size and paragraphs by the space between lines
+pub struct PdfParser<State = Waiting> {
+    name: String,
+    content: Vec<u8>,
+    source: String,
+    validators: CacheValidators,
+    state: std::marker::PhantomData<State>,
+}
+
+impl PdfParser {
+    pub fn new(name: &str, pdf: &[u8], source: impl Display) -> Self {
+        Self {
+            name: name.to_string(),
+            content: pdf.to_vec(),
+            source: source.to_string(),
+            validators: CacheValidators::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Keep the cache validators of the response the pdf was read from
+    pub fn with_validators(mut self, validators: CacheValidators) -> Self {
+        self.validators = validators;
+        self
+    }
+}
+
+impl PdfParser<Waiting> {
+    /// The document is named after the title in the PDF metadata if it has one
+    pub fn parse(self) -> Result<HtmlParser<Done>> {
+        // The PDF library panics on some malformed files
+        let (title, lines) = catch_unwind(AssertUnwindSafe(|| extract(&self.content)))
+            .map_err(|_| anyhow!("Failed to read PDF"))??;
+
+        let content = to_markdown(&lines);
+        if content.is_empty() {
+            bail!("The PDF contains no text, it may only contain scanned images");
+        }
+
+        Ok(HtmlParser::from_parts(
+            title.unwrap_or(self.name),
+            content,
+            self.source,
+            self.validators,
+        ))
+    }
+}
+
+/// The title from the metadata and the lines of text
+fn extract(pdf: &[u8]) -> Result<(Option<String>, Vec<Line>)> {
+    let mut document = Document::load_mem(pdf)?;
+    if document.is_encrypted() {
+        document
+            .decrypt("")
+            .map_err(|_| anyhow!("The PDF is protected by a password"))?;
+    }
+
+    let mut output = LineOutput::default();
+    pdf_extract::output_doc(&document, &mut output)?;
+    output.finish_line();
+
+    Ok((title(&document), output.lines))
+}
+
+fn title(document: &Document) -> Option<String> {
+    let info = match document.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => document.get_dictionary(*id).ok()?,
+        Object::Dictionary(info) => info,
+        _ => return None,
+    };
+    let title = match info.get(b"Title").ok()? {
+        Object::String(bytes, _) => decode_text_string(bytes),
+        _ => return None,
+    };
+
+    let title = title.trim();
+    (!title.is_empty()).then(|| title.to_owned())
+}
+
+/// PDF text strings are UTF-16 with a byte order mark, or else PDFDocEncoding,
+/// which matches Latin-1 for printable characters
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(b"\xfe\xff") {
+        Some(utf16) => {
+            let units = utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|byte| *byte as char).collect(),
+    }
+}
+
+/// A line of text with the largest font size in it
+#[derive(Debug, Default, Clone)]
+struct Line {
+    text: String,
+    font_size: f64,
+    /// The line starts a new paragraph or page
+    break_before: bool,
+}
+
+/// Collects the characters of a PDF into lines, in the same way as the plain
+/// text output of `pdf_extract`
+#[derive(Default)]
+struct LineOutput {
+    lines: Vec<Line>,
+    line: Line,
+    page_height: f64,
+    first_char: bool,
+    new_page: bool,
+    last_end: f64,
+    last_y: f64,
+}
+
+impl LineOutput {
+    fn finish_line(&mut self) {
+        let mut line = std::mem::take(&mut self.line);
+        line.text = line.text.trim().to_owned();
+        if !line.text.is_empty() {
+            self.lines.push(line);
+        }
+    }
+}
+
+impl OutputDev for LineOutput {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.page_height = media_box.ury - media_box.lly;
+        self.new_page = true;
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        self.finish_line();
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        // The size of a square with the area of the transformed font size
+        let size_x = font_size * trm.m11 + font_size * trm.m21;
+        let size_y = font_size * trm.m12 + font_size * trm.m22;
+        let font_size = (size_x * size_y).abs().sqrt();
+        let (x, y) = (trm.m31, self.page_height - trm.m32);
+
+        if self.new_page {
+            self.finish_line();
+            self.line.break_before = true;
+            self.new_page = false;
+        } else if self.first_char {
+            let distance = (y - self.last_y).abs();
+
+            if distance > font_size * 1.5 {
+                self.finish_line();
+                self.line.break_before = true;
+            } else if x < self.last_end && distance > font_size * 0.5 {
+                self.finish_line();
+            } else if x > self.last_end + font_size * 0.1 {
+                self.line.text.push(' ');
+            }
+        }
+
+        self.line.text.push_str(char);
+        self.line.font_size = self.line.font_size.max(font_size);
+        self.first_char = false;
+        self.last_y = y;
+        self.last_end = x + width * font_size;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        self.first_char = true;
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+/// Font sizes are compared in half points
+fn size_key(font_size: f64) -> i64 {
+    (font_size * 2.0).round() as i64
+}
+
+/// Lines in the font size used for most of the text are paragraphs, larger
+/// lines are headings with a level by their size.
+fn to_markdown(lines: &[Line]) -> String {
+    let mut characters = HashMap::<i64, usize>::new();
+    for line in lines {
+        *characters.entry(size_key(line.font_size)).or_default() += line.text.chars().count();
+    }
+    let Some(body_size) = characters
+        .into_iter()
+        .max_by_key(|(size, count)| (*count, -size))
+        .map(|(size, _)| size as f64 / 2.0)
+    else {
+        return String::new();
+    };
+
+    let is_heading = |line: &Line| line.font_size > body_size * HEADING_SCALE;
+    let mut heading_sizes = lines
+        .iter()
+        .filter(|line| is_heading(line))
+        .map(|line| size_key(line.font_size))
+        .collect::<Vec<_>>();
+    heading_sizes.sort_unstable_by(|a, b| b.cmp(a));
+    heading_sizes.dedup();
+    let level = |line: &Line| {
+        let rank = heading_sizes
+            .iter()
+            .position(|size| *size == size_key(line.font_size))
+            .unwrap_or_default();
+        rank.min(HEADING_LEVELS - 1) + 1
+    };
+
+    let mut blocks: Vec<String> = vec![];
+    let mut previous: Option<&Line> = None;
+
+    for line in lines {
+        let continues = previous.is_some_and(|previous| {
+            !line.break_before
+                && is_heading(previous) == is_heading(line)
+                && size_key(previous.font_size) == size_key(line.font_size)
+        });
+
+        match blocks.last_mut() {
+            Some(block) if continues => {
+                // Join words broken over lines
+                if block.ends_with('-') && line.text.starts_with(|c: char| c.is_lowercase()) {
+                    block.pop();
+                } else {
+                    block.push(' ');
+                }
+                block.push_str(&line.text);
+            }
+            _ if is_heading(line) => {
+                blocks.push(format!("{} {}", "#".repeat(level(line)), line.text))
+            }
+            _ => blocks.push(line.text.to_owned()),
+        }
+
+        previous = Some(line);
+    }
+
+    blocks.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Url;
+    use std::fs;
+
+    fn line(text: &str, font_size: f64, break_before: bool) -> Line {
+        Line {
+            text: text.to_owned(),
+            font_size,
+            break_before,
+        }
+    }
+
+    #[test]
+    fn test_parse_pdf() {
+        let pdf = fs::read("src/parsing/test_files/guide.pdf").unwrap();
+        let url = Url::parse("https://example.com/guide.pdf").unwrap();
+
+        let doc = PdfParser::new("guide", &pdf, url).parse().unwrap();
+
+        assert_eq!(doc.name(), "MagicDocs Guide");
+        assert_eq!(doc.source(), "https://example.com/guide.pdf");
+        assert_eq!(
+            doc.content(),
+            "# Getting Started\n\n\
+             MagicDocs turns documentation into answers. It reads websites and files.\n\n\
+             ## Installation\n\n\
+             Run the server with cargo.\n\n\
+             ## Usage\n\n\
+             Ask a question in the chat."
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_pdf() {
+        let url = Url::parse("https://example.com/broken.pdf").unwrap();
+
+        assert!(PdfParser::new("broken", b"%PDF-1.4\nbroken", url)
+            .parse()
+            .is_err());
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let lines = [
+            line("Title", 20.0, true),
+            line("A paragraph that is", 10.0, false),
+            line("hyphen-", 10.0, false),
+            line("ated over lines.", 10.0, false),
+            line("Another paragraph.", 10.0, true),
+            line("Section", 14.0, true),
+            line("Subsection", 12.0, true),
+            line("Text.", 10.0, false),
+        ];
+
+        assert_eq!(
+            to_markdown(&lines),
+            "# Title\n\n\
+             A paragraph that is hyphenated over lines.\n\n\
+             Another paragraph.\n\n\
+             ## Section\n\n\
+             ### Subsection\n\n\
+             Text."
+        );
+    }
+
+    #[test]
+    fn test_decode_text_string() {
+        assert_eq!(decode_text_string(b"Caf\xe9"), "Café");
+        assert_eq!(
+            decode_text_string(b"\xfe\xff\x00C\x00a\x00f\x00\xe9"),
+            "Café"
+        );
+    }
+}
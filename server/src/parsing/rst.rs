@@ -0,0 +1,273 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Admonitions that are kept as block quotes
+static ADMONITIONS: [&str; 10] = [
+    "note",
+    "warning",
+    "tip",
+    "important",
+    "caution",
+    "danger",
+    "attention",
+    "hint",
+    "error",
+    "seealso",
+];
+
+static DIRECTIVE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\.\.\s+([\w:-]+)::\s*(.*)$").expect("valid directive regex"));
+
+/// `` `text <url>`_ `` and `` `text <url>`__ ``
+static LINK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"`([^`<]+?)\s*<([^`>]+)>`__?").expect("valid link regex"));
+
+/// Roles like `` :func:`name` `` or `` :ref:`text <target>` ``
+static ROLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r":[\w:-]+:`(?:([^`<]+?)\s*<[^`>]+>|([^`]+))`").expect("valid role regex")
+});
+
+static INLINE_LITERAL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"``([^`]+)``").expect("valid literal regex"));
+
+/// Convert reStructuredText to Markdown. Covers section titles, literal and
+/// code blocks, admonitions, images, links and inline markup; other directives
+/// and comments are left out.
+pub fn rst_to_markdown(rst: &str) -> String {
+    let lines = rst.lines().collect::<Vec<_>>();
+    let mut output: Vec<String> = vec![];
+    // Adornment styles in the order they appear, which gives their level
+    let mut styles: Vec<(char, bool)> = vec![];
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        // Title with an overline
+        if is_adornment(line) && i + 2 < lines.len() && is_adornment(lines[i + 2]) {
+            let title = lines[i + 1].trim();
+            if !title.is_empty() && lines[i + 2].trim() == line.trim() {
+                let level = level(&mut styles, (adornment_char(line), true));
+                output.push(format!("{} {}", "#".repeat(level), inline(title)));
+                output.push(String::new());
+                i += 3;
+                continue;
+            }
+        }
+
+        // Title with an underline
+        if !line.trim().is_empty()
+            && !line.starts_with(char::is_whitespace)
+            && i + 1 < lines.len()
+            && is_adornment(lines[i + 1])
+            && lines[i + 1].trim().chars().count() >= line.trim().chars().count()
+        {
+            let level = level(&mut styles, (adornment_char(lines[i + 1]), false));
+            output.push(format!("{} {}", "#".repeat(level), inline(line.trim())));
+            output.push(String::new());
+            i += 2;
+            continue;
+        }
+
+        if let Some(captures) = DIRECTIVE.captures(line) {
+            let name = captures[1].to_lowercase();
+            let argument = captures[2].trim().to_owned();
+            let (body, next) = indented_block(&lines, i + 1);
+            i = next;
+
+            match name.as_str() {
+                "code-block" | "code" | "sourcecode" => {
+                    let body = body
+                        .into_iter()
+                        .skip_while(|line| line.trim_start().starts_with(':'))
+                        .skip_while(|line| line.trim().is_empty())
+                        .collect::<Vec<_>>();
+                    push_code_block(&mut output, &argument, &body);
+                }
+                "image" | "figure" => {
+                    output.push(format!("![]({argument})"));
+                    output.push(String::new());
+                }
+                name if ADMONITIONS.contains(&name) => {
+                    let mut title = name[..1].to_uppercase() + &name[1..];
+                    if name == "seealso" {
+                        title = "See also".to_owned();
+                    }
+                    let mut text = argument;
+                    for line in body {
+                        text.push(' ');
+                        text.push_str(line.trim());
+                    }
+                    output.push(format!("> **{title}:** {}", inline(text.trim())));
+                    output.push(String::new());
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        // Comments
+        if line.trim() == ".." || line.starts_with(".. ") {
+            i = indented_block(&lines, i + 1).1;
+            continue;
+        }
+
+        // A paragraph ending with `::` introduces a literal block
+        if let Some(text) = line.trim_end().strip_suffix("::") {
+            let (body, next) = indented_block(&lines, i + 1);
+            if !body.is_empty() {
+                let text = text.trim_end();
+                if !text.is_empty() {
+                    output.push(format!("{}:", inline(text)));
+                    output.push(String::new());
+                }
+                push_code_block(&mut output, "", &body);
+                i = next;
+                continue;
+            }
+        }
+
+        output.push(list_item(&inline(line)));
+        i += 1;
+    }
+
+    let markdown = output.join("\n");
+    let mut result = String::new();
+    // Collapse the blank lines left by removed blocks
+    for paragraph in markdown.split("\n\n") {
+        let paragraph = paragraph.trim_matches('\n');
+        if !paragraph.trim().is_empty() {
+            if !result.is_empty() {
+                result.push_str("\n\n");
+            }
+            result.push_str(paragraph);
+        }
+    }
+    result
+}
+
+/// A line of a single repeated punctuation character, at least two long
+fn is_adornment(line: &str) -> bool {
+    let line = line.trim_end();
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_punctuation() && line.len() >= 2 => chars.all(|c| c == first),
+        _ => false,
+    }
+}
+
+fn adornment_char(line: &str) -> char {
+    line.trim().chars().next().unwrap_or('=')
+}
+
+/// The heading level of a title style, at most 6
+fn level(styles: &mut Vec<(char, bool)>, style: (char, bool)) -> usize {
+    let index = match styles.iter().position(|known| *known == style) {
+        Some(index) => index,
+        None => {
+            styles.push(style);
+            styles.len() - 1
+        }
+    };
+    (index + 1).min(6)
+}
+
+/// The lines indented below a directive or literal block marker, without their
+/// common indentation, and the index of the line after them
+fn indented_block<'a>(lines: &[&'a str], start: usize) -> (Vec<&'a str>, usize) {
+    let mut end = start;
+    while end < lines.len()
+        && (lines[end].trim().is_empty() || lines[end].starts_with(char::is_whitespace))
+    {
+        end += 1;
+    }
+
+    let mut block = &lines[start..end];
+    while block.first().is_some_and(|line| line.trim().is_empty()) {
+        block = &block[1..];
+    }
+    while block.last().is_some_and(|line| line.trim().is_empty()) {
+        block = &block[..block.len() - 1];
+    }
+
+    let indent = block
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let block = block
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default())
+        .collect();
+
+    (block, end)
+}
+
+fn push_code_block(output: &mut Vec<String>, language: &str, body: &[&str]) {
+    output.push(format!("```{language}"));
+    output.extend(body.iter().map(|line| line.to_string()));
+    output.push("```".to_owned());
+    output.push(String::new());
+}
+
+/// Enumerated list items with `#.` are auto numbered
+fn list_item(line: &str) -> String {
+    let indent = line.len() - line.trim_start().len();
+    match line.trim_start().strip_prefix("#. ") {
+        Some(item) => format!("{}1. {item}", &line[..indent]),
+        None => line.to_owned(),
+    }
+}
+
+fn inline(text: &str) -> String {
+    let text = LINK.replace_all(text, "[$1]($2)");
+    let text = ROLE.replace_all(&text, "`$1$2`");
+    let text = INLINE_LITERAL.replace_all(&text, "`$1`");
+    text.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_titles() {
+        let rst = "=====\nGuide\n=====\n\nIntro\n\nInstall\n-------\n\nText\n\nFrom source\n~~~~~~~~~~~\n\nUsage\n-----";
+
+        assert_eq!(
+            rst_to_markdown(rst),
+            "# Guide\n\nIntro\n\n## Install\n\nText\n\n### From source\n\n## Usage"
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let rst = "Run this::\n\n    cargo run\n    cargo test\n\n.. code-block:: python\n   :linenos:\n\n   print('hi')\n\nAfter";
+
+        assert_eq!(
+            rst_to_markdown(rst),
+            "Run this:\n\n```\ncargo run\ncargo test\n```\n\n```python\nprint('hi')\n```\n\nAfter"
+        );
+    }
+
+    #[test]
+    fn test_directives_and_comments() {
+        let rst = ".. note:: Read this\n   carefully.\n\n.. toctree::\n   :maxdepth: 2\n\n   install\n\n.. a comment\n\n.. image:: /logo.png\n\nEnd";
+
+        assert_eq!(
+            rst_to_markdown(rst),
+            "> **Note:** Read this carefully.\n\n![](/logo.png)\n\nEnd"
+        );
+    }
+
+    #[test]
+    fn test_inline_markup() {
+        let rst = "Use ``cargo`` and :func:`run`, see `the docs <https://docs.rs>`_ and :ref:`setup <setup-label>`.\n\n#. First\n#. Second\n\n* **bold** *em*";
+
+        assert_eq!(
+            rst_to_markdown(rst),
+            "Use `cargo` and `run`, see [the docs](https://docs.rs) and `setup`.\n\n1. First\n1. Second\n\n* **bold** *em*"
+        );
+    }
+}
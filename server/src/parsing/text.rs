@@ -0,0 +1,168 @@
+use std::fmt::Display;
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::models::CacheValidators;
+
+use super::{front_matter, html::Waiting, Done, HtmlParser};
+
+static MDX_COMMENT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)\{/\*.*?\*/\}").expect("valid comment regex"));
+
+/// Markdown and plain text, which are stored as they are
+pub struct TextParser<State = Waiting> {
+    name: String,
+    content: String,
+    source: String,
+    validators: CacheValidators,
+    state: std::marker::PhantomData<State>,
+}
+
+impl TextParser {
+    pub fn new(name: &str, text: &str, source: impl Display) -> Self {
+        Self {
+            name: name.to_string(),
+            content: text.to_string(),
+            source: source.to_string(),
+            validators: CacheValidators::default(),
+            state: Default::default(),
+        }
+    }
+
+    /// Keep the cache validators of the response the text was read from
+    pub fn with_validators(mut self, validators: CacheValidators) -> Self {
+        self.validators = validators;
+        self
+    }
+}
+
+impl TextParser<Waiting> {
+    pub fn parse(self) -> Result<HtmlParser<Done>> {
+        let content = self.content.trim_start_matches('\u{feff}');
+
+        if content.trim().is_empty() {
+            bail!("The document is empty");
+        }
+
+        Ok(HtmlParser::from_parts(
+            self.name,
+            content.to_owned(),
+            self.source,
+            self.validators,
+        ))
+    }
+}
+
+/// Remove what MDX adds to Markdown that is not content: the `import` and
+/// `export` statements and `{/* comments */}`. JSX elements are kept as HTML.
+pub fn mdx_to_markdown(mdx: &str) -> String {
+    let mut lines = vec![];
+    let mut in_code_block = false;
+    let mut in_statement = false;
+
+    for line in mdx.lines() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+
+        if !in_code_block {
+            // Statements last until a blank line, as in MDX
+            if in_statement {
+                in_statement = !line.trim().is_empty();
+                continue;
+            }
+            if line.starts_with("import ") || line.starts_with("export ") {
+                in_statement = true;
+                continue;
+            }
+        }
+
+        lines.push(line);
+    }
+
+    let markdown = lines.join("\n");
+    MDX_COMMENT
+        .replace_all(&markdown, "")
+        .trim_start_matches('\n')
+        .to_owned()
+}
+
+/// The title in the front matter or the text of the first level one heading
+/// of a Markdown document, skipping code blocks
+pub fn markdown_title(markdown: &str) -> Option<String> {
+    if let Some(title) = front_matter(markdown).and_then(|metadata| metadata.title) {
+        return Some(title);
+    }
+
+    let mut in_code_block = false;
+
+    for line in markdown.lines() {
+        let line = line.trim_start_matches('\u{feff}');
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        if let Some(title) = line.strip_prefix("# ") {
+            let title = title.trim().trim_end_matches('#').trim();
+            if !title.is_empty() {
+                return Some(title.to_owned());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Url;
+    use rstest::*;
+
+    #[rstest]
+    #[case("# Getting Started\n\nText", Some("Getting Started"))]
+    #[case("\u{feff}# Title #\n", Some("Title"))]
+    #[case("Intro\n\n## Section\n\n# Title", Some("Title"))]
+    #[case("```sh\n# comment\n```\n# Title", Some("Title"))]
+    #[case("---\ntitle: Front matter\n---\n# Heading", Some("Front matter"))]
+    #[case("#Not a heading\n", None)]
+    #[case("Plain text", None)]
+    fn test_markdown_title(#[case] markdown: &str, #[case] expected: Option<&str>) {
+        assert_eq!(markdown_title(markdown).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_mdx_to_markdown() {
+        let mdx = "import Tabs from '@theme/Tabs';\nimport Tab from '@theme/Tab';\n\nexport const meta = {\n  title: 'Guide',\n};\n\n# Guide {/* todo */}\n\n```js\nimport x from 'y';\n```\n\n<Tabs>Text</Tabs>";
+
+        assert_eq!(
+            mdx_to_markdown(mdx),
+            "# Guide \n\n```js\nimport x from 'y';\n```\n\n<Tabs>Text</Tabs>"
+        );
+    }
+
+    #[test]
+    fn test_keeps_text_as_is() {
+        let url = Url::parse("https://example.com/docs/readme.md").unwrap();
+        let text = "\u{feff}# Readme\n\n  Some *text*\n";
+
+        let doc = TextParser::new("Readme", text, url).parse().unwrap();
+
+        assert_eq!(doc.name(), "Readme");
+        assert_eq!(doc.content(), "# Readme\n\n  Some *text*\n");
+        assert_eq!(doc.source(), "https://example.com/docs/readme.md");
+    }
+
+    #[test]
+    fn test_rejects_empty_text() {
+        let url = Url::parse("https://example.com/empty.txt").unwrap();
+
+        assert!(TextParser::new("empty", " \n", url).parse().is_err());
+    }
+}
@@ -162,8 +162,10 @@ pub async fn detail(
                 return HttpResponse::NotFound().finish();
             };
 
-            document.content = Markdown::to_html(&document.content);
+            let (content, toc) = Markdown::document_to_html(document.id, &document.content);
+            document.content = content;
             context.insert("document", &document);
+            context.insert("toc", &toc);
 
             tera.try_render("projects/documents/details.html", &context)
         }
@@ -5,11 +5,12 @@ use axum::{
     response::{sse::Event, IntoResponse, Response, Sse},
     Form,
 };
+use futures_util::{pin_mut, StreamExt};
 use http::{HeaderMap, HeaderValue};
 
 use crate::{
     database::Repo,
-    langchain::{LLMProvider, Langchain},
+    langchain::{embed_and_finalize, FinalizeProgress},
     models::{CreateProjectForm, Slugs},
     responses::HttpResponse,
     server::AppState,
@@ -95,7 +96,7 @@ pub async fn finalize(data: State<AppState>, Path(path): Path<Slugs>) -> Respons
 
     let db = &data.conn;
 
-    let mut documents = match db.documents().find_unembedded(project_id, version).await {
+    let documents = match db.documents().find_unembedded(project_id, version).await {
         Ok(documents) => documents,
         Err(e) => {
             tracing::error!("Failed to fetch documents: {:?}", e);
@@ -109,35 +110,21 @@ pub async fn finalize(data: State<AppState>, Path(path): Path<Slugs>) -> Respons
 
     let stream = async_stream::stream! {
         let db = &data.conn;
-        let lc = Langchain::new(LLMProvider::OpenAI);
+        let progress = embed_and_finalize(db, project_id, version, documents);
+        pin_mut!(progress);
 
-        let mut had_error = false;
-        while let Some(document) = documents.pop() {
-            yield Ok::<_, Infallible>(Event::default().data(format!("Embedding Document:\n\"{}\"", document.name)));
-            let embeddings = match lc.embed(&document.content).await {
-                Ok(embeddings) => embeddings,
-                Err(e) => {
-                    tracing::error!("Failed to embed document: {:?}", e);
-                    yield Ok::<_, Infallible>(Event::default().data(format!("Failed to embed document: {:?}", e)));
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    had_error = true;
-                    break;
+        while let Some(progress) = progress.next().await {
+            match progress {
+                Ok(FinalizeProgress::Embedding(name)) => {
+                    yield Ok::<_, Infallible>(Event::default().data(format!("Embedding Document:\n\"{}\"", name)));
                 }
-            };
-
-            match db.embeddings().create_many(document.id, embeddings).await {
-                Ok(_) => (),
+                Ok(FinalizeProgress::Finalized) => (),
                 Err(e) => {
-                    tracing::error!("Failed to save embeddings: {:?}", e);
-                    yield Ok::<_, Infallible>(Event::default().data(format!("Failed to save embeddings: {:?}", e)));
-                    had_error = true;
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    tracing::error!("Failed to finalize project version: {:?}", e);
+                    yield Ok::<_, Infallible>(Event::default().data(format!("{:#}", e)));
+                    tokio::time::sleep(Duration::from_secs(2)).await;
                 }
-            };
-        }
-
-        if !had_error {
-            db.projects_versions().finalize(project_id, version).await.ok();
+            }
         }
     };
 
@@ -0,0 +1,229 @@
+use std::{str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use cron::Schedule;
+use entity::crawl_schedule;
+use futures_util::{pin_mut, StreamExt};
+use migration::sea_orm::DatabaseConnection;
+
+use crate::{
+    database::Repo,
+    langchain::embed_and_finalize,
+    web_crawler::{
+        outcome::PageReport,
+        recrawl::{RecrawlOutput, Recrawler},
+    },
+};
+
+/// How often the scheduler looks for crawl schedules that are due
+const TICK: Duration = Duration::from_secs(60);
+
+/// Parse a cron expression. The common five field syntax is accepted as well
+/// as the six and seven field syntax with seconds and years.
+pub fn parse_cron(expression: &str) -> Result<Schedule> {
+    let expression = expression.trim();
+    let expression = match expression.split_whitespace().count() {
+        5 => format!("0 {expression}"),
+        _ => expression.to_owned(),
+    };
+
+    Schedule::from_str(&expression).map_err(|e| anyhow!("Invalid cron expression: {e}"))
+}
+
+/// Whether the schedule has fired since it last ran
+fn is_due(schedule: &crawl_schedule::Model, now: NaiveDateTime) -> bool {
+    let Ok(cron) = parse_cron(&schedule.cron) else {
+        return false;
+    };
+
+    let since = schedule
+        .last_run_at
+        .unwrap_or(schedule.created_at)
+        .and_utc();
+
+    cron.after(&since)
+        .next()
+        .is_some_and(|next| next.naive_utc() <= now)
+}
+
+/// Run the crawl schedules of all projects when they are due
+pub async fn run(db: DatabaseConnection) {
+    if let Err(e) = db
+        .crawl_runs()
+        .fail_unfinished("Interrupted by a server restart")
+        .await
+    {
+        tracing::error!("Failed to clean up unfinished crawl runs: {:?}", e);
+    }
+
+    let mut interval = tokio::time::interval(TICK);
+
+    loop {
+        interval.tick().await;
+
+        let schedules = match db.crawl_schedules().all_enabled().await {
+            Ok(schedules) => schedules,
+            Err(e) => {
+                tracing::error!("Failed to get crawl schedules: {:?}", e);
+                continue;
+            }
+        };
+
+        let now = Utc::now().naive_utc();
+
+        for schedule in schedules.into_iter().filter(|s| is_due(s, now)) {
+            // A schedule that is skipped stays due and is tried again on the next tick
+            let run_id = match db.crawl_schedules().claim(&schedule, now).await {
+                Ok(Some(run_id)) => run_id,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::error!("Failed to claim crawl schedule: {:?}", e);
+                    continue;
+                }
+            };
+
+            let db = db.clone();
+            tokio::spawn(async move { run_schedule(&db, schedule, run_id).await });
+        }
+    }
+}
+
+async fn run_schedule(db: &DatabaseConnection, schedule: crawl_schedule::Model, run_id: i32) {
+    tracing::info!(
+        "Running scheduled crawl of {} for project {}",
+        schedule.url,
+        schedule.project_id
+    );
+
+    let result = match recrawl(db, &schedule).await {
+        Ok((summary, version, reports)) => {
+            match db.crawl_runs().succeed(run_id, &summary, version).await {
+                Ok(()) => db.crawl_runs().add_pages(run_id, &reports).await,
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => {
+            tracing::error!("Scheduled crawl of {} failed: {:?}", schedule.url, e);
+            db.crawl_runs().fail(run_id, &format!("{e:#}")).await
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Failed to save crawl run: {:?}", e);
+    }
+}
+
+async fn recrawl(
+    db: &DatabaseConnection,
+    schedule: &crawl_schedule::Model,
+) -> Result<(String, Option<i32>, Vec<PageReport>)> {
+    let max_depth = schedule.max_depth.map(usize::try_from).transpose()?;
+    let recrawler = Recrawler::new(schedule.project_id, schedule.url.to_owned(), max_depth);
+
+    let stream = recrawler.start(db);
+    pin_mut!(stream);
+
+    let mut summary = None;
+    while let Some(output) = stream.next().await {
+        if let RecrawlOutput::Summary(s) = output? {
+            summary = Some(s);
+        }
+    }
+
+    let summary = summary.context("Re-crawl finished without a summary")?;
+
+    if !(schedule.finalize && summary.has_changes()) {
+        return Ok((summary.to_string(), summary.version, summary.reports));
+    }
+
+    let version = db
+        .projects_versions()
+        .find_latest_version_number(schedule.project_id, None)
+        .await?
+        .context("Project version not found")?;
+
+    finalize(db, schedule.project_id, version).await?;
+
+    Ok((
+        format!("{summary}, finalized version {version}"),
+        Some(version),
+        summary.reports,
+    ))
+}
+
+/// Embed the documents of the project version that are not embedded yet and finalize it
+async fn finalize(db: &DatabaseConnection, project_id: i32, version: i32) -> Result<()> {
+    let documents = db.documents().find_unembedded(project_id, version).await?;
+
+    let progress = embed_and_finalize(db, project_id, version, documents);
+    pin_mut!(progress);
+    while let Some(progress) = progress.next().await {
+        progress?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rstest::*;
+
+    fn schedule(cron: &str, last_run_at: Option<NaiveDateTime>) -> crawl_schedule::Model {
+        crawl_schedule::Model {
+            id: 1,
+            project_id: 1,
+            url: "https://example.com".to_owned(),
+            max_depth: None,
+            cron: cron.to_owned(),
+            finalize: false,
+            enabled: true,
+            last_run_at,
+            created_at: datetime(1, 0),
+        }
+    }
+
+    fn datetime(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case("0 3 * * 1")]
+    #[case("0 0 3 * * Mon")]
+    #[case("0 0 3 * * Mon 2024")]
+    fn test_parse_valid_cron(#[case] expression: &str) {
+        assert!(parse_cron(expression).is_ok());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("every monday")]
+    #[case("0 25 * * *")]
+    fn test_parse_invalid_cron(#[case] expression: &str) {
+        assert!(parse_cron(expression).is_err());
+    }
+
+    #[rstest]
+    // Daily at 03:00, never run, created on the 1st at midnight
+    #[case("0 3 * * *", None, datetime(1, 2), false)]
+    #[case("0 3 * * *", None, datetime(1, 3), true)]
+    // Daily at 03:00, last run on the 2nd at 03:00
+    #[case("0 3 * * *", Some(datetime(2, 3)), datetime(2, 12), false)]
+    #[case("0 3 * * *", Some(datetime(2, 3)), datetime(3, 3), true)]
+    // Missed runs are caught up once
+    #[case("0 3 * * *", Some(datetime(2, 3)), datetime(9, 12), true)]
+    #[case("not a cron expression", None, datetime(9, 12), false)]
+    fn test_is_due(
+        #[case] cron: &str,
+        #[case] last_run_at: Option<NaiveDateTime>,
+        #[case] now: NaiveDateTime,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_due(&schedule(cron, last_run_at), now), expected);
+    }
+}
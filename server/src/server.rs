@@ -17,8 +17,8 @@ use tower_http::trace::TraceLayer;
 use tracing::log;
 
 use crate::{
-    fallback::file_and_error_handler, keycloak::Keycloak, middleware, routes, wasm::app::App,
-    CONFIG,
+    database::Repo, fallback::file_and_error_handler, keycloak::Keycloak, middleware, routes,
+    scheduler, wasm::app::App, CONFIG,
 };
 
 #[derive(FromRef, Debug, Clone)]
@@ -46,6 +46,16 @@ pub async fn run() -> std::io::Result<()> {
     // Apply database migrations
     Migrator::up(&conn, None).await.unwrap();
 
+    // Front matter can't be parsed in a migration, so older documents get their metadata here
+    match conn.documents().backfill_metadata().await {
+        Ok(0) => (),
+        Ok(count) => tracing::info!("Filled the metadata of {} documents", count),
+        Err(e) => tracing::error!("Failed to fill document metadata: {:?}", e),
+    }
+
+    // Run scheduled crawls in the background
+    tokio::spawn(scheduler::run(conn.clone()));
+
     let keycloak = Keycloak::default();
 
     let conf = get_configuration(None).await.unwrap();
@@ -130,6 +130,7 @@ pub async fn get_user(
                 version: -1,
                 versions: Vec::new(),
                 finalized: false,
+                folders: Vec::new(),
             };
 
             let mut p = (false, false, false);
@@ -375,6 +376,7 @@ pub async fn get_role(
                 version: -1,
                 versions: Vec::new(),
                 finalized: false,
+                folders: Vec::new(),
             };
 
             let mut p = (false, false, false);
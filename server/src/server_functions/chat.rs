@@ -72,3 +72,11 @@ pub async fn chat(
 
     Ok(TextStream::new(stream))
 }
+
+/// Renders a finished chat answer on the server, where diagrams are rendered too
+#[server]
+pub async fn render_answer(answer: String) -> Result<String, ServerFnError> {
+    use crate::markdown::Markdown;
+
+    Ok(Markdown::to_html(&answer))
+}
@@ -0,0 +1,157 @@
+use super::models::CrawlCredentialsData;
+use leptos::{server, server_fn::codec::Json, ServerFnError};
+
+#[cfg(feature = "ssr")]
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+
+/// Get the hosts credentials are saved for. The secrets never leave the server.
+#[server]
+pub async fn get_crawl_credentials(
+    project_id: i32,
+) -> Result<Vec<CrawlCredentialsData>, ServerFnError> {
+    use crate::{
+        database::Repo, server::AppState, web_crawler::credentials::CrawlCredentials, CONFIG,
+    };
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    let Ok(credentials) = db.crawl_credentials().all(project_id).await else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get crawl credentials".to_string(),
+        ));
+    };
+
+    let credentials = credentials
+        .into_iter()
+        .map(|c| {
+            let kinds = match CONFIG.credentials_cipher() {
+                Some(cipher) => match CrawlCredentials::decrypt(&c.secret, cipher) {
+                    Ok(credentials) => credentials.kinds(),
+                    Err(_) => vec!["Can not be decrypted".to_owned()],
+                },
+                None => vec!["Encryption key missing".to_owned()],
+            };
+
+            CrawlCredentialsData {
+                id: c.id,
+                host: c.host,
+                kinds,
+                updated_at: c.updated_at.format(DATETIME_FORMAT).to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(credentials)
+}
+
+/// Save credentials for the host of the url, replacing the ones saved before.
+/// They are used by every crawl of the host, including scheduled re-crawls.
+#[server(input = Json)]
+pub async fn save_crawl_credentials(
+    project_id: i32,
+    url: String,
+    headers: Vec<(String, String)>,
+    cookies: Vec<(String, String)>,
+    basic_auth: Option<(String, String)>,
+    bearer_token: Option<String>,
+) -> Result<(), ServerFnError> {
+    use crate::{
+        database::Repo,
+        server::AppState,
+        web_crawler::credentials::{credentials_host, CrawlCredentials},
+        CONFIG,
+    };
+    use leptos::use_context;
+    use reqwest::Url;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let Some(host) = Url::parse(&url).ok().as_ref().and_then(credentials_host) else {
+        return Err(ServerFnError::ServerError("Invalid URL".to_string()));
+    };
+
+    let Some(cipher) = CONFIG.credentials_cipher() else {
+        return Err(ServerFnError::ServerError(
+            "Credentials can not be saved, CRAWL_CREDENTIALS_KEY is not set".to_string(),
+        ));
+    };
+
+    let credentials = CrawlCredentials {
+        headers,
+        cookies,
+        basic_auth,
+        bearer_token,
+    };
+
+    if credentials.is_empty() {
+        return Err(ServerFnError::ServerError(
+            "No credentials given".to_string(),
+        ));
+    }
+
+    if let Err(e) = credentials.headers() {
+        return Err(ServerFnError::ServerError(e.to_string()));
+    }
+
+    let Ok(secret) = credentials.encrypt(cipher) else {
+        return Err(ServerFnError::ServerError(
+            "Failed to encrypt credentials".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    if let Err(e) = db
+        .crawl_credentials()
+        .save(project_id, &host, &secret)
+        .await
+    {
+        tracing::error!("Error saving crawl credentials: {:?}", e);
+        return Err(ServerFnError::ServerError(
+            "Failed to save crawl credentials".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[server]
+pub async fn delete_crawl_credentials(
+    project_id: i32,
+    credentials_id: i32,
+) -> Result<(), ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    if let Err(e) = db
+        .crawl_credentials()
+        .delete(credentials_id, project_id)
+        .await
+    {
+        tracing::error!("Error deleting crawl credentials: {:?}", e);
+        return Err(ServerFnError::ServerError(
+            "Failed to delete crawl credentials".to_string(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,230 @@
+use super::models::{CrawlReportPageData, CrawlRunData, CrawlScheduleData};
+use leptos::{server, ServerFnError};
+
+/// Number of crawl runs shown in a project's run history
+#[cfg(feature = "ssr")]
+const RUN_HISTORY_LENGTH: u64 = 20;
+
+#[cfg(feature = "ssr")]
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+
+#[server]
+pub async fn get_crawl_schedules(
+    project_id: i32,
+) -> Result<(Vec<CrawlScheduleData>, Vec<CrawlRunData>), ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use entity::sea_orm_active_enums::CrawlRunStatus;
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        tracing::error!("Failed to get app state");
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = state.conn;
+
+    let Ok(schedules) = db.crawl_schedules().all(project_id).await else {
+        tracing::error!("Failed to get crawl schedules");
+        return Err(ServerFnError::ServerError(
+            "Failed to get crawl schedules".to_string(),
+        ));
+    };
+
+    let Ok(runs) = db.crawl_runs().latest(project_id, RUN_HISTORY_LENGTH).await else {
+        tracing::error!("Failed to get crawl runs");
+        return Err(ServerFnError::ServerError(
+            "Failed to get crawl runs".to_string(),
+        ));
+    };
+
+    let schedules = schedules
+        .into_iter()
+        .map(|s| CrawlScheduleData {
+            id: s.id,
+            url: s.url,
+            max_depth: s.max_depth,
+            cron: s.cron,
+            finalize: s.finalize,
+            enabled: s.enabled,
+            last_run_at: s.last_run_at.map(|t| t.format(DATETIME_FORMAT).to_string()),
+        })
+        .collect::<Vec<_>>();
+
+    let runs = runs
+        .into_iter()
+        .map(|r| CrawlRunData {
+            id: r.id,
+            url: r.url,
+            status: match r.status {
+                CrawlRunStatus::Running => "Running",
+                CrawlRunStatus::Succeeded => "Succeeded",
+                CrawlRunStatus::Failed => "Failed",
+            }
+            .to_owned(),
+            summary: r.summary,
+            error: r.error,
+            version: r.version,
+            started_at: r.started_at.format(DATETIME_FORMAT).to_string(),
+            finished_at: r.finished_at.map(|t| t.format(DATETIME_FORMAT).to_string()),
+        })
+        .collect::<Vec<_>>();
+
+    Ok((schedules, runs))
+}
+
+#[server]
+pub async fn create_crawl_schedule(
+    project_id: i32,
+    url: String,
+    max_depth: Option<i32>,
+    cron: String,
+    finalize: bool,
+) -> Result<i32, ServerFnError> {
+    use crate::{database::Repo, scheduler::parse_cron, server::AppState};
+    use leptos::use_context;
+    use reqwest::Url;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    if Url::parse(&url).is_err() {
+        return Err(ServerFnError::ServerError("Invalid URL".to_string()));
+    }
+
+    if let Err(e) = parse_cron(&cron) {
+        return Err(ServerFnError::ServerError(e.to_string()));
+    }
+
+    let db = &state.conn;
+
+    let Ok(id) = db
+        .crawl_schedules()
+        .create(project_id, &url, max_depth, cron.trim(), finalize)
+        .await
+    else {
+        return Err(ServerFnError::ServerError(
+            "Failed to create crawl schedule".to_string(),
+        ));
+    };
+
+    Ok(id)
+}
+
+#[server]
+pub async fn set_crawl_schedule_enabled(
+    project_id: i32,
+    schedule_id: i32,
+    enabled: bool,
+) -> Result<(), ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    if let Err(e) = db
+        .crawl_schedules()
+        .set_enabled(schedule_id, project_id, enabled)
+        .await
+    {
+        tracing::error!("Error updating crawl schedule: {:?}", e);
+        return Err(ServerFnError::ServerError(
+            "Failed to update crawl schedule".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[server]
+pub async fn delete_crawl_schedule(project_id: i32, schedule_id: i32) -> Result<(), ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    if let Err(e) = db.crawl_schedules().delete(schedule_id, project_id).await {
+        tracing::error!("Error deleting crawl schedule: {:?}", e);
+        return Err(ServerFnError::ServerError(
+            "Failed to delete crawl schedule".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Get the outcome of every url of a crawl run, or of the latest run of the
+/// project if no run is given
+#[server]
+pub async fn get_crawl_report(
+    project_id: i32,
+    run_id: Option<i32>,
+) -> Result<Vec<CrawlReportPageData>, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use entity::sea_orm_active_enums::CrawlPageOutcome;
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    let run = match db.crawl_runs().find(project_id, run_id).await {
+        Ok(Some(run)) => run,
+        Ok(None) => return Ok(vec![]),
+        Err(e) => {
+            tracing::error!("Error getting crawl run: {:?}", e);
+            return Err(ServerFnError::ServerError(
+                "Failed to get crawl run".to_string(),
+            ));
+        }
+    };
+
+    let Ok(pages) = db.crawl_runs().pages(run.id).await else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get crawl report".to_string(),
+        ));
+    };
+
+    let pages = pages
+        .into_iter()
+        .map(|p| CrawlReportPageData {
+            url: p.url,
+            depth: p.depth,
+            outcome: match p.outcome {
+                CrawlPageOutcome::Fetched => "Fetched",
+                CrawlPageOutcome::NotModified => "Not modified",
+                CrawlPageOutcome::SkippedByRobots => "Disallowed by robots.txt",
+                CrawlPageOutcome::SkippedByDepth => "Outside of crawl depth",
+                CrawlPageOutcome::HttpError => "HTTP error",
+                CrawlPageOutcome::NotHtml => "Unsupported content",
+                CrawlPageOutcome::Duplicate => "Duplicate",
+                CrawlPageOutcome::ParseError => "Parse error",
+                CrawlPageOutcome::Failed => "Failed",
+            }
+            .to_owned(),
+            detail: p.detail,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(pages)
+}
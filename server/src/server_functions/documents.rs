@@ -1,7 +1,7 @@
-use super::models::Document;
+use super::models::{Document, DocumentSource, TreeNode};
 use leptos::{
     server,
-    server_fn::codec::{StreamingText, TextStream},
+    server_fn::codec::{Json, StreamingText, TextStream},
     ServerFnError,
 };
 
@@ -34,16 +34,68 @@ pub async fn get_document(
         ));
     };
 
+    let tags = match db
+        .documents_versions()
+        .find_by_pks(document_id, project_id, version, None)
+        .await
+    {
+        Ok(Some(document_version)) => {
+            serde_json::from_value(document_version.tags).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    };
+
+    let writable = can_write(&db, project_id).await?;
+    let (content, toc) = Markdown::document_to_html(document.id, &document.content);
     let document = Document {
         id: document.id,
         name: document.name,
-        content: Markdown::to_html(&document.content),
+        content,
         source: document.source,
+        toc,
+        tags,
+        writable,
     };
 
     Ok(document)
 }
 
+#[server]
+pub async fn get_document_source(
+    project_id: i32,
+    version: i32,
+    document_id: i32,
+) -> Result<DocumentSource, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        tracing::error!("Failed to get app state");
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = state.conn;
+
+    let Ok(Some(document)) = db
+        .documents()
+        .find_by_id_and_version(project_id, version, document_id)
+        .await
+    else {
+        tracing::error!("Failed to get document");
+        return Err(ServerFnError::ServerError(
+            "Failed to get document".to_string(),
+        ));
+    };
+
+    Ok(DocumentSource {
+        id: document_id,
+        name: document.name,
+        content: document.content,
+    })
+}
+
 #[server]
 pub async fn create_document(
     project_id: i32,
@@ -71,24 +123,119 @@ pub async fn create_document(
     Ok(document_id)
 }
 
+/// Update the title and content of a document. Returns the new project version
+/// if the project version was finalized, as the changes are then saved in a new
+/// version of the project.
+#[server(input = Json)]
+pub async fn update_document(
+    project_id: i32,
+    project_version: i32,
+    document_id: i32,
+    title: String,
+    content: String,
+) -> Result<Option<i32>, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+    if !can_write(db, project_id).await? {
+        return Err(ServerFnError::ServerError("Unauthorized".to_string()));
+    }
+
+    let title = title.trim();
+    if title.is_empty() {
+        return Err(ServerFnError::ServerError(
+            "Document title is required".to_string(),
+        ));
+    }
+
+    tracing::info!(
+        "Updating document {} of project {}",
+        document_id,
+        project_id
+    );
+
+    match db
+        .documents()
+        .update(
+            document_id,
+            project_id,
+            project_version,
+            (title, &content),
+            None,
+            None,
+        )
+        .await
+    {
+        Ok(version) => Ok(version),
+        Err(e) => {
+            tracing::error!("Error updating document: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to update document".to_string(),
+            ))
+        }
+    }
+}
+
 #[server(output = StreamingText)]
 pub async fn crawl_website(
     project_id: i32,
     url: String,
     max_depth: Option<usize>,
 ) -> Result<TextStream, ServerFnError> {
+    use crate::server::AppState;
+    use http::header::{HeaderName, HeaderValue};
+    use leptos::{expect_context, use_context};
+    use leptos_axum::ResponseOptions;
+    use std::str::FromStr;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let response = expect_context::<ResponseOptions>();
+
+    let crawler = new_crawler(&state.conn, project_id, url, max_depth).await?;
+
+    let stream = crawl_and_save(state.conn, project_id, crawler);
+
+    if let Ok(key) = HeaderName::from_str("X-Accel-Buffering") {
+        let value = HeaderValue::from_static("no");
+        response.insert_header(key, value);
+    }
+
+    Ok(TextStream::new(stream))
+}
+
+/// Crawl a website like `crawl_website` without saving anything. The stream
+/// contains one JSON encoded `CrawlPreviewOutput` per line.
+#[server(output = StreamingText)]
+pub async fn preview_crawl(
+    project_id: i32,
+    url: String,
+    max_depth: Option<usize>,
+) -> Result<TextStream, ServerFnError> {
+    use super::models::{CrawlPreviewOutput, CrawlPreviewPage};
     use crate::{
-        database::Repo,
-        parsing::HtmlParser,
         server::AppState,
-        web_crawler::crawler::{Crawler, StreamOutput},
+        web_crawler::{crawler::StreamOutput, outcome::PageOutcome},
     };
     use futures_util::{pin_mut, StreamExt};
     use http::header::{HeaderName, HeaderValue};
     use leptos::{expect_context, use_context};
     use leptos_axum::ResponseOptions;
     use std::str::FromStr;
-    use tokio::time::sleep;
+
+    /// Characters of content shown for each page
+    const SAMPLE_LENGTH: usize = 200;
 
     let Some(state) = use_context::<AppState>() else {
         return Err(ServerFnError::ServerError(
@@ -98,15 +245,197 @@ pub async fn crawl_website(
 
     let response = expect_context::<ResponseOptions>();
 
+    let mut crawler = new_crawler(&state.conn, project_id, url, max_depth).await?;
+
+    let line = |output: CrawlPreviewOutput| match serde_json::to_string(&output) {
+        Ok(json) => Ok(format!("{json}\n")),
+        Err(e) => Err(ServerFnError::ServerError(e.to_string())),
+    };
+
     let stream = async_stream::stream! {
-        let db = &state.conn;
+        let stream = crawler.start().await;
+        pin_mut!(stream);
 
-        let mut results = vec![];
-        let Ok(mut crawler) = Crawler::new(url, max_depth) else {
-            yield Ok::<_, ServerFnError>("Error".to_owned());
-            return;
+        while let Some(output) = stream.next().await {
+            match output {
+                StreamOutput::Message(msg) => yield line(CrawlPreviewOutput::Message(msg)),
+                StreamOutput::Result(res) => {
+                    let (title, sample) = match res.parse().await {
+                        Ok(doc) => (doc.name().to_owned(), doc.excerpt(SAMPLE_LENGTH)),
+                        Err(_) => (res.title(), String::new()),
+                    };
+
+                    yield line(CrawlPreviewOutput::Page(CrawlPreviewPage {
+                        url: res.url().to_string(),
+                        title,
+                        sample,
+                    }));
+                }
+                StreamOutput::LimitReached(limit) => {
+                    yield line(CrawlPreviewOutput::Message(format!("Crawl stopped early: {limit}")));
+                }
+                StreamOutput::Report(report) if report.outcome != PageOutcome::Fetched => {
+                    yield line(CrawlPreviewOutput::Message(report.to_string()));
+                }
+                StreamOutput::Report(_) | StreamOutput::NotModified(_) | StreamOutput::Gone(_) => (),
+            }
+        }
+
+        yield line(CrawlPreviewOutput::Message("Done".to_owned()));
+    };
+
+    if let Ok(key) = HeaderName::from_str("X-Accel-Buffering") {
+        let value = HeaderValue::from_static("no");
+        response.insert_header(key, value);
+    }
+
+    Ok(TextStream::new(stream))
+}
+
+/// Fetch and save the pages selected from a crawl preview
+#[server(input = Json, output = StreamingText)]
+pub async fn import_crawled_pages(
+    project_id: i32,
+    url: String,
+    max_depth: Option<usize>,
+    pages: Vec<String>,
+) -> Result<TextStream, ServerFnError> {
+    use crate::server::AppState;
+    use http::header::{HeaderName, HeaderValue};
+    use leptos::{expect_context, use_context};
+    use leptos_axum::ResponseOptions;
+    use reqwest::Url;
+    use std::str::FromStr;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let response = expect_context::<ResponseOptions>();
+
+    let Ok(pages) = pages
+        .iter()
+        .map(|page| Url::parse(page))
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return Err(ServerFnError::ServerError("Invalid page URL".to_string()));
+    };
+
+    let crawler = new_crawler(&state.conn, project_id, url, max_depth)
+        .await?
+        .with_selection(pages);
+
+    let stream = crawl_and_save(state.conn, project_id, crawler);
+
+    if let Ok(key) = HeaderName::from_str("X-Accel-Buffering") {
+        let value = HeaderValue::from_static("no");
+        response.insert_header(key, value);
+    }
+
+    Ok(TextStream::new(stream))
+}
+
+/// Whether the user of the request may change the documents of the project.
+/// Admins may change every project.
+#[cfg(feature = "ssr")]
+async fn can_write(
+    db: &migration::sea_orm::DatabaseConnection,
+    project_id: i32,
+) -> Result<bool, ServerFnError> {
+    use crate::{database::Repo, utils::claims::Claims};
+    use axum::Extension;
+    use entity::sea_orm_active_enums::PermissionEnum;
+    use leptos_axum::extract;
+
+    let claims: Extension<Claims> = extract().await?;
+    if claims.is_admin() {
+        return Ok(true);
+    }
+
+    match db
+        .projects()
+        .has_permission(
+            project_id,
+            &claims.sub(),
+            claims.roles(),
+            PermissionEnum::Create,
+        )
+        .await
+    {
+        Ok(can_write) => Ok(can_write),
+        Err(e) => {
+            tracing::error!("Failed to get project permissions: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to get project permissions".to_string(),
+            ))
+        }
+    }
+}
+
+/// A crawler with the configured limits and fetch policy, and the credentials
+/// saved for the host of the url
+#[cfg(feature = "ssr")]
+async fn new_crawler(
+    db: &migration::sea_orm::DatabaseConnection,
+    project_id: i32,
+    url: String,
+    max_depth: Option<usize>,
+) -> Result<crate::web_crawler::crawler::Crawler, ServerFnError> {
+    use crate::{
+        web_crawler::{crawler::Crawler, credentials::load_credentials},
+        CONFIG,
+    };
+
+    let Ok(crawler) = Crawler::new(url, max_depth) else {
+        return Err(ServerFnError::ServerError("Invalid URL".to_string()));
+    };
+
+    let credentials = match load_credentials(db, project_id, crawler.url()).await {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            tracing::error!("Failed to load crawl credentials: {:?}", e);
+            return Err(ServerFnError::ServerError(format!(
+                "Failed to load crawl credentials: {e}"
+            )));
+        }
+    };
+
+    Ok(crawler
+        .with_limits(CONFIG.crawl_limits().clone())
+        .with_policy(CONFIG.fetch_policy().clone())
+        .with_credentials(credentials))
+}
+
+/// Run the crawler and save the pages it finds in the latest project version.
+/// The outcome of every url is saved as a crawl run of the project.
+#[cfg(feature = "ssr")]
+fn crawl_and_save(
+    db: migration::sea_orm::DatabaseConnection,
+    project_id: i32,
+    mut crawler: crate::web_crawler::crawler::Crawler,
+) -> impl futures_util::Stream<Item = Result<String, ServerFnError>> {
+    use crate::{
+        database::Repo,
+        web_crawler::{crawler::StreamOutput, outcome::PageOutcome, recrawl::set_outcome},
+    };
+    use futures_util::{pin_mut, StreamExt};
+    use tokio::time::sleep;
+
+    async_stream::stream! {
+        let run_id = match db.crawl_runs().start(project_id, None, crawler.url().as_str()).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::error!("Failed to start crawl run: {:?}", e);
+                None
+            }
         };
 
+        let mut results = vec![];
+        let mut reports = vec![];
+        let mut stopped_by = None;
+
         let stream = crawler.start().await;
         pin_mut!(stream);
 
@@ -118,6 +447,17 @@ pub async fn crawl_website(
                 StreamOutput::Result(res) => {
                     results.push(res);
                 }
+                StreamOutput::Report(report) => {
+                    if report.outcome != PageOutcome::Fetched {
+                        yield Ok(report.to_string());
+                    }
+                    reports.push(report);
+                }
+                StreamOutput::LimitReached(limit) => {
+                    yield Ok(format!("Crawl stopped early: {limit}"));
+                    stopped_by = Some(limit);
+                }
+                StreamOutput::NotModified(_) | StreamOutput::Gone(_) => (),
             }
         };
 
@@ -127,12 +467,12 @@ pub async fn crawl_website(
         for res in results {
             let message = format!("Processing {}", res.title());
             yield Ok(message);
-            let parser = HtmlParser::new(&res.title(), &res.html(), res.url());
-            match parser.parse() {
+            match res.parse().await {
                 Ok(doc) => documents.push(doc),
                 Err(e) => {
                     let message = format!("Error: {}", e);
                     yield Ok(message);
+                    set_outcome(&mut reports, &res.url(), PageOutcome::ParseError(e.to_string()));
                 }
             }
             sleep(std::time::Duration::from_millis(100)).await;
@@ -140,9 +480,102 @@ pub async fn crawl_website(
 
         yield Ok("Saving documents...".to_owned());
 
-        let _ = db.documents().create_many_from_documents(project_id, documents).await;
+        let count = documents.len();
+        let saved = db.documents().create_many_from_documents(project_id, documents).await;
+
+        if let Some(run_id) = run_id {
+            let result = match saved {
+                Ok(_) => {
+                    let mut summary = format!("Imported {count} pages");
+                    if let Some(limit) = stopped_by {
+                        summary.push_str(&format!(". The crawl stopped early because it {limit}"));
+                    }
+                    match db.crawl_runs().succeed(run_id, &summary, None).await {
+                        Ok(()) => db.crawl_runs().add_pages(run_id, &reports).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                Err(e) => db.crawl_runs().fail(run_id, &format!("{e:#}")).await,
+            };
+
+            if let Err(e) = result {
+                tracing::error!("Failed to save crawl run: {:?}", e);
+            }
+        }
 
         yield Ok("Done".to_owned());
+        sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[server(output = StreamingText)]
+pub async fn recrawl_website(
+    project_id: i32,
+    url: String,
+    max_depth: Option<usize>,
+) -> Result<TextStream, ServerFnError> {
+    use crate::{
+        database::Repo,
+        server::AppState,
+        web_crawler::recrawl::{RecrawlOutput, Recrawler},
+    };
+    use futures_util::{pin_mut, StreamExt};
+    use http::header::{HeaderName, HeaderValue};
+    use leptos::{expect_context, use_context};
+    use leptos_axum::ResponseOptions;
+    use std::str::FromStr;
+    use tokio::time::sleep;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let response = expect_context::<ResponseOptions>();
+
+    let stream = async_stream::stream! {
+        let db = &state.conn;
+        let run_id = match db.crawl_runs().start(project_id, None, &url).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::error!("Failed to start crawl run: {:?}", e);
+                None
+            }
+        };
+        let recrawler = Recrawler::new(project_id, url, max_depth);
+
+        let stream = recrawler.start(db);
+        pin_mut!(stream);
+
+        while let Some(output) = stream.next().await {
+            match output {
+                Ok(RecrawlOutput::Message(msg)) => yield Ok::<_, ServerFnError>(msg),
+                Ok(RecrawlOutput::Summary(summary)) => {
+                    if let Some(run_id) = run_id {
+                        let result = match db.crawl_runs().succeed(run_id, &summary.to_string(), summary.version).await {
+                            Ok(()) => db.crawl_runs().add_pages(run_id, &summary.reports).await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = result {
+                            tracing::error!("Failed to save crawl run: {:?}", e);
+                        }
+                    }
+                    yield Ok(summary.to_string())
+                }
+                Err(e) => {
+                    tracing::error!("Failed to re-crawl website: {:?}", e);
+                    if let Some(run_id) = run_id {
+                        if let Err(e) = db.crawl_runs().fail(run_id, &format!("{e:#}")).await {
+                            tracing::error!("Failed to save crawl run: {:?}", e);
+                        }
+                    }
+                    yield Ok(format!("Error: {}", e));
+                    break;
+                }
+            }
+        }
+
         sleep(std::time::Duration::from_secs(1)).await;
     };
 
@@ -187,3 +620,147 @@ pub async fn delete_document(
 
     Ok(())
 }
+
+/// Replace the tags of a document, returning the new project version if the
+/// project version was finalized
+#[server(input = Json)]
+pub async fn set_document_tags(
+    project_id: i32,
+    project_version: i32,
+    document_id: i32,
+    tags: Vec<String>,
+) -> Result<Option<i32>, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    match db
+        .documents_versions()
+        .set_tags(document_id, project_id, project_version, tags)
+        .await
+    {
+        Ok(version) => Ok(version),
+        Err(e) => {
+            tracing::error!("Error setting document tags: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to set document tags".to_string(),
+            ))
+        }
+    }
+}
+
+/// Create a folder at the end of `parent_id`, returning the new project version
+/// if the project version was finalized
+#[server]
+pub async fn create_folder(
+    project_id: i32,
+    project_version: i32,
+    name: String,
+    parent_id: Option<i32>,
+) -> Result<Option<i32>, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServerFnError::ServerError(
+            "Folder name is required".to_string(),
+        ));
+    }
+
+    let db = &state.conn;
+
+    match db
+        .document_folders()
+        .create(project_id, project_version, name, parent_id)
+        .await
+    {
+        Ok(version) => Ok(version),
+        Err(e) => {
+            tracing::error!("Error creating folder: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to create folder".to_string(),
+            ))
+        }
+    }
+}
+
+/// Move a document or folder into `parent_id` at `position` among its children,
+/// returning the new project version if the project version was finalized
+#[server(input = Json)]
+pub async fn move_tree_node(
+    project_id: i32,
+    project_version: i32,
+    node: TreeNode,
+    parent_id: Option<i32>,
+    position: usize,
+) -> Result<Option<i32>, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    match db
+        .document_folders()
+        .move_node(project_id, project_version, node, parent_id, position)
+        .await
+    {
+        Ok(version) => Ok(version),
+        Err(e) => {
+            tracing::error!("Error moving {:?}: {:?}", node, e);
+            Err(ServerFnError::ServerError(format!("Failed to move: {e}")))
+        }
+    }
+}
+
+/// Delete a folder, its children are moved to the parent of the folder
+#[server]
+pub async fn delete_folder(
+    project_id: i32,
+    project_version: i32,
+    folder_id: i32,
+) -> Result<Option<i32>, ServerFnError> {
+    use crate::{database::Repo, server::AppState};
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let db = &state.conn;
+
+    match db
+        .document_folders()
+        .delete(folder_id, project_id, project_version)
+        .await
+    {
+        Ok(version) => Ok(version),
+        Err(e) => {
+            tracing::error!("Error deleting folder: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to delete folder".to_string(),
+            ))
+        }
+    }
+}
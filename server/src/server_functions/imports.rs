@@ -0,0 +1,409 @@
+use super::models::ImportResult;
+use leptos::{
+    server,
+    server_fn::codec::{Json, MultipartData, MultipartFormData},
+    ServerFnError,
+};
+
+/// Import the Markdown, MDX and reStructuredText files of a commit from a git
+/// repository below `GIT_IMPORT_ROOT`. Documents from an earlier import of the
+/// same repository are matched by their path, so only changed files are updated.
+#[server(input = Json)]
+pub async fn import_git_repository(
+    project_id: i32,
+    repository: String,
+    reference: String,
+    globs: Vec<String>,
+) -> Result<ImportResult, ServerFnError> {
+    use crate::{
+        importers::{
+            apply_import,
+            git::{resolve_repository, GitImporter},
+        },
+        server::AppState,
+        CONFIG,
+    };
+    use leptos::use_context;
+    use std::sync::Arc;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let Some(root) = CONFIG.git_import_root() else {
+        return Err(ServerFnError::ServerError(
+            "Importing from git is not enabled".to_string(),
+        ));
+    };
+
+    let importer = match resolve_repository(root, &repository)
+        .and_then(|repository| GitImporter::new(repository, &reference).with_globs(&globs))
+    {
+        Ok(importer) => Arc::new(importer),
+        Err(e) => return Err(ServerFnError::ServerError(e.to_string())),
+    };
+
+    let reader = importer.clone();
+    let import = match tokio::task::spawn_blocking(move || reader.read()).await {
+        Ok(Ok(import)) => import,
+        Ok(Err(e)) => return Err(ServerFnError::ServerError(format!("{e:#}"))),
+        Err(e) => {
+            tracing::error!("Failed to read git repository: {:?}", e);
+            return Err(ServerFnError::ServerError(
+                "Failed to read git repository".to_string(),
+            ));
+        }
+    };
+
+    tracing::info!(
+        "Importing {} documents from {} at {} for project {}",
+        import.documents.len(),
+        repository,
+        import.commit,
+        project_id
+    );
+
+    let key = |source: &str| importer.document_key(source);
+    match apply_import(&state.conn, project_id, import.documents, key).await {
+        Ok(mut summary) => {
+            summary.skipped = import.skipped;
+            Ok(summary.into())
+        }
+        Err(e) => {
+            tracing::error!("Failed to import git repository: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to save imported documents".to_string(),
+            ))
+        }
+    }
+}
+
+/// Import an OpenAPI 3 or Swagger 2 spec in JSON or YAML as one document per
+/// operation and one per schema. Documents from an earlier import of the same
+/// API are matched by operation and schema, so only changes are saved.
+#[server(input = Json)]
+pub async fn import_openapi(project_id: i32, spec: String) -> Result<ImportResult, ServerFnError> {
+    use crate::{
+        importers::{apply_import, openapi::OpenApiSpec},
+        server::AppState,
+    };
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let spec = match OpenApiSpec::parse(&spec) {
+        Ok(spec) => spec,
+        Err(e) => return Err(ServerFnError::ServerError(format!("{e:#}"))),
+    };
+    let documents = spec.documents();
+
+    tracing::info!(
+        "Importing {} documents from the {} spec for project {}",
+        documents.len(),
+        spec.title(),
+        project_id
+    );
+
+    let key = |source: &str| spec.document_key(source);
+    match apply_import(&state.conn, project_id, documents, key).await {
+        Ok(summary) => Ok(summary.into()),
+        Err(e) => {
+            tracing::error!("Failed to import OpenAPI spec: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to save imported documents".to_string(),
+            ))
+        }
+    }
+}
+
+/// Import the JSON output of `cargo rustdoc` as one document per public module
+/// and item. Items are matched with the ones imported from earlier releases of
+/// the crate by their path, so after finalizing the project version a new
+/// release is imported into a new version with only the changed items.
+#[server(input = Json)]
+pub async fn import_rustdoc(project_id: i32, json: String) -> Result<ImportResult, ServerFnError> {
+    use crate::{
+        importers::{apply_import, rustdoc::RustdocCrate},
+        server::AppState,
+    };
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let crate_doc = match RustdocCrate::parse(&json) {
+        Ok(crate_doc) => crate_doc,
+        Err(e) => return Err(ServerFnError::ServerError(format!("{e:#}"))),
+    };
+    let documents = crate_doc.documents();
+
+    tracing::info!(
+        "Importing {} documents from {} {} for project {}",
+        documents.len(),
+        crate_doc.name(),
+        crate_doc.version().unwrap_or("unversioned"),
+        project_id
+    );
+
+    let key = |source: &str| crate_doc.document_key(source);
+    match apply_import(&state.conn, project_id, documents, key).await {
+        Ok(summary) => Ok(summary.into()),
+        Err(e) => {
+            tracing::error!("Failed to import rustdoc JSON: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to save imported documents".to_string(),
+            ))
+        }
+    }
+}
+
+/// Upload Markdown, HTML and text files, or `.zip` and `.tar.gz` archives of
+/// them, as documents named by their relative path. The form has a
+/// `project_id` field and the `files`. Uploading a file again updates the
+/// document from the earlier upload.
+#[server(input = MultipartFormData)]
+pub async fn upload_documents(data: MultipartData) -> Result<ImportResult, ServerFnError> {
+    use crate::{
+        importers::{apply_import, upload::UploadReader},
+        server::AppState,
+        CONFIG,
+    };
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+    let limits = CONFIG.upload_limits().clone();
+    let (fields, files) = read_form(data, limits.max_total_bytes).await?;
+
+    let Some(project_id) = fields
+        .get("project_id")
+        .and_then(|id| id.parse::<i32>().ok())
+    else {
+        return Err(ServerFnError::ServerError(
+            "The project is missing".to_string(),
+        ));
+    };
+    if files.is_empty() {
+        return Err(ServerFnError::ServerError(
+            "Choose files to upload".to_string(),
+        ));
+    }
+
+    let read = move || {
+        let mut reader = UploadReader::new(limits);
+        for (file_name, bytes) in files {
+            reader.add(&file_name, bytes)?;
+        }
+        anyhow::Ok(reader.finish())
+    };
+    let mut upload = match tokio::task::spawn_blocking(read).await {
+        Ok(Ok(upload)) => upload,
+        Ok(Err(e)) => return Err(ServerFnError::ServerError(format!("{e:#}"))),
+        Err(e) => {
+            tracing::error!("Failed to read uploaded files: {:?}", e);
+            return Err(ServerFnError::ServerError(
+                "Failed to read uploaded files".to_string(),
+            ));
+        }
+    };
+
+    let documents = std::mem::take(&mut upload.documents);
+    tracing::info!(
+        "Importing {} uploaded documents for project {}",
+        documents.len(),
+        project_id
+    );
+
+    let key = |source: &str| upload.document_key(source);
+    match apply_import(&state.conn, project_id, documents, key).await {
+        Ok(mut summary) => {
+            summary.skipped = std::mem::take(&mut upload.skipped);
+            Ok(summary.into())
+        }
+        Err(e) => {
+            tracing::error!("Failed to import uploaded documents: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to save imported documents".to_string(),
+            ))
+        }
+    }
+}
+
+/// Import a Confluence HTML space export or a Notion Markdown and CSV export.
+/// The form has the `project_id`, the `format`, `confluence` or `notion`, and
+/// the zip archive as `file`. Pages from an earlier export are matched by their
+/// id, links between the pages point to their documents.
+#[server(input = MultipartFormData)]
+pub async fn import_export(data: MultipartData) -> Result<ImportResult, ServerFnError> {
+    use crate::{
+        importers::{
+            apply_linked_import, confluence::ConfluenceExport, notion::NotionExport,
+            upload::extract_zip,
+        },
+        server::AppState,
+        CONFIG,
+    };
+    use leptos::use_context;
+
+    let Some(state) = use_context::<AppState>() else {
+        return Err(ServerFnError::ServerError(
+            "Failed to get app state".to_string(),
+        ));
+    };
+
+    let limits = CONFIG.upload_limits().clone();
+    let (fields, mut files) = read_form(data, limits.max_total_bytes).await?;
+
+    let Some(project_id) = fields
+        .get("project_id")
+        .and_then(|id| id.parse::<i32>().ok())
+    else {
+        return Err(ServerFnError::ServerError(
+            "The project is missing".to_string(),
+        ));
+    };
+    let format = fields.get("format").cloned().unwrap_or_default();
+    let Some((_, archive)) = files.pop() else {
+        return Err(ServerFnError::ServerError(
+            "Choose an export to import".to_string(),
+        ));
+    };
+
+    // The export identifies the documents of earlier imports, so it is kept
+    // for the key once its documents are taken
+    type Key = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+    let read = move || {
+        let extracted = extract_zip(archive, &limits)?;
+        let files = extracted.files;
+        let (documents, mut skipped, key): (_, _, Key) = match format.as_str() {
+            "confluence" => {
+                let mut export = ConfluenceExport::read(files)?;
+                let documents = std::mem::take(&mut export.documents);
+                let skipped = std::mem::take(&mut export.skipped);
+                (
+                    documents,
+                    skipped,
+                    Box::new(move |source| export.document_key(source)),
+                )
+            }
+            "notion" => {
+                let mut export = NotionExport::read(files, &limits)?;
+                let documents = std::mem::take(&mut export.documents);
+                let skipped = std::mem::take(&mut export.skipped);
+                (
+                    documents,
+                    skipped,
+                    Box::new(move |source| export.document_key(source)),
+                )
+            }
+            format => anyhow::bail!("Unknown export format {format}"),
+        };
+        skipped.extend(extracted.skipped);
+        anyhow::Ok((documents, skipped, key))
+    };
+    let (documents, skipped, key) = match tokio::task::spawn_blocking(read).await {
+        Ok(Ok(export)) => export,
+        Ok(Err(e)) => return Err(ServerFnError::ServerError(format!("{e:#}"))),
+        Err(e) => {
+            tracing::error!("Failed to read export: {:?}", e);
+            return Err(ServerFnError::ServerError(
+                "Failed to read export".to_string(),
+            ));
+        }
+    };
+
+    tracing::info!(
+        "Importing {} pages from a {} export for project {}",
+        documents.len(),
+        fields.get("format").map_or("", String::as_str),
+        project_id
+    );
+
+    match apply_linked_import(&state.conn, project_id, documents, key).await {
+        Ok(mut summary) => {
+            summary.skipped = skipped;
+            Ok(summary.into())
+        }
+        Err(e) => {
+            tracing::error!("Failed to import export: {:?}", e);
+            Err(ServerFnError::ServerError(
+                "Failed to save imported documents".to_string(),
+            ))
+        }
+    }
+}
+
+/// The text fields and the files of a multipart form. Reading stops with an
+/// error once the files are larger than `max_bytes`, archives can only be
+/// checked for their extracted size once they are complete.
+#[cfg(feature = "ssr")]
+async fn read_form(
+    data: MultipartData,
+    max_bytes: usize,
+) -> Result<
+    (
+        std::collections::HashMap<String, String>,
+        Vec<(String, Vec<u8>)>,
+    ),
+    ServerFnError,
+> {
+    use crate::web_crawler::limits::format_bytes;
+
+    let Some(mut data) = data.into_inner() else {
+        return Err(ServerFnError::ServerError("Invalid upload".to_string()));
+    };
+
+    let mut fields = std::collections::HashMap::new();
+    let mut files = vec![];
+    let mut total_bytes = 0;
+
+    loop {
+        let mut field = match data.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Err(ServerFnError::ServerError(format!("Invalid upload: {e}"))),
+        };
+        let name = field.name().unwrap_or_default().to_owned();
+
+        let Some(file_name) = field.file_name().map(str::to_owned) else {
+            let value = field.text().await.unwrap_or_default();
+            fields.insert(name, value.trim().to_owned());
+            continue;
+        };
+
+        let mut bytes = vec![];
+        loop {
+            let chunk = match field.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => return Err(ServerFnError::ServerError(format!("Invalid upload: {e}"))),
+            };
+            total_bytes += chunk.len();
+            if total_bytes > max_bytes {
+                return Err(ServerFnError::ServerError(format!(
+                    "The upload is larger than {}",
+                    format_bytes(max_bytes)
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        // Browsers send an empty file when none was chosen
+        if !file_name.is_empty() {
+            files.push((file_name, bytes));
+        }
+    }
+
+    Ok((fields, files))
+}
@@ -1,12 +1,18 @@
 mod admin;
 mod app_data;
 mod chat;
+mod crawl_credentials;
+mod crawl_schedules;
 mod documents;
+mod imports;
 pub mod models;
 mod projects;
 
 pub use admin::*;
 pub use app_data::*;
 pub use chat::*;
+pub use crawl_credentials::*;
+pub use crawl_schedules::*;
 pub use documents::*;
+pub use imports::*;
 pub use projects::*;
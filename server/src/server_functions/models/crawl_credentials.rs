@@ -0,0 +1,10 @@
+use leptos::server_fn::serde::{Deserialize, Serialize};
+
+/// Credentials saved for a host, without the secrets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlCredentialsData {
+    pub id: i32,
+    pub host: String,
+    pub kinds: Vec<String>,
+    pub updated_at: String,
+}
@@ -0,0 +1,15 @@
+use leptos::server_fn::serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlPreviewPage {
+    pub url: String,
+    pub title: String,
+    pub sample: String,
+}
+
+/// A line of the crawl preview stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CrawlPreviewOutput {
+    Message(String),
+    Page(CrawlPreviewPage),
+}
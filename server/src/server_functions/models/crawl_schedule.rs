@@ -0,0 +1,33 @@
+use leptos::server_fn::serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlScheduleData {
+    pub id: i32,
+    pub url: String,
+    pub max_depth: Option<i32>,
+    pub cron: String,
+    pub finalize: bool,
+    pub enabled: bool,
+    pub last_run_at: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlRunData {
+    pub id: i32,
+    pub url: String,
+    pub status: String,
+    pub summary: Option<String>,
+    pub error: Option<String>,
+    pub version: Option<i32>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// The outcome of a url of a crawl run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlReportPageData {
+    pub url: String,
+    pub depth: i32,
+    pub outcome: String,
+    pub detail: Option<String>,
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use leptos::server_fn::serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,4 +8,44 @@ pub struct Document {
     pub name: String,
     pub content: String,
     pub source: Option<String>,
+    pub toc: Vec<TocEntry>,
+    pub tags: Vec<String>,
+    /// Whether the user may edit the document
+    pub writable: bool,
+}
+
+/// The Markdown of a document, for editing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSource {
+    pub id: i32,
+    pub name: String,
+    pub content: String,
+}
+
+/// A heading of a document, `id` is the anchor of the heading in the content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TocEntry {
+    pub level: u8,
+    pub id: String,
+    pub title: String,
+}
+
+/// Metadata of a document from its YAML front matter. Keys other than the
+/// known ones are kept as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Position of the document in the document list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i64>,
+    /// Other names the document is known by, e.g. old paths
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(flatten)]
+    pub custom: BTreeMap<String, serde_json::Value>,
 }
@@ -0,0 +1,25 @@
+use leptos::server_fn::serde::{Deserialize, Serialize};
+
+/// What an import changed in a project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportResult {
+    pub summary: String,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    /// Files that could not be imported, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<crate::importers::ImportSummary> for ImportResult {
+    fn from(summary: crate::importers::ImportSummary) -> Self {
+        Self {
+            summary: summary.to_string(),
+            added: summary.added,
+            changed: summary.changed,
+            removed: summary.removed,
+            skipped: summary.skipped,
+        }
+    }
+}
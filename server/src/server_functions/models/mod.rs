@@ -1,11 +1,19 @@
 mod app_data;
+mod crawl_credentials;
+mod crawl_preview;
+mod crawl_schedule;
 mod documents;
+mod import;
 mod permission;
 mod project;
 mod user;
 
 pub use app_data::*;
+pub use crawl_credentials::*;
+pub use crawl_preview::*;
+pub use crawl_schedule::*;
 pub use documents::*;
+pub use import::*;
 pub use permission::*;
 pub use project::*;
 pub use user::*;
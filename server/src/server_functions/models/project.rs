@@ -1,5 +1,7 @@
 use leptos::server_fn::serde::{Deserialize, Serialize};
 
+use super::DocumentMetadata;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProjectData {
     pub id: i32,
@@ -8,6 +10,7 @@ pub struct ProjectData {
     pub version: i32,
     pub versions: Vec<i32>,
     pub finalized: bool,
+    pub folders: Vec<ProjectFolder>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -22,4 +25,162 @@ pub struct ProjectDocument {
     pub id: i32,
     pub name: String,
     pub is_embedded: bool,
+    pub metadata: DocumentMetadata,
+    pub folder_id: Option<i32>,
+    pub position: i32,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectFolder {
+    pub id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub position: i32,
+}
+
+/// A document or folder in the document tree of a project version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TreeNode {
+    Folder(i32),
+    Document(i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentTree {
+    Folder(ProjectFolder, Vec<DocumentTree>),
+    Document(ProjectDocument),
+}
+
+impl DocumentTree {
+    /// Build the tree from the folders and documents of a project version.
+    /// Children are sorted by their position, folders first on equal positions,
+    /// and nodes in a folder that does not exist are put at the top level.
+    pub fn build(folders: &[ProjectFolder], documents: &[ProjectDocument]) -> Vec<Self> {
+        let parent = |id: Option<i32>| id.filter(|id| folders.iter().any(|f| f.id == *id));
+        Self::children(None, folders, documents, &parent)
+    }
+
+    fn children(
+        parent_id: Option<i32>,
+        folders: &[ProjectFolder],
+        documents: &[ProjectDocument],
+        parent: &dyn Fn(Option<i32>) -> Option<i32>,
+    ) -> Vec<Self> {
+        let mut children = folders
+            .iter()
+            .filter(|folder| parent(folder.parent_id) == parent_id)
+            .map(|folder| {
+                let children = Self::children(Some(folder.id), folders, documents, parent);
+                (folder.position, Self::Folder(folder.to_owned(), children))
+            })
+            .chain(
+                documents
+                    .iter()
+                    .filter(|doc| parent(doc.folder_id) == parent_id)
+                    .map(|doc| (doc.position, Self::Document(doc.to_owned()))),
+            )
+            .collect::<Vec<_>>();
+        children.sort_by_key(|(position, _)| *position);
+
+        children.into_iter().map(|(_, node)| node).collect()
+    }
+
+    /// The tree without the folders that have no documents in them
+    pub fn without_empty_folders(tree: Vec<Self>) -> Vec<Self> {
+        tree.into_iter()
+            .filter_map(|item| match item {
+                Self::Folder(folder, children) => {
+                    let children = Self::without_empty_folders(children);
+                    (!children.is_empty()).then_some(Self::Folder(folder, children))
+                }
+                document => Some(document),
+            })
+            .collect()
+    }
+
+    pub fn node(&self) -> TreeNode {
+        match self {
+            Self::Folder(folder, _) => TreeNode::Folder(folder.id),
+            Self::Document(doc) => TreeNode::Document(doc.id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(id: i32, parent_id: Option<i32>, position: i32) -> ProjectFolder {
+        ProjectFolder {
+            id,
+            name: format!("Folder {id}"),
+            parent_id,
+            position,
+        }
+    }
+
+    fn document(id: i32, folder_id: Option<i32>, position: i32) -> ProjectDocument {
+        ProjectDocument {
+            id,
+            name: format!("Document {id}"),
+            is_embedded: false,
+            metadata: DocumentMetadata::default(),
+            folder_id,
+            position,
+            tags: Vec::new(),
+        }
+    }
+
+    fn nodes(tree: &[DocumentTree]) -> Vec<(TreeNode, Vec<TreeNode>)> {
+        tree.iter()
+            .map(|item| match item {
+                DocumentTree::Folder(_, children) => (
+                    item.node(),
+                    children.iter().map(DocumentTree::node).collect(),
+                ),
+                DocumentTree::Document(_) => (item.node(), vec![]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_document_tree() {
+        let folders = vec![folder(1, None, 1), folder(2, Some(1), 0)];
+        let documents = vec![
+            document(1, None, 2),
+            document(2, Some(1), 1),
+            document(3, None, 0),
+            // The folder does not exist
+            document(4, Some(9), 1),
+        ];
+
+        let tree = DocumentTree::build(&folders, &documents);
+
+        assert_eq!(
+            nodes(&tree),
+            vec![
+                (TreeNode::Document(3), vec![]),
+                (
+                    TreeNode::Folder(1),
+                    vec![TreeNode::Folder(2), TreeNode::Document(2)]
+                ),
+                (TreeNode::Document(4), vec![]),
+                (TreeNode::Document(1), vec![]),
+            ]
+        );
+
+        let folders = vec![
+            folder(1, None, 0),
+            folder(2, Some(1), 0),
+            folder(3, None, 1),
+        ];
+        let documents = vec![document(1, Some(3), 0)];
+        let tree = DocumentTree::without_empty_folders(DocumentTree::build(&folders, &documents));
+
+        assert_eq!(
+            nodes(&tree),
+            vec![(TreeNode::Folder(3), vec![TreeNode::Document(1)])]
+        );
+    }
 }
@@ -45,6 +45,7 @@ pub async fn get_projects() -> Result<Vec<ProjectData>, ServerFnError> {
         version: -1,
         versions: Vec::new(),
         finalized: false,
+        folders: Vec::new(),
     })
     .collect::<Vec<_>>();
 
@@ -56,7 +57,7 @@ pub async fn get_project_data(
     project_id: i32,
     version: Option<i32>,
 ) -> Result<(ProjectData, Vec<ProjectDocument>), ServerFnError> {
-    use crate::{database::Repo, server::AppState};
+    use crate::{database::Repo, server::AppState, server_functions::models::ProjectFolder};
     use leptos::use_context;
 
     let Some(state) = use_context::<AppState>() else {
@@ -79,10 +80,10 @@ pub async fn get_project_data(
     let project_version = match version {
         Some(version) => {
             db.projects_versions()
-                .find_by_pks(project_id, version)
+                .find_by_pks(project_id, version, None)
                 .await
         }
-        None => db.projects_versions().find_latest(project_id).await,
+        None => db.projects_versions().find_latest(project_id, None).await,
     };
 
     let Ok(Some(project_version)) = project_version else {
@@ -103,6 +104,17 @@ pub async fn get_project_data(
         ));
     };
 
+    let Ok(folders) = db
+        .document_folders()
+        .all(project_id, project_version.version, None)
+        .await
+    else {
+        tracing::error!("Failed to get folders");
+        return Err(ServerFnError::ServerError(
+            "Failed to get folders".to_string(),
+        ));
+    };
+
     let Ok(versions) = db.projects_versions().all(project_id).await else {
         tracing::error!("Failed to get versions");
         return Err(ServerFnError::ServerError(
@@ -117,16 +129,36 @@ pub async fn get_project_data(
         version: project_version.version,
         versions: versions.iter().map(|v| v.version).collect(),
         finalized: project_version.finalized,
+        folders: folders
+            .into_iter()
+            .map(|f| ProjectFolder {
+                id: f.id,
+                name: f.name,
+                parent_id: f.parent_id,
+                position: f.position,
+            })
+            .collect(),
     };
 
-    let documents = documents
+    let mut documents = documents
         .iter()
         .map(|d| ProjectDocument {
             id: d.id,
             name: d.name.to_owned(),
             is_embedded: d.is_embedded,
+            metadata: d
+                .metadata
+                .to_owned()
+                .and_then(|metadata| serde_json::from_value(metadata).ok())
+                .unwrap_or_default(),
+            folder_id: d.folder_id,
+            position: d.position,
+            tags: serde_json::from_value(d.tags.to_owned()).unwrap_or_default(),
         })
         .collect::<Vec<_>>();
+    // Documents with an order in their front matter come first among documents with the same
+    // position in the tree
+    documents.sort_by_key(|d| (d.metadata.order.is_none(), d.metadata.order));
 
     Ok((project, documents))
 }
@@ -196,9 +228,10 @@ pub async fn finalize_project_version(
 ) -> Result<TextStream, ServerFnError> {
     use crate::{
         database::Repo,
-        langchain::{LLMProvider, Langchain},
+        langchain::{embed_and_finalize, FinalizeProgress},
         server::AppState,
     };
+    use futures_util::{pin_mut, StreamExt};
     use http::header::{HeaderName, HeaderValue};
     use leptos::{expect_context, use_context};
     use leptos_axum::ResponseOptions;
@@ -216,7 +249,7 @@ pub async fn finalize_project_version(
 
     let db = state.conn;
 
-    let mut documents = match db.documents().find_unembedded(project_id, version).await {
+    let documents = match db.documents().find_unembedded(project_id, version).await {
         Ok(documents) => documents,
         Err(e) => {
             tracing::error!("Failed to fetch documents: {:?}", e);
@@ -233,35 +266,21 @@ pub async fn finalize_project_version(
     }
 
     let stream = async_stream::stream! {
-        let lc = Langchain::new(LLMProvider::OpenAI);
+        let progress = embed_and_finalize(&db, project_id, version, documents);
+        pin_mut!(progress);
 
-        let mut had_error = false;
-        while let Some(document) = documents.pop() {
-            yield Ok::<_, ServerFnError>(format!("Embedding Document: {}", document.name));
-            let embeddings = match lc.embed(&document.content).await {
-                Ok(embeddings) => embeddings,
-                Err(e) => {
-                    tracing::error!("Failed to embed document: {:?}", e);
-                    yield Ok::<_, ServerFnError>(format!("Failed to embed document: {:?}", e));
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                    had_error = true;
-                    break;
+        while let Some(progress) = progress.next().await {
+            match progress {
+                Ok(FinalizeProgress::Embedding(name)) => {
+                    yield Ok::<_, ServerFnError>(format!("Embedding Document: {}", name));
                 }
-            };
-
-            match db.embeddings().create_many(document.id, embeddings).await {
-                Ok(_) => (),
+                Ok(FinalizeProgress::Finalized) => (),
                 Err(e) => {
-                    tracing::error!("Failed to save embeddings: {:?}", e);
-                    yield Ok::<_, ServerFnError>(format!("Failed to save embeddings: {:?}", e));
-                    had_error = true;
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    tracing::error!("Failed to finalize project version: {:?}", e);
+                    yield Ok::<_, ServerFnError>(format!("{:#}", e));
+                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                 }
-            };
-        }
-
-        if !had_error {
-            db.projects_versions().finalize(project_id, version).await.ok();
+            }
         }
     };
 
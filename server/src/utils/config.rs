@@ -1,3 +1,14 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    importers::upload::UploadLimits, outbound::FetchPolicy, utils::encryption::Cipher,
+    web_crawler::limits::CrawlLimits,
+};
+
 pub struct Config {
     rust_env: String,
     my_log: String,
@@ -9,6 +20,11 @@ pub struct Config {
     keycloak_client_uuid: String,
     keycloak_client_secret: String,
     openai_api_key: String,
+    crawl_limits: CrawlLimits,
+    fetch_policy: FetchPolicy,
+    credentials_cipher: Option<Cipher>,
+    git_import_root: Option<PathBuf>,
+    upload_limits: UploadLimits,
 }
 
 impl Default for Config {
@@ -26,10 +42,97 @@ impl Default for Config {
             keycloak_client_secret: std::env::var("KEYCLOAK_CLIENT_SECRET")
                 .expect("KEYCLOAK_CLIENT_SECRET must be set"),
             openai_api_key: std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set"),
+            crawl_limits: crawl_limits_from_env(),
+            fetch_policy: fetch_policy_from_env(),
+            credentials_cipher: credentials_cipher_from_env(),
+            git_import_root: std::env::var("GIT_IMPORT_ROOT")
+                .ok()
+                .filter(|root| !root.trim().is_empty())
+                .map(PathBuf::from),
+            upload_limits: upload_limits_from_env(),
         }
     }
 }
 
+/// Cipher for crawl credentials from the base64 encoded 32 byte `CRAWL_CREDENTIALS_KEY`.
+/// Credentials can not be saved without it.
+fn credentials_cipher_from_env() -> Option<Cipher> {
+    let key = std::env::var("CRAWL_CREDENTIALS_KEY").ok()?;
+    if key.trim().is_empty() {
+        return None;
+    }
+
+    match Cipher::from_base64(&key) {
+        Ok(cipher) => Some(cipher),
+        Err(e) => panic!("CRAWL_CREDENTIALS_KEY is invalid: {e}"),
+    }
+}
+
+/// Hosts, addresses and CIDR ranges from the comma separated `OUTBOUND_ALLOWLIST`
+/// that may be fetched even though they are not public
+fn fetch_policy_from_env() -> FetchPolicy {
+    let allowlist = std::env::var("OUTBOUND_ALLOWLIST").unwrap_or_default();
+    let entries = allowlist
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty());
+
+    match FetchPolicy::new(entries) {
+        Ok(policy) => policy,
+        Err(e) => panic!("OUTBOUND_ALLOWLIST is invalid: {e}"),
+    }
+}
+
+/// A number from the variable, if it is set
+fn var<T: FromStr>(name: &str) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => panic!("{name} must be a number"),
+    }
+}
+
+/// Crawl limits from the `CRAWL_*` variables, falling back to the defaults.
+/// Setting a maximum to 0 removes the limit.
+fn crawl_limits_from_env() -> CrawlLimits {
+    fn max<T: FromStr + Default + PartialEq>(name: &str, default: Option<T>) -> Option<T> {
+        match var::<T>(name) {
+            Some(value) if value == T::default() => None,
+            Some(value) => Some(value),
+            None => default,
+        }
+    }
+
+    let defaults = CrawlLimits::default();
+
+    CrawlLimits {
+        max_pages: max("CRAWL_MAX_PAGES", defaults.max_pages),
+        max_page_bytes: max("CRAWL_MAX_PAGE_BYTES", defaults.max_page_bytes),
+        max_total_bytes: max("CRAWL_MAX_TOTAL_BYTES", defaults.max_total_bytes),
+        max_duration: max(
+            "CRAWL_MAX_DURATION_SECS",
+            defaults.max_duration.map(|d| d.as_secs()),
+        )
+        .map(Duration::from_secs),
+        connect_timeout: var("CRAWL_CONNECT_TIMEOUT_SECS")
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.connect_timeout),
+        read_timeout: var("CRAWL_READ_TIMEOUT_SECS")
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.read_timeout),
+    }
+}
+
+/// Upload limits from the `UPLOAD_*` variables, falling back to the defaults
+fn upload_limits_from_env() -> UploadLimits {
+    let defaults = UploadLimits::default();
+
+    UploadLimits {
+        max_file_bytes: var("UPLOAD_MAX_FILE_BYTES").unwrap_or(defaults.max_file_bytes),
+        max_total_bytes: var("UPLOAD_MAX_TOTAL_BYTES").unwrap_or(defaults.max_total_bytes),
+        max_files: var("UPLOAD_MAX_FILES").unwrap_or(defaults.max_files),
+    }
+}
+
 impl Config {
     pub fn rust_env(&self) -> &str {
         &self.rust_env
@@ -66,4 +169,25 @@ impl Config {
     pub fn openai_api_key(&self) -> &str {
         &self.openai_api_key
     }
+
+    pub fn crawl_limits(&self) -> &CrawlLimits {
+        &self.crawl_limits
+    }
+
+    pub fn fetch_policy(&self) -> &FetchPolicy {
+        &self.fetch_policy
+    }
+
+    pub fn credentials_cipher(&self) -> Option<&Cipher> {
+        self.credentials_cipher.as_ref()
+    }
+
+    /// Directory that git repositories may be imported from, if importing is enabled
+    pub fn git_import_root(&self) -> Option<&Path> {
+        self.git_import_root.as_deref()
+    }
+
+    pub fn upload_limits(&self) -> &UploadLimits {
+        &self.upload_limits
+    }
 }
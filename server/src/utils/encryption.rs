@@ -0,0 +1,103 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Length of the AES-GCM nonce stored in front of each ciphertext
+const NONCE_LENGTH: usize = 12;
+
+/// Encrypts secrets stored in the database with AES-256-GCM. Each value gets a
+/// random nonce, which is stored with the ciphertext as base64.
+#[derive(Clone)]
+pub struct Cipher(Aes256Gcm);
+
+impl Cipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+    }
+
+    /// Create a cipher from a base64 encoded 32 byte key
+    pub fn from_base64(key: &str) -> Result<Self> {
+        let key = STANDARD
+            .decode(key.trim())
+            .context("The key is not valid base64")?;
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| anyhow!("The key must be 32 bytes long"))?;
+
+        Ok(Self::new(&key))
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Failed to encrypt secret"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+
+        Ok(STANDARD.encode(data))
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<Vec<u8>> {
+        let data = STANDARD
+            .decode(encrypted)
+            .context("The secret is not valid base64")?;
+
+        if data.len() < NONCE_LENGTH {
+            bail!("The secret is too short");
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+
+        self.0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt secret, was the key changed?"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher = Cipher::new(&[7; 32]);
+
+        let encrypted = cipher.encrypt(b"hunter2").unwrap();
+
+        assert!(!encrypted.contains("hunter2"));
+        assert_ne!(encrypted, cipher.encrypt(b"hunter2").unwrap());
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"hunter2");
+    }
+
+    #[test]
+    fn test_decrypt_with_other_key_fails() {
+        let encrypted = Cipher::new(&[7; 32]).encrypt(b"hunter2").unwrap();
+
+        assert!(Cipher::new(&[8; 32]).decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_tampered_secret_fails() {
+        let cipher = Cipher::new(&[7; 32]);
+        let mut data = STANDARD
+            .decode(cipher.encrypt(b"hunter2").unwrap())
+            .unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+
+        assert!(cipher.decrypt(&STANDARD.encode(data)).is_err());
+        assert!(cipher.decrypt("c2hvcnQ=").is_err());
+    }
+
+    #[test]
+    fn test_key_from_base64() {
+        assert!(Cipher::from_base64(&STANDARD.encode([1; 32])).is_ok());
+        assert!(Cipher::from_base64(&STANDARD.encode([1; 16])).is_err());
+        assert!(Cipher::from_base64("not base64!").is_err());
+    }
+}
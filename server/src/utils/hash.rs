@@ -0,0 +1,6 @@
+use sha2::{Digest, Sha256};
+
+/// Hex encoded SHA-256 hash of a document's content, used to detect changed pages
+pub fn content_hash(content: impl AsRef<[u8]>) -> String {
+    format!("{:x}", Sha256::digest(content.as_ref()))
+}
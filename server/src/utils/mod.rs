@@ -2,7 +2,9 @@ pub mod claims;
 pub mod config;
 // pub mod context_data;
 pub mod cookies;
+pub mod encryption;
 pub mod extractor;
+pub mod hash;
 pub mod info;
 // pub mod tera_testers;
 // pub mod traits;
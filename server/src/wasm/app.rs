@@ -12,6 +12,18 @@ pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
 
+    // The inline scripts that hydrate the page are allowed by their nonce
+    #[cfg(feature = "ssr")]
+    if let (Some(nonce), Some(response)) = (
+        nonce::use_nonce(),
+        use_context::<leptos_axum::ResponseOptions>(),
+    ) {
+        response.insert_header(
+            http::header::CONTENT_SECURITY_POLICY,
+            crate::middleware::headers::content_security_policy(Some(&nonce)),
+        );
+    }
+
     view! {
         <Stylesheet id="leptos" href="/pkg/magicdocs.css"/>
         <Link rel="shortcut icon" type_="image/ico" href="/favicon.ico"/>
@@ -4,10 +4,11 @@ use futures_util::StreamExt;
 use leptos::{ev::KeyboardEvent, *};
 
 use crate::{
-    markdown::Markdown,
+    markdown::IncrementalMarkdown,
     server_functions::{
         chat,
         models::{AppData, ProjectData},
+        render_answer,
     },
     wasm::{
         components::icons::*,
@@ -40,15 +41,20 @@ pub fn ChatPanel(project: ProjectData) -> impl IntoView {
             let user_message = ChatMessage {
                 user: ChatUser::User(user.given_name.clone()),
                 content: create_rw_signal(prompt),
+                rendered: create_rw_signal(None),
                 key: uuid::Uuid::new_v4().as_u128(),
             };
 
             let bot_message = ChatMessage {
                 user: ChatUser::Assistant,
                 content: create_rw_signal(String::new()),
+                rendered: create_rw_signal(None),
                 key: uuid::Uuid::new_v4().as_u128(),
             };
 
+            let content = bot_message.content;
+            let rendered = bot_message.rendered;
+
             set_messages.update(|messages| {
                 messages.push_front(user_message);
                 messages.push_front(bot_message);
@@ -69,6 +75,14 @@ pub fn ChatPanel(project: ProjectData) -> impl IntoView {
                     }
                 });
             }
+
+            // Diagrams are only rendered on the server
+            let answer = content.get_untracked();
+            if answer.contains("```mermaid") {
+                if let Ok(html) = render_answer(answer).await {
+                    rendered.set(Some(html));
+                }
+            }
         });
     };
 
@@ -134,18 +148,30 @@ pub fn ChatPanel(project: ProjectData) -> impl IntoView {
                                             <div class="message-body">{child.content}</div>
                                         </>
                                     },
-                                    ChatUser::Assistant => view! {
-                                        <>
-                                            <div class="message-header">
-                                                <BotIcon size="20px" />
-                                                <span class="message-user">"Magic Docs"</span>
-                                            </div>
-                                            <div
-                                                class="message-body"
-                                                inner_html=move || Markdown::to_html(&child.content.get())
-                                            ></div>
-                                        </>
-                                    },
+                                    ChatUser::Assistant => {
+                                        // Streamed answers are rendered a block at a time
+                                        let markdown = store_value(IncrementalMarkdown::default());
+                                        view! {
+                                            <>
+                                                <div class="message-header">
+                                                    <BotIcon size="20px" />
+                                                    <span class="message-user">"Magic Docs"</span>
+                                                </div>
+                                                <div
+                                                    class="message-body"
+                                                    inner_html=move || {
+                                                        if let Some(html) = child.rendered.get() {
+                                                            return html;
+                                                        }
+                                                        let content = child.content.get();
+                                                        markdown
+                                                            .try_update_value(|markdown| markdown.render(&content))
+                                                            .unwrap_or_default()
+                                                    }
+                                                ></div>
+                                            </>
+                                        }
+                                    }
                                 }}
                             </div>
                         </For>
@@ -0,0 +1,240 @@
+use leptos::*;
+
+use crate::{
+    server_functions::{delete_crawl_credentials, get_crawl_credentials, save_crawl_credentials},
+    wasm::components::icons::TrashIcon,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum AuthKind {
+    None,
+    Basic,
+    Bearer,
+}
+
+/// Splits each non empty line at the first separator, e.g. `Name: value`
+fn parse_pairs(text: &str, separator: char) -> Result<Vec<(String, String)>, String> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once(separator) {
+            Some((name, value)) => Ok((name.trim().to_owned(), value.trim().to_owned())),
+            None => Err(format!(
+                "Expected \"name{separator}value\" but got \"{line}\""
+            )),
+        })
+        .collect()
+}
+
+#[component]
+pub fn CrawlCredentials(project_id: i32) -> impl IntoView {
+    let data = create_resource(
+        move || project_id,
+        move |project_id| async move { get_crawl_credentials(project_id).await },
+    );
+
+    let url = create_rw_signal(String::new());
+    let auth_kind = create_rw_signal(AuthKind::None);
+    let username = create_rw_signal(String::new());
+    let password = create_rw_signal(String::new());
+    let token = create_rw_signal(String::new());
+    let headers = create_rw_signal(String::new());
+    let cookies = create_rw_signal(String::new());
+    let error = create_rw_signal(None::<String>);
+
+    let on_save = move |_| {
+        let parsed = parse_pairs(&headers.get(), ':')
+            .and_then(|headers| Ok((headers, parse_pairs(&cookies.get(), '=')?)));
+        let (header_pairs, cookie_pairs) = match parsed {
+            Ok(pairs) => pairs,
+            Err(e) => {
+                error.set(Some(e));
+                return;
+            }
+        };
+
+        let (basic_auth, bearer_token) = match auth_kind.get() {
+            AuthKind::None => (None, None),
+            AuthKind::Basic => (Some((username.get(), password.get())), None),
+            AuthKind::Bearer => (None, Some(token.get())),
+        };
+
+        spawn_local(async move {
+            match save_crawl_credentials(
+                project_id,
+                url.get_untracked(),
+                header_pairs,
+                cookie_pairs,
+                basic_auth,
+                bearer_token,
+            )
+            .await
+            {
+                Ok(_) => {
+                    error.set(None);
+                    password.set(String::new());
+                    token.set(String::new());
+                    data.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => error.set(Some(e)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let on_delete = move |credentials_id: i32| {
+        let confirm =
+            window().confirm_with_message("Are you sure you want to delete these credentials?");
+        if !matches!(confirm, Ok(true)) {
+            return;
+        }
+
+        spawn_local(async move {
+            if delete_crawl_credentials(project_id, credentials_id)
+                .await
+                .is_err()
+            {
+                logging::log!("Failed to delete crawl credentials");
+            }
+            data.refetch();
+        });
+    };
+
+    view! {
+        <h2>"Crawl Credentials"</h2>
+        <p class="text-gray-300 mb-4">
+            "Credentials for private websites. They are stored encrypted, only sent to their host "
+            "and used by every crawl of it, including scheduled re-crawls."
+        </p>
+
+        <Transition fallback=|| ()>
+            {move || match data.get() {
+                Some(Ok(credentials)) => Some(view! {
+                    <table class="data-table mb-4">
+                        <thead>
+                            <tr>
+                                <th>"Host"</th>
+                                <th>"Credentials"</th>
+                                <th>"Updated"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            <For
+                                each=move || credentials.to_owned()
+                                key=|credentials| (credentials.id, credentials.updated_at.to_owned())
+                                let:credentials
+                            >
+                                <tr>
+                                    <td>{credentials.host}</td>
+                                    <td>{credentials.kinds.join(", ")}</td>
+                                    <td>{credentials.updated_at}</td>
+                                    <td>
+                                        <button
+                                            on:click=move |_| on_delete(credentials.id)
+                                            class="w-5 h-5 opacity-60 text-red-500"
+                                        >
+                                            <TrashIcon />
+                                        </button>
+                                    </td>
+                                </tr>
+                            </For>
+                        </tbody>
+                    </table>
+                }),
+                _ => None,
+            }}
+        </Transition>
+
+        <div class="flex flex-col gap-4 mb-8 max-w-2xl">
+            <div class="flex flex-col gap-1">
+                <label for="credentials-url">"Website"</label>
+                <input
+                    type="text"
+                    id="credentials-url"
+                    placeholder="https://docs.example.com"
+                    on:input=move |e| url.set(event_target_value(&e))
+                />
+            </div>
+
+            <div class="flex flex-col gap-1">
+                <label for="credentials-auth">"Authentication"</label>
+                <select
+                    id="credentials-auth"
+                    class="w-fit"
+                    on:change=move |e| auth_kind.set(match event_target_value(&e).as_str() {
+                        "basic" => AuthKind::Basic,
+                        "bearer" => AuthKind::Bearer,
+                        _ => AuthKind::None,
+                    })
+                >
+                    <option value="none">"None"</option>
+                    <option value="basic">"Basic auth"</option>
+                    <option value="bearer">"Bearer token"</option>
+                </select>
+            </div>
+
+            {move || match auth_kind.get() {
+                AuthKind::Basic => Some(view! {
+                    <div class="flex gap-4">
+                        <div class="flex flex-col gap-1">
+                            <label for="credentials-username">"Username"</label>
+                            <input
+                                type="text"
+                                id="credentials-username"
+                                autocomplete="off"
+                                on:input=move |e| username.set(event_target_value(&e))
+                            />
+                        </div>
+                        <div class="flex flex-col gap-1">
+                            <label for="credentials-password">"Password"</label>
+                            <input
+                                type="password"
+                                id="credentials-password"
+                                autocomplete="new-password"
+                                prop:value=move || password.get()
+                                on:input=move |e| password.set(event_target_value(&e))
+                            />
+                        </div>
+                    </div>
+                }.into_view()),
+                AuthKind::Bearer => Some(view! {
+                    <div class="flex flex-col gap-1">
+                        <label for="credentials-token">"Token"</label>
+                        <input
+                            type="password"
+                            id="credentials-token"
+                            autocomplete="off"
+                            prop:value=move || token.get()
+                            on:input=move |e| token.set(event_target_value(&e))
+                        />
+                    </div>
+                }.into_view()),
+                AuthKind::None => None,
+            }}
+
+            <div class="flex flex-col gap-1">
+                <label for="credentials-cookies">"Cookies, one name=value per line"</label>
+                <textarea
+                    id="credentials-cookies"
+                    rows="2"
+                    placeholder="session=..."
+                    on:input=move |e| cookies.set(event_target_value(&e))
+                />
+            </div>
+
+            <div class="flex flex-col gap-1">
+                <label for="credentials-headers">"Headers, one Name: value per line"</label>
+                <textarea
+                    id="credentials-headers"
+                    rows="2"
+                    placeholder="X-Api-Key: ..."
+                    on:input=move |e| headers.set(event_target_value(&e))
+                />
+            </div>
+
+            <button on:click=on_save class="btn-primary cursor-pointer w-fit">"Save Credentials"</button>
+
+            {move || error.get().map(|e| view! { <p class="text-red-500">{e}</p> })}
+        </div>
+    }
+}
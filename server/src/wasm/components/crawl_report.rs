@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use leptos::*;
+
+use crate::server_functions::get_crawl_report;
+
+/// Table of what happened to every url of a crawl run, filterable by outcome
+/// and url. Shows the latest run of the project if no run is given.
+#[component]
+pub fn CrawlReport(project_id: i32, run_id: Option<i32>) -> impl IntoView {
+    let report = create_resource(
+        move || (project_id, run_id),
+        move |(project_id, run_id)| async move { get_crawl_report(project_id, run_id).await },
+    );
+
+    let outcome_filter = create_rw_signal(String::new());
+    let url_filter = create_rw_signal(String::new());
+
+    view! {
+        <Transition fallback=|| ()>
+            {move || match report.get() {
+                Some(Ok(pages)) if pages.is_empty() => Some(view! {
+                    <p class="text-gray-300">"No crawl report available."</p>
+                }.into_view()),
+                Some(Ok(pages)) => {
+                    let mut counts = BTreeMap::<String, usize>::new();
+                    for page in pages.iter() {
+                        *counts.entry(page.outcome.to_owned()).or_default() += 1;
+                    }
+                    let total = pages.len();
+
+                    let filtered = move || {
+                        let outcome = outcome_filter.get();
+                        let search = url_filter.get().to_lowercase();
+
+                        pages
+                            .iter()
+                            .filter(|page| outcome.is_empty() || page.outcome == outcome)
+                            .filter(|page| page.url.to_lowercase().contains(&search))
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    };
+
+                    Some(view! {
+                        <div class="flex flex-wrap gap-4 mb-4">
+                            <select on:change=move |e| outcome_filter.set(event_target_value(&e))>
+                                <option value="">{format!("All outcomes ({total})")}</option>
+                                {counts.into_iter().map(|(outcome, count)| view! {
+                                    <option value=outcome.to_owned()>{format!("{outcome} ({count})")}</option>
+                                }).collect_view()}
+                            </select>
+                            <input
+                                type="text"
+                                placeholder="Filter by URL"
+                                on:input=move |e| url_filter.set(event_target_value(&e))
+                            />
+                        </div>
+
+                        <table class="data-table">
+                            <thead>
+                                <tr>
+                                    <th>"URL"</th>
+                                    <th>"Depth"</th>
+                                    <th>"Outcome"</th>
+                                    <th>"Detail"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                <For
+                                    each=filtered
+                                    key=|page| (page.url.to_owned(), page.outcome.to_owned())
+                                    let:page
+                                >
+                                    <tr>
+                                        <td>{page.url}</td>
+                                        <td>{page.depth}</td>
+                                        <td>{page.outcome}</td>
+                                        <td>{page.detail.unwrap_or_default()}</td>
+                                    </tr>
+                                </For>
+                            </tbody>
+                        </table>
+                    }.into_view())
+                }
+                Some(Err(e)) => Some(view! { <p class="text-red-500">{e.to_string()}</p> }.into_view()),
+                None => None,
+            }}
+        </Transition>
+    }
+}
@@ -0,0 +1,221 @@
+use leptos::*;
+
+use crate::{
+    server_functions::{
+        create_crawl_schedule, delete_crawl_schedule, get_crawl_schedules,
+        set_crawl_schedule_enabled,
+    },
+    wasm::components::{icons::TrashIcon, CrawlReport},
+};
+
+#[component]
+pub fn CrawlSchedules(project_id: i32) -> impl IntoView {
+    let data = create_resource(
+        move || project_id,
+        move |project_id| async move { get_crawl_schedules(project_id).await },
+    );
+
+    let url = create_rw_signal(String::new());
+    let max_depth = create_rw_signal(String::new());
+    let cron = create_rw_signal(String::new());
+    let finalize = create_rw_signal(false);
+    let error = create_rw_signal(None::<String>);
+    let report_run = create_rw_signal(None::<i32>);
+
+    let on_create = move |_| {
+        let max_depth = max_depth.get().parse::<i32>().ok();
+
+        spawn_local(async move {
+            match create_crawl_schedule(
+                project_id,
+                url.get(),
+                max_depth,
+                cron.get(),
+                finalize.get(),
+            )
+            .await
+            {
+                Ok(_) => {
+                    error.set(None);
+                    data.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => error.set(Some(e)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let on_toggle = move |schedule_id: i32, enabled: bool| {
+        spawn_local(async move {
+            if set_crawl_schedule_enabled(project_id, schedule_id, enabled)
+                .await
+                .is_err()
+            {
+                logging::log!("Failed to update crawl schedule");
+            }
+            data.refetch();
+        });
+    };
+
+    let on_delete = move |schedule_id: i32| {
+        let confirm =
+            window().confirm_with_message("Are you sure you want to delete this crawl schedule?");
+        if !matches!(confirm, Ok(true)) {
+            return;
+        }
+
+        spawn_local(async move {
+            if delete_crawl_schedule(project_id, schedule_id)
+                .await
+                .is_err()
+            {
+                logging::log!("Failed to delete crawl schedule");
+            }
+            data.refetch();
+        });
+    };
+
+    view! {
+        <h2>"Crawl Schedules"</h2>
+        <p class="text-gray-300 mb-4">
+            "Websites that are re-crawled automatically. Schedules use cron syntax, e.g. "
+            <code>"0 3 * * 1"</code>" for every Monday at 03:00 UTC."
+        </p>
+
+        <Transition fallback=|| ()>
+            {move || match data.get() {
+                Some(Ok((schedules, runs))) => Some(view! {
+                    <table class="data-table mb-4">
+                        <thead>
+                            <tr>
+                                <th>"URL"</th>
+                                <th>"Max Depth"</th>
+                                <th>"Schedule"</th>
+                                <th>"Finalize"</th>
+                                <th>"Last Run"</th>
+                                <th>"Enabled"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            <For
+                                each=move || schedules.to_owned()
+                                key=|schedule| (schedule.id, schedule.enabled)
+                                let:schedule
+                            >
+                                <tr>
+                                    <td>{schedule.url}</td>
+                                    <td>{schedule.max_depth.map(|d| d.to_string()).unwrap_or("-".to_owned())}</td>
+                                    <td><code>{schedule.cron}</code></td>
+                                    <td>{if schedule.finalize {"Yes"} else {"No"}}</td>
+                                    <td>{schedule.last_run_at.unwrap_or("Never".to_owned())}</td>
+                                    <td>
+                                        <input
+                                            type="checkbox"
+                                            checked=schedule.enabled
+                                            on:change=move |e| on_toggle(schedule.id, event_target_checked(&e))
+                                        />
+                                    </td>
+                                    <td>
+                                        <button
+                                            on:click=move |_| on_delete(schedule.id)
+                                            class="w-5 h-5 opacity-60 text-red-500"
+                                        >
+                                            <TrashIcon />
+                                        </button>
+                                    </td>
+                                </tr>
+                            </For>
+                        </tbody>
+                    </table>
+
+                    <div class="flex flex-wrap items-end gap-4 mb-8">
+                        <div class="flex flex-col gap-1">
+                            <label for="schedule-url">"URL"</label>
+                            <input
+                                type="text"
+                                id="schedule-url"
+                                placeholder="https://example.com"
+                                on:input=move |e| url.set(event_target_value(&e))
+                            />
+                        </div>
+                        <div class="flex flex-col gap-1">
+                            <label for="schedule-depth">"Max Depth"</label>
+                            <input
+                                type="number"
+                                id="schedule-depth"
+                                min="0"
+                                max="99"
+                                class="max-w-24"
+                                on:input=move |e| max_depth.set(event_target_value(&e))
+                            />
+                        </div>
+                        <div class="flex flex-col gap-1">
+                            <label for="schedule-cron">"Schedule"</label>
+                            <input
+                                type="text"
+                                id="schedule-cron"
+                                placeholder="0 3 * * 1"
+                                on:input=move |e| cron.set(event_target_value(&e))
+                            />
+                        </div>
+                        <div class="flex gap-1 items-center mb-3">
+                            <input
+                                type="checkbox"
+                                id="schedule-finalize"
+                                on:change=move |e| finalize.set(event_target_checked(&e))
+                            />
+                            <label for="schedule-finalize" class="mt-0">"Finalize new versions"</label>
+                        </div>
+                        <button on:click=on_create class="btn-primary cursor-pointer">"Add Schedule"</button>
+                    </div>
+
+                    {move || error.get().map(|e| view! { <p class="text-red-500 mb-8">{e}</p> })}
+
+                    <h3>"Run History"</h3>
+                    <table class="data-table">
+                        <thead>
+                            <tr>
+                                <th>"Started"</th>
+                                <th>"URL"</th>
+                                <th>"Status"</th>
+                                <th>"Result"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            <For
+                                each=move || runs.to_owned()
+                                key=|run| run.id
+                                let:run
+                            >
+                                <tr>
+                                    <td>{run.started_at}</td>
+                                    <td>{run.url}</td>
+                                    <td>{run.status}</td>
+                                    <td>{run.error.or(run.summary).unwrap_or_default()}</td>
+                                    <td>
+                                        <button
+                                            on:click=move |_| report_run.update(|id| {
+                                                *id = if *id == Some(run.id) { None } else { Some(run.id) }
+                                            })
+                                            class="underline"
+                                        >
+                                            {move || if report_run.get() == Some(run.id) {"Hide Report"} else {"Report"}}
+                                        </button>
+                                    </td>
+                                </tr>
+                            </For>
+                        </tbody>
+                    </table>
+
+                    {move || report_run.get().map(|run_id| view! {
+                        <h3 class="mt-8">"Crawl Report"</h3>
+                        <CrawlReport project_id=project_id run_id=Some(run_id) />
+                    })}
+                }),
+                _ => None,
+            }}
+        </Transition>
+    }
+}
@@ -1,11 +1,28 @@
+use std::collections::HashSet;
+
 use futures_util::StreamExt;
 use leptos::*;
 
 use crate::{
-    server_functions::crawl_website,
-    wasm::{components::icons::SpinnerIcon, types::ProjectDataResource},
+    server_functions::{
+        crawl_website, import_crawled_pages,
+        models::{CrawlPreviewOutput, CrawlPreviewPage},
+        preview_crawl, recrawl_website,
+    },
+    wasm::{
+        components::{icons::SpinnerIcon, CrawlReport},
+        types::ProjectDataResource,
+    },
 };
 
+#[derive(Clone, Copy, PartialEq)]
+enum CrawlMode {
+    Crawl,
+    Recrawl,
+    Preview,
+    Import,
+}
+
 #[component]
 pub fn Crawler(project_id: i32) -> impl IntoView {
     let project_data =
@@ -16,13 +33,22 @@ pub fn Crawler(project_id: i32) -> impl IntoView {
     let max_depth = create_rw_signal(String::new());
     let crawling = create_rw_signal(false);
     let output_message = create_rw_signal(String::new());
+    let summary = create_rw_signal(None::<String>);
+    let preview = create_rw_signal(None::<Vec<CrawlPreviewPage>>);
+    let selected = create_rw_signal(HashSet::<String>::new());
+    let show_report = create_rw_signal(false);
 
-    let on_start = move |_| {
+    let start = move |mode: CrawlMode| {
         if crawling.get() {
             return;
         }
 
         crawling.set(true);
+        summary.set(None);
+        show_report.set(false);
+        if mode != CrawlMode::Import {
+            preview.set(None);
+        }
 
         let url = url.get();
         let use_max_depth = max_depth_enabled.get();
@@ -32,17 +58,71 @@ pub fn Crawler(project_id: i32) -> impl IntoView {
         };
 
         spawn_local(async move {
-            let mut stream = crawl_website(project_id, url, max_depth)
-                .await
-                .expect("Failed to start crawl stream")
-                .into_inner();
+            let stream = match mode {
+                CrawlMode::Crawl => crawl_website(project_id, url, max_depth).await,
+                CrawlMode::Recrawl => recrawl_website(project_id, url, max_depth).await,
+                CrawlMode::Preview => preview_crawl(project_id, url, max_depth).await,
+                CrawlMode::Import => {
+                    let pages = selected.get_untracked().into_iter().collect();
+                    import_crawled_pages(project_id, url, max_depth, pages).await
+                }
+            };
+
+            let mut stream = match stream {
+                Ok(stream) => stream.into_inner(),
+                Err(ServerFnError::ServerError(e)) => {
+                    summary.set(Some(e));
+                    crawling.set(false);
+                    return;
+                }
+                Err(e) => {
+                    summary.set(Some(e.to_string()));
+                    crawling.set(false);
+                    return;
+                }
+            };
+
+            if mode == CrawlMode::Preview {
+                // The preview is sent as one JSON document per line
+                let mut pages = vec![];
+                let mut buffer = String::new();
+
+                while let Some(Ok(chunk)) = stream.next().await {
+                    buffer.push_str(&chunk);
+
+                    while let Some((line, rest)) = buffer.split_once('\n') {
+                        match serde_json::from_str(line) {
+                            Ok(CrawlPreviewOutput::Message(msg)) => output_message.set(msg),
+                            Ok(CrawlPreviewOutput::Page(page)) => {
+                                output_message.set(format!("Found {}", page.url));
+                                pages.push(page);
+                            }
+                            Err(e) => logging::log!("Failed to read crawl preview: {:?}", e),
+                        }
+                        buffer = rest.to_owned();
+                    }
+                }
+
+                pages.sort_by(|a, b| a.url.cmp(&b.url));
+                selected.set(pages.iter().map(|page| page.url.to_owned()).collect());
+                preview.set(Some(pages));
+                crawling.set(false);
+                return;
+            }
 
             while let Some(Ok(output)) = stream.next().await {
                 output_message.set(output);
             }
 
+            match mode {
+                CrawlMode::Recrawl => summary.set(Some(output_message.get_untracked())),
+                CrawlMode::Import => preview.set(None),
+                _ => (),
+            }
+
             project_data.refetch();
 
+            show_report.set(true);
             crawling.set(false);
         });
     };
@@ -87,13 +167,31 @@ pub fn Crawler(project_id: i32) -> impl IntoView {
                 />
             </div>
 
-            <input
-                on:click=on_start
-                type="submit"
-                value="Start"
-                disabled=move || crawling.get()
-                class="btn-primary cursor-pointer w-fit"
-            />
+            <div class="flex gap-4">
+                <input
+                    on:click=move |_| start(CrawlMode::Crawl)
+                    type="submit"
+                    value="Start"
+                    disabled=move || crawling.get()
+                    class="btn-primary cursor-pointer w-fit"
+                />
+                <input
+                    on:click=move |_| start(CrawlMode::Preview)
+                    type="submit"
+                    value="Preview"
+                    title="Find the pages without importing them, then pick the ones to import"
+                    disabled=move || crawling.get()
+                    class="btn-primary cursor-pointer w-fit"
+                />
+                <input
+                    on:click=move |_| start(CrawlMode::Recrawl)
+                    type="submit"
+                    value="Re-crawl"
+                    title="Update the pages previously crawled from this URL"
+                    disabled=move || crawling.get()
+                    class="btn-primary cursor-pointer w-fit"
+                />
+            </div>
 
             <div
                 id="crawler-output-container"
@@ -104,6 +202,93 @@ pub fn Crawler(project_id: i32) -> impl IntoView {
                 </div>
                 <p class="text-gray-200">{ move || output_message.get() }</p>
             </div>
+
+            {move || summary.get().map(|summary| view! {
+                <p class="text-gray-200">{summary}</p>
+            })}
+
+            {move || preview.get().map(|pages| view! {
+                <CrawlPreview pages=pages selected=selected />
+                <input
+                    on:click=move |_| start(CrawlMode::Import)
+                    type="submit"
+                    value=move || format!("Import {} Pages", selected.get().len())
+                    disabled=move || crawling.get() || selected.get().is_empty()
+                    class="btn-primary cursor-pointer w-fit"
+                />
+            })}
+
+            {move || show_report.get().then(|| view! {
+                <h3>"Crawl Report"</h3>
+                <CrawlReport project_id=project_id run_id=None />
+            })}
+        </div>
+    }
+}
+
+/// Checklist of the pages found by a crawl preview, nested by url path.
+/// Checking a page checks the pages below it as well.
+#[component]
+fn CrawlPreview(
+    pages: Vec<CrawlPreviewPage>,
+    selected: RwSignal<HashSet<String>>,
+) -> impl IntoView {
+    let all_urls: Vec<String> = pages.iter().map(|page| page.url.to_owned()).collect();
+    let segments = |url: &str| url.trim_end_matches('/').matches('/').count();
+    let min_depth = all_urls.iter().map(|url| segments(url)).min().unwrap_or(0);
+
+    let toggle = {
+        let all_urls = all_urls.clone();
+        move |url: String, checked: bool| {
+            let prefix = format!("{}/", url.trim_end_matches('/'));
+            selected.update(|selected| {
+                for other in all_urls.iter() {
+                    if *other == url || other.starts_with(&prefix) {
+                        match checked {
+                            true => selected.insert(other.to_owned()),
+                            false => selected.remove(other),
+                        };
+                    }
+                }
+            });
+        }
+    };
+
+    let select_all = move |_| selected.set(all_urls.iter().cloned().collect());
+
+    view! {
+        <div class="flex gap-4">
+            <button on:click=select_all class="underline">"Select all"</button>
+            <button on:click=move |_| selected.set(HashSet::new()) class="underline">"Select none"</button>
         </div>
+
+        <ul id="crawl-preview">
+            {pages.into_iter().map(|page| {
+                let depth = segments(&page.url) - min_depth;
+                let url = page.url.to_owned();
+                let checked = {
+                    let url = url.to_owned();
+                    move || selected.get().contains(&url)
+                };
+                let toggle = toggle.clone();
+
+                view! {
+                    <li style=format!("padding-left: {}rem", depth * 2)>
+                        <label class="flex gap-2 items-start">
+                            <input
+                                type="checkbox"
+                                prop:checked=checked
+                                on:change=move |e| toggle(url.to_owned(), event_target_checked(&e))
+                            />
+                            <div class="flex flex-col">
+                                <span>{page.title}</span>
+                                <span class="text-sm text-gray-400">{page.url}</span>
+                                <span class="text-sm text-gray-300">{page.sample}</span>
+                            </div>
+                        </label>
+                    </li>
+                }
+            }).collect_view()}
+        </ul>
     }
 }
@@ -1,32 +1,56 @@
 use std::ops::Not as _;
 
+use ev::MouseEvent;
 use leptos::*;
 
-use crate::{server_functions::models::Document, wasm::components::FinalizeButton};
+use crate::{
+    server_functions::models::Document,
+    wasm::components::{icons::PenIcon, DocumentTags, FinalizeButton},
+};
 
 #[component]
-pub fn DocumentContent(document: Document, finalized: bool) -> impl IntoView {
+pub fn DocumentContent(
+    document: Document,
+    finalized: bool,
+    /// Whether the document is in the latest version of the project and the user may write to it
+    editable: bool,
+    #[prop(into)] on_edit: Callback<MouseEvent>,
+) -> impl IntoView {
     view! {
         <div class="flex justify-between items-center">
             <div class="flex gap-2 items-center">
                 <h1>{document.name}</h1>
-                // {% if is_latest_version and permissions.write %}
-                <div
-                    // hx-get="/projects/{{ project.id }}/v/{{ project_version }}/documents/{{ document.id }}/edit"
-                    // hx-target="#doc-parent"
-                    // hx-swap="innerHTML"
-                    class="w-6 h-6 cursor-pointer"
-                >
-                // {% include 'icons/pen.svg' %}
-                </div>
-                // {% endif %}
+                {editable.then(|| view! {
+                    <button
+                        id="document-edit"
+                        title="Edit document"
+                        class="w-6 h-6 cursor-pointer"
+                        on:click=move |e| on_edit.call(e)
+                    >
+                        <PenIcon />
+                    </button>
+                })}
             </div>
 
             {move || finalized.not().then(|| {
                 view! { <FinalizeButton /> }
             })}
         </div>
+        <DocumentTags document_id=document.id tags=document.tags />
         <hr class="my-8"/>
-        <div id="document-content" inner_html=document.content></div>
+        <div class="flex gap-8">
+            <div id="document-content" class="flex-1 min-w-0" inner_html=document.content></div>
+            {document.toc.is_empty().not().then(|| view! {
+                <nav id="document-outline" class="sticky top-20 w-52 shrink-0 self-start">
+                    <ul>
+                        {document.toc.into_iter().map(|entry| view! {
+                            <li style=format!("padding-left: {}rem", entry.level.saturating_sub(1))>
+                                <a href=format!("#{}", entry.id)>{entry.title}</a>
+                            </li>
+                        }).collect_view()}
+                    </ul>
+                </nav>
+            })}
+        </div>
     }
 }
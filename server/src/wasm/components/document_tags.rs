@@ -0,0 +1,113 @@
+use ev::SubmitEvent;
+use leptos::*;
+use leptos_router::use_navigate;
+
+use crate::{
+    server_functions::set_document_tags,
+    wasm::types::{ProjectDataContext, ProjectDataResource, VersionNotice},
+};
+
+/// The tags of a document, which can be added and removed on the latest
+/// version of the project
+#[component]
+pub fn DocumentTags(document_id: i32, tags: Vec<String>) -> impl IntoView {
+    let Some((project, _)) = use_context::<ProjectDataContext>() else {
+        return view! { <></> }.into_view();
+    };
+    let project_data = use_context::<ProjectDataResource>();
+    let notice = use_context::<VersionNotice>();
+    let navigate = use_navigate();
+
+    let editable = project.versions.iter().max() == Some(&project.version);
+    let (project_id, version) = (project.id, project.version);
+
+    let tags = create_rw_signal(tags);
+    let input_ref = create_node_ref::<html::Input>();
+
+    let save = Callback::new(move |new_tags: Vec<String>| {
+        let navigate = navigate.clone();
+        spawn_local(async move {
+            let Ok(new_version) =
+                set_document_tags(project_id, version, document_id, new_tags.to_owned()).await
+            else {
+                logging::log!("Failed to set document tags");
+                return;
+            };
+
+            tags.set(new_tags);
+            if let Some(project_data) = project_data {
+                project_data.refetch();
+            }
+            // Tagging a document of a finalized version creates a new version
+            if let Some(new_version) = new_version {
+                if let Some(notice) = notice {
+                    notice.0.set(Some(format!(
+                        "Version {version} is finalized, the tags were saved in the new version {new_version}"
+                    )));
+                }
+                navigate(
+                    &format!(
+                        "/projects/{project_id}/documents/{document_id}?version={new_version}"
+                    ),
+                    Default::default(),
+                );
+            }
+        });
+    });
+
+    let on_submit = move |e: SubmitEvent| {
+        e.prevent_default();
+
+        let input = input_ref.get().expect("input_ref not found");
+        let value = input.value().trim().to_owned();
+        if value.is_empty() || tags.with_untracked(|tags| tags.contains(&value)) {
+            return;
+        }
+        input.set_value("");
+
+        let mut new_tags = tags.get_untracked();
+        new_tags.push(value);
+        save.call(new_tags);
+    };
+
+    view! {
+        <div id="document-tags" class="flex flex-wrap gap-2 items-center mt-2">
+            <For
+                each=move || tags.get()
+                key=|tag| tag.to_owned()
+                let:tag
+            >
+                <span class="px-2 py-0.5 rounded-full bg-[#303030] text-sm">
+                    {tag.to_owned()}
+                    {editable.then(|| view! {
+                        <button
+                            class="ml-1 opacity-60 hover:opacity-100"
+                            on:click=move |_| {
+                                let new_tags = tags
+                                    .get_untracked()
+                                    .into_iter()
+                                    .filter(|t| *t != tag)
+                                    .collect();
+                                save.call(new_tags);
+                            }
+                        >
+                            "×"
+                        </button>
+                    })}
+                </span>
+            </For>
+
+            {editable.then(|| view! {
+                <form on:submit=on_submit>
+                    <input
+                        ref=input_ref
+                        type="text"
+                        placeholder="Add tag"
+                        class="w-28 px-2 py-0.5 text-sm bg-transparent border-1 border-base rounded-full"
+                    />
+                </form>
+            })}
+        </div>
+    }
+    .into_view()
+}
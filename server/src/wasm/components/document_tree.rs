@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use ev::{DragEvent, MouseEvent};
+use leptos::*;
+use leptos_router::*;
+
+use crate::{
+    server_functions::{
+        create_folder, delete_folder,
+        models::{DocumentTree, ProjectData, ProjectDocument, TreeNode},
+        move_tree_node,
+    },
+    wasm::{
+        components::icons::{TrashIcon, TriangleDownIcon},
+        types::{ProjectDataResource, VersionQuery},
+    },
+};
+
+/// The state shared by the nodes of the tree
+#[derive(Clone, Copy)]
+struct TreeState {
+    editable: bool,
+    /// Nodes are not moved while the list is filtered, as their position is not known
+    draggable: bool,
+    version: Signal<Option<i32>>,
+    collapsed: RwSignal<HashSet<i32>>,
+    dragged: RwSignal<Option<TreeNode>>,
+    on_move: Callback<(Option<i32>, usize)>,
+    on_delete: Callback<(MouseEvent, i32)>,
+    on_delete_folder: Callback<(MouseEvent, i32)>,
+}
+
+/// The documents of a project version as a collapsible tree of folders.
+/// Nodes are moved by dragging them onto a folder, or onto a document to put
+/// them before it. The documents can be filtered by tag.
+#[component]
+pub fn DocumentTreeView(
+    project: ProjectData,
+    documents: Vec<ProjectDocument>,
+    editable: bool,
+    #[prop(into)] on_delete: Callback<(MouseEvent, i32)>,
+) -> impl IntoView {
+    let project_data =
+        use_context::<ProjectDataResource>().expect("ProjectDataResource context not found");
+    let query = use_query::<VersionQuery>();
+
+    let version = Signal::derive(move || {
+        with!(|query| query
+            .as_ref()
+            .map(|query| query.version())
+            .unwrap_or_default())
+    });
+
+    let (project_id, project_version) = (project.id, project.version);
+    let collapsed = create_rw_signal(HashSet::new());
+    let dragged = create_rw_signal(None::<TreeNode>);
+
+    let on_move = Callback::new(move |(parent_id, position): (Option<i32>, usize)| {
+        let Some(node) = dragged.get_untracked() else {
+            return;
+        };
+        dragged.set(None);
+
+        spawn_local(async move {
+            let Ok(_) =
+                move_tree_node(project_id, project_version, node, parent_id, position).await
+            else {
+                logging::log!("Failed to move {:?}", node);
+                return;
+            };
+            project_data.refetch();
+        });
+    });
+
+    let on_delete_folder = Callback::new(move |(e, folder_id): (MouseEvent, i32)| {
+        e.stop_propagation();
+
+        let confirm = window().confirm_with_message(
+            "Are you sure you want to delete this folder? Its documents are kept.",
+        );
+        match confirm {
+            Ok(true) => (),
+            _ => return,
+        }
+
+        spawn_local(async move {
+            let Ok(_) = delete_folder(project_id, project_version, folder_id).await else {
+                logging::log!("Failed to delete folder");
+                return;
+            };
+            project_data.refetch();
+        });
+    });
+
+    let on_new_folder = move |_| {
+        let Ok(Some(name)) = window().prompt_with_message("Folder name") else {
+            return;
+        };
+        if name.trim().is_empty() {
+            return;
+        }
+
+        spawn_local(async move {
+            let Ok(_) = create_folder(project_id, project_version, name, None).await else {
+                logging::log!("Failed to create folder");
+                return;
+            };
+            project_data.refetch();
+        });
+    };
+
+    let tag = create_rw_signal(None::<String>);
+    let mut tags = documents
+        .iter()
+        .flat_map(|doc| doc.tags.to_owned())
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags.dedup();
+
+    let folders = project.folders;
+    let root_len = DocumentTree::build(&folders, &documents).len();
+
+    let nodes = move || {
+        let state = TreeState {
+            editable,
+            draggable: editable && tag.with(Option::is_none),
+            version,
+            collapsed,
+            dragged,
+            on_move,
+            on_delete,
+            on_delete_folder,
+        };
+
+        tag.with(|tag| match tag {
+            Some(tag) => {
+                let documents = documents
+                    .iter()
+                    .filter(|doc| doc.tags.contains(tag))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let tree = DocumentTree::build(&folders, &documents);
+                tree_nodes(
+                    DocumentTree::without_empty_folders(tree),
+                    None,
+                    String::new(),
+                    state,
+                )
+            }
+            None => tree_nodes(
+                DocumentTree::build(&folders, &documents),
+                None,
+                String::new(),
+                state,
+            ),
+        })
+    };
+
+    view! {
+        {(!tags.is_empty()).then(|| view! {
+            <li class="p-2">
+                <select
+                    id="document-tag-filter"
+                    class="w-full p-1 bg-[#181818] text-white text-sm"
+                    on:change=move |e| {
+                        let value = event_target_value(&e);
+                        tag.set((!value.is_empty()).then_some(value));
+                    }
+                >
+                    <option value="">"All tags"</option>
+                    {tags.into_iter().map(|value| view! {
+                        <option value=value.to_owned()>{value}</option>
+                    }).collect_view()}
+                </select>
+            </li>
+        })}
+
+        {nodes}
+
+        // Dropping below the tree moves the node to the end of the top level
+        {editable.then(|| view! {
+            <li
+                class="flex justify-center p-2"
+                on:dragover=|e: DragEvent| e.prevent_default()
+                on:drop=move |e: DragEvent| {
+                    e.prevent_default();
+                    on_move.call((None, root_len));
+                }
+            >
+                <button class="text-sm opacity-60 hover:opacity-100" on:click=on_new_folder>
+                    "New Folder"
+                </button>
+            </li>
+        })}
+    }
+}
+
+/// The nodes of a folder, `path` is the names of the folder and its ancestors
+/// joined by `/`
+fn tree_nodes(
+    tree: Vec<DocumentTree>,
+    parent_id: Option<i32>,
+    path: String,
+    state: TreeState,
+) -> View {
+    tree.into_iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let node = item.node();
+
+            let on_dragstart = move |e: DragEvent| {
+                e.stop_propagation();
+                state.dragged.set(Some(node));
+            };
+
+            match item {
+                DocumentTree::Folder(folder, children) => {
+                    let id = folder.id;
+                    let is_collapsed = move || state.collapsed.with(|c| c.contains(&id));
+                    let toggle = move |_| {
+                        state.collapsed.update(|c| {
+                            if !c.remove(&id) {
+                                c.insert(id);
+                            }
+                        })
+                    };
+                    let children_len = children.len();
+                    let path = match path.is_empty() {
+                        true => folder.name.to_owned(),
+                        false => format!("{path}/{}", folder.name),
+                    };
+
+                    view! {
+                        <li>
+                            <div
+                                class="relative group flex items-center gap-1 cursor-pointer"
+                                draggable=state.draggable.to_string()
+                                on:dragstart=on_dragstart
+                                on:dragover=|e: DragEvent| e.prevent_default()
+                                on:drop=move |e: DragEvent| {
+                                    e.prevent_default();
+                                    e.stop_propagation();
+                                    state.on_move.call((Some(id), children_len));
+                                }
+                                on:click=toggle
+                            >
+                                <span class=move || match is_collapsed() {
+                                    true => "w-4 h-4 shrink-0 transition-transform -rotate-90",
+                                    false => "w-4 h-4 shrink-0 transition-transform",
+                                }>
+                                    <TriangleDownIcon />
+                                </span>
+                                <p>{folder.name}</p>
+
+                                {state.editable.then(|| view! {
+                                    <button
+                                        on:click=move |e| state.on_delete_folder.call((e, id))
+                                        class="absolute right-2 opacity-0 group-hover:opacity-60 w-5 h-5 text-red-500"
+                                    >
+                                        <TrashIcon />
+                                    </button>
+                                })}
+                            </div>
+                            <ul class="pl-3" class:hidden=is_collapsed>
+                                {tree_nodes(children, Some(id), path, state)}
+                            </ul>
+                        </li>
+                    }
+                    .into_view()
+                }
+                DocumentTree::Document(doc) => {
+                    let id = doc.id;
+                    // Imported documents are named after their path, which the folders already show
+                    let name = match doc.name.strip_prefix(&format!("{path}/")) {
+                        Some(name) if !path.is_empty() => name.to_owned(),
+                        _ => doc.name,
+                    };
+
+                    view! {
+                        <li
+                            class="relative group flex items-center"
+                            draggable=state.draggable.to_string()
+                            on:dragstart=on_dragstart
+                            on:dragover=|e: DragEvent| e.prevent_default()
+                            on:drop=move |e: DragEvent| {
+                                e.prevent_default();
+                                e.stop_propagation();
+                                state.on_move.call((parent_id, position));
+                            }
+                        >
+                            <A
+                                href=move || match state.version.get() {
+                                    Some(v) => format!("documents/{}?version={}", id, v),
+                                    None => format!("documents/{}", id),
+                                }
+                                class="w-full"
+                                exact=true
+                            >
+                                <p>{doc.metadata.title.unwrap_or(name)}</p>
+                            </A>
+
+                            // TODO if permissions.delete
+                            {state.editable.then(|| view! {
+                                <button
+                                    on:click=move |e| state.on_delete.call((e, id))
+                                    class="absolute right-2 opacity-0 group-hover:opacity-60 w-5 h-5 text-red-500"
+                                >
+                                    <TrashIcon />
+                                </button>
+                            })}
+                        </li>
+                    }
+                    .into_view()
+                }
+            }
+        })
+        .collect_view()
+}
@@ -1,13 +1,15 @@
-use ev::SubmitEvent;
+use ev::{MouseEvent, SubmitEvent};
 use leptos::*;
 
 #[component]
-/// Route component which redirects to the latest version of a project.
+/// Form for the title and content of a document, filled with the current values of `title` and
+/// `content`
 pub fn Editor<F>(
     header: &'static str,
     title: RwSignal<String>,
     content: RwSignal<String>,
     on_submit: F,
+    #[prop(optional, into)] on_cancel: Option<Callback<MouseEvent>>,
 ) -> impl IntoView
 where
     F: Fn(SubmitEvent) + 'static,
@@ -19,6 +21,7 @@ where
                 <label for="document-title" class="text-white mt-8 ml-1 font-bold">Title</label>
                 <input
                     on:input=move |e| title.set(event_target_value(&e))
+                    prop:value=title
                     id="document-title"
                     type="text"
                     class="w-full p-4 mb-8 text-white bg-[#181818] border-2 focus:ring-0 focus:border-pink-500/50"
@@ -29,17 +32,24 @@ where
 
                 <label for="textarea" class="text-white mt-8 ml-1 font-bold">Content</label>
 
-                <div id="editor-wrapper">
+                <div id="editor-wrapper" data-replicated-value=content>
                     <textarea
                         on:input=move |e| content.set(event_target_value(&e))
-                        onInput="this.parentNode.dataset.replicatedValue = this.value"
+                        prop:value=content
                         name="content"
                         id="textarea"
                         class="min-h-80"
                     ></textarea>
                 </div>
 
-                <button type="submit" class="absolute top-8 right-8 btn-primary w-fit bg-green-900">Save</button>
+                <div class="absolute top-8 right-8 flex gap-4">
+                    {on_cancel.map(|on_cancel| view! {
+                        <button type="button" class="btn-primary w-fit" on:click=move |e| on_cancel.call(e)>
+                            "Cancel"
+                        </button>
+                    })}
+                    <button type="submit" class="btn-primary w-fit bg-green-900">Save</button>
+                </div>
             </form>
         </div>
     }
@@ -0,0 +1,112 @@
+use ev::SubmitEvent;
+use leptos::*;
+use web_sys::FormData;
+
+use crate::{
+    server_functions::{import_export, models::ImportResult},
+    wasm::{
+        components::{icons::SpinnerIcon, ImportResults},
+        types::ProjectDataResource,
+    },
+};
+
+/// The platforms whose exports can be imported
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportKind {
+    /// An HTML export of a Confluence space
+    Confluence,
+    /// A Markdown and CSV export of Notion pages
+    Notion,
+}
+
+impl ExportKind {
+    fn format(&self) -> &'static str {
+        match self {
+            Self::Confluence => "confluence",
+            Self::Notion => "notion",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Confluence => "Confluence space export, as HTML in a .zip archive",
+            Self::Notion => "Notion export, as Markdown & CSV in a .zip archive",
+        }
+    }
+}
+
+/// Form uploading the export of another documentation platform
+#[component]
+pub fn ExportImport(project_id: i32, kind: ExportKind) -> impl IntoView {
+    let project_data =
+        use_context::<ProjectDataResource>().expect("ProjectDataResource context not found");
+
+    let form_ref = create_node_ref::<html::Form>();
+    let importing = create_rw_signal(false);
+    let error = create_rw_signal(None::<String>);
+    let result = create_rw_signal(None::<ImportResult>);
+
+    let on_submit = move |e: SubmitEvent| {
+        e.prevent_default();
+        if importing.get() {
+            return;
+        }
+
+        let Some(form_data) = form_ref
+            .get()
+            .and_then(|form| FormData::new_with_form(&form).ok())
+        else {
+            return;
+        };
+
+        importing.set(true);
+        error.set(None);
+        result.set(None);
+
+        spawn_local(async move {
+            match import_export(form_data.into()).await {
+                Ok(import) => {
+                    result.set(Some(import));
+                    project_data.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => error.set(Some(e)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            importing.set(false);
+        });
+    };
+
+    view! {
+        <form
+            node_ref=form_ref
+            on:submit=on_submit
+            class="flex-grow flex flex-col gap-4 p-8"
+        >
+            <input type="hidden" name="project_id" value=project_id />
+            <input type="hidden" name="format" value=kind.format() />
+            <div class="flex flex-col gap-1">
+                <label for="export-file" class="text-lg">{kind.label()}</label>
+                <input type="file" name="file" id="export-file" accept=".zip" />
+            </div>
+
+            <input
+                type="submit"
+                value="Import"
+                disabled=move || importing.get()
+                class="btn-primary cursor-pointer w-fit"
+            />
+
+            {move || importing.get().then(|| view! {
+                <div class="w-8 h-8 animate-spin">
+                    <SpinnerIcon />
+                </div>
+            })}
+
+            {move || error.get().map(|error| view! {
+                <p class="text-gray-200">{error}</p>
+            })}
+
+            {move || result.get().map(|result| view! { <ImportResults result=result /> })}
+        </form>
+    }
+}
@@ -0,0 +1,127 @@
+use leptos::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{
+    server_functions::{import_openapi, import_rustdoc, models::ImportResult},
+    wasm::{
+        components::{icons::SpinnerIcon, ImportResults},
+        types::ProjectDataResource,
+    },
+};
+
+/// The kinds of files that are imported as a whole
+#[derive(Clone, Copy, PartialEq)]
+pub enum FileImportKind {
+    /// An OpenAPI or Swagger spec
+    OpenApi,
+    /// The JSON output of rustdoc
+    Rustdoc,
+}
+
+impl FileImportKind {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::OpenApi => "OpenAPI or Swagger spec",
+            Self::Rustdoc => "Rustdoc JSON, from `cargo rustdoc -- --output-format json`",
+        }
+    }
+
+    fn accept(&self) -> &'static str {
+        match self {
+            Self::OpenApi => ".json,.yaml,.yml",
+            Self::Rustdoc => ".json",
+        }
+    }
+}
+
+/// Form uploading a file that is imported as a set of documents
+#[component]
+pub fn FileImport(project_id: i32, kind: FileImportKind) -> impl IntoView {
+    let project_data =
+        use_context::<ProjectDataResource>().expect("ProjectDataResource context not found");
+
+    let file_input = create_node_ref::<html::Input>();
+    let importing = create_rw_signal(false);
+    let error = create_rw_signal(None::<String>);
+    let result = create_rw_signal(None::<ImportResult>);
+
+    let start = move |_| {
+        if importing.get() {
+            return;
+        }
+
+        let Some(file) = file_input
+            .get()
+            .and_then(|input| input.files())
+            .and_then(|files| files.get(0))
+        else {
+            error.set(Some("Choose a file to import".to_owned()));
+            return;
+        };
+
+        importing.set(true);
+        error.set(None);
+        result.set(None);
+
+        spawn_local(async move {
+            let text = match JsFuture::from(file.text()).await {
+                Ok(text) => text.as_string().unwrap_or_default(),
+                Err(_) => {
+                    error.set(Some("Failed to read the file".to_owned()));
+                    importing.set(false);
+                    return;
+                }
+            };
+
+            let import = match kind {
+                FileImportKind::OpenApi => import_openapi(project_id, text).await,
+                FileImportKind::Rustdoc => import_rustdoc(project_id, text).await,
+            };
+
+            match import {
+                Ok(import) => {
+                    result.set(Some(import));
+                    project_data.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => error.set(Some(e)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            importing.set(false);
+        });
+    };
+
+    view! {
+        <div class="flex-grow flex flex-col gap-4 p-8">
+            <div class="flex flex-col gap-1">
+                <label for="import-file" class="text-lg">{kind.label()}</label>
+                <input
+                    type="file"
+                    name="import-file"
+                    id="import-file"
+                    accept=kind.accept()
+                    node_ref=file_input
+                />
+            </div>
+
+            <input
+                on:click=start
+                type="submit"
+                value="Import"
+                disabled=move || importing.get()
+                class="btn-primary cursor-pointer w-fit"
+            />
+
+            {move || importing.get().then(|| view! {
+                <div class="w-8 h-8 animate-spin">
+                    <SpinnerIcon />
+                </div>
+            })}
+
+            {move || error.get().map(|error| view! {
+                <p class="text-gray-200">{error}</p>
+            })}
+
+            {move || result.get().map(|result| view! { <ImportResults result=result /> })}
+        </div>
+    }
+}
@@ -0,0 +1,115 @@
+use leptos::*;
+
+use crate::{
+    server_functions::{import_git_repository, models::ImportResult},
+    wasm::{
+        components::{icons::SpinnerIcon, ImportResults},
+        types::ProjectDataResource,
+    },
+};
+
+/// Form importing the documentation files of a git repository on the server
+#[component]
+pub fn GitImport(project_id: i32) -> impl IntoView {
+    let project_data =
+        use_context::<ProjectDataResource>().expect("ProjectDataResource context not found");
+
+    let repository = create_rw_signal(String::new());
+    let reference = create_rw_signal(String::new());
+    let globs = create_rw_signal(String::new());
+    let importing = create_rw_signal(false);
+    let error = create_rw_signal(None::<String>);
+    let result = create_rw_signal(None::<ImportResult>);
+
+    let start = move |_| {
+        if importing.get() {
+            return;
+        }
+
+        importing.set(true);
+        error.set(None);
+        result.set(None);
+
+        let repository = repository.get();
+        let reference = reference.get();
+        let globs = globs
+            .get()
+            .lines()
+            .map(|glob| glob.trim().to_owned())
+            .filter(|glob| !glob.is_empty())
+            .collect();
+
+        spawn_local(async move {
+            match import_git_repository(project_id, repository, reference, globs).await {
+                Ok(import) => {
+                    result.set(Some(import));
+                    project_data.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => error.set(Some(e)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            importing.set(false);
+        });
+    };
+
+    view! {
+        <div class="flex-grow flex flex-col gap-4 p-8">
+            <div class="flex flex-col gap-1">
+                <label for="repository" class="text-lg">Repository</label>
+                <input
+                    type="text"
+                    name="repository"
+                    id="repository"
+                    placeholder="project-docs.git"
+                    title="Path of the repository or bare clone, relative to the git import root"
+                    required
+                    on:input=move |e| repository.set(event_target_value(&e))
+                />
+            </div>
+
+            <div class="flex flex-col gap-1">
+                <label for="reference" class="text-lg">Ref</label>
+                <input
+                    type="text"
+                    name="reference"
+                    id="reference"
+                    placeholder="HEAD"
+                    title="Branch, tag or commit to import"
+                    on:input=move |e| reference.set(event_target_value(&e))
+                />
+            </div>
+
+            <div class="flex flex-col gap-1">
+                <label for="globs" class="text-lg">Paths</label>
+                <textarea
+                    name="globs"
+                    id="globs"
+                    rows="3"
+                    placeholder="docs/**"
+                    title="Globs of the files to import, one per line. All Markdown, MDX and reStructuredText files are imported if empty"
+                    on:input=move |e| globs.set(event_target_value(&e))
+                />
+            </div>
+
+            <input
+                on:click=start
+                type="submit"
+                value="Import"
+                disabled=move || importing.get()
+                class="btn-primary cursor-pointer w-fit"
+            />
+
+            {move || importing.get().then(|| view! {
+                <div class="w-8 h-8 animate-spin">
+                    <SpinnerIcon />
+                </div>
+            })}
+
+            {move || error.get().map(|error| view! {
+                <p class="text-gray-200">{error}</p>
+            })}
+
+            {move || result.get().map(|result| view! { <ImportResults result=result /> })}
+        </div>
+    }
+}
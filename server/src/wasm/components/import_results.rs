@@ -0,0 +1,36 @@
+use leptos::*;
+
+use crate::server_functions::models::ImportResult;
+
+/// The summary of an import with the documents it added, changed and removed
+/// and the files it skipped
+#[component]
+pub fn ImportResults(result: ImportResult) -> impl IntoView {
+    let list = |label: &'static str, names: Vec<String>| {
+        (!names.is_empty()).then(|| {
+            view! {
+                <h4>{label}</h4>
+                <ul class="text-sm text-gray-300">
+                    {names.into_iter().map(|name| view! { <li>{name}</li> }).collect_view()}
+                </ul>
+            }
+        })
+    };
+
+    view! {
+        <div class="flex flex-col gap-2">
+            <p class="text-gray-200">{result.summary}</p>
+            {list("Added", result.added)}
+            {list("Changed", result.changed)}
+            {list("Removed", result.removed)}
+            {(!result.skipped.is_empty()).then(|| view! {
+                <h4>"Skipped"</h4>
+                <ul class="text-sm text-gray-300">
+                    {result.skipped.into_iter().map(|(file, reason)| view! {
+                        <li>{file}": "<span class="text-gray-400">{reason}</span></li>
+                    }).collect_view()}
+                </ul>
+            })}
+        </div>
+    }
+}
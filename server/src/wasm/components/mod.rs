@@ -1,21 +1,41 @@
 mod chat_panel;
+mod crawl_credentials;
+mod crawl_report;
+mod crawl_schedules;
 mod crawler;
 mod document_content;
+mod document_tags;
+mod document_tree;
 mod editor;
 mod error_template;
+mod export_import;
+mod file_import;
 mod finalize_button;
+mod git_import;
 mod header;
 pub mod icons;
+mod import_results;
 pub mod modals;
 mod outlet;
 mod sidebar;
+mod upload;
 
 pub use chat_panel::*;
+pub use crawl_credentials::*;
+pub use crawl_report::*;
+pub use crawl_schedules::*;
 pub use crawler::*;
 pub use document_content::*;
+pub use document_tags::*;
+pub use document_tree::*;
 pub use editor::*;
 pub use error_template::*;
+pub use export_import::*;
+pub use file_import::*;
 pub use finalize_button::*;
+pub use git_import::*;
 pub use header::*;
+pub use import_results::*;
 pub use outlet::*;
 pub use sidebar::*;
+pub use upload::*;
@@ -0,0 +1,94 @@
+use ev::SubmitEvent;
+use leptos::*;
+use web_sys::FormData;
+
+use crate::{
+    server_functions::{models::ImportResult, upload_documents},
+    wasm::{
+        components::{icons::SpinnerIcon, ImportResults},
+        types::ProjectDataResource,
+    },
+};
+
+/// Form uploading files and archives of them as documents
+#[component]
+pub fn Upload(project_id: i32) -> impl IntoView {
+    let project_data =
+        use_context::<ProjectDataResource>().expect("ProjectDataResource context not found");
+
+    let form_ref = create_node_ref::<html::Form>();
+    let uploading = create_rw_signal(false);
+    let error = create_rw_signal(None::<String>);
+    let result = create_rw_signal(None::<ImportResult>);
+
+    let on_submit = move |e: SubmitEvent| {
+        e.prevent_default();
+        if uploading.get() {
+            return;
+        }
+
+        let Some(form_data) = form_ref
+            .get()
+            .and_then(|form| FormData::new_with_form(&form).ok())
+        else {
+            return;
+        };
+
+        uploading.set(true);
+        error.set(None);
+        result.set(None);
+
+        spawn_local(async move {
+            match upload_documents(form_data.into()).await {
+                Ok(upload) => {
+                    result.set(Some(upload));
+                    project_data.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => error.set(Some(e)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            uploading.set(false);
+        });
+    };
+
+    view! {
+        <form
+            node_ref=form_ref
+            on:submit=on_submit
+            class="flex-grow flex flex-col gap-4 p-8"
+        >
+            <input type="hidden" name="project_id" value=project_id />
+            <div class="flex flex-col gap-1">
+                <label for="files" class="text-lg">
+                    "Markdown, HTML and text files, or .zip and .tar.gz archives of them"
+                </label>
+                <input
+                    type="file"
+                    name="files"
+                    id="files"
+                    multiple
+                    accept=".md,.markdown,.html,.htm,.txt,.zip,.tar.gz,.tgz"
+                />
+            </div>
+
+            <input
+                type="submit"
+                value="Upload"
+                disabled=move || uploading.get()
+                class="btn-primary cursor-pointer w-fit"
+            />
+
+            {move || uploading.get().then(|| view! {
+                <div class="w-8 h-8 animate-spin">
+                    <SpinnerIcon />
+                </div>
+            })}
+
+            {move || error.get().map(|error| view! {
+                <p class="text-gray-200">{error}</p>
+            })}
+
+            {move || result.get().map(|result| view! { <ImportResults result=result /> })}
+        </form>
+    }
+}
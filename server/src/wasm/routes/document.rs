@@ -1,22 +1,26 @@
+use ev::SubmitEvent;
 use leptos::*;
 use leptos_router::*;
 
 use crate::{
-    server_functions::get_document,
+    server_functions::{get_document, get_document_source, update_document},
     wasm::{
-        components::DocumentContent,
-        types::{ProjectDataContext, ProjectParams},
+        components::{DocumentContent, Editor},
+        types::{ProjectDataContext, ProjectDataResource, ProjectParams, VersionNotice},
     },
 };
 
 #[component]
-/// Route component which redirects to the latest version of a project.
+/// Route component which shows a document, or an editor for it in edit mode.
 pub fn Document() -> impl IntoView {
     let params = use_params::<ProjectParams>();
+    let navigate = use_navigate();
 
     let Some(project_data) = use_context::<ProjectDataContext>() else {
-        return view! { <div>"No project data found"</div> };
+        return view! { <div>"No project data found"</div> }.into_view();
     };
+    let project_data_resource = use_context::<ProjectDataResource>();
+    let notice = use_context::<VersionNotice>();
 
     let document_id = move || {
         with!(|params| {
@@ -28,7 +32,16 @@ pub fn Document() -> impl IntoView {
         })
     };
 
-    let is_finalized = move || project_data.0.finalized;
+    let (project_id, version) = (project_data.0.id, project_data.0.version);
+    let is_finalized = project_data.0.finalized;
+    // Documents of older versions cannot be changed
+    let is_latest_version = project_data.0.versions.iter().max() == Some(&version);
+
+    let editing = create_rw_signal(false);
+    let title = create_rw_signal(String::new());
+    let content = create_rw_signal(String::new());
+    let redirect_url = create_rw_signal(None::<String>);
+    let error = create_rw_signal(None::<String>);
 
     let document = create_blocking_resource(
         move || (project_data.to_owned(), document_id()),
@@ -37,14 +50,106 @@ pub fn Document() -> impl IntoView {
         },
     );
 
+    // The editor is filled with the markdown source of the document, not the rendered content
+    let source = create_resource(
+        move || (editing.get(), document_id()),
+        move |(editing, document_id)| async move {
+            match editing {
+                true => Some(get_document_source(project_id, version, document_id).await),
+                false => None,
+            }
+        },
+    );
+
+    create_effect(move |_| {
+        if let Some(Some(Ok(source))) = source.get() {
+            title.set(source.name);
+            content.set(source.content);
+        }
+    });
+
+    let on_submit = move |e: SubmitEvent| {
+        e.prevent_default();
+        let document_id = document_id();
+        let title = title.get();
+        let content = content.get();
+        spawn_local(async move {
+            let new_version =
+                match update_document(project_id, version, document_id, title, content).await {
+                    Ok(new_version) => new_version,
+                    // The editor stays open so the changes are not lost
+                    Err(e) => {
+                        error.set(Some(match e {
+                            ServerFnError::ServerError(e) => e,
+                            e => e.to_string(),
+                        }));
+                        return;
+                    }
+                };
+
+            error.set(None);
+            editing.set(false);
+            if let Some(project_data) = project_data_resource {
+                project_data.refetch();
+            }
+            match new_version {
+                // Editing a document of a finalized version creates a new version
+                Some(new_version) => {
+                    if let Some(notice) = notice {
+                        notice.0.set(Some(format!(
+                            "Version {version} is finalized, the changes were saved in the new version {new_version}"
+                        )));
+                    }
+                    redirect_url.set(Some(format!(
+                        "/projects/{project_id}/documents/{document_id}?version={new_version}"
+                    )));
+                }
+                None => document.refetch(),
+            }
+        });
+    };
+
+    create_effect(move |_| {
+        if let Some(url) = redirect_url.get() {
+            navigate(url.as_str(), Default::default());
+        }
+    });
+
     view! {
         <div id="doc-parent" class="relative p-10">
-            <Transition fallback=|| ()>
-                {move || document.get().and_then(|document| match document {
-                    Ok(document) => Some(view! { <DocumentContent document finalized=is_finalized() /> }),
-                    _ => None,
-                })}
-            </Transition>
+            {move || match editing.get() {
+                true => view! {
+                    <Editor
+                        header="Edit Document"
+                        title
+                        content
+                        on_submit
+                        on_cancel=move |_| {
+                            error.set(None);
+                            editing.set(false);
+                        }
+                    />
+                    {move || error.get().map(|e| view! { <p class="text-red-500 px-8">{e}</p> })}
+                }
+                .into_view(),
+                false => view! {
+                    <Transition fallback=|| ()>
+                        {move || document.get().and_then(|document| match document {
+                            Ok(document) => Some(view! {
+                                <DocumentContent
+                                    editable=is_latest_version && document.writable
+                                    document
+                                    finalized=is_finalized
+                                    on_edit=move |_| editing.set(true)
+                                />
+                            }),
+                            _ => None,
+                        })}
+                    </Transition>
+                }
+                .into_view(),
+            }}
         </div>
     }
+    .into_view()
 }
@@ -5,11 +5,26 @@ use leptos_router::*;
 use crate::{
     server_functions::create_document,
     wasm::{
-        components::{Crawler, Editor},
+        components::{
+            Crawler, Editor, ExportImport, ExportKind, FileImport, FileImportKind, GitImport,
+            Upload,
+        },
         types::{ProjectDataResource, ProjectParams},
     },
 };
 
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Editor,
+    Crawler,
+    Git,
+    OpenApi,
+    Rustdoc,
+    Upload,
+    Confluence,
+    Notion,
+}
+
 #[component]
 /// Route component which redirects to the latest version of a project.
 pub fn DocumentNew() -> impl IntoView {
@@ -18,7 +33,7 @@ pub fn DocumentNew() -> impl IntoView {
     let params = use_params::<ProjectParams>();
     let navigate = use_navigate();
 
-    let use_editor = create_rw_signal(true);
+    let tab = create_rw_signal(Tab::Editor);
     let title = create_rw_signal(String::new());
     let content = create_rw_signal(String::new());
     let redirect_url = create_rw_signal(None::<String>);
@@ -57,27 +72,47 @@ pub fn DocumentNew() -> impl IntoView {
 
     view! {
         <div class="flex items-end gap-8 w-full h-[75px] bg-[#181818] pl-8">
-            <button
-                on:click=move |_| use_editor.set(true)
-                class=move || if use_editor.get() {"editor-tab active-editor-tab"} else {"editor-tab"}
-            >
-                Editor
-            </button>
-
-            <button
-                on:click=move |_| use_editor.set(false)
-                class=move || if use_editor.get() {"editor-tab"} else {"editor-tab active-editor-tab"}
-            >
-                Crawler
-            </button>
+            <TabButton tab selected=Tab::Editor label="Editor" />
+            <TabButton tab selected=Tab::Crawler label="Crawler" />
+            <TabButton tab selected=Tab::Git label="Git" />
+            <TabButton tab selected=Tab::OpenApi label="OpenAPI" />
+            <TabButton tab selected=Tab::Rustdoc label="Rustdoc" />
+            <TabButton tab selected=Tab::Upload label="Upload" />
+            <TabButton tab selected=Tab::Confluence label="Confluence" />
+            <TabButton tab selected=Tab::Notion label="Notion" />
         </div>
 
-        {move || if use_editor.get() {
-            view! {
+        {move || match tab.get() {
+            Tab::Editor => view! {
                 <Editor header="New Document" title content on_submit=on_submit_editor />
-            }.into_view()
-        } else {
-            view! { <Crawler project_id=project_id() /> }.into_view()
+            }.into_view(),
+            Tab::Crawler => view! { <Crawler project_id=project_id() /> }.into_view(),
+            Tab::Git => view! { <GitImport project_id=project_id() /> }.into_view(),
+            Tab::OpenApi => view! {
+                <FileImport project_id=project_id() kind=FileImportKind::OpenApi />
+            }.into_view(),
+            Tab::Rustdoc => view! {
+                <FileImport project_id=project_id() kind=FileImportKind::Rustdoc />
+            }.into_view(),
+            Tab::Upload => view! { <Upload project_id=project_id() /> }.into_view(),
+            Tab::Confluence => view! {
+                <ExportImport project_id=project_id() kind=ExportKind::Confluence />
+            }.into_view(),
+            Tab::Notion => view! {
+                <ExportImport project_id=project_id() kind=ExportKind::Notion />
+            }.into_view(),
         }}
     }
 }
+
+#[component]
+fn TabButton(tab: RwSignal<Tab>, selected: Tab, label: &'static str) -> impl IntoView {
+    view! {
+        <button
+            on:click=move |_| tab.set(selected)
+            class=move || if tab.get() == selected {"editor-tab active-editor-tab"} else {"editor-tab"}
+        >
+            {label}
+        </button>
+    }
+}
@@ -2,7 +2,10 @@ use std::ops::Not as _;
 
 use leptos::*;
 
-use crate::wasm::{components::FinalizeButton, types::ProjectDataContext};
+use crate::wasm::{
+    components::{CrawlCredentials, CrawlSchedules, FinalizeButton},
+    types::ProjectDataContext,
+};
 
 #[component]
 /// Route component which redirects to the latest version of a project.
@@ -26,6 +29,12 @@ pub fn Project() -> impl IntoView {
             </div>
 
             <hr class="my-8"/>
+
+            <CrawlSchedules project_id=project.id />
+
+            <hr class="my-8"/>
+
+            <CrawlCredentials project_id=project.id />
         </div>
     }
 }
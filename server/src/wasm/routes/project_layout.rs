@@ -8,8 +8,8 @@ use leptos_router::*;
 use crate::{
     server_functions::{delete_document, get_project_data},
     wasm::{
-        components::{icons::*, *},
-        types::{ProjectParams, VersionQuery},
+        components::*,
+        types::{ProjectParams, VersionNotice, VersionQuery},
     },
 };
 
@@ -50,6 +50,9 @@ pub fn ProjectLayout() -> impl IntoView {
 
     provide_context(project_data);
 
+    let notice = VersionNotice(create_rw_signal(None::<String>));
+    provide_context(notice);
+
     view! {
         <Transition>
             {move || match project_data.get() {
@@ -120,7 +123,7 @@ pub fn ProjectLayout() -> impl IntoView {
                                 let redirect_url = create_rw_signal(None::<String>);
 
                                 let is_latest_version = project.version
-                                    .eq(&project.versions.into_iter().max().unwrap());
+                                    .eq(project.versions.iter().max().unwrap());
 
                                 // On delete document
                                 let on_delete = move |e: MouseEvent, doc_id: i32| {
@@ -156,38 +159,12 @@ pub fn ProjectLayout() -> impl IntoView {
                                 });
 
                                 Some(view! {
-                                    <For
-                                        each=move || documents.to_owned()
-                                        key=|doc| doc.id
-                                        let:doc
-                                    >
-                                        <li class="relative group flex items-center">
-                                            <A
-                                                href=move || if let Some(v) = version() {
-                                                    format!("documents/{}?version={}", doc.id, v)
-                                                } else {
-                                                    format!("documents/{}", doc.id)
-                                                }
-                                                class="w-full"
-                                                exact=true
-                                            >
-                                                <p>{doc.name}</p>
-
-                                            </A>
-
-                                            // TODO if is_latest_version and permissions.delete
-                                            {move || is_latest_version.then(|| {
-                                                view!{
-                                                    <button
-                                                        on:click=move |e| on_delete(e, doc.id)
-                                                        class="absolute right-2 opacity-0 group-hover:opacity-60 w-5 h-5 text-red-500"
-                                                    >
-                                                        <TrashIcon />
-                                                    </button>
-                                                }
-                                            })}
-                                        </li>
-                                    </For>
+                                    <DocumentTreeView
+                                        project
+                                        documents
+                                        editable=is_latest_version
+                                        on_delete=move |(e, doc_id): (MouseEvent, i32)| on_delete(e, doc_id)
+                                    />
                                 })
                             },
                             _ => None,
@@ -211,6 +188,15 @@ pub fn ProjectLayout() -> impl IntoView {
                     }}
                 </Suspense>
 
+                {move || notice.0.get().map(|message| view! {
+                    <div id="version-notice" class="absolute top-6 left-0 right-0 z-10 flex justify-between items-center px-4 py-1 bg-blue-900">
+                        <p class="text-white">{message}</p>
+                        <button class="text-white opacity-60 hover:opacity-100" on:click=move |_| notice.0.set(None)>
+                            "×"
+                        </button>
+                    </div>
+                })}
+
                 <Transition>
                     {move || match project_data.get() {
                         Some(Ok(data)) => {
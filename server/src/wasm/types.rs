@@ -8,6 +8,11 @@ pub type ProjectDataContext = (ProjectData, Vec<ProjectDocument>);
 pub type ProjectDataResource =
     Resource<(i32, Option<i32>), Result<(ProjectData, Vec<ProjectDocument>), ServerFnError>>;
 
+/// A message shown above the project pages, e.g. that a change was saved in a
+/// new version of the project
+#[derive(Clone, Copy)]
+pub struct VersionNotice(pub RwSignal<Option<String>>);
+
 #[derive(Params, PartialEq)]
 pub struct ProjectParams {
     project_id: Option<i32>,
@@ -61,4 +66,6 @@ pub struct ChatMessage {
     pub user: ChatUser,
     pub key: u128,
     pub content: RwSignal<String>,
+    /// Answer rendered on the server once streaming ends
+    pub rendered: RwSignal<Option<String>>,
 }
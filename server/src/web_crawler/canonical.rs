@@ -0,0 +1,103 @@
+use reqwest::Url;
+
+/// Query parameters that only track where a visitor came from
+static TRACKING_PARAMS: [&str; 11] = [
+    "gclid", "dclid", "fbclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga", "_gl",
+    "ref_src",
+];
+static TRACKING_PARAM_PREFIXES: [&str; 2] = ["utm_", "pk_"];
+
+/// File names servers commonly serve for a directory
+static DEFAULT_DOCUMENTS: [&str; 6] = [
+    "index.html",
+    "index.htm",
+    "index.php",
+    "default.html",
+    "default.htm",
+    "default.aspx",
+];
+
+/// Normalises a url so that addresses of the same page compare equal.
+///
+/// Fragments and tracking parameters are removed, the remaining query parameters
+/// are sorted, default documents like `index.html` are dropped and trailing slashes
+/// are trimmed.
+pub fn canonicalize(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !is_tracking_param(key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    params.sort();
+
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+
+    let mut path = url.path().to_owned();
+    if let Some((directory, file)) = path.rsplit_once('/') {
+        if DEFAULT_DOCUMENTS.contains(&file.to_lowercase().as_str()) {
+            path = format!("{directory}/");
+        }
+    }
+
+    let trimmed = path.trim_end_matches('/');
+    url.set_path(if trimmed.is_empty() { "/" } else { trimmed });
+
+    url
+}
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_lowercase();
+
+    TRACKING_PARAMS.contains(&key.as_str())
+        || TRACKING_PARAM_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("https://example.com", "https://example.com/")]
+    #[case("https://example.com/", "https://example.com/")]
+    #[case("https://EXAMPLE.com:443/docs", "https://example.com/docs")]
+    #[case("https://example.com/docs/", "https://example.com/docs")]
+    #[case("https://example.com/docs//", "https://example.com/docs")]
+    #[case("https://example.com/docs#intro", "https://example.com/docs")]
+    #[case("https://example.com/index.html", "https://example.com/")]
+    #[case("https://example.com/docs/index.html", "https://example.com/docs")]
+    #[case("https://example.com/docs/Index.HTM", "https://example.com/docs")]
+    #[case("https://example.com/docs/default.aspx", "https://example.com/docs")]
+    #[case(
+        "https://example.com/docs/intro.html",
+        "https://example.com/docs/intro.html"
+    )]
+    #[case(
+        "https://example.com/docs?utm_source=x&utm_medium=y",
+        "https://example.com/docs"
+    )]
+    #[case(
+        "https://example.com/docs?fbclid=1&page=2",
+        "https://example.com/docs?page=2"
+    )]
+    #[case("https://example.com/docs?b=2&a=1", "https://example.com/docs?a=1&b=2")]
+    #[case("https://example.com/docs?", "https://example.com/docs")]
+    #[case(
+        "https://example.com/docs/?page=2#top",
+        "https://example.com/docs?page=2"
+    )]
+    fn test_canonicalize(#[case] url: &str, #[case] expected: &str) {
+        let url = Url::parse(url).unwrap();
+
+        assert_eq!(canonicalize(&url).as_str(), expected);
+    }
+}
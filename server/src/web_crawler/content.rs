@@ -0,0 +1,351 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use mime::Mime;
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+use reqwest::Url;
+
+/// Bytes searched for a charset declaration, as in the HTML standard
+const PRESCAN_BYTES: usize = 1024;
+
+/// Tags that mark the start of an HTML document when sniffing, following the
+/// WHATWG MIME sniffing standard
+static HTML_SIGNATURES: [&str; 17] = [
+    "<!doctype html",
+    "<html",
+    "<head",
+    "<script",
+    "<iframe",
+    "<h1",
+    "<div",
+    "<font",
+    "<table",
+    "<a",
+    "<style",
+    "<title",
+    "<b",
+    "<body",
+    "<br",
+    "<p",
+    "<!--",
+];
+
+/// Start of every PDF file
+const PDF_SIGNATURE: &[u8] = b"%PDF-";
+
+/// The kinds of content that can be imported as documents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentKind {
+    Html,
+    Markdown,
+    PlainText,
+    Pdf,
+}
+
+/// `<meta charset="...">`, `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// and `<?xml ... encoding="..."?>`
+static CHARSET_DECLARATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)<meta\s[^>]*?charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)|<\?xml\s[^>]*?encoding\s*=\s*["']([a-z0-9_:.\-]+)"#,
+    )
+    .expect("valid charset regex")
+});
+
+/// Parse a `Content-Type` header value. Type and subtype are case insensitive.
+pub fn parse_content_type(value: &str) -> Option<Mime> {
+    value.trim().parse().ok()
+}
+
+/// HTML or XHTML
+pub fn is_html(content_type: &Mime) -> bool {
+    matches!(
+        content_type.essence_str(),
+        "text/html" | "application/xhtml+xml"
+    )
+}
+
+/// Whether the content type is missing or says nothing about the content, in
+/// which case the content is sniffed
+pub fn is_unknown(content_type: Option<&Mime>) -> bool {
+    match content_type {
+        Some(content_type) => matches!(
+            content_type.essence_str(),
+            "application/octet-stream" | "application/unknown" | "unknown/unknown" | "*/*"
+        ),
+        None => true,
+    }
+}
+
+/// The kind of content declared by the content type. Markdown is often served
+/// as plain text, so text with a Markdown file extension is taken for Markdown.
+pub fn declared_kind(content_type: &Mime, url: &Url) -> Option<ContentKind> {
+    if is_html(content_type) {
+        return Some(ContentKind::Html);
+    }
+
+    match content_type.essence_str() {
+        "text/markdown" | "text/x-markdown" => Some(ContentKind::Markdown),
+        "text/plain" if is_markdown_file(url) => Some(ContentKind::Markdown),
+        "text/plain" => Some(ContentKind::PlainText),
+        "application/pdf" | "application/x-pdf" => Some(ContentKind::Pdf),
+        _ => None,
+    }
+}
+
+/// The kind of content without a known type, from the content itself or else
+/// from the file extension of the url
+pub fn sniff_kind(bytes: &[u8], url: &Url) -> Option<ContentKind> {
+    if bytes.starts_with(PDF_SIGNATURE) {
+        return Some(ContentKind::Pdf);
+    }
+    if sniff_html(&decode(bytes, None).0) {
+        return Some(ContentKind::Html);
+    }
+
+    match extension(url)?.as_str() {
+        "md" | "markdown" => Some(ContentKind::Markdown),
+        "txt" => Some(ContentKind::PlainText),
+        "pdf" => Some(ContentKind::Pdf),
+        _ => None,
+    }
+}
+
+fn is_markdown_file(url: &Url) -> bool {
+    matches!(extension(url).as_deref(), Some("md" | "markdown"))
+}
+
+/// The lowercase file extension of the last path segment
+fn extension(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    let (stem, extension) = segment.rsplit_once('.')?;
+    (!stem.is_empty()).then(|| extension.to_lowercase())
+}
+
+/// Whether decoded content looks like an HTML or XHTML document. A byte order
+/// mark, leading whitespace and an XML declaration are skipped.
+pub fn sniff_html(content: &str) -> bool {
+    let mut content = content.trim_start_matches('\u{feff}').trim_start();
+
+    if starts_with_ignore_case(content, "<?xml") {
+        let Some(end) = content.find("?>") else {
+            return false;
+        };
+        content = content[end + 2..].trim_start();
+    }
+
+    HTML_SIGNATURES.iter().any(|signature| {
+        if !starts_with_ignore_case(content, signature) {
+            return false;
+        }
+        // Tags have to end, so that e.g. `<abbr` is not taken for `<a`
+        match content[signature.len()..].chars().next() {
+            Some(next) => *signature == "<!--" || next == '>' || next.is_ascii_whitespace(),
+            None => false,
+        }
+    })
+}
+
+/// Decode a document, finding the encoding from a byte order mark, the charset of
+/// the content type, a charset declared in the document or else from the bytes
+/// themselves, in that order.
+pub fn decode(bytes: &[u8], charset: Option<&str>) -> (String, &'static Encoding) {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        return (text.into_owned(), encoding);
+    }
+
+    let encoding = charset
+        .and_then(|charset| Encoding::for_label(charset.trim().as_bytes()))
+        .or_else(|| declared_encoding(bytes))
+        .unwrap_or_else(|| {
+            let mut detector = EncodingDetector::new();
+            detector.feed(bytes, true);
+            detector.guess(None, true)
+        });
+
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), encoding)
+}
+
+/// The encoding declared at the start of the document
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let start = &bytes[..bytes.len().min(PRESCAN_BYTES)];
+    let captures = CHARSET_DECLARATION.captures(start)?;
+    let label = captures.get(1).or_else(|| captures.get(2))?;
+    let encoding = Encoding::for_label(label.as_bytes())?;
+
+    // A document that could be read to find the declaration is not UTF-16
+    match encoding == UTF_16LE || encoding == UTF_16BE {
+        true => Some(UTF_8),
+        false => Some(encoding),
+    }
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+    use rstest::*;
+
+    #[rstest]
+    #[case("text/html", true)]
+    #[case("text/html; charset=utf-8", true)]
+    #[case("Text/HTML;Charset=\"UTF-8\"", true)]
+    #[case("application/xhtml+xml", true)]
+    #[case("text/plain", false)]
+    #[case("application/pdf", false)]
+    fn test_is_html(#[case] content_type: &str, #[case] expected: bool) {
+        let content_type = parse_content_type(content_type).unwrap();
+
+        assert_eq!(is_html(&content_type), expected);
+    }
+
+    #[rstest]
+    #[case(None, true)]
+    #[case(Some("application/octet-stream"), true)]
+    #[case(Some("text/html"), false)]
+    #[case(Some("text/plain"), false)]
+    fn test_is_unknown(#[case] content_type: Option<&str>, #[case] expected: bool) {
+        let content_type = content_type.and_then(parse_content_type);
+
+        assert_eq!(is_unknown(content_type.as_ref()), expected);
+    }
+
+    #[rstest]
+    #[case("text/html; charset=utf-8", "/", Some(ContentKind::Html))]
+    #[case("text/markdown", "/readme", Some(ContentKind::Markdown))]
+    #[case("text/x-markdown", "/readme", Some(ContentKind::Markdown))]
+    #[case("text/plain", "/docs/README.md", Some(ContentKind::Markdown))]
+    #[case("text/plain", "/docs/notes.txt", Some(ContentKind::PlainText))]
+    #[case("text/plain", "/docs/notes", Some(ContentKind::PlainText))]
+    #[case("application/pdf", "/guide", Some(ContentKind::Pdf))]
+    #[case("image/png", "/logo.png", None)]
+    #[case("application/json", "/api.md", None)]
+    fn test_declared_kind(
+        #[case] content_type: &str,
+        #[case] path: &str,
+        #[case] expected: Option<ContentKind>,
+    ) {
+        let content_type = parse_content_type(content_type).unwrap();
+        let url = Url::parse("https://example.com")
+            .unwrap()
+            .join(path)
+            .unwrap();
+
+        assert_eq!(declared_kind(&content_type, &url), expected);
+    }
+
+    #[rstest]
+    #[case(b"%PDF-1.7\n", "/download", Some(ContentKind::Pdf))]
+    #[case(b"<!DOCTYPE html><html>", "/file.md", Some(ContentKind::Html))]
+    #[case(b"# Readme", "/README.MD", Some(ContentKind::Markdown))]
+    #[case(b"# Readme", "/docs/guide.markdown", Some(ContentKind::Markdown))]
+    #[case(b"Notes", "/notes.txt", Some(ContentKind::PlainText))]
+    #[case(b"Notes", "/.txt", None)]
+    #[case(b"\x89PNG", "/logo.png", None)]
+    #[case(b"# Readme", "/readme", None)]
+    fn test_sniff_kind(
+        #[case] bytes: &[u8],
+        #[case] path: &str,
+        #[case] expected: Option<ContentKind>,
+    ) {
+        let url = Url::parse("https://example.com")
+            .unwrap()
+            .join(path)
+            .unwrap();
+
+        assert_eq!(sniff_kind(bytes, &url), expected);
+    }
+
+    #[test]
+    fn test_charset_param() {
+        let content_type = parse_content_type("text/html; charset=\"ISO-8859-1\"").unwrap();
+
+        assert_eq!(
+            content_type.get_param(mime::CHARSET).unwrap().as_str(),
+            "iso-8859-1"
+        );
+    }
+
+    #[rstest]
+    #[case("<!DOCTYPE html><html></html>", true)]
+    #[case("<!doctype HTML>", true)]
+    #[case("  \n\t<html lang=\"en\">", true)]
+    #[case("\u{feff}<html>", true)]
+    #[case("\u{feff}\n  <!DOCTYPE html>", true)]
+    #[case(
+        "<?xml version=\"1.0\"?>\n<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Strict//EN\">",
+        true
+    )]
+    #[case(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><html xmlns=\"http://www.w3.org/1999/xhtml\">",
+        true
+    )]
+    #[case("<!-- generated --><html>", true)]
+    #[case("<body>", true)]
+    #[case("<p>Hello</p>", true)]
+    #[case("<abbr>", false)]
+    #[case("<?xml version=\"1.0\"?><rss>", false)]
+    #[case("%PDF-1.4", false)]
+    #[case("# Markdown", false)]
+    #[case("{\"html\": true}", false)]
+    #[case("", false)]
+    fn test_sniff_html(#[case] content: &str, #[case] expected: bool) {
+        assert_eq!(sniff_html(content), expected);
+    }
+
+    #[rstest]
+    #[case(b"\xef\xbb\xbf<p>Caf\xc3\xa9</p>".to_vec(), None, "<p>Café</p>", UTF_8)]
+    #[case(b"\xef\xbb\xbf<p>Caf\xc3\xa9</p>".to_vec(), Some("ISO-8859-1"), "<p>Café</p>", UTF_8)]
+    #[case(b"\xff\xfe<\0p\0>\0".to_vec(), None, "<p>", UTF_16LE)]
+    #[case(b"<p>Caf\xe9</p>".to_vec(), Some("ISO-8859-1"), "<p>Café</p>", WINDOWS_1252)]
+    #[case(b"<p>Caf\xc3\xa9</p>".to_vec(), Some("utf-8"), "<p>Café</p>", UTF_8)]
+    #[case(
+        b"<html><head><meta charset=\"windows-1252\"></head><p>Caf\xe9</p>".to_vec(),
+        None,
+        "<html><head><meta charset=\"windows-1252\"></head><p>Café</p>",
+        WINDOWS_1252
+    )]
+    #[case(
+        b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\"><p>Caf\xe9</p>".to_vec(),
+        None,
+        "<meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\"><p>Café</p>",
+        WINDOWS_1252
+    )]
+    #[case(
+        b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><p>Caf\xe9</p>".to_vec(),
+        None,
+        "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><p>Café</p>",
+        WINDOWS_1252
+    )]
+    #[case(b"<meta charset=\"utf-16\"><p>Hi</p>".to_vec(), None, "<meta charset=\"utf-16\"><p>Hi</p>", UTF_8)]
+    #[case(b"<p>Caf\xc3\xa9</p>".to_vec(), None, "<p>Café</p>", UTF_8)]
+    fn test_decode(
+        #[case] bytes: Vec<u8>,
+        #[case] charset: Option<&str>,
+        #[case] expected: &str,
+        #[case] expected_encoding: &'static Encoding,
+    ) {
+        let (text, encoding) = decode(&bytes, charset);
+
+        assert_eq!(text, expected);
+        assert_eq!(encoding, expected_encoding);
+    }
+
+    #[test]
+    fn test_decode_detects_encoding_from_bytes() {
+        let (bytes, _, _) = SHIFT_JIS.encode(
+            "<html><body><p>日本語のドキュメントです。これはテストのための文章です。</p></body></html>",
+        );
+
+        let (text, encoding) = decode(&bytes, None);
+
+        assert_eq!(encoding, SHIFT_JIS);
+        assert!(text.contains("日本語のドキュメントです"));
+    }
+}
@@ -1,23 +1,49 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures_util::stream;
-use reqwest::Url;
-use tokio::time::sleep;
+use reqwest::{header::HeaderMap, StatusCode, Url};
+use tokio::time::{sleep, timeout};
+
+use crate::{
+    models::CacheValidators,
+    outbound::{FetchPolicy, OutboundClient},
+    parsing::{Content, Done, HtmlParser},
+};
 
 use super::{
+    canonical::canonicalize,
+    limits::{CrawlLimit, CrawlLimits},
+    outcome::{PageOutcome, PageReport},
     robots_txt::RobotsTxt,
-    spider::{Spider, SpiderResult},
+    sitemap,
+    spider::{Spider, SpiderError, SpiderResult},
 };
 
 pub enum StreamOutput {
     Message(String),
     Result(CrawlerResult),
+    /// A previously crawled page answered a conditional request with 304 Not Modified
+    NotModified(Url),
+    /// A page answered with 404 Not Found or 410 Gone
+    Gone(Url),
+    /// The crawl stopped before all pages were visited
+    LimitReached(CrawlLimit),
+    /// What happened to a url, sent once for every url the crawler came across
+    Report(PageReport),
 }
 
 pub struct Crawler {
     max_depth: Option<usize>,
     url: Url,
+    known_pages: HashMap<String, (Url, CacheValidators)>,
+    limits: CrawlLimits,
+    policy: FetchPolicy,
+    selection: Option<Vec<Url>>,
+    credentials: HeaderMap,
 }
 
 impl Crawler {
@@ -27,28 +53,122 @@ impl Crawler {
         Ok(Self {
             max_depth,
             url: url.clone(),
+            known_pages: HashMap::new(),
+            limits: CrawlLimits::default(),
+            policy: FetchPolicy::default(),
+            selection: None,
+            credentials: HeaderMap::new(),
         })
     }
 
+    /// Only visit these pages, without following their links. Used to import
+    /// the pages picked from a preview.
+    pub fn with_selection(mut self, pages: Vec<Url>) -> Self {
+        let selection = pages
+            .into_iter()
+            .filter(|url| self.is_in_scope(url))
+            .rev()
+            .collect();
+        self.selection = Some(selection);
+        self
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Policy for the hosts the crawler may reach, only public addresses by default
+    pub fn with_policy(mut self, policy: FetchPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Headers sent with every request to the host of the start url, e.g. for
+    /// authentication. They are not sent to other hosts, even after a redirect.
+    pub fn with_credentials(mut self, headers: HeaderMap) -> Self {
+        self.credentials = headers;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: CrawlLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Pages from a previous crawl. They are visited even if no longer linked to,
+    /// and fetched with conditional requests using their cache validators.
+    pub fn with_known_pages(mut self, pages: Vec<(Url, CacheValidators)>) -> Self {
+        self.known_pages = pages
+            .into_iter()
+            .filter(|(url, _)| self.is_in_scope(url))
+            .map(|(url, validators)| (Self::page_key(&url), (url, validators)))
+            .collect();
+        self
+    }
+
+    /// Whether the url is the start url or one of the pages below it within the max depth
+    pub fn is_in_scope(&self, url: &Url) -> bool {
+        if url.host_str() != self.url.host_str() {
+            return false;
+        }
+
+        if Self::page_key(url) == Self::page_key(&self.url) {
+            return true;
+        }
+
+        let relative_depth = self.find_relative_depth(&canonicalize(url));
+        let max_depth = self.max_depth.unwrap_or(usize::MAX);
+
+        0 < relative_depth && relative_depth <= max_depth
+    }
+
+    /// Key identifying a page, urls with the same canonical form are the same page
+    pub fn page_key(url: &Url) -> String {
+        canonicalize(url).to_string()
+    }
+
     pub async fn start(&mut self) -> impl stream::Stream<Item = StreamOutput> + '_ {
         async_stream::stream! {
-            let robots = RobotsTxt::from_url(&self.url).await;
+            let read_timeout = self.limits.read_timeout;
+            let Ok(client) = OutboundClient::new(self.policy.clone(), self.limits.connect_timeout) else {
+                yield StreamOutput::Message("Failed to create HTTP client".to_owned());
+                return;
+            };
+            let client = client.with_credentials(&self.url, self.credentials.clone());
+
+            let robots = timeout(read_timeout, RobotsTxt::from_url(&self.url, &client))
+                .await
+                .unwrap_or_default();
             let delay = robots.delay().unwrap_or(500);
 
-            let mut queue: Vec<Url> = vec![self.url.clone()];
+            let mut queue: Vec<Url> = match &self.selection {
+                Some(selection) => selection.clone(),
+                None => self.discover_start_pages(&robots, &client).await,
+            };
             let mut visited: HashSet<String> = HashSet::new();
+            let mut seen_content: HashMap<String, Url> = HashMap::new();
+            let mut pages = 0;
+            let mut bytes = 0;
+            // The duration is counted from the first page request, fetching robots.txt and
+            // the sitemaps does not use it up
+            let mut started = None;
 
-            while let Some(url) = queue.pop() {
-                if !robots.is_allowed(&url) {
+            while let Some(mut url) = queue.pop() {
+                if !visited.insert(Self::page_key(&url)) {
                     continue;
                 }
 
-                let path = url.path().to_owned();
+                url.set_fragment(None);
 
-                if visited.contains(&path) {
+                if !robots.is_allowed(&url) {
+                    yield self.report(&url, PageOutcome::SkippedByRobots);
                     continue;
-                } else {
-                    visited.insert(path.to_owned());
+                }
+
+                let started = *started.get_or_insert_with(Instant::now);
+                if let Some(limit) = self.limit_reached(pages, bytes, started) {
+                    yield StreamOutput::LimitReached(limit);
+                    break;
                 }
 
                 let scheme = url.scheme();
@@ -56,31 +176,79 @@ impl Crawler {
                 let path = url.path();
                 yield StreamOutput::Message(format!("Visiting {}://{}{}", scheme, host, path));
 
-                let spider = Spider::new(url.clone());
-                let result = match spider.start().await {
-                    Ok(result) => result,
-                    Err(_) => continue,
+                let spider = Spider::new(url.clone(), client.clone())
+                    .with_max_bytes(self.limits.max_page_bytes)
+                    .with_read_timeout(read_timeout);
+                let spider = match self.known_pages.get(&Self::page_key(&url)) {
+                    Some((_, validators)) => spider.with_validators(validators.clone()),
+                    None => spider,
                 };
 
-                for link in result.found_urls() {
-                    let path = link.path().to_owned();
-                    let path = path.trim_end_matches('/');
+                pages += 1;
 
-                    if visited.contains(path) {
-                        continue;
-                    }
+                match spider.start().await {
+                    Ok(mut result) => 'result: {
+                        bytes += result.bytes();
+
+                        if let Some(canonical_url) = result.canonical_url() {
+                            let key = Self::page_key(&canonical_url);
+                            if key != Self::page_key(&url) && !visited.insert(key) {
+                                yield self.report(&url, PageOutcome::Duplicate(canonical_url));
+                                break 'result;
+                            }
+                            result.set_url(canonical_url);
+                        }
 
-                    let relative_depth = self.find_relative_depth(&link);
-                    let max_depth = self.max_depth.unwrap_or(usize::MAX);
+                        if let Some(original) = seen_content.get(result.content_hash()) {
+                            yield self.report(&url, PageOutcome::Duplicate(original.clone()));
+                            break 'result;
+                        }
+                        seen_content.insert(result.content_hash().to_owned(), result.url());
 
-                    if 0 < relative_depth && relative_depth <= max_depth {
-                        queue.push(link);
-                    } else {
-                        visited.insert(path.to_owned());
+                        let links = match self.selection {
+                            Some(_) => vec![],
+                            None => result.found_urls(),
+                        };
+
+                        for link in links {
+                            let key = Self::page_key(&link);
+
+                            if visited.contains(&key) {
+                                continue;
+                            }
+
+                            let relative_depth = self.find_relative_depth(&canonicalize(&link));
+                            let max_depth = self.max_depth.unwrap_or(usize::MAX);
+
+                            if 0 < relative_depth && relative_depth <= max_depth {
+                                queue.push(link);
+                            } else {
+                                visited.insert(key);
+                                yield self.report(&link, PageOutcome::SkippedByDepth);
+                            }
+                        }
+
+                        yield self.report(&result.url(), PageOutcome::Fetched);
+                        yield StreamOutput::Result(CrawlerResult::from_spider_result(result));
                     }
-                }
+                    Err(e) => {
+                        let outcome = match e.downcast_ref::<SpiderError>() {
+                            Some(SpiderError::NotModified) => PageOutcome::NotModified,
+                            Some(SpiderError::Status(status)) => PageOutcome::HttpError(status.as_u16()),
+                            Some(SpiderError::Unsupported(content_type)) => PageOutcome::Unsupported(content_type.clone()),
+                            _ => PageOutcome::Failed(e.to_string()),
+                        };
+                        yield self.report(&url, outcome);
 
-                yield StreamOutput::Result(CrawlerResult::from_spider_result(result));
+                        match e.downcast_ref::<SpiderError>() {
+                            Some(SpiderError::NotModified) => yield StreamOutput::NotModified(url),
+                            Some(SpiderError::Status(StatusCode::NOT_FOUND | StatusCode::GONE)) => {
+                                yield StreamOutput::Gone(url)
+                            }
+                            _ => (),
+                        }
+                    }
+                };
 
                 if queue.is_empty() {
                     break;
@@ -90,6 +258,60 @@ impl Crawler {
         }
     }
 
+    fn report(&self, url: &Url, outcome: PageOutcome) -> StreamOutput {
+        let mut url = url.clone();
+        url.set_fragment(None);
+
+        StreamOutput::Report(PageReport {
+            depth: self.find_relative_depth(&canonicalize(&url)),
+            url,
+            outcome,
+        })
+    }
+
+    /// The start url, the pages from a previous crawl and the pages in sitemaps
+    async fn discover_start_pages(&self, robots: &RobotsTxt, client: &OutboundClient) -> Vec<Url> {
+        let mut pages: Vec<Url> = self
+            .known_pages
+            .values()
+            .map(|(url, _)| url.clone())
+            .collect();
+
+        for sitemap in robots.sitemaps() {
+            if sitemap.host_str() != self.url.host_str() {
+                continue;
+            }
+            let urls = timeout(
+                self.limits.read_timeout,
+                sitemap::fetch_urls(sitemap, client),
+            )
+            .await
+            .unwrap_or_default();
+            pages.extend(urls.into_iter().filter(|url| self.is_in_scope(url)));
+        }
+
+        pages.push(self.url.clone());
+        pages
+    }
+
+    /// The first limit the crawl has used up, if any. The total size may be
+    /// exceeded by the last page downloaded.
+    fn limit_reached(&self, pages: usize, bytes: usize, started: Instant) -> Option<CrawlLimit> {
+        let limits = &self.limits;
+
+        if let Some(max_pages) = limits.max_pages.filter(|max| pages >= *max) {
+            return Some(CrawlLimit::Pages(max_pages));
+        }
+        if let Some(max_bytes) = limits.max_total_bytes.filter(|max| bytes >= *max) {
+            return Some(CrawlLimit::TotalBytes(max_bytes));
+        }
+        if let Some(max_duration) = limits.max_duration.filter(|max| started.elapsed() >= *max) {
+            return Some(CrawlLimit::Duration(max_duration));
+        }
+
+        None
+    }
+
     fn find_relative_depth(&self, url: &Url) -> usize {
         let normalised_base_path = self.url.path().trim_end_matches('/');
         let normalised_path = url.path().trim_end_matches('/');
@@ -111,7 +333,8 @@ impl Crawler {
 pub struct CrawlerResult {
     url: Url,
     title: String,
-    html: String,
+    content: Content,
+    validators: CacheValidators,
 }
 
 impl CrawlerResult {
@@ -119,7 +342,8 @@ impl CrawlerResult {
         Self {
             url: spider_result.url().to_owned(),
             title: spider_result.page_title(),
-            html: spider_result.html(),
+            content: spider_result.content(),
+            validators: spider_result.validators(),
         }
     }
 
@@ -129,8 +353,29 @@ impl CrawlerResult {
     pub fn title(&self) -> String {
         self.title.clone()
     }
-    pub fn html(&self) -> String {
-        self.html.clone()
+    pub fn content(&self) -> &Content {
+        &self.content
+    }
+    pub fn validators(&self) -> CacheValidators {
+        self.validators.clone()
+    }
+
+    /// Parse the page into a document with the parser for its kind of content.
+    /// PDFs are parsed on a blocking thread as they take a while.
+    pub async fn parse(&self) -> Result<HtmlParser<Done>> {
+        if !matches!(self.content, Content::Pdf(_)) {
+            return self
+                .content
+                .parse(&self.title, self.url(), self.validators());
+        }
+
+        let page = self.clone();
+        tokio::task::spawn_blocking(move || {
+            page.content
+                .parse(&page.title, page.url(), page.validators())
+        })
+        .await
+        .context("Failed to parse PDF")?
     }
 }
 
@@ -0,0 +1,243 @@
+use std::fmt::Debug;
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use migration::sea_orm::DatabaseConnection;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, COOKIE},
+    Url,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{database::Repo, utils::encryption::Cipher, CONFIG};
+
+/// Credentials for crawling a private website. They are stored encrypted per
+/// project and host, and only sent to that host.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlCredentials {
+    pub headers: Vec<(String, String)>,
+    pub cookies: Vec<(String, String)>,
+    pub basic_auth: Option<(String, String)>,
+    pub bearer_token: Option<String>,
+}
+
+impl CrawlCredentials {
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+            && self.cookies.is_empty()
+            && self.basic_auth.is_none()
+            && self.bearer_token.is_none()
+    }
+
+    /// Names of the kinds of credentials that are set, to show without the secrets
+    pub fn kinds(&self) -> Vec<String> {
+        let mut kinds = vec![];
+        if self.basic_auth.is_some() {
+            kinds.push("Basic auth".to_owned());
+        }
+        if self.bearer_token.is_some() {
+            kinds.push("Bearer token".to_owned());
+        }
+        if !self.cookies.is_empty() {
+            kinds.push(format!("{} cookies", self.cookies.len()));
+        }
+        if !self.headers.is_empty() {
+            kinds.push(format!("{} headers", self.headers.len()));
+        }
+        kinds
+    }
+
+    /// The request headers for the credentials. Fails if a name or value can not
+    /// be sent in a header, or if both basic auth and a bearer token are set.
+    pub fn headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in self.headers.iter() {
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .with_context(|| format!("Invalid header name {name}"))?;
+            headers.append(name, Self::secret(value.trim())?);
+        }
+
+        if !self.cookies.is_empty() {
+            let mut cookies = vec![];
+            for (name, value) in self.cookies.iter() {
+                let (name, value) = (name.trim(), value.trim());
+                if name.is_empty() || name.contains(['=', ';']) || value.contains(';') {
+                    bail!("Invalid cookie {name}");
+                }
+                cookies.push(format!("{name}={value}"));
+            }
+            headers.insert(COOKIE, Self::secret(&cookies.join("; "))?);
+        }
+
+        let authorization = match (&self.basic_auth, &self.bearer_token) {
+            (Some(_), Some(_)) => bail!("Use either basic auth or a bearer token, not both"),
+            (Some((username, password)), None) => {
+                if username.contains(':') {
+                    bail!("The username can not contain a colon");
+                }
+                let encoded = STANDARD.encode(format!("{username}:{password}"));
+                Some(format!("Basic {encoded}"))
+            }
+            (None, Some(token)) => Some(format!("Bearer {}", token.trim())),
+            (None, None) => None,
+        };
+        if let Some(authorization) = authorization {
+            headers.insert(AUTHORIZATION, Self::secret(&authorization)?);
+        }
+
+        Ok(headers)
+    }
+
+    pub fn encrypt(&self, cipher: &Cipher) -> Result<String> {
+        cipher.encrypt(&serde_json::to_vec(self)?)
+    }
+
+    pub fn decrypt(secret: &str, cipher: &Cipher) -> Result<Self> {
+        serde_json::from_slice(&cipher.decrypt(secret)?).context("Failed to read credentials")
+    }
+
+    /// Header value that is left out of debug output
+    fn secret(value: &str) -> Result<HeaderValue> {
+        let mut value = HeaderValue::from_str(value).context("Invalid header value")?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+/// Only the kinds of credentials, so that secrets do not end up in logs
+impl Debug for CrawlCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrawlCredentials")
+            .field("kinds", &self.kinds())
+            .finish()
+    }
+}
+
+/// The host credentials are saved for, including the port if it is not the default
+pub fn credentials_host(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    match url.port() {
+        Some(port) => Some(format!("{host}:{port}")),
+        None => Some(host.to_owned()),
+    }
+}
+
+/// The headers of the credentials saved for the host of the url, if there are any
+pub async fn load_credentials(
+    db: &DatabaseConnection,
+    project_id: i32,
+    url: &Url,
+) -> Result<HeaderMap> {
+    let Some(host) = credentials_host(url) else {
+        return Ok(HeaderMap::new());
+    };
+    let Some(model) = db.crawl_credentials().find(project_id, &host).await? else {
+        return Ok(HeaderMap::new());
+    };
+    let Some(cipher) = CONFIG.credentials_cipher() else {
+        bail!("Credentials are saved for {host} but CRAWL_CREDENTIALS_KEY is not set");
+    };
+
+    CrawlCredentials::decrypt(&model.secret, cipher)?.headers()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers() {
+        let credentials = CrawlCredentials {
+            headers: vec![("X-Api-Key".to_owned(), "key".to_owned())],
+            cookies: vec![
+                ("session".to_owned(), "abc".to_owned()),
+                ("sso".to_owned(), "def".to_owned()),
+            ],
+            basic_auth: Some(("user".to_owned(), "pass".to_owned())),
+            bearer_token: None,
+        };
+
+        let headers = credentials.headers().unwrap();
+
+        assert_eq!(headers["x-api-key"], "key");
+        assert_eq!(headers[COOKIE], "session=abc; sso=def");
+        assert_eq!(headers[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+        assert!(headers[AUTHORIZATION].is_sensitive());
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let credentials = CrawlCredentials {
+            bearer_token: Some("token".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            credentials.headers().unwrap()[AUTHORIZATION],
+            "Bearer token"
+        );
+    }
+
+    #[test]
+    fn test_invalid_credentials() {
+        let invalid = [
+            CrawlCredentials {
+                headers: vec![("Bad Name".to_owned(), "value".to_owned())],
+                ..Default::default()
+            },
+            CrawlCredentials {
+                headers: vec![("X-Key".to_owned(), "line\nbreak".to_owned())],
+                ..Default::default()
+            },
+            CrawlCredentials {
+                cookies: vec![("a;b".to_owned(), "c".to_owned())],
+                ..Default::default()
+            },
+            CrawlCredentials {
+                basic_auth: Some(("user".to_owned(), "pass".to_owned())),
+                bearer_token: Some("token".to_owned()),
+                ..Default::default()
+            },
+        ];
+
+        for credentials in invalid {
+            assert!(credentials.headers().is_err(), "{:?}", credentials.kinds());
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher = Cipher::new(&[3; 32]);
+        let credentials = CrawlCredentials {
+            bearer_token: Some("token".to_owned()),
+            ..Default::default()
+        };
+
+        let secret = credentials.encrypt(&cipher).unwrap();
+
+        assert!(!secret.contains("token"));
+        assert_eq!(
+            CrawlCredentials::decrypt(&secret, &cipher).unwrap(),
+            credentials
+        );
+    }
+
+    #[test]
+    fn test_credentials_host() {
+        let host = |url: &str| credentials_host(&Url::parse(url).unwrap());
+
+        assert_eq!(
+            host("https://docs.example.com/a"),
+            Some("docs.example.com".to_owned())
+        );
+        assert_eq!(
+            host("https://docs.example.com:443/a"),
+            Some("docs.example.com".to_owned())
+        );
+        assert_eq!(
+            host("http://localhost:8080/"),
+            Some("localhost:8080".to_owned())
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fmt::Display, time::Duration};
+
+/// Budget for a single crawl. `None` means unlimited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlLimits {
+    pub max_pages: Option<usize>,
+    pub max_page_bytes: Option<usize>,
+    pub max_total_bytes: Option<usize>,
+    pub max_duration: Option<Duration>,
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+}
+
+impl Default for CrawlLimits {
+    fn default() -> Self {
+        Self {
+            max_pages: Some(1_000),
+            max_page_bytes: Some(10 * 1024 * 1024),
+            max_total_bytes: Some(500 * 1024 * 1024),
+            max_duration: Some(Duration::from_secs(60 * 60)),
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl CrawlLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_pages: None,
+            max_page_bytes: None,
+            max_total_bytes: None,
+            max_duration: None,
+            ..Default::default()
+        }
+    }
+}
+
+/// The limit that stopped a crawl before all pages were visited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrawlLimit {
+    Pages(usize),
+    TotalBytes(usize),
+    Duration(Duration),
+}
+
+impl Display for CrawlLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CrawlLimit::Pages(pages) => write!(f, "reached the limit of {pages} pages"),
+            CrawlLimit::TotalBytes(bytes) => {
+                write!(
+                    f,
+                    "reached the limit of {} downloaded",
+                    format_bytes(*bytes)
+                )
+            }
+            CrawlLimit::Duration(duration) => {
+                write!(
+                    f,
+                    "reached the time limit of {} seconds",
+                    duration.as_secs()
+                )
+            }
+        }
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const KIB: usize = 1024;
+    const MIB: usize = 1024 * KIB;
+
+    match bytes {
+        b if b >= MIB => format!("{:.1} MiB", b as f64 / MIB as f64),
+        b if b >= KIB => format!("{:.1} KiB", b as f64 / KIB as f64),
+        b => format!("{b} bytes"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(CrawlLimit::Pages(100), "reached the limit of 100 pages")]
+    #[case(
+        CrawlLimit::TotalBytes(512),
+        "reached the limit of 512 bytes downloaded"
+    )]
+    #[case(
+        CrawlLimit::TotalBytes(1536),
+        "reached the limit of 1.5 KiB downloaded"
+    )]
+    #[case(CrawlLimit::TotalBytes(10 * 1024 * 1024), "reached the limit of 10.0 MiB downloaded")]
+    #[case(
+        CrawlLimit::Duration(Duration::from_secs(90)),
+        "reached the time limit of 90 seconds"
+    )]
+    fn test_limit_display(#[case] limit: CrawlLimit, #[case] expected: &str) {
+        assert_eq!(limit.to_string(), expected);
+    }
+}
@@ -1,18 +1,38 @@
+mod canonical;
+mod content;
 pub mod crawler;
+pub mod credentials;
+pub mod limits;
+pub mod outcome;
+pub mod recrawl;
 mod robots_txt;
+mod sitemap;
 mod spider;
 
 static USER_AGENT_NAME: &str = "MagicDocsBot";
 
 #[cfg(test)]
 mod tests {
-    use crate::web_crawler::crawler::StreamOutput;
+    use std::time::Duration;
 
-    use super::crawler::Crawler;
+    use crate::{
+        models::CacheValidators, outbound::FetchPolicy, web_crawler::crawler::StreamOutput,
+    };
+
+    use super::{
+        crawler::Crawler,
+        limits::{CrawlLimit, CrawlLimits},
+        outcome::PageOutcome,
+    };
     use futures_util::{pin_mut, StreamExt};
-    use mockito::Server;
+    use mockito::{Matcher, Server};
+    use reqwest::Url;
     use tokio::test;
 
+    fn local_policy() -> FetchPolicy {
+        FetchPolicy::new(["127.0.0.1"]).unwrap()
+    }
+
     #[test]
     async fn test_crawler_with_no_max_depth() {
         let mut server = Server::new_async().await;
@@ -36,7 +56,7 @@ mod tests {
             .create_async()
             .await;
 
-        let mut crawler = Crawler::new(url, None).unwrap();
+        let mut crawler = Crawler::new(url, None).unwrap().with_policy(local_policy());
         let stream = crawler.start().await;
         pin_mut!(stream);
         let mut results = vec![];
@@ -78,7 +98,9 @@ mod tests {
             .await
             .expect(0);
 
-        let mut crawler = Crawler::new(url, Some(0)).unwrap();
+        let mut crawler = Crawler::new(url, Some(0))
+            .unwrap()
+            .with_policy(local_policy());
         let stream = crawler.start().await;
         pin_mut!(stream);
         let mut results = vec![];
@@ -126,7 +148,9 @@ mod tests {
             .await
             .expect(0);
 
-        let mut crawler = Crawler::new(url, Some(1)).unwrap();
+        let mut crawler = Crawler::new(url, Some(1))
+            .unwrap()
+            .with_policy(local_policy());
         let stream = crawler.start().await;
         pin_mut!(stream);
         let mut results = vec![];
@@ -181,7 +205,9 @@ mod tests {
             .await
             .expect(0);
 
-        let mut crawler = Crawler::new(url, Some(2)).unwrap();
+        let mut crawler = Crawler::new(url, Some(2))
+            .unwrap()
+            .with_policy(local_policy());
         let stream = crawler.start().await;
         pin_mut!(stream);
         let mut results = vec![];
@@ -225,7 +251,7 @@ mod tests {
             .await
             .expect(0);
 
-        let mut crawler = Crawler::new(url, None).unwrap();
+        let mut crawler = Crawler::new(url, None).unwrap().with_policy(local_policy());
         let stream = crawler.start().await;
         pin_mut!(stream);
         let mut results = vec![];
@@ -267,7 +293,7 @@ mod tests {
             .await
             .expect(0);
 
-        let mut crawler = Crawler::new(url, None).unwrap();
+        let mut crawler = Crawler::new(url, None).unwrap().with_policy(local_policy());
         let stream = crawler.start().await;
         pin_mut!(stream);
         let mut results = vec![];
@@ -284,4 +310,637 @@ mod tests {
 
         assert_eq!(results.len(), 1);
     }
+
+    #[test]
+    async fn test_crawler_sends_conditional_requests_for_known_pages() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body>No links</body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/one")
+            .match_header("if-none-match", "\"abc\"")
+            .match_header("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let known = Url::parse(&format!("{url}/one")).unwrap();
+        let validators = CacheValidators::new(
+            Some("\"abc\"".to_owned()),
+            Some("Wed, 21 Oct 2015 07:28:00 GMT".to_owned()),
+        );
+
+        let mut crawler = Crawler::new(url, None)
+            .unwrap()
+            .with_policy(local_policy())
+            .with_known_pages(vec![(known.clone(), validators)]);
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = vec![];
+        let mut not_modified = vec![];
+        while let Some(output) = stream.next().await {
+            match output {
+                StreamOutput::Result(result) => results.push(result),
+                StreamOutput::NotModified(url) => not_modified.push(url),
+                _ => {}
+            }
+        }
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(not_modified, vec![known]);
+    }
+
+    #[test]
+    async fn test_crawler_reports_gone_pages() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body><a href='/two'>Two</a></body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/one")
+            .with_status(410)
+            .create_async()
+            .await;
+
+        let m3 = server
+            .mock("GET", "/two")
+            .match_header("if-none-match", Matcher::Missing)
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let known = Url::parse(&format!("{url}/one")).unwrap();
+        let validators = CacheValidators::new(Some("\"abc\"".to_owned()), None);
+
+        let mut crawler = Crawler::new(url.to_owned(), None)
+            .unwrap()
+            .with_policy(local_policy())
+            .with_known_pages(vec![(known, validators)]);
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut gone = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Gone(url) = output {
+                gone.push(url.path().to_owned());
+            }
+        }
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+
+        gone.sort();
+        assert_eq!(gone, vec!["/one", "/two"]);
+    }
+
+    #[test]
+    async fn test_crawler_visits_url_variants_once() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body(
+                "<html><body>\
+                    <a href='/one'>One</a>\
+                    <a href='/one/'>One</a>\
+                    <a href='/one#intro'>One</a>\
+                    <a href='/one/index.html'>One</a>\
+                    <a href='/one?utm_source=docs'>One</a>\
+                    <a href='/two?page=2'>Two</a>\
+                </body></html>",
+            )
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", Matcher::Regex("^/one".to_owned()))
+            .with_body("<html><body><h1>One</h1></body></html>")
+            .create_async()
+            .await
+            .expect(1);
+
+        let m3 = server
+            .mock("GET", "/two?page=2")
+            .with_body("<html><body><h1>Two</h1></body></html>")
+            .create_async()
+            .await;
+
+        let mut crawler = Crawler::new(url, None).unwrap().with_policy(local_policy());
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Result(result) = output {
+                results.push(result);
+            }
+        }
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    async fn test_crawler_honours_canonical_link() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body><a href='/print'>Print</a></body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/print")
+            .with_body(
+                "<html><head><link rel='canonical' href='/one'></head>\
+                <body><h1>One</h1><a href='/one'>One</a></body></html>",
+            )
+            .create_async()
+            .await;
+
+        let m3 = server.mock("GET", "/one").create_async().await.expect(0);
+
+        let mut crawler = Crawler::new(url.to_owned(), None)
+            .unwrap()
+            .with_policy(local_policy());
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Result(result) = output {
+                results.push(result.url().path().to_owned());
+            }
+        }
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+
+        results.sort();
+        assert_eq!(results, vec!["/", "/one"]);
+    }
+
+    #[test]
+    async fn test_crawler_skips_duplicate_content() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body><a href='/one'>One</a><a href='/two'>Two</a></body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/one")
+            .with_body("<html><body><h1>Same</h1>\n<p>Content</p></body></html>")
+            .create_async()
+            .await;
+
+        let m3 = server
+            .mock("GET", "/two")
+            .with_body("<html> <body> <h1>Same</h1> <p>Content</p> </body> </html>")
+            .create_async()
+            .await;
+
+        let mut crawler = Crawler::new(url, None).unwrap().with_policy(local_policy());
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Result(result) = output {
+                results.push(result);
+            }
+        }
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    async fn test_crawler_visits_pages_from_sitemap() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nAllow: /\n\nSitemap: /sitemap.xml")
+            .create_async()
+            .await;
+
+        let sitemap = server
+            .mock("GET", "/sitemap.xml")
+            .with_body(format!(
+                "<urlset><url><loc>{url}/unlinked</loc></url><url><loc>https://example.com/other</loc></url></urlset>"
+            ))
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body><h1>Home</h1></body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/unlinked")
+            .with_body("<html><body><h1>Unlinked</h1></body></html>")
+            .create_async()
+            .await;
+
+        let mut crawler = Crawler::new(url, None).unwrap().with_policy(local_policy());
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Result(result) = output {
+                results.push(result);
+            }
+        }
+
+        robots.assert();
+        sitemap.assert();
+        m1.assert();
+        m2.assert();
+
+        assert_eq!(results.len(), 2);
+    }
+
+    async fn crawl_with_limits(url: String, limits: CrawlLimits) -> (usize, Option<CrawlLimit>) {
+        let mut crawler = Crawler::new(url, None)
+            .unwrap()
+            .with_policy(local_policy())
+            .with_limits(limits);
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = 0;
+        let mut stopped_by = None;
+        while let Some(output) = stream.next().await {
+            match output {
+                StreamOutput::Result(_) => results += 1,
+                StreamOutput::LimitReached(limit) => stopped_by = Some(limit),
+                _ => {}
+            }
+        }
+        (results, stopped_by)
+    }
+
+    #[test]
+    async fn test_crawler_stops_at_max_pages() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body><a href='/one'>One</a><a href='/two'>Two</a></body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", Matcher::Regex("^/(one|two)$".to_owned()))
+            .with_body("<html><body><h1>Page</h1></body></html>")
+            .create_async()
+            .await
+            .expect(1);
+
+        let limits = CrawlLimits {
+            max_pages: Some(2),
+            ..CrawlLimits::unlimited()
+        };
+        let (results, stopped_by) = crawl_with_limits(url, limits).await;
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+
+        assert_eq!(results, 2);
+        assert_eq!(stopped_by, Some(CrawlLimit::Pages(2)));
+    }
+
+    #[test]
+    async fn test_crawler_stops_at_max_total_bytes() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body(format!(
+                "<html><body><a href='/one'>One</a>{}</body></html>",
+                "a".repeat(100)
+            ))
+            .create_async()
+            .await;
+
+        let m2 = server.mock("GET", "/one").create_async().await.expect(0);
+
+        let limits = CrawlLimits {
+            max_total_bytes: Some(100),
+            ..CrawlLimits::unlimited()
+        };
+        let (results, stopped_by) = crawl_with_limits(url, limits).await;
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+
+        assert_eq!(results, 1);
+        assert_eq!(stopped_by, Some(CrawlLimit::TotalBytes(100)));
+    }
+
+    #[test]
+    async fn test_crawler_stops_at_max_duration() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        // The duration starts at the first page, the delay after it uses the duration up
+        // however long robots.txt and the sitemaps take
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nCrawl-delay: 200")
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body("<html><body><a href='/one'>One</a></body></html>")
+            .create_async()
+            .await;
+
+        let m2 = server.mock("GET", "/one").create_async().await.expect(0);
+
+        let limits = CrawlLimits {
+            max_duration: Some(Duration::from_millis(100)),
+            ..CrawlLimits::unlimited()
+        };
+        let (results, stopped_by) = crawl_with_limits(url, limits).await;
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+
+        assert_eq!(results, 1);
+        assert_eq!(
+            stopped_by,
+            Some(CrawlLimit::Duration(Duration::from_millis(100)))
+        );
+    }
+
+    #[test]
+    async fn test_crawler_skips_large_pages() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/")
+            .with_body(
+                "<html><body><a href='/large'>Large</a><a href='/small'>Small</a></body></html>",
+            )
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/large")
+            .with_body(format!("<html><body>{}</body></html>", "a".repeat(200)))
+            .create_async()
+            .await;
+
+        let m3 = server
+            .mock("GET", "/small")
+            .with_body("<html><body>Small</body></html>")
+            .create_async()
+            .await;
+
+        let limits = CrawlLimits {
+            max_page_bytes: Some(150),
+            ..CrawlLimits::unlimited()
+        };
+        let (results, stopped_by) = crawl_with_limits(url, limits).await;
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+
+        assert_eq!(results, 2);
+        assert_eq!(stopped_by, None);
+    }
+
+    #[test]
+    async fn test_crawler_visits_only_selected_pages() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m1 = server.mock("GET", "/").create_async().await.expect(0);
+
+        let m2 = server
+            .mock("GET", "/one")
+            .with_body("<html><body><h1>One</h1><a href='/two'>Two</a></body></html>")
+            .create_async()
+            .await;
+
+        let m3 = server.mock("GET", "/two").create_async().await.expect(0);
+
+        let m4 = server
+            .mock("GET", "/three")
+            .with_body("<html><body><h1>Three</h1></body></html>")
+            .create_async()
+            .await;
+
+        let selection = vec![
+            Url::parse(&format!("{url}/one")).unwrap(),
+            Url::parse(&format!("{url}/three")).unwrap(),
+            Url::parse("https://example.com/other").unwrap(),
+        ];
+
+        let mut crawler = Crawler::new(url, None)
+            .unwrap()
+            .with_policy(local_policy())
+            .with_selection(selection);
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut results = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Result(result) = output {
+                results.push(result.url().path().to_owned());
+            }
+        }
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+        m4.assert();
+
+        assert_eq!(results, vec!["/one", "/three"]);
+    }
+
+    #[test]
+    async fn test_crawler_reports_outcome_of_every_url() {
+        let mut server = Server::new_async().await;
+        let url = server.url();
+
+        let robots = server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nDisallow: /docs/private")
+            .create_async()
+            .await;
+
+        let m1 = server
+            .mock("GET", "/docs")
+            .with_body(
+                "<html><body>
+                    <a href='/docs/one'>One</a>
+                    <a href='/docs/private'>Private</a>
+                    <a href='/docs/one/two'>Two</a>
+                    <a href='/docs/missing'>Missing</a>
+                    <a href='/docs/logo.png'>Logo</a>
+                    <a href='/other'>Other</a>
+                </body></html>",
+            )
+            .create_async()
+            .await;
+
+        let m2 = server
+            .mock("GET", "/docs/one")
+            .with_body("<html><body><h1>One</h1></body></html>")
+            .create_async()
+            .await;
+
+        let m3 = server
+            .mock("GET", "/docs/missing")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let m4 = server
+            .mock("GET", "/docs/logo.png")
+            .with_header("content-type", "image/png")
+            .with_body(b"\x89PNG")
+            .create_async()
+            .await;
+
+        let m5 = server
+            .mock("GET", "/docs/private")
+            .create_async()
+            .await
+            .expect(0);
+
+        let mut crawler = Crawler::new(format!("{url}/docs"), Some(1))
+            .unwrap()
+            .with_policy(local_policy());
+        let stream = crawler.start().await;
+        pin_mut!(stream);
+        let mut reports = vec![];
+        while let Some(output) = stream.next().await {
+            if let StreamOutput::Report(report) = output {
+                reports.push((report.url.path().to_owned(), report.depth, report.outcome));
+            }
+        }
+        reports.sort_by(|a, b| a.0.cmp(&b.0));
+
+        robots.assert();
+        m1.assert();
+        m2.assert();
+        m3.assert();
+        m4.assert();
+        m5.assert();
+
+        assert_eq!(
+            reports,
+            vec![
+                ("/docs".to_owned(), 0, PageOutcome::Fetched),
+                (
+                    "/docs/logo.png".to_owned(),
+                    1,
+                    PageOutcome::Unsupported(Some("image/png".to_owned()))
+                ),
+                ("/docs/missing".to_owned(), 1, PageOutcome::HttpError(404)),
+                ("/docs/one".to_owned(), 1, PageOutcome::Fetched),
+                ("/docs/one/two".to_owned(), 2, PageOutcome::SkippedByDepth),
+                ("/docs/private".to_owned(), 1, PageOutcome::SkippedByRobots),
+                ("/other".to_owned(), 0, PageOutcome::SkippedByDepth),
+            ]
+        );
+    }
 }
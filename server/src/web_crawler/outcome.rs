@@ -0,0 +1,67 @@
+use std::fmt::Display;
+
+use reqwest::Url;
+
+/// What happened to a url the crawler came across
+#[derive(Debug, Clone, PartialEq)]
+pub enum PageOutcome {
+    Fetched,
+    NotModified,
+    SkippedByRobots,
+    /// Outside of the start url or deeper than the max depth
+    SkippedByDepth,
+    HttpError(u16),
+    /// The response had a content type that can not be imported, which is given
+    Unsupported(Option<String>),
+    /// The same page was already crawled under this url
+    Duplicate(Url),
+    ParseError(String),
+    /// The page could not be fetched, e.g. because of a timeout
+    Failed(String),
+}
+
+impl PageOutcome {
+    /// Details like the status code or error message, if there are any
+    pub fn detail(&self) -> Option<String> {
+        match self {
+            PageOutcome::HttpError(status) => Some(status.to_string()),
+            PageOutcome::Unsupported(content_type) => content_type.to_owned(),
+            PageOutcome::Duplicate(url) => Some(url.to_string()),
+            PageOutcome::ParseError(error) | PageOutcome::Failed(error) => Some(error.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PageOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageOutcome::Fetched => write!(f, "fetched"),
+            PageOutcome::NotModified => write!(f, "not modified"),
+            PageOutcome::SkippedByRobots => write!(f, "disallowed by robots.txt"),
+            PageOutcome::SkippedByDepth => write!(f, "outside of the crawl depth"),
+            PageOutcome::HttpError(status) => write!(f, "HTTP error {status}"),
+            PageOutcome::Unsupported(Some(content_type)) => {
+                write!(f, "unsupported content type {content_type}")
+            }
+            PageOutcome::Unsupported(None) => write!(f, "unsupported content"),
+            PageOutcome::Duplicate(url) => write!(f, "duplicate of {url}"),
+            PageOutcome::ParseError(error) => write!(f, "failed to parse: {error}"),
+            PageOutcome::Failed(error) => write!(f, "failed: {error}"),
+        }
+    }
+}
+
+/// The outcome for a url, with its depth below the start url
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageReport {
+    pub url: Url,
+    pub depth: usize,
+    pub outcome: PageOutcome,
+}
+
+impl Display for PageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.url, self.outcome)
+    }
+}
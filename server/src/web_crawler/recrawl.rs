@@ -0,0 +1,204 @@
+use std::{collections::HashMap, fmt::Display};
+
+use anyhow::Result;
+use futures_util::{pin_mut, stream, StreamExt};
+use migration::sea_orm::DatabaseConnection;
+use reqwest::Url;
+
+use crate::{
+    database::Repo,
+    models::{CacheValidators, DocumentChanges},
+    utils::hash::content_hash,
+    CONFIG,
+};
+
+use super::{
+    crawler::{Crawler, StreamOutput},
+    credentials::load_credentials,
+    limits::CrawlLimit,
+    outcome::{PageOutcome, PageReport},
+};
+
+pub enum RecrawlOutput {
+    Message(String),
+    Summary(RecrawlSummary),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RecrawlSummary {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+    /// The project version created for the changes, if any
+    pub version: Option<i32>,
+    /// The limit that stopped the crawl before all pages were visited
+    pub stopped_by: Option<CrawlLimit>,
+    /// What happened to every url the crawler came across
+    pub reports: Vec<PageReport>,
+}
+
+impl RecrawlSummary {
+    pub fn has_changes(&self) -> bool {
+        !(self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty())
+    }
+}
+
+impl Display for RecrawlSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.has_changes() {
+            write!(f, "No changes found")?;
+        } else {
+            write!(
+                f,
+                "Added {}, changed {} and removed {} pages",
+                self.added.len(),
+                self.changed.len(),
+                self.removed.len()
+            )?;
+
+            if let Some(version) = self.version {
+                write!(f, " in new project version {version}")?;
+            }
+        }
+
+        match self.stopped_by {
+            Some(limit) => write!(f, ". The crawl stopped early because it {limit}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Crawls a website again and applies the differences to the documents
+/// previously imported from it
+pub struct Recrawler {
+    project_id: i32,
+    url: String,
+    max_depth: Option<usize>,
+}
+
+impl Recrawler {
+    pub fn new(project_id: i32, url: String, max_depth: Option<usize>) -> Self {
+        Self {
+            project_id,
+            url,
+            max_depth,
+        }
+    }
+
+    pub fn start<'a>(
+        &'a self,
+        db: &'a DatabaseConnection,
+    ) -> impl stream::Stream<Item = Result<RecrawlOutput>> + 'a {
+        async_stream::try_stream! {
+            let version = db
+                .projects_versions()
+                .find_latest_version_number_or_create(self.project_id)
+                .await?;
+
+            let crawler = Crawler::new(self.url.to_owned(), self.max_depth)?;
+            let credentials = load_credentials(db, self.project_id, crawler.url()).await?;
+            let crawler = crawler
+                .with_limits(CONFIG.crawl_limits().clone())
+                .with_policy(CONFIG.fetch_policy().clone())
+                .with_credentials(credentials);
+
+            // Documents previously crawled from this website, keyed by page
+            let mut known = HashMap::new();
+            for (document_version, document) in db.documents().all_with_source(self.project_id, version).await? {
+                let Some(Ok(url)) = document.source.as_deref().map(Url::parse) else {
+                    continue;
+                };
+
+                if crawler.is_in_scope(&url) {
+                    known.insert(Crawler::page_key(&url), (url, document_version, document));
+                }
+            }
+
+            let known_pages = known
+                .values()
+                .map(|(url, _, document)| {
+                    let validators =
+                        CacheValidators::new(document.etag.to_owned(), document.last_modified.to_owned());
+                    (url.to_owned(), validators)
+                })
+                .collect();
+
+            let mut crawler = crawler.with_known_pages(known_pages);
+            let mut changes = DocumentChanges::default();
+            let mut summary = RecrawlSummary::default();
+
+            let stream = crawler.start().await;
+            pin_mut!(stream);
+
+            while let Some(output) = stream.next().await {
+                match output {
+                    StreamOutput::Message(msg) => yield RecrawlOutput::Message(msg),
+                    StreamOutput::Report(report) => summary.reports.push(report),
+                    StreamOutput::Result(res) => {
+                        let doc = match res.parse().await {
+                            Ok(doc) => doc,
+                            Err(e) => {
+                                // Keep the existing document rather than removing it
+                                known.remove(&Crawler::page_key(&res.url()));
+                                set_outcome(&mut summary.reports, &res.url(), PageOutcome::ParseError(e.to_string()));
+                                continue;
+                            }
+                        };
+
+                        match known.remove(&Crawler::page_key(&res.url())) {
+                            Some((url, document_version, document)) => {
+                                let old_hash = document
+                                    .content_hash
+                                    .unwrap_or_else(|| content_hash(&document.content));
+
+                                if old_hash != content_hash(doc.content()) || document.name != doc.name() {
+                                    summary.changed.push(url.to_string());
+                                    changes.changed.push((document_version, doc));
+                                }
+                            }
+                            None => {
+                                summary.added.push(res.url().to_string());
+                                changes.added.push(doc);
+                            }
+                        }
+                    }
+                    StreamOutput::LimitReached(limit) => {
+                        yield RecrawlOutput::Message(format!("Crawl stopped early: {limit}"));
+                        summary.stopped_by = Some(limit);
+                    }
+                    StreamOutput::NotModified(url) => {
+                        known.remove(&Crawler::page_key(&url));
+                    }
+                    StreamOutput::Gone(url) => {
+                        if let Some((url, document_version, _)) = known.remove(&Crawler::page_key(&url)) {
+                            summary.removed.push(url.to_string());
+                            changes.removed.push(document_version);
+                        }
+                    }
+                }
+            }
+
+            for url in summary.added.iter() {
+                yield RecrawlOutput::Message(format!("Added {url}"));
+            }
+            for url in summary.changed.iter() {
+                yield RecrawlOutput::Message(format!("Changed {url}"));
+            }
+            for url in summary.removed.iter() {
+                yield RecrawlOutput::Message(format!("Removed {url}"));
+            }
+
+            yield RecrawlOutput::Message("Saving changes...".to_owned());
+            summary.version = db.documents().apply_changes(self.project_id, changes).await?;
+
+            yield RecrawlOutput::Summary(summary);
+        }
+    }
+}
+
+/// Replaces the outcome reported for a url, e.g. when a fetched page fails to parse
+pub fn set_outcome(reports: &mut [PageReport], url: &Url, outcome: PageOutcome) {
+    if let Some(report) = reports.iter_mut().rev().find(|report| report.url == *url) {
+        report.outcome = outcome;
+    }
+}
@@ -1,101 +1,80 @@
-use std::collections::HashMap;
-
 use regex::{self, Regex};
-use reqwest::{header::USER_AGENT, Url};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, USER_AGENT},
+    Url,
+};
+
+use crate::outbound::OutboundClient;
 
 use super::USER_AGENT_NAME;
 
+/// Rules for this crawler from a robots.txt file, following RFC 9309
+#[derive(Default)]
 pub struct RobotsTxt {
     rules: Vec<Rule>,
     delay: Option<u64>,
+    sitemaps: Vec<Url>,
 }
 
 #[derive(Debug)]
-enum Rule {
-    Allow(String),
-    Disallow(String),
-    CrawlDelay(u64),
+struct Rule {
+    allow: bool,
+    /// Length of the path pattern in octets, the longest matching rule wins
+    length: usize,
+    regex: Regex,
+}
+
+/// A group of rules for one or more user agents
+#[derive(Debug, Default)]
+struct Group {
+    user_agents: Vec<String>,
+    rules: Vec<Rule>,
+    delay: Option<u64>,
 }
 
 impl RobotsTxt {
-    pub async fn from_url(url: &Url) -> Self {
-        let body = Self::fetch(url).await;
-        let rules = Self::parse(&body);
-        let delay = rules.iter().find_map(|rule| match rule {
-            Rule::CrawlDelay(delay) => Some(*delay),
-            _ => None,
-        });
-
-        Self { rules, delay }
+    pub async fn from_url(url: &Url, client: &OutboundClient) -> Self {
+        let body = Self::fetch(url, client).await;
+        Self::parse(&body, url)
     }
 
     pub fn delay(&self) -> Option<u64> {
         self.delay
     }
 
-    pub fn is_allowed<'a>(&'a self, url: &'a Url) -> bool {
-        let path = url.path();
-        let mut is_allowed = true;
-
-        for rule in self.rules.iter() {
-            match rule {
-                Rule::Allow(rule) => {
-                    let pattern = self.convert_pattern(rule);
-                    match Regex::new(&pattern) {
-                        Ok(regex) => {
-                            if regex.is_match(path) {
-                                is_allowed = true;
-                            }
-                        }
-                        Err(_) => {
-                            dbg!(rule);
-                            continue;
-                        }
-                    }
-                }
-                Rule::Disallow(rule) => {
-                    let pattern = self.convert_pattern(rule);
-                    match Regex::new(&pattern) {
-                        Ok(regex) => {
-                            if regex.is_match(path) {
-                                is_allowed = false;
-                            }
-                        }
-                        Err(_) => {
-                            dbg!(rule);
-                            continue;
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        is_allowed
+    /// Sitemaps listed in the file, regardless of the group they appear in
+    pub fn sitemaps(&self) -> &[Url] {
+        &self.sitemaps
     }
 
-    fn convert_pattern(&self, rule: &str) -> String {
-        let mut pattern = regex::escape(rule);
-        pattern = pattern.replace(r"\*", ".*");
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_owned(),
+        };
 
-        if pattern.ends_with(r"\$") {
-            pattern.pop(); // Remove trailing $
-            pattern.pop(); // Remove trailing \
-            pattern.push('$'); // End-of-line anchor in regex
+        if path == "/robots.txt" {
+            return true;
         }
 
-        format!("^{pattern}")
+        let matched = self
+            .rules
+            .iter()
+            .filter(|rule| rule.regex.is_match(&path))
+            .max_by_key(|rule| (rule.length, rule.allow));
+
+        matched.is_none_or(|rule| rule.allow)
     }
 
-    async fn fetch(url: &Url) -> String {
+    async fn fetch(url: &Url, client: &OutboundClient) -> String {
         let Ok(url) = url.join("/robots.txt") else {
             return String::new();
         };
 
-        let client = reqwest::Client::new();
-        let request = client.get(url).header(USER_AGENT, USER_AGENT_NAME);
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_NAME));
 
-        let Ok(response) = request.send().await else {
+        let Ok(response) = client.get(url, headers).await else {
             return String::new();
         };
 
@@ -108,73 +87,122 @@ impl RobotsTxt {
         }
     }
 
-    fn parse(body: &str) -> Vec<Rule> {
-        if body.is_empty() {
-            return Vec::new();
-        }
-
-        let mut rules = HashMap::new();
-        let mut current_user_agents = Vec::new();
+    fn parse(body: &str, url: &Url) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut sitemaps = Vec::new();
         let mut last_was_user_agent = false;
 
         for line in body.lines() {
-            let line = line.trim();
-            if line.starts_with('#') || line.is_empty() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
                 continue;
-            }
-
-            if line.starts_with("User-agent:") {
-                if !last_was_user_agent {
-                    current_user_agents.clear();
-                }
-
-                if let Some(user_agent) = line.split_whitespace().nth(1) {
-                    current_user_agents.push(user_agent);
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if !last_was_user_agent {
+                        groups.push(Group::default());
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.user_agents.push(value.to_lowercase());
+                    }
                     last_was_user_agent = true;
-                };
-            } else if line.starts_with("Disallow:") || line.starts_with("Allow:") {
-                let allow = line.starts_with("Allow:");
-
-                if let Some(rule) = line.split_whitespace().nth(1) {
-                    for user_agent in current_user_agents.iter() {
-                        rules
-                            .entry(user_agent.to_string())
-                            .or_insert(Vec::new())
-                            .push(if allow {
-                                Rule::Allow(rule.to_string())
-                            } else {
-                                Rule::Disallow(rule.to_string())
-                            });
+                    continue;
+                }
+                "allow" | "disallow" => {
+                    // An empty rule matches nothing
+                    if let (Some(group), false) = (groups.last_mut(), value.is_empty()) {
+                        if let Some(rule) = Rule::new(key == "allow", value) {
+                            group.rules.push(rule);
+                        }
                     }
                 }
-
-                last_was_user_agent = false;
-            } else if line.starts_with("Crawl-delay:") {
-                if let Some(delay) = line.split_whitespace().nth(1) {
-                    let Ok(delay) = delay.parse() else {
-                        continue;
-                    };
-
-                    for user_agent in current_user_agents.iter() {
-                        rules
-                            .entry(user_agent.to_string())
-                            .or_insert(Vec::new())
-                            .push(Rule::CrawlDelay(delay));
+                "crawl-delay" => {
+                    if let (Some(group), Ok(delay)) = (groups.last_mut(), value.parse()) {
+                        group.delay.get_or_insert(delay);
+                    }
+                }
+                "sitemap" => {
+                    if let Ok(sitemap) = url.join(value) {
+                        sitemaps.push(sitemap);
                     }
                 }
+                _ => {}
+            }
 
-                last_was_user_agent = false;
-            } else {
-                last_was_user_agent = false;
+            last_was_user_agent = false;
+        }
+
+        // Only the groups for this crawler apply, or the * groups if there are none
+        let product_token = USER_AGENT_NAME.to_lowercase();
+        let applies_to = |user_agent: &str| {
+            groups
+                .iter()
+                .any(|group| group.user_agents.iter().any(|agent| agent == user_agent))
+        };
+        let user_agent = match applies_to(&product_token) {
+            true => product_token.as_str(),
+            false => "*",
+        };
+
+        let mut rules = Vec::new();
+        let mut delay = None;
+        for group in groups {
+            if group.user_agents.iter().any(|agent| agent == user_agent) {
+                delay = delay.or(group.delay);
+                rules.extend(group.rules);
             }
         }
 
-        // Save * and USER_AGENT into a single vector
-        let mut relevant_rules = Vec::new();
-        relevant_rules.extend(rules.remove("*").unwrap_or_default());
-        relevant_rules.extend(rules.remove(USER_AGENT_NAME).unwrap_or_default());
+        Self {
+            rules,
+            delay,
+            sitemaps,
+        }
+    }
+}
+
+impl Rule {
+    fn new(allow: bool, pattern: &str) -> Option<Self> {
+        let pattern = Self::percent_encode(pattern);
+        let regex = Regex::new(&Self::convert_pattern(&pattern)).ok()?;
+
+        Some(Self {
+            allow,
+            length: pattern.len(),
+            regex,
+        })
+    }
+
+    fn convert_pattern(rule: &str) -> String {
+        let mut pattern = regex::escape(rule);
+        pattern = pattern.replace(r"\*", ".*");
+
+        if pattern.ends_with(r"\$") {
+            pattern.pop(); // Remove trailing $
+            pattern.pop(); // Remove trailing \
+            pattern.push('$'); // End-of-line anchor in regex
+        }
+
+        format!("^{pattern}")
+    }
 
-        relevant_rules
+    /// Paths are compared in their percent-encoded form, like the paths of parsed urls
+    fn percent_encode(pattern: &str) -> String {
+        let mut encoded = String::with_capacity(pattern.len());
+        for c in pattern.chars() {
+            if c.is_ascii() {
+                encoded.push(c);
+            } else {
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    encoded.push_str(&format!("%{byte:02X}"));
+                }
+            }
+        }
+        encoded
     }
 }
 
@@ -214,15 +242,103 @@ mod tests {
     #[case("example.com", "/anypath", false)]
     #[case("example.com", "/anyfile.html", false)]
     #[case("example.com", "/anyfile.jsx", false)]
+    #[case("example.com", "/robots.txt", true)]
+    #[case("googe.com", "/search", false)]
+    #[case("googe.com", "/search/about", true)]
+    #[case("googe.com", "/m/finance", true)]
+    #[case("googe.com", "/m/other", false)]
+    #[case("googe.com", "/?hl=en", true)]
+    #[case("googe.com", "/?hl=en&q=rust", false)]
+    #[case("googe.com", "/?hl=en&gws_rd=ssl", true)]
+    #[case("en.wikipedia.org", "/wiki/Rust", true)]
+    #[case("en.wikipedia.org", "/w/index.php", false)]
+    #[case("en.wikipedia.org", "/w/load.php?lang=en", true)]
+    #[case("en.wikipedia.org", "/api/rest_v1/?doc", true)]
+    #[case("en.wikipedia.org", "/wiki/Wikipedia:Löschkandidaten/Rust", false)]
+    #[case("en.wikipedia.org", "/wiki/Wikipedia:Qualitätssicherung/", false)]
     fn test_process_url(#[case] domain: &str, #[case] path: &str, #[case] expected: bool) {
         let body =
             fs::read_to_string(format!("src/web_crawler/test_files/{domain}.robots.txt")).unwrap();
-        let rules = RobotsTxt::parse(&body);
-        let robots = RobotsTxt { rules, delay: None };
         let url = Url::parse(format!("https://{domain}{path}").as_str()).unwrap();
+        let robots = RobotsTxt::parse(&body, &url);
 
         let is_allowed = robots.is_allowed(&url);
 
         assert_eq!(is_allowed, expected);
     }
+
+    #[rstest]
+    #[case("/page", true)]
+    #[case("/folder/page", false)]
+    #[case("/folder/allowed/page", true)]
+    #[case("/page.php", false)]
+    #[case("/page.php?id=1", true)]
+    fn test_longest_match_wins(#[case] path: &str, #[case] expected: bool) {
+        let body = "User-agent: *\n\
+            Disallow: /folder\n\
+            Allow: /folder/allowed\n\
+            Disallow: /*.php$\n";
+        let url = Url::parse(&format!("https://example.com{path}")).unwrap();
+        let robots = RobotsTxt::parse(body, &url);
+
+        assert_eq!(robots.is_allowed(&url), expected);
+    }
+
+    #[rstest]
+    #[case("User-agent: *\nDisallow: /page\nAllow: /page")]
+    #[case("User-agent: *\nAllow: /page\nDisallow: /page")]
+    #[case("User-agent: *\nAllow: /pag*\nDisallow: /page")]
+    fn test_allow_wins_ties(#[case] body: &str) {
+        let url = Url::parse("https://example.com/page").unwrap();
+        let robots = RobotsTxt::parse(body, &url);
+
+        assert!(robots.is_allowed(&url));
+    }
+
+    #[rstest]
+    #[case("User-agent: MagicDocsBot\nDisallow: /private\n\nUser-agent: *\nDisallow: /")]
+    #[case("User-agent: magicdocsbot\nDisallow: /private\n\nUser-agent: *\nDisallow: /")]
+    #[case("user-agent: MAGICDOCSBOT\ndisallow: /private\n\nUSER-AGENT: *\nDISALLOW: /")]
+    #[case("User-agent: OtherBot\nUser-agent: MagicDocsBot\nDisallow: /private\n\nUser-agent: *\nDisallow: /")]
+    #[case("User-agent: MagicDocsBot\nDisallow: /private/a\n\nUser-agent: *\nDisallow: /\n\nUser-agent: MagicDocsBot\nDisallow: /private")]
+    fn test_specific_group_only(#[case] body: &str) {
+        let url = Url::parse("https://example.com").unwrap();
+        let robots = RobotsTxt::parse(body, &url);
+
+        assert!(robots.is_allowed(&url.join("/public").unwrap()));
+        assert!(!robots.is_allowed(&url.join("/private").unwrap()));
+    }
+
+    #[test]
+    fn test_rules_outside_groups_are_ignored() {
+        let body = "Disallow: /\n\nUser-agent: OtherBot\nDisallow: /";
+        let url = Url::parse("https://example.com/page").unwrap();
+        let robots = RobotsTxt::parse(body, &url);
+
+        assert!(robots.is_allowed(&url));
+    }
+
+    #[test]
+    fn test_sitemaps_and_delay() {
+        let body = "Sitemap: https://example.com/sitemap.xml\n\
+            User-agent: *\n\
+            Crawl-delay: 5\n\
+            Disallow: /private # comment\n\
+            Sitemap: /docs/sitemap.xml\n\
+            \n\
+            User-agent: OtherBot\n\
+            Crawl-delay: 60\n";
+        let url = Url::parse("https://example.com").unwrap();
+        let robots = RobotsTxt::parse(body, &url);
+
+        assert_eq!(robots.delay(), Some(5));
+        assert_eq!(
+            robots.sitemaps(),
+            &[
+                Url::parse("https://example.com/sitemap.xml").unwrap(),
+                Url::parse("https://example.com/docs/sitemap.xml").unwrap(),
+            ]
+        );
+        assert!(!robots.is_allowed(&url.join("/private").unwrap()));
+    }
 }
@@ -0,0 +1,100 @@
+use reqwest::{
+    header::{HeaderMap, HeaderValue, USER_AGENT},
+    Url,
+};
+use scraper::{Html, Selector};
+
+use crate::outbound::OutboundClient;
+
+use super::USER_AGENT_NAME;
+
+/// Page urls listed in a sitemap. Sitemap indexes are followed one level deep.
+pub async fn fetch_urls(url: &Url, client: &OutboundClient) -> Vec<Url> {
+    let Some(body) = fetch(client, url).await else {
+        return Vec::new();
+    };
+
+    let (is_index, locations) = parse(&body, url);
+    if !is_index {
+        return locations;
+    }
+
+    let mut urls = Vec::new();
+    for sitemap in locations {
+        if let Some(body) = fetch(client, &sitemap).await {
+            urls.extend(parse(&body, &sitemap).1);
+        }
+    }
+    urls
+}
+
+async fn fetch(client: &OutboundClient, url: &Url) -> Option<String> {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_NAME));
+
+    let response = client.get(url.clone(), headers).await.ok()?;
+
+    match response.status().is_success() {
+        true => response.text().await.ok(),
+        false => None,
+    }
+}
+
+/// Whether the sitemap is an index of other sitemaps, and the urls it lists
+fn parse(body: &str, url: &Url) -> (bool, Vec<Url>) {
+    let document = Html::parse_document(body);
+    let (Ok(index), Ok(loc)) = (Selector::parse("sitemapindex"), Selector::parse("loc")) else {
+        return (false, Vec::new());
+    };
+
+    let urls = document
+        .select(&loc)
+        .map(|e| e.text().collect::<String>())
+        .filter_map(|location| url.join(location.trim()).ok())
+        .collect();
+
+    (document.select(&index).next().is_some(), urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_urlset() {
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <url><loc>https://example.com/docs</loc></url>
+                <url><loc> https://example.com/docs/intro?a=1&amp;b=2 </loc></url>
+            </urlset>"#;
+        let url = Url::parse("https://example.com/sitemap.xml").unwrap();
+
+        let (is_index, urls) = parse(body, &url);
+
+        assert!(!is_index);
+        assert_eq!(
+            urls,
+            vec![
+                Url::parse("https://example.com/docs").unwrap(),
+                Url::parse("https://example.com/docs/intro?a=1&b=2").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap><loc>https://example.com/docs/sitemap.xml</loc></sitemap>
+            </sitemapindex>"#;
+        let url = Url::parse("https://example.com/sitemap.xml").unwrap();
+
+        let (is_index, urls) = parse(body, &url);
+
+        assert!(is_index);
+        assert_eq!(
+            urls,
+            vec![Url::parse("https://example.com/docs/sitemap.xml").unwrap()]
+        );
+    }
+}
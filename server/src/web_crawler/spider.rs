@@ -1,69 +1,225 @@
+use std::{future::Future, time::Duration};
+
 use anyhow::{bail, Result};
 use reqwest::{
-    header::{HeaderValue, CONTENT_TYPE, USER_AGENT},
-    Url,
+    header::{
+        HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED, USER_AGENT,
+    },
+    StatusCode, Url,
 };
 use scraper::{Html, Selector};
+use thiserror::Error;
+
+use crate::{
+    models::CacheValidators,
+    outbound::OutboundClient,
+    parsing::{markdown_title, name_from_url, Content},
+    utils::hash::content_hash,
+};
 
-use super::USER_AGENT_NAME;
+use super::{
+    content::{self, ContentKind},
+    limits::format_bytes,
+    USER_AGENT_NAME,
+};
 
-static EXPECTED_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("text/html");
+#[derive(Debug, Error)]
+pub enum SpiderError {
+    #[error("Page not modified")]
+    NotModified,
+    #[error("Failed to fetch page: {0}")]
+    Status(StatusCode),
+    #[error("Page is larger than {}", format_bytes(*.0))]
+    TooLarge(usize),
+    #[error("Timed out waiting for the server")]
+    Timeout,
+    #[error("Content type is not supported")]
+    Unsupported(Option<String>),
+}
 
 pub struct Spider {
     url: Url,
-    client: reqwest::Client,
+    client: OutboundClient,
+    validators: Option<CacheValidators>,
+    max_bytes: Option<usize>,
+    read_timeout: Option<Duration>,
 }
 
 impl Spider {
-    pub fn new(url: Url) -> Self {
+    pub fn new(url: Url, client: OutboundClient) -> Self {
         Self {
             url,
-            client: reqwest::Client::new(),
+            client,
+            validators: None,
+            max_bytes: None,
+            read_timeout: None,
         }
     }
 
+    /// Stop downloading pages larger than the limit
+    pub fn with_max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Give up when the server has not sent anything for the duration
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = Some(read_timeout);
+        self
+    }
+
+    /// Send a conditional request using validators from a previous crawl
+    pub fn with_validators(mut self, validators: CacheValidators) -> Self {
+        self.validators = Some(validators);
+        self
+    }
+
     pub async fn start(&self) -> Result<SpiderResult> {
-        let html = self.fetch_html(&self.url).await?;
-        let links = self.extract_urls(&html)?;
-        let title = self.extract_title(&html)?;
-        let result = SpiderResult::new(self.url.clone(), links, title, html);
+        let (content, bytes, validators) = self.fetch_content(&self.url).await?;
+
+        let (links, title, canonical_url, content_hash) = match &content {
+            Content::Html(html) => (
+                self.extract_urls(html)?,
+                self.extract_title(html)?,
+                self.extract_canonical_url(html)?,
+                self.extract_content_hash(html),
+            ),
+            Content::Markdown(text) => (
+                vec![],
+                markdown_title(text).unwrap_or_else(|| name_from_url(&self.url)),
+                None,
+                Self::text_hash(text),
+            ),
+            Content::PlainText(text) | Content::Rst(text) => (
+                vec![],
+                name_from_url(&self.url),
+                None,
+                Self::text_hash(text),
+            ),
+            Content::Pdf(pdf) => (vec![], name_from_url(&self.url), None, content_hash(pdf)),
+        };
+
+        let result = SpiderResult {
+            url: self.url.clone(),
+            canonical_url,
+            found_urls: links,
+            page_title: title,
+            content,
+            validators,
+            content_hash,
+            bytes,
+        };
         Ok(result)
     }
 
-    async fn fetch_html(&self, url: &Url) -> Result<String> {
-        let req = self
-            .client
-            .get(url.clone())
-            .header(USER_AGENT, USER_AGENT_NAME);
-        let res = req.send().await?;
+    /// The content of the page, the number of bytes downloaded and the cache validators
+    async fn fetch_content(&self, url: &Url) -> Result<(Content, usize, CacheValidators)> {
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_NAME));
+
+        if let Some(validators) = &self.validators {
+            if let Some(etag) = validators
+                .etag()
+                .and_then(|v| HeaderValue::from_str(v).ok())
+            {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = validators
+                .last_modified()
+                .and_then(|v| HeaderValue::from_str(v).ok())
+            {
+                headers.insert(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let mut res = self.read(self.client.get(url.clone(), headers)).await??;
+
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Err(SpiderError::NotModified.into());
+        }
 
         if !res.status().is_success() {
-            bail!("Failed to fetch page");
+            return Err(SpiderError::Status(res.status()).into());
         }
 
-        let content_type_html = match res.headers().get(CONTENT_TYPE) {
-            Some(content_type) => content_type.eq(&EXPECTED_CONTENT_TYPE),
-            None => false,
+        let validators = Self::extract_validators(res.headers());
+
+        let content_type_header = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        let content_type = content_type_header
+            .as_deref()
+            .and_then(content::parse_content_type);
+        let unsupported = || SpiderError::Unsupported(content_type_header.clone());
+
+        // Skip downloading content that is declared as something that can not be imported
+        let declared_kind = match &content_type {
+            Some(content_type) if !content::is_unknown(Some(content_type)) => {
+                Some(content::declared_kind(content_type, url).ok_or_else(unsupported)?)
+            }
+            _ => None,
         };
 
-        let html = res.text().await?;
+        if let (Some(max_bytes), Some(length)) = (self.max_bytes, res.content_length()) {
+            if length as usize > max_bytes {
+                return Err(SpiderError::TooLarge(max_bytes).into());
+            }
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = self.read(res.chunk()).await?? {
+            body.extend_from_slice(&chunk);
 
-        let html = match content_type_html {
-            false => self.sniff_html_content(html)?,
-            true => html,
+            if let Some(max_bytes) = self.max_bytes {
+                if body.len() > max_bytes {
+                    return Err(SpiderError::TooLarge(max_bytes).into());
+                }
+            }
+        }
+
+        let kind = match declared_kind {
+            Some(kind) => kind,
+            None => content::sniff_kind(&body, url).ok_or_else(unsupported)?,
+        };
+
+        let charset = content_type
+            .as_ref()
+            .and_then(|content_type| content_type.get_param(mime::CHARSET))
+            .map(|charset| charset.as_str());
+        let bytes = body.len();
+
+        let content = match kind {
+            ContentKind::Html => Content::Html(content::decode(&body, charset).0),
+            ContentKind::Markdown => Content::Markdown(content::decode(&body, charset).0),
+            ContentKind::PlainText => Content::PlainText(content::decode(&body, charset).0),
+            ContentKind::Pdf => Content::Pdf(body),
         };
 
-        Ok(html)
+        Ok((content, bytes, validators))
     }
 
-    fn sniff_html_content(&self, html: String) -> Result<String> {
-        let lower_content = html.to_lowercase();
-        let is_html =
-            lower_content.starts_with("<!doctype html>") || lower_content.starts_with("<html");
-        if !is_html {
-            bail!("Content is not HTML");
+    /// Wait for the future, failing if the read timeout passes first
+    async fn read<T>(&self, future: impl Future<Output = T>) -> Result<T> {
+        match self.read_timeout {
+            Some(read_timeout) => tokio::time::timeout(read_timeout, future)
+                .await
+                .map_err(|_| SpiderError::Timeout.into()),
+            None => Ok(future.await),
         }
-        Ok(html)
+    }
+
+    fn extract_validators(headers: &HeaderMap) -> CacheValidators {
+        let header = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+
+        CacheValidators::new(header(ETAG), header(LAST_MODIFIED))
     }
 
     fn extract_urls(&self, html: &str) -> Result<Vec<Url>> {
@@ -105,6 +261,39 @@ impl Spider {
         Ok(title.unwrap_or("unnamed".to_string()))
     }
 
+    /// The url declared by `<link rel="canonical">`, if it points to the same host
+    fn extract_canonical_url(&self, html: &str) -> Result<Option<Url>> {
+        let document = Html::parse_document(html);
+        let Ok(selector) = Selector::parse("link[rel~=canonical][href]") else {
+            bail!("Failed to parse selector");
+        };
+
+        let canonical_url = document
+            .select(&selector)
+            .filter_map(|e| e.value().attr("href"))
+            .find_map(|link| self.parse_link(link).ok());
+
+        Ok(canonical_url)
+    }
+
+    /// Hash of the visible text, so pages served under different urls can be recognised
+    fn extract_content_hash(&self, html: &str) -> String {
+        let document = Html::parse_document(html);
+        let text = document
+            .root_element()
+            .text()
+            .flat_map(|text| text.split_whitespace())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        content_hash(&text)
+    }
+
+    /// Hash of the text ignoring differences in whitespace
+    fn text_hash(text: &str) -> String {
+        content_hash(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+
     fn parse_link(&self, link: &str) -> Result<Url> {
         match Url::parse(link) {
             Ok(parsed_url) => {
@@ -127,41 +316,60 @@ impl Spider {
 
 pub struct SpiderResult {
     url: Url,
+    canonical_url: Option<Url>,
     found_urls: Vec<Url>,
     page_title: String,
-    html: String,
+    content: Content,
+    validators: CacheValidators,
+    content_hash: String,
+    bytes: usize,
 }
 
 impl SpiderResult {
-    fn new(url: Url, links: Vec<Url>, page_title: String, html: String) -> Self {
-        Self {
-            url,
-            found_urls: links,
-            page_title,
-            html,
-        }
-    }
     pub fn url(&self) -> Url {
         self.url.clone()
     }
+    pub fn canonical_url(&self) -> Option<Url> {
+        self.canonical_url.clone()
+    }
+    /// Report the page under another url, e.g. the one it declares canonical
+    pub fn set_url(&mut self, url: Url) {
+        self.url = url;
+    }
     pub fn found_urls(&self) -> Vec<Url> {
         self.found_urls.clone()
     }
     pub fn page_title(&self) -> String {
         self.page_title.clone()
     }
-    pub fn html(&self) -> String {
-        self.html.clone()
+    pub fn content(&self) -> Content {
+        self.content.clone()
+    }
+    pub fn validators(&self) -> CacheValidators {
+        self.validators.clone()
+    }
+    pub fn content_hash(&self) -> &str {
+        &self.content_hash
+    }
+    /// Size of the downloaded page
+    pub fn bytes(&self) -> usize {
+        self.bytes
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::outbound::FetchPolicy;
     use mockito::*;
     use std::fs;
     use tokio::test;
 
+    fn client() -> OutboundClient {
+        let policy = FetchPolicy::new(["127.0.0.1"]).unwrap();
+        OutboundClient::new(policy, Duration::from_secs(5)).unwrap()
+    }
+
     #[test]
     async fn test_spider() {
         let mut server = Server::new_async().await;
@@ -177,7 +385,7 @@ mod tests {
             .create_async()
             .await;
 
-        let spider = Spider::new(url.clone());
+        let spider = Spider::new(url.clone(), client());
         let result = spider.start().await.unwrap();
 
         mock.assert();
@@ -185,10 +393,373 @@ mod tests {
         assert_eq!(&result.url(), &url);
         assert_eq!(result.page_title(), "Rust");
         assert!(!result.found_urls().is_empty());
-        assert!(!result.html().is_empty());
+        assert!(matches!(result.content(), Content::Html(html) if !html.is_empty()));
 
         for found_url in result.found_urls() {
             assert_eq!(found_url.host_str(), url.host_str());
         }
     }
+
+    #[test]
+    async fn test_spider_reads_cache_validators() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_header("content-type", "text/html")
+            .with_header("etag", "\"abc\"")
+            .with_header("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")
+            .with_body("<html><body><h1>Title</h1></body></html>")
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client()).start().await.unwrap();
+
+        mock.assert();
+
+        assert_eq!(result.validators().etag(), Some("\"abc\""));
+        assert_eq!(
+            result.validators().last_modified(),
+            Some("Wed, 21 Oct 2015 07:28:00 GMT")
+        );
+    }
+
+    #[test]
+    async fn test_spider_reads_canonical_url() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body(
+                "<html><head><link rel='canonical' href='/docs'></head><body><h1>Docs</h1></body></html>",
+            )
+            .create_async()
+            .await;
+
+        let result = Spider::new(url.clone(), client()).start().await.unwrap();
+
+        mock.assert();
+
+        assert_eq!(result.canonical_url(), Some(url.join("/docs").unwrap()));
+    }
+
+    #[test]
+    async fn test_spider_ignores_canonical_url_on_other_host() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body(
+                "<html><head><link rel='canonical' href='https://example.com/docs'></head></html>",
+            )
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client()).start().await.unwrap();
+
+        mock.assert();
+
+        assert_eq!(result.canonical_url(), None);
+    }
+
+    #[test]
+    async fn test_spider_rejects_large_pages() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body(format!("<html><body>{}</body></html>", "a".repeat(100)))
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client())
+            .with_max_bytes(Some(50))
+            .start()
+            .await;
+
+        mock.assert();
+
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<SpiderError>(),
+            Some(SpiderError::TooLarge(50))
+        ));
+    }
+
+    #[test]
+    async fn test_spider_rejects_large_chunked_pages() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                w.write_all(b"<html><body>")?;
+                for _ in 0..10 {
+                    w.write_all(&[b'a'; 10])?;
+                }
+                w.write_all(b"</body></html>")
+            })
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client())
+            .with_max_bytes(Some(50))
+            .start()
+            .await;
+
+        mock.assert();
+
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<SpiderError>(),
+            Some(SpiderError::TooLarge(50))
+        ));
+    }
+
+    #[test]
+    async fn test_spider_read_timeout() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(500));
+                w.write_all(b"<html><body>Slow</body></html>")
+            })
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client())
+            .with_read_timeout(Duration::from_millis(100))
+            .start()
+            .await;
+
+        mock.assert();
+
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<SpiderError>(),
+            Some(SpiderError::Timeout)
+        ));
+    }
+
+    #[test]
+    async fn test_spider_decodes_charset() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_header("content-type", "text/html; charset=ISO-8859-1")
+            .with_body(b"<html><body><h1>Caf\xe9</h1></body></html>")
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client()).start().await.unwrap();
+
+        mock.assert();
+
+        assert_eq!(result.page_title(), "Café");
+    }
+
+    #[test]
+    async fn test_spider_detects_html_and_charset() {
+        let cases: [(Option<&str>, &[u8]); 6] = [
+            (
+                Some("text/html; charset=utf-8"),
+                b"\n  <!DOCTYPE html><html><body><h1>Caf\xc3\xa9</h1></body></html>",
+            ),
+            (
+                Some("application/xhtml+xml"),
+                b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<html xmlns=\"http://www.w3.org/1999/xhtml\"><body><h1>Caf\xe9</h1></body></html>",
+            ),
+            (
+                None,
+                b"\xef\xbb\xbf\n<html><body><h1>Caf\xc3\xa9</h1></body></html>",
+            ),
+            (
+                Some("TEXT/HTML"),
+                b"<html><head><meta charset=\"windows-1252\"></head><body><h1>Caf\xe9</h1></body></html>",
+            ),
+            (
+                Some("application/octet-stream"),
+                b"<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\"></head><body><h1>Caf\xe9</h1></body></html>",
+            ),
+            (
+                Some("text/html"),
+                b"<html><body><h1>Caf\xe9, cr\xe8me br\xfbl\xe9e et na\xefvet\xe9 \xe0 la fran\xe7aise</h1></body></html>",
+            ),
+        ];
+
+        for (content_type, body) in cases {
+            let mut server = Server::new_async().await;
+            let url = Url::parse(server.url().as_str()).unwrap();
+
+            let mock = server.mock("GET", "/").with_status(200).with_body(body);
+            let mock = match content_type {
+                Some(content_type) => mock.with_header("content-type", content_type),
+                None => mock,
+            };
+            let mock = mock.create_async().await;
+
+            let result = Spider::new(url, client()).start().await.unwrap();
+
+            mock.assert();
+
+            assert!(result.page_title().starts_with("Café"), "{content_type:?}");
+        }
+    }
+
+    #[test]
+    async fn test_spider_reads_markdown_and_text() {
+        let cases = [
+            (
+                Some("text/markdown; charset=utf-8"),
+                "/readme",
+                "# Caf\u{e9}\n\nText",
+                "Café",
+            ),
+            (
+                Some("text/plain"),
+                "/docs/getting-started.md",
+                "# Start\n\nText",
+                "Start",
+            ),
+            (None, "/docs/notes.md", "No heading", "notes"),
+            (
+                Some("text/plain"),
+                "/docs/notes.txt",
+                "# Not a title",
+                "notes",
+            ),
+        ];
+
+        for (content_type, path, body, title) in cases {
+            let mut server = Server::new_async().await;
+            let url = Url::parse(server.url().as_str())
+                .unwrap()
+                .join(path)
+                .unwrap();
+
+            let mock = server.mock("GET", path).with_status(200).with_body(body);
+            let mock = match content_type {
+                Some(content_type) => mock.with_header("content-type", content_type),
+                None => mock,
+            };
+            let mock = mock.create_async().await;
+
+            let result = Spider::new(url, client()).start().await.unwrap();
+
+            mock.assert();
+
+            assert_eq!(result.page_title(), title, "{path}");
+            assert!(result.found_urls().is_empty());
+            match result.content() {
+                Content::Markdown(text) if path.ends_with(".md") || path == "/readme" => {
+                    assert_eq!(text, body)
+                }
+                Content::PlainText(text) if path.ends_with(".txt") => assert_eq!(text, body),
+                _ => panic!("Wrong kind of content for {path}"),
+            }
+        }
+    }
+
+    #[test]
+    async fn test_spider_reads_pdf() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str())
+            .unwrap()
+            .join("/guide")
+            .unwrap();
+        let pdf = fs::read("src/parsing/test_files/guide.pdf").unwrap();
+
+        let mock = server
+            .mock("GET", "/guide")
+            .with_status(200)
+            .with_header("content-type", "application/octet-stream")
+            .with_body(&pdf)
+            .create_async()
+            .await;
+
+        let result = Spider::new(url, client()).start().await.unwrap();
+
+        mock.assert();
+
+        assert_eq!(result.page_title(), "guide");
+        assert_eq!(result.bytes(), pdf.len());
+        assert!(matches!(result.content(), Content::Pdf(bytes) if bytes == pdf));
+    }
+
+    #[test]
+    async fn test_spider_rejects_other_content_types() {
+        let cases: [(Option<&str>, &[u8]); 3] = [
+            (Some("image/png"), b"\x89PNG"),
+            (Some("application/json"), b"{\"html\": true}"),
+            (None, b"\x00\x01binary"),
+        ];
+
+        for (content_type, body) in cases {
+            let mut server = Server::new_async().await;
+            let url = Url::parse(server.url().as_str()).unwrap();
+
+            let mock = server.mock("GET", "/").with_status(200).with_body(body);
+            let mock = match content_type {
+                Some(content_type) => mock.with_header("content-type", content_type),
+                None => mock,
+            };
+            let mock = mock.create_async().await;
+
+            let error = Spider::new(url, client()).start().await.err().unwrap();
+
+            mock.assert();
+
+            assert!(
+                matches!(
+                    error.downcast_ref::<SpiderError>(),
+                    Some(SpiderError::Unsupported(found)) if found.as_deref() == content_type
+                ),
+                "{content_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_spider_not_modified() {
+        let mut server = Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+
+        let mock = server
+            .mock("GET", "/")
+            .match_header("if-none-match", "\"abc\"")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let validators = CacheValidators::new(Some("\"abc\"".to_owned()), None);
+        let result = Spider::new(url, client())
+            .with_validators(validators)
+            .start()
+            .await;
+
+        mock.assert();
+
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error.downcast_ref::<SpiderError>(),
+            Some(SpiderError::NotModified)
+        ));
+    }
 }